@@ -2,20 +2,58 @@ use chromiumoxide_pdl::build::Generator;
 use std::env;
 use std::path::{Path, PathBuf};
 
+/// The env vars that configure the generated code
+const ENV_VARS: &[&str] = &[
+    "CDP_JS_PROTOCOL_PATH",
+    "CDP_BROWSER_PROTOCOL_PATH",
+    "CDP_NO_EXPERIMENTAL",
+    "CDP_DEPRECATED",
+    "CDP_GENERATE_DIR",
+];
+
 /// Compile the pdl files
 fn main() {
-    let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
-    let js_proto = env::var("CDP_JS_PROTOCOL_PATH")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| dir.join("js_protocol.pdl"));
+    for var in ENV_VARS {
+        println!("cargo:rerun-if-env-changed={}", var);
+    }
 
-    let browser_proto = env::var("CDP_BROWSER_PROTOCOL_PATH")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| dir.join("browser_protocol.pdl"));
+    let js_proto = protocol_path("CDP_JS_PROTOCOL_PATH", "js_protocol.pdl");
+    let browser_proto = protocol_path("CDP_BROWSER_PROTOCOL_PATH", "browser_protocol.pdl");
+    for pdl in &[&js_proto, &browser_proto] {
+        println!("cargo:rerun-if-changed={}", pdl.display());
+    }
+    let pdls = [js_proto, browser_proto];
 
-    Generator::default()
+    let mut generator = Generator::default();
+    generator
         .experimental(env::var("CDP_NO_EXPERIMENTAL").is_err())
-        .deprecated(env::var("CDP_DEPRECATED").is_ok())
-        .compile_pdls(&[js_proto, browser_proto])
-        .unwrap();
+        .deprecated(env::var("CDP_DEPRECATED").is_ok());
+    generator.clone().compile_pdls(&pdls).unwrap();
+
+    // additionally write the bindings to a source tree, to check them in
+    if let Some(dir) = env::var_os("CDP_GENERATE_DIR") {
+        std::fs::create_dir_all(&dir).unwrap();
+        generator.out_dir(dir).compile_pdls(&pdls).unwrap();
+    }
+}
+
+/// The pdl file set via the env var `var` or the `file` of the protocol
+/// version selected by the `cdp-*` features
+fn protocol_path(var: &str, file: &str) -> PathBuf {
+    if let Some(path) = env::var_os(var) {
+        return PathBuf::from(path);
+    }
+    protocol_dir().join(file)
+}
+
+/// The directory of the bundled pdl files of the selected protocol version.
+///
+/// A pinned revision takes precedence over the tip-of-tree protocol, so that
+/// the features stay additive, like with `--all-features`.
+fn protocol_dir() -> PathBuf {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    if env::var_os("CARGO_FEATURE_CDP_R818858").is_some() {
+        return dir.join("protocol").join("r818858");
+    }
+    dir.to_path_buf()
 }
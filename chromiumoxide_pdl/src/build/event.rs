@@ -37,7 +37,9 @@ impl<'a> EventBuilder<'a> {
     pub fn build(self) -> TokenStream {
         let mut variants_stream = TokenStream::default();
         let mut var_idents = Vec::new();
+        let mut into_any = Vec::new();
         let mut deserialize_from_method = TokenStream::default();
+        let mut from_json = TokenStream::default();
 
         for event in &self.events {
             let var_ident = event.var_ident();
@@ -56,8 +58,11 @@ impl<'a> EventBuilder<'a> {
             let ty_qualifier = quote! {super::#protocol_mod::#domain_mod::#ty_ident};
 
             let ty_ident = if event.needs_box {
+                // boxed events coerce into `Box<dyn Any>` directly
+                into_any.push(quote! {inner});
                 quote! {Box<#ty_qualifier>}
             } else {
+                into_any.push(quote! {Box::new(inner)});
                 ty_qualifier.clone()
             };
 
@@ -67,10 +72,16 @@ impl<'a> EventBuilder<'a> {
             });
 
             let deserialize_from = if event.needs_box {
+                from_json.extend(quote! {
+                        #ty_qualifier::IDENTIFIER =>CdpEvent::#var_ident(Box::new(serde_json::from_value::<#ty_qualifier>(params)?)),
+                });
                 quote! {
                         #ty_qualifier::IDENTIFIER =>CdpEvent::#var_ident(Box::new(map.next_value::<#ty_qualifier>()?)),
                 }
             } else {
+                from_json.extend(quote! {
+                        #ty_qualifier::IDENTIFIER =>CdpEvent::#var_ident(serde_json::from_value::<#ty_qualifier>(params)?),
+                });
                 quote! {
                         #ty_qualifier::IDENTIFIER =>CdpEvent::#var_ident(map.next_value::<#ty_qualifier>()?),
                 }
@@ -112,12 +123,31 @@ impl<'a> EventBuilder<'a> {
             }
 
             impl CdpEvent {
+                /// Deserializes the `params` of the event with the `method`
+                /// identifier into its typed event. Events that are not part
+                /// of the protocol are kept as `CdpEvent::Other`.
+                pub fn from_json(method: &str, params: serde_json::Value) -> serde_json::Result<Self> {
+                    Ok(match method {
+                        #from_json
+                        _ => CdpEvent::Other(params)
+                    })
+                }
+
                 pub fn into_json(self) -> serde_json::Result<serde_json::Value> {
                     match self {
                         #(CdpEvent::#var_idents(inner) => serde_json::to_value(inner),)*
                          CdpEvent::Other(val) => Ok(val)
                     }
                 }
+
+                /// Erases the type of the event, the typed event can be
+                /// restored by downcasting to the event's type.
+                pub fn into_any(self) -> Box<dyn ::std::any::Any + Send + Sync> {
+                    match self {
+                        #(CdpEvent::#var_idents(inner) => #into_any,)*
+                         CdpEvent::Other(val) => Box::new(val)
+                    }
+                }
            }
            // #event_json serde.generate_event_json_support
         };
@@ -183,6 +213,7 @@ impl<'a> EventBuilder<'a> {
                             let mut method = None;
                             let mut session_id = None;
                             let mut params = None;
+                            let mut raw_params = None;
                             while let Some(key) = map.next_key()? {
                                 match key {
                                     Field::Method => {
@@ -201,17 +232,27 @@ impl<'a> EventBuilder<'a> {
                                         if params.is_some() {
                                             return Err(de::Error::duplicate_field("params"));
                                         }
-                                        params = Some(match method.as_ref().ok_or_else(|| de::Error::missing_field("params"))
-                                        ?.as_str() {
-                                            #deserialize_from_method
-                                            _=>CdpEvent::Other(map.next_value::<serde_json::Value>()?)
+                                        params = Some(match method.as_ref() {
+                                            Some(method) => match method.as_str() {
+                                                #deserialize_from_method
+                                                _=>CdpEvent::Other(map.next_value::<serde_json::Value>()?)
+                                            },
+                                            // the type of the params is only
+                                            // known once the method was read
+                                            None => {
+                                                raw_params = Some(map.next_value::<serde_json::Value>()?);
+                                                CdpEvent::Other(serde_json::Value::Null)
+                                            }
                                         });
                                     }
                                 }
                             }
 
                             let method = method.ok_or_else(|| de::Error::missing_field("method"))?;
-                            let params = params.ok_or_else(|| de::Error::missing_field("params"))?;
+                            let params = match raw_params {
+                                Some(raw) => CdpEvent::from_json(&method, raw).map_err(de::Error::custom)?,
+                                None => params.ok_or_else(|| de::Error::missing_field("params"))?,
+                            };
                             Ok(CdpEventMessage {
                                 method: ::std::borrow::Cow::Owned(method),
                                 session_id,
@@ -237,6 +278,18 @@ impl<'a> EventBuilder<'a> {
                 }
            }
 
+            impl std::convert::TryFrom<chromiumoxide_types::CdpJsonEventMessage> for CdpEventMessage {
+                type Error = serde_json::Error;
+
+                fn try_from(msg: chromiumoxide_types::CdpJsonEventMessage) -> Result<Self, Self::Error> {
+                    Ok(CdpEventMessage {
+                        params: CdpEvent::from_json(&msg.method, msg.params)?,
+                        method: msg.method,
+                        session_id: msg.session_id,
+                    })
+                }
+           }
+
         };
 
         quote! {
@@ -1,7 +1,7 @@
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::{self, Error, ErrorKind};
+use std::io::{self, Error};
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 
@@ -154,9 +154,7 @@ impl Generator {
     pub fn compile_pdls<P: AsRef<Path>>(&mut self, pdls: &[P]) -> io::Result<()> {
         let target: PathBuf = self.out_dir.clone().map(Ok).unwrap_or_else(|| {
             std::env::var_os("OUT_DIR")
-                .ok_or_else(|| {
-                    Error::new(ErrorKind::Other, "OUT_DIR environment variable is not set")
-                })
+                .ok_or_else(|| Error::other("OUT_DIR environment variable is not set"))
                 .map(Into::into)
         })?;
 
@@ -165,10 +163,7 @@ impl Generator {
         for path in pdls {
             let path = path.as_ref();
             let file_name = path.file_stem().ok_or_else(|| {
-                Error::new(
-                    ErrorKind::Other,
-                    format!("Failed to read file name for {}", path.display()),
-                )
+                Error::other(format!("Failed to read file name for {}", path.display()))
             })?;
             let mod_name = file_name.to_string_lossy().to_string();
             self.protocol_mods.push(mod_name);
@@ -179,7 +174,7 @@ impl Generator {
         let mut protocols = vec![];
 
         for (idx, input) in inputs.iter().enumerate() {
-            let pdl = parse_pdl(&input).map_err(|e| Error::new(ErrorKind::Other, e.message))?;
+            let pdl = parse_pdl(input).map_err(|e| Error::other(e.message))?;
 
             self.domains
                 .extend(pdl.domains.iter().map(|d| (d.name.to_string(), idx)));
@@ -220,7 +215,7 @@ impl Generator {
             let ref_size = *self
                 .type_size
                 .get(&reff)
-                .unwrap_or_else(|| panic!(format!("No type found for ref {}", reff)));
+                .unwrap_or_else(|| panic!("No type found for ref {}", reff));
             self.store_size(&name, Either::Left(ref_size));
         }
 
@@ -351,6 +346,18 @@ impl Generator {
                             Self::IDENTIFIER.into()
                         }
                     }
+
+                    impl chromiumoxide_types::MethodType for #name {
+                        fn method_id() -> ::std::borrow::Cow<'static, str> {
+                            Self::IDENTIFIER.into()
+                        }
+                    }
+                });
+            }
+
+            if dt.is_event() {
+                stream.extend(quote! {
+                    impl chromiumoxide_types::EventKind for #name {}
                 });
             }
 
@@ -403,7 +410,7 @@ impl Generator {
 
     /// Generates the struct definitions including enum definitions inner
     /// parameter enums
-    fn generate_struct<'a, T: 'a>(
+    fn generate_struct<'a, T>(
         &mut self,
         domain: &Domain,
         dt: &DomainDatatype,
@@ -457,7 +464,7 @@ impl Generator {
 
             builder
                 .fields
-                .push((field.generate_meta(&self.serde_support, &param), field));
+                .push((field.generate_meta(&self.serde_support, param), field));
         }
 
         let derives = if !builder.has_mandatory_types() {
@@ -510,9 +517,9 @@ impl Generator {
                             }
                         }
 
-                        impl Into<String> for #name {
-                            fn into(self) -> String {
-                                self.0
+                        impl From<#name> for String {
+                            fn from(el: #name) -> String {
+                                el.0
                             }
                         }
 
@@ -675,7 +682,7 @@ impl Generator {
                 let ty = if let Type::Ref(name) = ty.deref() {
                     self.projected_type(domain, name)
                 } else {
-                    let (ty, _) = self.generate_field_type(domain, parent, param_name, &*ty);
+                    let (ty, _) = self.generate_field_type(domain, parent, param_name, ty);
                     quote! {#ty}
                 };
                 (FieldType::new_vec(ty), Either::Left(size_of::<Vec<()>>()))
@@ -757,9 +764,10 @@ impl Generator {
                 .filter(|ev| self.with_deprecated || !ev.is_deprecated())
                 .filter(|ev| self.with_experimental || !ev.is_experimental())
             {
-                let domain_idx = self.domains.get(domain.name.as_ref()).unwrap_or_else(|| {
-                    panic!(format!("No matching domain registered for {}", domain.name))
-                });
+                let domain_idx = self
+                    .domains
+                    .get(domain.name.as_ref())
+                    .unwrap_or_else(|| panic!("No matching domain registered for {}", domain.name));
                 let protocol_mod = format_ident!("{}", self.protocol_mods[*domain_idx]);
 
                 let ev_name = format!("Event{}", event.name().to_camel_case());
@@ -767,7 +775,7 @@ impl Generator {
                 let size = *self
                     .type_size
                     .get(&ev_name)
-                    .unwrap_or_else(|| panic!(format!("No type found for ref {}", ev_name)));
+                    .unwrap_or_else(|| panic!("No type found for ref {}", ev_name));
 
                 // See https://rust-lang.github.io/rust-clippy/master/#large_enum_variant
                 // The maximum size of a enum’s variant to avoid box suggestion is 200
@@ -851,9 +859,10 @@ fn subenum_name(parent: &str, inner: &str) -> String {
     format!("{}{}", parent.to_camel_case(), generate_type_name(inner))
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub enum SerdeSupport {
     None,
+    #[default]
     Default,
     Feature(String),
 }
@@ -981,12 +990,6 @@ impl SerdeSupport {
     }
 }
 
-impl Default for SerdeSupport {
-    fn default() -> Self {
-        SerdeSupport::Default
-    }
-}
-
 pub fn fmt(out_dir: impl AsRef<Path>) {
     use std::io::Write;
     use std::process::{exit, Command};
@@ -1029,9 +1032,11 @@ mod tests {
 
     #[test]
     fn test_serde_import() {
-        let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../chromiumoxide_cdp");
+        let out_dir = std::env::temp_dir().join("chromiumoxide_pdl_serde_import");
+        fs::create_dir_all(&out_dir).unwrap();
         Generator::default()
-            .out_dir(dir.join("src"))
+            .out_dir(&out_dir)
             .serde(SerdeSupport::with_feature("serde0"))
             .compile_pdls(&[
                 dir.join("js_protocol.pdl"),
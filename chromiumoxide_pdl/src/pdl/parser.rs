@@ -10,7 +10,10 @@ use std::fmt;
 macro_rules! regex {
     ($re:literal $(,)?) => {{
         static RE: once_cell::sync::OnceCell<regex::Regex> = once_cell::sync::OnceCell::new();
-        RE.get_or_init(|| regex::Regex::new($re).unwrap())
+        // the regex is only compiled once, on first use
+        #[allow(clippy::regex_creation_in_loops)]
+        let re = RE.get_or_init(|| regex::Regex::new($re).unwrap());
+        re
     }};
 }
 
@@ -60,7 +63,7 @@ macro_rules! borrowed {
 /// Rewrite of the Python script from the Chromium source tree.
 ///
 ///  See: https://chromium.googlesource.com/deps/inspector_protocol/+/refs/heads/master/pdl.py
-pub fn parse_pdl(input: &str) -> Result<Protocol, Error> {
+pub fn parse_pdl(input: &str) -> Result<Protocol<'_>, Error> {
     let mut protocol = Protocol::default();
     let mut description: Option<String> = None;
     let mut version = None;
@@ -140,6 +140,18 @@ pub trait Method {
     }
 }
 
+/// A `Method` whose identifier is known without an instance of the type.
+pub trait MethodType {
+    /// The whole string identifier for this method like: `DOM.removeNode`
+    fn method_id() -> Cow<'static, str>
+    where
+        Self: Sized;
+}
+
+/// Trait that all the typed events emitted by the chromium instance, like
+/// `Page.loadEventFired`, implement.
+pub trait EventKind: MethodType + fmt::Debug + Send + Sync + 'static {}
+
 /// A Wrapper for json serialized requests
 #[derive(Deserialize, Debug, PartialEq, Clone)]
 pub struct Request {
@@ -246,9 +258,9 @@ impl AsRef<[u8]> for Binary {
     }
 }
 
-impl Into<String> for Binary {
-    fn into(self) -> String {
-        self.0
+impl From<Binary> for String {
+    fn from(b: Binary) -> String {
+        b.0
     }
 }
 
@@ -0,0 +1,127 @@
+//! Functions exposed to the page via `Runtime.addBinding`.
+//!
+//! The binding itself can only send a string to chromiumoxide, so it is
+//! wrapped in a function that returns a promise, like puppeteer's
+//! [`exposeFunction`](https://github.com/puppeteer/puppeteer/blob/main/src/common/helper.ts).
+//! The arguments of a call are serialized as JSON and the result of the rust
+//! function is delivered back to the promise by evaluating a script in the
+//! execution context of the caller.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::Value;
+
+use chromiumoxide_cdp::cdp::js_protocol::runtime::{
+    EvaluateParams, EventBindingCalled, ExecutionContextId,
+};
+
+use crate::handler::PageInner;
+use crate::listeners::EventStream;
+use crate::utils;
+
+/// Replaces the binding `name` with a function that returns a promise which
+/// resolves once the result of the call is delivered.
+const ADD_PAGE_BINDING: &str = r#"(function addPageBinding(name) {
+    const binding = window[name];
+    window[name] = (...args) => {
+        const me = window[name];
+        let callbacks = me.callbacks;
+        if (!callbacks) {
+            callbacks = new Map();
+            me.callbacks = callbacks;
+        }
+        const seq = (me.lastSeq || 0) + 1;
+        me.lastSeq = seq;
+        const promise = new Promise((resolve, reject) => callbacks.set(seq, { resolve, reject }));
+        binding(JSON.stringify({ name, seq, args }));
+        return promise;
+    };
+})"#;
+
+/// Resolves or rejects the promise of the call `seq`
+const DELIVER_RESULT: &str = r#"(function deliverResult(name, seq, result, error) {
+    const callbacks = window[name].callbacks;
+    const callback = callbacks.get(seq);
+    callbacks.delete(seq);
+    if (error !== null) {
+        callback.reject(new Error(error));
+    } else {
+        callback.resolve(result);
+    }
+})"#;
+
+/// The script that wraps the binding `name`
+pub(crate) fn binding_script(name: &str) -> String {
+    format!("{}({})", ADD_PAGE_BINDING, Value::from(name))
+}
+
+/// The payload the wrapper sends via the binding
+#[derive(Debug, Deserialize)]
+struct BindingPayload {
+    name: String,
+    seq: u64,
+    args: Vec<Value>,
+}
+
+/// Calls `function` for every call of the binding `name` in the page until the
+/// page is closed.
+pub(crate) fn serve<F, Fut>(
+    page: Arc<PageInner>,
+    name: String,
+    mut calls: EventStream<EventBindingCalled>,
+    function: F,
+) where
+    F: Fn(Vec<Value>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = std::result::Result<Value, String>> + Send + 'static,
+{
+    let function = Arc::new(function);
+    utils::spawn(async move {
+        while let Some(call) = calls.next().await {
+            if call.name != name {
+                continue;
+            }
+            let payload = match serde_json::from_str::<BindingPayload>(&call.payload) {
+                Ok(payload) if payload.name == name => payload,
+                // not sent by the wrapper
+                _ => continue,
+            };
+            let page = Arc::clone(&page);
+            let function = Arc::clone(&function);
+            let context_id = call.execution_context_id;
+            utils::spawn(async move {
+                let result = function(payload.args).await;
+                deliver_result(&page, context_id, &payload.name, payload.seq, result).await;
+            });
+        }
+    });
+}
+
+/// Settles the promise of the call in the page
+async fn deliver_result(
+    page: &PageInner,
+    context_id: ExecutionContextId,
+    name: &str,
+    seq: u64,
+    result: std::result::Result<Value, String>,
+) {
+    let (result, error) = match result {
+        Ok(value) => (value, Value::Null),
+        Err(err) => (Value::Null, Value::from(err)),
+    };
+    let mut params = EvaluateParams::new(format!(
+        "{}({}, {}, {}, {})",
+        DELIVER_RESULT,
+        Value::from(name),
+        seq,
+        result,
+        error
+    ));
+    params.context_id = Some(context_id);
+    if let Err(err) = page.execute(params).await {
+        // the execution context may be gone already
+        log::debug!("Failed to deliver the result of binding {}: {}", name, err);
+    }
+}
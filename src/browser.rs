@@ -1,24 +1,64 @@
-use std::time::Duration;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
 use std::{
     collections::HashMap,
-    io::{self, BufRead, BufReader},
+    fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
     path::{Path, PathBuf},
-    process::{self, Child, Stdio},
+    process::{self, Child, ExitStatus, Stdio},
+    sync::{Arc, Mutex},
 };
 
-use futures::channel::mpsc::{channel, Sender};
+use futures::channel::mpsc::{channel, unbounded, Sender, UnboundedReceiver};
 use futures::channel::oneshot::channel as oneshot_channel;
-use futures::SinkExt;
+use futures::task::{Context, Poll};
+use futures::{Future, SinkExt, Stream};
+use futures_timer::Delay;
+use tempfile::TempDir;
+use url::Url;
 
-use chromiumoxide_cdp::cdp::browser_protocol::target::CreateTargetParams;
+use chromiumoxide_cdp::cdp::browser_protocol::browser::{
+    Bounds, BrowserContextId, CloseParams, GetVersionParams, GetVersionReturns,
+    GetWindowForTargetParams, GetWindowForTargetReturns, GrantPermissionsParams,
+    ResetPermissionsParams, SetWindowBoundsParams,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::io::StreamHandle;
+use chromiumoxide_cdp::cdp::browser_protocol::target::{
+    AttachToTargetParams, CloseTargetParams, CreateBrowserContextParams, CreateTargetParams,
+    DisposeBrowserContextParams, EventTargetCrashed, EventTargetCreated, EventTargetDestroyed,
+    EventTargetInfoChanged, GetTargetsParams, SessionId, TargetId, TargetInfo,
+};
 use chromiumoxide_cdp::cdp::CdpEventMessage;
 use chromiumoxide_types::*;
 
-use crate::cmd::{to_command_response, CommandMessage};
-use crate::conn::Connection;
+use crate::cmd::{to_command_response, to_raw_response, CommandMessage};
+use crate::conn::{ConnectOptions, Connection, ConnectionEvent};
 use crate::error::{CdpError, Result};
-use crate::handler::{Handler, HandlerMessage};
+use crate::extension::ExtensionTarget;
+use crate::handler::domains::DomainConfig;
+use crate::handler::network::Credentials;
+use crate::handler::viewport::Viewport;
+use crate::handler::{
+    BrowserProcess, Handler, HandlerConfig, HandlerMessage, HandlerStats, JoinHandle,
+    CHANNEL_CAPACITY, REQUEST_TIMEOUT,
+};
+use crate::io_stream::IoStream;
+use crate::listeners::{EventListenerRequest, EventStream};
 use crate::page::Page;
+use crate::pipe;
+use crate::pool::{BrowserPool, PoolConfig};
+use crate::retry::{self, RetryPolicy};
+use crate::storage::Storage;
+use crate::tracing::Tracing;
+use crate::utils;
+use crate::wire_log::WireLogConfig;
+use crate::worker::Worker;
+
+pub use chromiumoxide_cdp::cdp::browser_protocol::browser::PermissionType as Permission;
+
+/// Default timeout in milliseconds to wait for a launched browser
+pub const LAUNCH_TIMEOUT: u64 = 20_000;
 
 /// A [`Browser`] is created when chromiumoxide connects to a Chromium instance.
 #[derive(Debug)]
@@ -29,77 +69,322 @@ pub struct Browser {
     /// How the spawned chromium instance was configured, if any
     config: Option<BrowserConfig>,
     /// The spawned chromium instance
-    child: Option<Child>,
+    child: Option<BrowserProcess>,
     /// The debug web socket url of the chromium instance
     debug_ws_url: String,
+    /// The temporary user data dir of the spawned chromium instance, deleted
+    /// once the instance exited
+    temp_user_data_dir: Option<TempDir>,
+    /// How browser commands are retried that fail with a transient error
+    retry_policy: Option<RetryPolicy>,
 }
 
 impl Browser {
     /// Connect to an already running chromium instance via websocket
+    ///
+    /// Besides the websocket url of the browser,
+    /// `ws://host:port/devtools/browser/<id>`, this accepts the address of
+    /// its debugging endpoint, `http://host:port` or `host:port`, and the
+    /// websocket url of one of its pages. The websocket url of the browser is
+    /// then requested from the `/json/version` endpoint.
     pub async fn connect(debug_ws_url: impl Into<String>) -> Result<(Self, Handler)> {
-        let debug_ws_url = debug_ws_url.into();
-        let conn = Connection::<CdpEventMessage>::connect(&debug_ws_url).await?;
+        Self::connect_with_config(debug_ws_url, HandlerConfig::default()).await
+    }
+
+    /// Connect to an already running chromium instance via websocket with a
+    /// custom configuration of the `Handler`, like reconnecting
+    ///
+    /// See [`Browser::connect`] for the supported urls.
+    pub async fn connect_with_config(
+        debug_ws_url: impl Into<String>,
+        config: HandlerConfig,
+    ) -> Result<(Self, Handler)> {
+        let debug_ws_url = resolve_ws_url(debug_ws_url.into(), &config.connect_options).await?;
+        let conn = Connection::<CdpEventMessage>::connect_with_options(
+            &debug_ws_url,
+            config.connect_options.clone(),
+        )
+        .await?;
 
-        let (tx, rx) = channel(1);
+        let (tx, rx) = channel(config.channel_capacity);
 
-        let fut = Handler::new(conn, rx);
+        let retry_policy = config.retry_policy.clone();
+        let fut = Handler::new(conn, rx, config);
         let browser = Self {
             sender: tx,
             config: None,
             child: None,
             debug_ws_url,
+            temp_user_data_dir: None,
+            retry_policy,
         };
         Ok((browser, fut))
     }
 
-    /// Launches a new instance of `chromium` in the background and attaches to
-    /// its debug web socket.
+    /// Connect to an already running chromium instance via websocket, with
+    /// additional headers of the handshake request, through a proxy or with
+    /// custom TLS settings for `wss://` urls.
+    ///
+    /// The options also apply to the request of the `/json/version` endpoint,
+    /// see [`Browser::connect`] for the supported urls and [`ConnectOptions`]
+    /// for an example.
+    pub async fn connect_with_options(
+        debug_ws_url: impl Into<String>,
+        options: ConnectOptions,
+    ) -> Result<(Self, Handler)> {
+        let config = HandlerConfig {
+            connect_options: options,
+            ..Default::default()
+        };
+        Self::connect_with_config(debug_ws_url, config).await
+    }
+
+    /// Connect to the browser of the page target with the websocket url
+    /// `ws://host:port/devtools/page/<id>` and attach to that page.
+    ///
+    /// The returned future resolves to the page once the `Handler` is polled.
     ///
-    /// This fails when no chromium executable could be detected.
+    /// # Example
     ///
-    /// This fails if no web socket url could be detected from the child
-    /// processes stderr for more than 20 seconds.
-    pub async fn launch(config: BrowserConfig) -> Result<(Self, Handler)> {
-        // launch a new chromium instance
-        let mut child = config.launch()?;
+    /// ```no_run
+    /// # use chromiumoxide::browser::Browser;
+    /// # use chromiumoxide::error::Result;
+    /// # use futures::StreamExt;
+    /// # async fn demo(url: &str) -> Result<()> {
+    ///     let (browser, mut handler, page) = Browser::connect_to_page(url).await?;
+    ///     async_std::task::spawn(async move {
+    ///         while let Some(_) = handler.next().await {}
+    ///     });
+    ///     let page = page.await?;
+    ///     println!("connected to {:?}", page.url().await?);
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect_to_page(
+        page_ws_url: impl Into<String>,
+    ) -> Result<(Self, Handler, impl Future<Output = Result<Page>>)> {
+        let page_ws_url = page_ws_url.into();
+        let target_id = page_ws_url
+            .split(PAGE_WS_PATH)
+            .nth(1)
+            .map(|id| TargetId::from(id.to_string()))
+            .ok_or_else(|| {
+                CdpError::msg(format!("Not the websocket url of a page: {}", page_ws_url))
+            })?;
+        let (browser, handler) = Self::connect(page_ws_url).await?;
+        // queued until the handler is polled
+        let (tx, rx) = oneshot_channel();
+        browser
+            .sender
+            .clone()
+            .send(HandlerMessage::AttachToTarget(target_id, tx))
+            .await?;
+        Ok((browser, handler, async move { rx.await? }))
+    }
 
-        // extract the ws:
-        let get_ws_url = ws_url_from_output(&mut child);
+    /// Launches a headless chromium with the default [`BrowserConfig`] and
+    /// drives its [`Handler`] in the background, see [`Handler::spawn`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::browser::Browser;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo() -> Result<()> {
+    ///     let (browser, _handle) = Browser::launch_default().await?;
+    ///     let page = browser.new_page("https://example.com").await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn launch_default() -> Result<(Self, JoinHandle)> {
+        Self::launch_spawned(BrowserConfig::builder()).await
+    }
 
-        let dur = Duration::from_secs(20);
+    /// Like [`Browser::launch_default`], but shows the browser window
+    pub async fn launch_headful() -> Result<(Self, JoinHandle)> {
+        Self::launch_spawned(BrowserConfig::builder().with_head()).await
+    }
 
-        cfg_if::cfg_if! {
-            if #[cfg(feature = "async-std-runtime")] {
-                let debug_ws_url = async_std::future::timeout(dur, get_ws_url)
-            .await
-            .map_err(|_| CdpError::Timeout)?;
-            } else if #[cfg(feature = "tokio-runtime")] {
-                let debug_ws_url = tokio::time::timeout(dur, get_ws_url).await
-            .map_err(|_| CdpError::Timeout)?;
-            }
+    async fn launch_spawned(config: BrowserConfigBuilder) -> Result<(Self, JoinHandle)> {
+        let config = config.build().map_err(CdpError::msg)?;
+        let (browser, handler) = Self::launch(config).await?;
+        Ok((browser, handler.spawn()))
+    }
+
+    /// Launches a new instance of `chromium` in the background and attaches to
+    /// its debug web socket.
+    ///
+    /// This fails when no chromium executable could be detected. With the
+    /// `fetcher` feature, the pinned chromium revision is downloaded in the
+    /// background instead, unless it was downloaded already.
+    ///
+    /// This fails with [`CdpError::LaunchTimeout`] if no web socket url could
+    /// be detected within the configured launch timeout (20 seconds by
+    /// default), or with [`CdpError::LaunchExit`] if the process exits before.
+    ///
+    /// If no user data dir is configured, the browser uses a new temporary
+    /// one that is deleted again once the browser is closed or dropped. With
+    /// a template profile, the template is copied into that temporary dir
+    /// first.
+    pub async fn launch(mut config: BrowserConfig) -> Result<(Self, Handler)> {
+        if config.executable.is_none() {
+            let executable = utils::spawn_blocking(fetch_executable)
+                .await
+                .map_err(CdpError::msg)?;
+            config.executable = Some(executable);
         }
+        let temp_user_data_dir = if let Some(template) = config.template_profile.clone() {
+            let dir = tempfile::Builder::new()
+                .prefix("chromiumoxide-")
+                .tempdir()?;
+            let profile = dir.path().to_path_buf();
+            utils::spawn_blocking(move || copy_profile(&template, &profile)).await?;
+            config.user_data_dir = Some(dir.path().to_path_buf());
+            Some(dir)
+        } else if config.user_data_dir.is_none() {
+            let dir = tempfile::Builder::new()
+                .prefix("chromiumoxide-")
+                .tempdir()?;
+            config.user_data_dir = Some(dir.path().to_path_buf());
+            Some(dir)
+        } else {
+            None
+        };
+        let (child, conn, debug_ws_url) = if config.pipe {
+            let (mut child, pipe) = pipe::spawn(&mut config.command()?)?;
+            // keep draining the output so that the process doesn't block
+            let (tx, _) = std::sync::mpsc::channel();
+            let output = Arc::new(Mutex::new(Some(String::new())));
+            if let Some(stdout) = child.stdout.take() {
+                read_process_output(
+                    stdout,
+                    Arc::clone(&output),
+                    tx.clone(),
+                    config.log_chrome_output,
+                );
+            }
+            if let Some(stderr) = child.stderr.take() {
+                read_process_output(stderr, Arc::clone(&output), tx, config.log_chrome_output);
+            }
+            // there is no websocket url to wait for, a browser that dies
+            // later is detected by the `Handler`
+            let status = child.try_wait()?;
+            // stop capturing the output
+            let captured = output.lock().ok().and_then(|mut output| output.take());
+            if let Some(status) = status {
+                return Err(CdpError::LaunchExit(status, captured.unwrap_or_default()));
+            }
+            (
+                child,
+                Connection::<CdpEventMessage>::pipe(pipe),
+                String::new(),
+            )
+        } else {
+            if config.port == 0 {
+                config.port = free_port()?;
+            }
+
+            // launch a new chromium instance
+            let child = config.launch()?;
 
-        let conn = Connection::<CdpEventMessage>::connect(&debug_ws_url).await?;
+            // extract the ws:
+            let (mut child, debug_ws_url) = ws_url_from_output(child, &config).await;
+            let debug_ws_url = match debug_ws_url {
+                Ok(url) => url,
+                Err(err) => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(err);
+                }
+            };
+            let conn = Connection::<CdpEventMessage>::connect(&debug_ws_url).await?;
+            (child, conn, debug_ws_url)
+        };
 
-        let (tx, rx) = channel(1);
+        let (tx, rx) = channel(config.channel_capacity);
 
-        let fut = Handler::new(conn, rx);
+        let handler_config = HandlerConfig {
+            proxy_credentials: config.proxy_credentials.clone(),
+            request_timeout: config.request_timeout,
+            reconnect_attempts: config.reconnect_attempts,
+            reconnect_interval: config.reconnect_interval,
+            keep_alive_interval: config.keep_alive_interval,
+            channel_capacity: config.channel_capacity,
+            ignore_https_errors: config.ignore_https_errors.unwrap_or(true),
+            user_agent: None,
+            max_commands_in_flight: config.max_commands_in_flight,
+            max_commands_in_flight_per_session: config.max_commands_in_flight_per_session,
+            viewport: config.viewport.clone(),
+            retry_policy: config.retry_policy.clone(),
+            event_replay_capacity: config.event_replay_capacity,
+            wire_log: config.wire_log.clone(),
+            connect_options: Default::default(),
+            domains: config.domains,
+            #[cfg(feature = "stealth")]
+            stealth: config.stealth,
+        };
+        #[cfg(feature = "stealth")]
+        let handler_config = if config.stealth {
+            HandlerConfig {
+                user_agent: browser_user_agent(&debug_ws_url)
+                    .await
+                    .map(|ua| crate::stealth::user_agent(&ua)),
+                ..handler_config
+            }
+        } else {
+            handler_config
+        };
+        let mut fut = Handler::new(conn, rx, handler_config);
+        let child = BrowserProcess::new(child);
+        fut.set_process(child.clone());
 
         let browser = Self {
             sender: tx,
+            retry_policy: config.retry_policy.clone(),
             config: Some(config),
             child: Some(child),
             debug_ws_url,
+            temp_user_data_dir,
         };
 
         Ok((browser, fut))
     }
 
-    /// Returns the address of the websocket this browser is attached to
+    /// The process of the chromium instance, if it was launched by this
+    /// `Browser` and not connected to
+    pub fn process(&self) -> Option<&BrowserProcess> {
+        self.child.as_ref()
+    }
+
+    /// Returns the address of the websocket this browser is attached to, empty
+    /// if it was launched with `BrowserConfigBuilder::pipe`
     pub fn websocket_address(&self) -> &String {
         &self.debug_ws_url
     }
 
+    /// The debugging port of the chromium instance
+    pub fn port(&self) -> Option<u16> {
+        Url::parse(&self.debug_ws_url).ok()?.port_or_known_default()
+    }
+
+    /// The address of the http endpoints of the chromium instance, like
+    /// `http://127.0.0.1:9222`, so that other tools can attach to the same
+    /// instance, e.g. via its `/json/version` endpoint
+    pub fn debug_http_url(&self) -> Option<String> {
+        let url = Url::parse(&self.debug_ws_url).ok()?;
+        let scheme = if url.scheme() == "wss" {
+            "https"
+        } else {
+            "http"
+        };
+        Some(format!(
+            "{}://{}:{}",
+            scheme,
+            url.host_str()?,
+            url.port_or_known_default()?
+        ))
+    }
+
     /// The config of the spawned chromium instance if any.
     pub fn config(&self) -> Option<&BrowserConfig> {
         self.config.as_ref()
@@ -125,70 +410,1004 @@ impl Browser {
 
     /// Call a browser method.
     pub async fn execute<T: Command>(&self, cmd: T) -> Result<CommandResponse<T::Response>> {
+        self.execute_command(cmd, None).await
+    }
+
+    /// Call any method of the devtools protocol outside of a page's session
+    /// and return its raw result.
+    ///
+    /// This is useful for methods that are not generated for the protocol
+    /// version chromiumoxide is built with.
+    pub async fn execute_raw(
+        &self,
+        method: impl Into<String>,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
         let (tx, rx) = oneshot_channel();
+        let method = method.into();
+        let msg = CommandMessage::raw(method.clone(), params, tx, None);
+        self.sender
+            .clone()
+            .send(HandlerMessage::Command(msg))
+            .await?;
+        to_raw_response(rx.await??, &method)
+    }
+
+    /// Call a browser method, fails with [`CdpError::Timeout`] if chromium
+    /// doesn't respond within `timeout`.
+    ///
+    /// This overrides the request timeout of the `Handler` for this command.
+    pub async fn execute_with_timeout<T: Command>(
+        &self,
+        cmd: T,
+        timeout: Duration,
+    ) -> Result<CommandResponse<T::Response>> {
+        self.execute_command(cmd, Some(timeout)).await
+    }
+
+    async fn execute_command<T: Command>(
+        &self,
+        cmd: T,
+        timeout: Option<Duration>,
+    ) -> Result<CommandResponse<T::Response>> {
         let method = cmd.identifier();
-        let msg = CommandMessage::new(cmd, tx)?;
+        let params = serde_json::to_value(cmd)?;
+        retry::with_retries(self.retry_policy.as_ref(), || {
+            let (tx, rx) = oneshot_channel();
+            let msg =
+                CommandMessage::raw(method.clone(), params.clone(), tx, None).with_timeout(timeout);
+            let mut sender = self.sender.clone();
+            let method = method.clone();
+            async move {
+                sender.send(HandlerMessage::Command(msg)).await?;
+                let resp = rx.await??;
+                to_command_response::<T>(resp, method)
+            }
+        })
+        .await
+    }
+
+    /// Retry browser commands that fail with a transient error of chromium
+    /// according to the `policy`, or never if `None`.
+    ///
+    /// This doesn't affect pages, see `Page::set_retry_policy`.
+    pub fn set_retry_policy(&mut self, policy: Option<RetryPolicy>) -> &mut Self {
+        self.retry_policy = policy;
+        self
+    }
 
+    /// Returns a stream over all the events of type `T` that are not emitted
+    /// within the session of a page, like `Target.targetCreated`.
+    ///
+    /// To listen for events of a specific page see `Page::event_listener`.
+    pub async fn event_listener<T: EventKind>(&self) -> Result<EventStream<T>> {
+        let (tx, rx) = unbounded();
         self.sender
             .clone()
-            .send(HandlerMessage::Command(msg))
+            .send(HandlerMessage::AddEventListener(
+                EventListenerRequest::new::<T>(tx),
+            ))
             .await?;
-        let resp = rx.await??;
-        to_command_response::<T>(resp, method)
+        Ok(EventStream::new(rx))
+    }
+
+    /// Returns a stream over the browser wide events of type `T` that starts
+    /// with up to `replay` of the most recent events that fired before
+    /// subscribing, see `Page::subscribe_with_replay`.
+    pub async fn subscribe_with_replay<T: EventKind>(
+        &self,
+        replay: usize,
+    ) -> Result<EventStream<T>> {
+        let (tx, rx) = unbounded();
+        self.sender
+            .clone()
+            .send(HandlerMessage::AddEventListener(
+                EventListenerRequest::with_replay::<T>(tx, replay),
+            ))
+            .await?;
+        Ok(EventStream::new(rx))
+    }
+
+    /// Returns a stream of the changes of the connection to the browser, like
+    /// disconnects and reconnects.
+    ///
+    /// Once the connection is lost, requests that are still awaiting their
+    /// response fail with [`CdpError::Disconnected`]. If reconnecting is
+    /// enabled, the `Handler` tries to re-establish the connection to the same
+    /// websocket url and attaches to the targets again.
+    pub async fn connection_events(&self) -> Result<ConnectionEvents> {
+        Ok(ConnectionEvents {
+            events: self.connection_listener().await?,
+        })
+    }
+
+    async fn connection_listener(&self) -> Result<UnboundedReceiver<ConnectionEvent>> {
+        let (tx, rx) = unbounded();
+        self.sender
+            .clone()
+            .send(HandlerMessage::AddConnectionListener(tx))
+            .await?;
+        Ok(rx)
     }
 
-    /// Return all of the pages of the browser
+    /// A stream of the browser's session, like a trace
+    pub(crate) fn io_stream(&self, handle: StreamHandle) -> IoStream {
+        IoStream::browser(self.sender.clone(), handle)
+    }
+
+    /// Returns a pool of pages of this browser that are reused for many
+    /// tasks, see [`BrowserPool`].
+    pub fn pool(&self, config: PoolConfig) -> BrowserPool {
+        BrowserPool::new(self.sender.clone(), config)
+    }
+
+    /// Returns a handle to record a performance trace of the browser
+    pub fn tracing(&self) -> Tracing<'_> {
+        Tracing::new(self)
+    }
+
+    /// Returns a stream over the lifecycle events of all the targets of the
+    /// browser, like new tabs and popups that were opened.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::browser::{Browser, BrowserEvent};
+    /// # use chromiumoxide::error::Result;
+    /// # use futures::StreamExt;
+    /// # async fn demo(browser: Browser) -> Result<()> {
+    ///     let mut events = browser.target_events().await?;
+    ///     while let Some(event) = events.next().await {
+    ///         if let BrowserEvent::TargetCreated(info) = event {
+    ///             println!("new target: {}", info.url);
+    ///         }
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn target_events(&self) -> Result<BrowserEvents> {
+        Ok(BrowserEvents {
+            created: self.event_listener().await?,
+            destroyed: self.event_listener().await?,
+            info_changed: self.event_listener().await?,
+            crashed: self.event_listener().await?,
+            connection: self.connection_listener().await?,
+        })
+    }
+
+    /// Return all of the pages of the browser, including the tabs that were
+    /// already open when connecting to the browser.
     pub async fn pages(&self) -> Result<Vec<Page>> {
+        // ask the browser, the `Handler` may not know about all the targets
+        // right after connecting
+        let targets = self.execute(GetTargetsParams::default()).await?;
+        let mut pages = Vec::new();
+        for info in targets.result.target_infos {
+            if info.r#type == "page" {
+                pages.push(self.attach_to_target(info.target_id).await?);
+            }
+        }
+        Ok(pages)
+    }
+
+    /// Returns the info of all the targets of the browser that are currently
+    /// open, like pages, iframes and workers.
+    ///
+    /// The targets are tracked by the `Handler` as they are created, changed
+    /// and destroyed.
+    pub async fn targets(&self) -> Result<Vec<TargetInfo>> {
+        let (tx, rx) = oneshot_channel();
+        self.sender
+            .clone()
+            .send(HandlerMessage::GetTargets(tx))
+            .await?;
+        Ok(rx.await?)
+    }
+
+    /// Returns the bookkeeping of the `Handler`, like how many commands await
+    /// their response.
+    ///
+    /// A growing number of pending commands or responses to unknown commands
+    /// hint at a connection that drops responses.
+    pub async fn handler_stats(&self) -> Result<HandlerStats> {
+        let (tx, rx) = oneshot_channel();
+        self.sender
+            .clone()
+            .send(HandlerMessage::GetStats(tx))
+            .await?;
+        Ok(rx.await?)
+    }
+
+    /// Returns the version information of the browser, like its product,
+    /// `HeadlessChrome/89.0.4389.0`, and the version of the protocol
+    pub async fn version(&self) -> Result<GetVersionReturns> {
+        Ok(self.execute(GetVersionParams::default()).await?.result)
+    }
+
+    /// Returns the version of the devtools protocol the browser speaks, like
+    /// `1.3`, see also [`Page::supports_domain`]
+    pub async fn protocol_version(&self) -> Result<String> {
+        Ok(self.version().await?.protocol_version)
+    }
+
+    /// Checks that the browser still responds, by round-tripping
+    /// `Browser.getVersion` within the `timeout`, and returns how long it
+    /// took.
+    ///
+    /// A browser whose process hangs fails with `CdpError::Timeout`, one
+    /// whose process exited fails with `CdpError::BrowserCrashed`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use chromiumoxide::browser::Browser;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(browser: Browser) -> Result<()> {
+    ///     if browser.health_check(Duration::from_secs(5)).await.is_err() {
+    ///         // replace the browser
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn health_check(&self, timeout: Duration) -> Result<Duration> {
+        let start = Instant::now();
+        self.execute_with_timeout(GetVersionParams::default(), timeout)
+            .await?;
+        Ok(start.elapsed())
+    }
+
+    /// Like [`Browser::health_check`], but additionally creates and closes a
+    /// blank page, which also checks that the browser can still spawn
+    /// renderers and warms up a freshly launched browser. All of it has to
+    /// complete within the `timeout`.
+    pub async fn warm_up(&self, timeout: Duration) -> Result<Duration> {
+        let start = Instant::now();
+        // the page that still needs to be closed
+        let mut unclosed = None;
+        let res = utils::timeout(Some(timeout), async {
+            self.execute(GetVersionParams::default()).await?;
+            let page = self.new_page("about:blank").await?;
+            unclosed = Some(page.target_id().clone());
+            self.execute(CloseTargetParams::new(page.target_id().clone()))
+                .await?;
+            unclosed = None;
+            Ok(())
+        })
+        .await;
+        if let Some(target_id) = unclosed {
+            let _ = self.execute(CloseTargetParams::new(target_id)).await;
+        }
+        res?;
+        Ok(start.elapsed())
+    }
+
+    /// Returns the default user agent of the browser
+    pub async fn user_agent(&self) -> Result<String> {
+        Ok(self.version().await?.user_agent)
+    }
+
+    /// Returns the position, size and state of the window the `page` is
+    /// shown in
+    pub async fn window_bounds(&self, page: &Page) -> Result<Bounds> {
+        Ok(self.window_for(page).await?.bounds)
+    }
+
+    /// Moves, resizes, minimizes or maximizes the window the `page` is shown
+    /// in.
+    ///
+    /// The position and size can only be set together with the `Normal`
+    /// window state.
+    pub async fn set_window_bounds(&self, page: &Page, bounds: Bounds) -> Result<&Self> {
+        let window_id = self.window_for(page).await?.window_id;
+        self.execute(SetWindowBoundsParams::new(window_id, bounds))
+            .await?;
+        Ok(self)
+    }
+
+    /// Activates the tab of the `page` and focuses its window
+    pub async fn bring_to_front(&self, page: &Page) -> Result<&Self> {
+        page.bring_to_front().await?;
+        Ok(self)
+    }
+
+    /// The window the `page` is shown in
+    async fn window_for(&self, page: &Page) -> Result<GetWindowForTargetReturns> {
+        let params = GetWindowForTargetParams::builder()
+            .target_id(page.target_id().clone())
+            .build();
+        Ok(self.execute(params).await?.result)
+    }
+
+    /// Returns the `Page` of the page target with the `target_id`, like a tab
+    /// that was opened before connecting to the browser.
+    ///
+    /// Attaches to the target if it is not attached already.
+    pub async fn attach_to_target(&self, target_id: TargetId) -> Result<Page> {
+        let (tx, rx) = oneshot_channel();
+        self.sender
+            .clone()
+            .send(HandlerMessage::AttachToTarget(target_id, tx))
+            .await?;
+        rx.await?
+    }
+
+    /// Returns the dedicated workers spawned by the pages of the browser.
+    ///
+    /// Pages attach to the workers they spawn automatically, via
+    /// `Target.setAutoAttach`.
+    pub async fn workers(&self) -> Result<Vec<Worker>> {
+        Ok(self
+            .attached_workers()
+            .await?
+            .into_iter()
+            .filter(|(_, info)| info.r#type == "worker")
+            .map(|(session_id, info)| Worker::new(info, session_id, self.sender.clone()))
+            .collect())
+    }
+
+    /// Returns the service workers registered in the browser.
+    ///
+    /// Attaches to the service workers that are not attached already.
+    pub async fn service_workers(&self) -> Result<Vec<Worker>> {
+        self.attach_workers(|info| info.r#type == "service_worker")
+            .await
+    }
+
+    /// Returns the background pages and service workers of the extensions
+    /// loaded into the browser, see `BrowserConfigBuilder::extension`.
+    ///
+    /// Attaches to the targets that are not attached already.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::browser::Browser;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(browser: Browser) -> Result<()> {
+    ///     for extension in browser.extension_targets().await? {
+    ///         let version = extension
+    ///             .evaluate("chrome.runtime.getManifest().version")
+    ///             .await?;
+    ///         println!("{}: {:?}", extension.extension_id(), version.value);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn extension_targets(&self) -> Result<Vec<ExtensionTarget>> {
+        Ok(self
+            .attach_workers(ExtensionTarget::is_extension_target)
+            .await?
+            .into_iter()
+            .filter_map(ExtensionTarget::new)
+            .collect())
+    }
+
+    /// Returns the targets that match the `filter` as `Worker`, attaches to
+    /// those that are not attached already
+    async fn attach_workers(&self, filter: impl Fn(&TargetInfo) -> bool) -> Result<Vec<Worker>> {
+        let attached = self.attached_workers().await?;
+        let mut workers = Vec::new();
+        for info in self
+            .targets()
+            .await?
+            .into_iter()
+            .filter(|info| filter(info))
+        {
+            let session_id = match attached
+                .iter()
+                .find(|(_, attached)| attached.target_id == info.target_id)
+            {
+                Some((session_id, _)) => session_id.clone(),
+                None => {
+                    let attach = AttachToTargetParams::builder()
+                        .target_id(info.target_id.clone())
+                        .flatten(true)
+                        .build()
+                        .map_err(CdpError::msg)?;
+                    self.execute(attach).await?.result.session_id
+                }
+            };
+            workers.push(Worker::new(info, session_id, self.sender.clone()));
+        }
+        Ok(workers)
+    }
+
+    /// The sessions of all the workers and background pages the `Handler` is
+    /// attached to
+    async fn attached_workers(&self) -> Result<Vec<(SessionId, TargetInfo)>> {
         let (tx, rx) = oneshot_channel();
         self.sender
             .clone()
-            .send(HandlerMessage::GetPages(tx))
+            .send(HandlerMessage::GetWorkers(tx))
             .await?;
         Ok(rx.await?)
     }
+
+    /// Creates a new incognito browser context.
+    ///
+    /// Pages created within this context don't share cookies, cache or
+    /// storage with pages of other contexts.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::browser::Browser;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(browser: Browser) -> Result<()> {
+    ///     let context = browser.new_incognito_context().await?;
+    ///     let page = context.new_page("https://en.wikipedia.org").await?;
+    ///     // ...
+    ///     context.dispose().await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn new_incognito_context(&self) -> Result<BrowserContext> {
+        self.create_browser_context(CreateBrowserContextParams::default())
+            .await
+    }
+
+    /// Creates a new browser context with the given parameters, like a
+    /// dedicated proxy server.
+    pub async fn create_browser_context(
+        &self,
+        params: CreateBrowserContextParams,
+    ) -> Result<BrowserContext> {
+        let resp = self.execute(params).await?;
+        Ok(BrowserContext {
+            id: resp.result.browser_context_id,
+            sender: self.sender.clone(),
+        })
+    }
+
+    /// Creates a new incognito browser context whose traffic is routed through
+    /// the proxy server at `proxy_server`, except for the hosts in
+    /// `bypass_list`.
+    pub async fn new_context_with_proxy<I, S>(
+        &self,
+        proxy_server: impl Into<String>,
+        bypass_list: I,
+    ) -> Result<BrowserContext>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let bypass_list = bypass_list
+            .into_iter()
+            .map(Into::into)
+            .collect::<Vec<String>>();
+        let mut params = CreateBrowserContextParams::builder().proxy_server(proxy_server);
+        if !bypass_list.is_empty() {
+            params = params.proxy_bypass_list(bypass_list.join(";"));
+        }
+        self.create_browser_context(params.build()).await
+    }
+
+    /// Grants the `permissions` to the `origin` within the default browser
+    /// context, see [`BrowserContext::grant_permissions`]
+    pub async fn grant_permissions(
+        &self,
+        origin: impl Into<String>,
+        permissions: Vec<Permission>,
+    ) -> Result<&Self> {
+        let mut params = GrantPermissionsParams::new(permissions);
+        params.origin = Some(origin.into());
+        self.execute(params).await?;
+        Ok(self)
+    }
+
+    /// Resets all the permissions that were granted within the default
+    /// browser context
+    pub async fn clear_permission_overrides(&self) -> Result<&Self> {
+        self.execute(ResetPermissionsParams::default()).await?;
+        Ok(self)
+    }
+
+    /// Closes the browser gracefully via `Browser.close`.
+    ///
+    /// If the browser was spawned by this instance, this waits for the
+    /// process to exit and kills it only if it didn't exit within the
+    /// timeout.
+    pub async fn close(&mut self) -> Result<()> {
+        if let Some(child) = self.child.as_ref() {
+            child.set_closing();
+        }
+        // the connection may be closed before the response arrives
+        let res = self.execute(CloseParams::default()).await.map(|_| ());
+        if let Some(child) = self.child.take() {
+            let deadline = Instant::now() + CLOSE_TIMEOUT;
+            while child.try_wait()?.is_none() {
+                if Instant::now() > deadline {
+                    child.kill()?;
+                    child.wait_killed()?;
+                    break;
+                }
+                Delay::new(Duration::from_millis(50)).await;
+            }
+            if let Some(dir) = self.temp_user_data_dir.take() {
+                dir.close()?;
+            }
+            return Ok(());
+        }
+        res
+    }
 }
 
-impl Drop for Browser {
-    fn drop(&mut self) {
-        if let Some(child) = self.child.as_mut() {
-            child.kill().expect("!kill");
+/// A lifecycle event of a target of the browser
+#[derive(Debug, Clone)]
+pub enum BrowserEvent {
+    /// A new target was created, like a new tab or a popup
+    TargetCreated(TargetInfo),
+    /// The target was closed
+    TargetDestroyed(TargetId),
+    /// The info of the target changed, like its url or title after a
+    /// navigation
+    TargetInfoChanged(TargetInfo),
+    /// The renderer process of the target crashed
+    TargetCrashed {
+        target_id: TargetId,
+        /// The termination status of the process
+        status: String,
+        error_code: i64,
+    },
+    /// The process of the launched browser exited unexpectedly, all pending
+    /// commands failed with `CdpError::BrowserCrashed` and the `Handler` is
+    /// done.
+    Crashed(ExitStatus),
+}
+
+/// A `Stream` over all the `BrowserEvent`s of a `Browser`
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct BrowserEvents {
+    created: EventStream<EventTargetCreated>,
+    destroyed: EventStream<EventTargetDestroyed>,
+    info_changed: EventStream<EventTargetInfoChanged>,
+    crashed: EventStream<EventTargetCrashed>,
+    connection: UnboundedReceiver<ConnectionEvent>,
+}
+
+impl Stream for BrowserEvents {
+    type Item = BrowserEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        while let Poll::Ready(Some(ev)) = Pin::new(&mut pin.connection).poll_next(cx) {
+            if let ConnectionEvent::Crashed(status) = ev {
+                return Poll::Ready(Some(BrowserEvent::Crashed(status)));
+            }
+        }
+        if let Poll::Ready(Some(ev)) = Pin::new(&mut pin.created).poll_next(cx) {
+            return Poll::Ready(Some(BrowserEvent::TargetCreated(ev.target_info.clone())));
+        }
+        if let Poll::Ready(Some(ev)) = Pin::new(&mut pin.info_changed).poll_next(cx) {
+            return Poll::Ready(Some(BrowserEvent::TargetInfoChanged(
+                ev.target_info.clone(),
+            )));
+        }
+        if let Poll::Ready(Some(ev)) = Pin::new(&mut pin.crashed).poll_next(cx) {
+            return Poll::Ready(Some(BrowserEvent::TargetCrashed {
+                target_id: ev.target_id.clone(),
+                status: ev.status.clone(),
+                error_code: ev.error_code,
+            }));
+        }
+        match Pin::new(&mut pin.destroyed).poll_next(cx) {
+            Poll::Ready(Some(ev)) => {
+                Poll::Ready(Some(BrowserEvent::TargetDestroyed(ev.target_id.clone())))
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
         }
     }
 }
 
-async fn ws_url_from_output(child_process: &mut Child) -> String {
-    let stdout = child_process.stderr.take().expect("no stderror");
+/// A `Stream` over the [`ConnectionEvent`]s of the connection to the browser
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct ConnectionEvents {
+    events: UnboundedReceiver<ConnectionEvent>,
+}
 
-    fn read_debug_url(stdout: std::process::ChildStderr) -> String {
-        let mut buf = BufReader::new(stdout);
-        let mut line = String::new();
-        loop {
-            if buf.read_line(&mut line).is_ok() {
-                // check for ws in line
-                if let Some(ws) = line.rsplit("listening on ").next() {
-                    if ws.starts_with("ws") && ws.contains("devtools/browser") {
-                        return ws.trim().to_string();
-                    }
+impl Stream for ConnectionEvents {
+    type Item = ConnectionEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().events).poll_next(cx)
+    }
+}
+
+/// An isolated browser session, like an incognito window.
+///
+/// Created via [`Browser::new_incognito_context`].
+#[derive(Debug, Clone)]
+pub struct BrowserContext {
+    /// The identifier of this context within the chromium instance
+    id: BrowserContextId,
+    /// The `Sender` to send messages to the connection handler
+    sender: Sender<HandlerMessage>,
+}
+
+impl BrowserContext {
+    /// The identifier of this browser context
+    pub fn id(&self) -> &BrowserContextId {
+        &self.id
+    }
+
+    /// Create a new page that is bound to this context
+    pub async fn new_page(&self, params: impl Into<CreateTargetParams>) -> Result<Page> {
+        let mut params = params.into();
+        params.browser_context_id = Some(self.id.clone());
+        let (tx, rx) = oneshot_channel();
+        self.sender
+            .clone()
+            .send(HandlerMessage::CreatePage(params, tx))
+            .await?;
+        rx.await?
+    }
+
+    /// Returns the info of all the targets that belong to this context
+    pub async fn targets(&self) -> Result<Vec<TargetInfo>> {
+        let resp = self.execute(GetTargetsParams::default()).await?;
+        Ok(resp
+            .result
+            .target_infos
+            .into_iter()
+            .filter(|info| info.browser_context_id.as_ref() == Some(&self.id))
+            .collect())
+    }
+
+    /// Grants the `permissions` to the `origin` within this context, so that
+    /// the page doesn't prompt for them.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::browser::{BrowserContext, Permission};
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(context: BrowserContext) -> Result<()> {
+    ///     context
+    ///         .grant_permissions(
+    ///             "https://example.com",
+    ///             vec![Permission::Geolocation, Permission::Notifications],
+    ///         )
+    ///         .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn grant_permissions(
+        &self,
+        origin: impl Into<String>,
+        permissions: Vec<Permission>,
+    ) -> Result<&Self> {
+        let mut params = GrantPermissionsParams::new(permissions);
+        params.origin = Some(origin.into());
+        params.browser_context_id = Some(self.id.clone());
+        self.execute(params).await?;
+        Ok(self)
+    }
+
+    /// Resets all the permissions that were granted within this context
+    pub async fn clear_permission_overrides(&self) -> Result<&Self> {
+        self.execute(
+            ResetPermissionsParams::builder()
+                .browser_context_id(self.id.clone())
+                .build(),
+        )
+        .await?;
+        Ok(self)
+    }
+
+    /// Returns access to the `localStorage` and IndexedDB databases of the
+    /// origins within this context.
+    ///
+    /// The storage is only accessible through a page, so this uses one of the
+    /// context's pages and fails with `CdpError::NotFound` if there is none.
+    pub async fn storage(&self) -> Result<Storage> {
+        let page = self
+            .targets()
+            .await?
+            .into_iter()
+            .find(|info| info.r#type == "page")
+            .ok_or(CdpError::NotFound)?;
+        let (tx, rx) = oneshot_channel();
+        self.sender
+            .clone()
+            .send(HandlerMessage::AttachToTarget(page.target_id, tx))
+            .await?;
+        Ok(rx.await??.storage())
+    }
+
+    /// Closes this context and all of its targets
+    pub async fn dispose(self) -> Result<()> {
+        self.execute(DisposeBrowserContextParams::new(self.id.clone()))
+            .await?;
+        Ok(())
+    }
+
+    async fn execute<T: Command>(&self, cmd: T) -> Result<CommandResponse<T::Response>> {
+        let (tx, rx) = oneshot_channel();
+        let method = cmd.identifier();
+        let msg = CommandMessage::new(cmd, tx)?;
+        self.sender
+            .clone()
+            .send(HandlerMessage::Command(msg))
+            .await?;
+        let resp = rx.await??;
+        to_command_response::<T>(resp, method)
+    }
+}
+
+/// How long to wait for the chromium process to exit after `Browser.close`
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl Drop for Browser {
+    fn drop(&mut self) {
+        if let Some(child) = self.child.as_ref() {
+            child.set_closing();
+            // the process may have already exited
+            if let Ok(None) = child.try_wait() {
+                let _ = child.kill();
+                // the temporary user data dir can only be removed once the
+                // process is gone
+                if self.temp_user_data_dir.is_some() {
+                    let _ = child.wait_killed();
+                } else {
+                    let _ = child.try_wait();
                 }
-            } else {
-                line = String::new();
             }
         }
     }
+}
+
+/// Waits until the websocket url of the launched browser is known.
+///
+/// The url is taken from the first line of the process' stdout or stderr that
+/// announces it. If a fixed debugging port is configured, the
+/// `/json/version` endpoint is polled as well, in case chromium logs
+/// differently. Fails with the captured output of the process if it exits or
+/// `timeout` elapses first.
+async fn ws_url_from_output(child: Child, config: &BrowserConfig) -> (Child, Result<String>) {
+    let (port, timeout, log_level) = (config.port, config.launch_timeout, config.log_chrome_output);
     cfg_if::cfg_if! {
         if #[cfg(feature = "async-std-runtime")] {
-            async_std::task::spawn_blocking(|| read_debug_url(stdout)).await
+            async_std::task::spawn_blocking(move || wait_for_ws_url(child, port, timeout, log_level)).await
         } else if #[cfg(feature = "tokio-runtime")] {
-            tokio::task::spawn_blocking(move || read_debug_url(stdout)).await.expect("Failed to read debug url from process output")
+            tokio::task::spawn_blocking(move || wait_for_ws_url(child, port, timeout, log_level)).await.expect("Failed to read debug url from process output")
+        }
+    }
+}
+
+fn wait_for_ws_url(
+    mut child: Child,
+    port: u16,
+    timeout: Duration,
+    log_level: Option<log::Level>,
+) -> (Child, Result<String>) {
+    let output = Arc::new(Mutex::new(Some(String::new())));
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    if let Some(stdout) = child.stdout.take() {
+        read_process_output(stdout, Arc::clone(&output), tx.clone(), log_level);
+    }
+    if let Some(stderr) = child.stderr.take() {
+        read_process_output(stderr, Arc::clone(&output), tx, log_level);
+    }
+
+    let captured = || {
+        output
+            .lock()
+            .ok()
+            .and_then(|output| output.clone())
+            .unwrap_or_default()
+    };
+    let deadline = Instant::now() + timeout;
+    loop {
+        let ws = rx
+            .recv_timeout(Duration::from_millis(100))
+            .ok()
+            .or_else(|| {
+                (port != 0)
+                    .then(|| ws_url_from_json_version(port))
+                    .flatten()
+            });
+        if let Some(ws) = ws {
+            // stop capturing the output
+            if let Ok(mut output) = output.lock() {
+                output.take();
+            }
+            return (child, Ok(ws));
+        }
+        if let Ok(Some(status)) = child.try_wait() {
+            let err = CdpError::LaunchExit(status, captured());
+            return (child, Err(err));
+        }
+        if Instant::now() >= deadline {
+            let err = CdpError::LaunchTimeout(captured());
+            return (child, Err(err));
         }
     }
 }
 
+/// Reads the output of the process line by line on a separate thread, sends
+/// the websocket url once it is logged.
+///
+/// The output is captured until the browser is launched and logged with the
+/// `log_level` for as long as the process runs.
+fn read_process_output(
+    out: impl Read + Send + 'static,
+    output: Arc<Mutex<Option<String>>>,
+    tx: std::sync::mpsc::Sender<String>,
+    log_level: Option<log::Level>,
+) {
+    std::thread::spawn(move || {
+        let mut buf = BufReader::new(out);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match buf.read_line(&mut line) {
+                Ok(0) | Err(_) => return,
+                Ok(_) => {}
+            }
+            if let Some(level) = log_level {
+                log::log!(target: "chromiumoxide::browser::chrome", level, "{}", line.trim_end());
+            }
+            match output.lock().as_deref_mut() {
+                Ok(Some(output)) => output.push_str(&line),
+                // the browser is launched
+                _ => continue,
+            }
+            // check for ws in line
+            if let Some(ws) = line.rsplit("listening on ").next() {
+                if ws.starts_with("ws") && ws.contains("devtools/browser") {
+                    let _ = tx.send(ws.trim().to_string());
+                }
+            }
+        }
+    });
+}
+
+/// The files of a profile that belong to the chromium instance using it and
+/// must not be copied into another profile
+const PROFILE_LOCK_FILES: [&str; 5] = [
+    "SingletonLock",
+    "SingletonSocket",
+    "SingletonCookie",
+    "lockfile",
+    "DevToolsActivePort",
+];
+
+/// Copies the profile at `from` into the user data dir `to`, without the
+/// lock files of the instance that may currently use it
+fn copy_profile(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let name = entry.file_name();
+        if file_type.is_symlink() || PROFILE_LOCK_FILES.iter().any(|lock| name == *lock) {
+            continue;
+        }
+        let target = to.join(&name);
+        if file_type.is_dir() {
+            copy_profile(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Finds a free port on the loopback interface by binding to a port the OS
+/// picks and releasing it again
+fn free_port() -> io::Result<u16> {
+    Ok(std::net::TcpListener::bind(("127.0.0.1", 0))?
+        .local_addr()?
+        .port())
+}
+
+/// Requests `http://127.0.0.1:{port}/json/version` and returns the
+/// `webSocketDebuggerUrl` of the response, if the endpoint is up.
+fn ws_url_from_json_version(port: u16) -> Option<String> {
+    let version = json_version(&format!("127.0.0.1:{}", port), Duration::from_millis(500))?;
+    version["webSocketDebuggerUrl"]
+        .as_str()
+        .map(ToString::to_string)
+}
+
+/// The path of the websocket urls of page targets
+const PAGE_WS_PATH: &str = "/devtools/page/";
+
+/// Resolves the websocket url of the browser from the `url` passed to
+/// [`Browser::connect`] via the `/json/version` endpoint, unless it already
+/// is the websocket url of a browser.
+async fn resolve_ws_url(url: String, options: &ConnectOptions) -> Result<String> {
+    if (url.starts_with("ws://") || url.starts_with("wss://")) && !url.contains(PAGE_WS_PATH) {
+        return Ok(url);
+    }
+    let version = fetch_json_version(&url, options).await.ok_or_else(|| {
+        CdpError::msg(format!(
+            "Failed to request the websocket url of the browser at {}",
+            url
+        ))
+    })?;
+    version["webSocketDebuggerUrl"]
+        .as_str()
+        .map(ToString::to_string)
+        .ok_or_else(|| CdpError::msg(format!("No websocket url reported by {}", url)))
+}
+
+/// Requests the `/json/version` endpoint of the browser at the host of `url`,
+/// like `http://localhost:9222` or `ws://localhost:9222/devtools/page/<id>`,
+/// with the headers and through the proxy of the `options`
+///
+/// `https://` and `wss://` urls request the endpoint via TLS.
+async fn fetch_json_version(url: &str, options: &ConnectOptions) -> Option<serde_json::Value> {
+    let secure = url.starts_with("https://") || url.starts_with("wss://");
+    let host = url
+        .split("://")
+        .last()?
+        .split('/')
+        .next()
+        .filter(|host| !host.is_empty())?;
+    let url = format!(
+        "{}://{}/json/version",
+        if secure { "https" } else { "http" },
+        host
+    );
+    let timeout = Duration::from_millis(REQUEST_TIMEOUT);
+    utils::timeout(Some(timeout), crate::conn::get_json(&url, options))
+        .await
+        .ok()
+}
+
+/// Requests the `/json/version` endpoint of the browser at `host`, like
+/// `127.0.0.1:9222`
+fn json_version(host: &str, timeout: Duration) -> Option<serde_json::Value> {
+    let addr = host.to_socket_addrs().ok()?.next()?;
+    let mut stream = TcpStream::connect_timeout(&addr, timeout).ok()?;
+    stream.set_read_timeout(Some(timeout)).ok()?;
+    write!(
+        stream,
+        "GET /json/version HTTP/1.0\r\nHost: {}\r\n\r\n",
+        host
+    )
+    .ok()?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).ok()?;
+    let body = response.split("\r\n\r\n").nth(1)?;
+    serde_json::from_str(body).ok()
+}
+
+/// The `User-Agent` the browser behind the `ws` debug url reports in its
+/// `/json/version` endpoint
+#[cfg(feature = "stealth")]
+async fn browser_user_agent(ws: &str) -> Option<String> {
+    let version = fetch_json_version(ws, &ConnectOptions::default()).await?;
+    version["User-Agent"].as_str().map(ToString::to_string)
+}
+
+/// Whether and how the browser runs without a head
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeadlessMode {
+    /// The old headless implementation, `--headless`
+    #[default]
+    True,
+    /// The new headless implementation that behaves like the headful browser,
+    /// `--headless=new`
+    New,
+    /// Launch the browser with a head
+    False,
+}
+
 #[derive(Debug, Clone)]
 pub struct BrowserConfig {
     /// Determines whether to run headless version of the browser. Defaults to
-    /// true.
-    headless: bool,
+    /// [`HeadlessMode::True`].
+    headless: HeadlessMode,
+    /// Whether to open the devtools for every tab, this requires a headful
+    /// browser
+    devtools: bool,
     /// Determines whether to run the browser with a sandbox.
     sandbox: bool,
     /// Launch the browser with a specific window width and height.
@@ -198,8 +1417,9 @@ pub struct BrowserConfig {
     /// Path for Chrome or Chromium.
     ///
     /// If unspecified, the create will try to automatically detect a suitable
-    /// binary.
-    executable: std::path::PathBuf,
+    /// binary. `None` if none was detected and the pinned revision is
+    /// downloaded on launch, see the `fetcher` feature.
+    executable: Option<std::path::PathBuf>,
 
     /// A list of Chrome extensions to load.
     ///
@@ -216,11 +1436,93 @@ pub struct BrowserConfig {
 
     /// Data dir for user data
     pub user_data_dir: Option<PathBuf>,
+
+    /// A profile that is copied into a fresh temporary user data dir on
+    /// every launch
+    template_profile: Option<PathBuf>,
+
+    /// The proxy server all traffic is routed through, like
+    /// `http://localhost:8080` or `socks5://localhost:1080`
+    proxy: Option<String>,
+
+    /// Hosts that bypass the proxy, like `*.example.com` or `<local>`
+    proxy_bypass_list: Vec<String>,
+
+    /// The credentials to answer authentication challenges of the proxy with
+    proxy_credentials: Option<Credentials>,
+
+    /// How long to wait for the response to a request before it fails with a
+    /// timeout error
+    request_timeout: Duration,
+
+    /// How long to wait for the launched browser to report its websocket url
+    launch_timeout: Duration,
+
+    /// The level the output of the chromium process is logged with, if at
+    /// all
+    log_chrome_output: Option<log::Level>,
+
+    /// Whether to talk to the browser over pipes instead of a websocket
+    pipe: bool,
+
+    /// How often to try to re-establish a lost connection to the browser
+    reconnect_attempts: usize,
+
+    /// How long to wait before each attempt to reconnect
+    reconnect_interval: Duration,
+
+    /// How often to send a ping to keep the connection alive, if at all
+    keep_alive_interval: Option<Duration>,
+
+    /// Additional arguments passed to the chromium process
+    args: Vec<String>,
+
+    /// Whether to launch without the [`DEFAULT_ARGS`]
+    disable_default_args: bool,
+
+    /// Flags of the [`DEFAULT_ARGS`] that are not passed
+    removed_default_args: Vec<String>,
+
+    /// How many messages can be queued for the `Handler` and the pages
+    channel_capacity: usize,
+
+    /// Whether to ignore certificate errors, the pages ignore them unless
+    /// this is `Some(false)` and `Some(true)` also launches the browser with
+    /// `--ignore-certificate-errors`
+    ignore_https_errors: Option<bool>,
+
+    /// How many commands may await their response at once
+    max_commands_in_flight: Option<usize>,
+
+    /// How many commands of the same page may await their response at once
+    max_commands_in_flight_per_session: Option<usize>,
+
+    /// The viewport every new page is emulated with, independent of the
+    /// `window_size`
+    viewport: Viewport,
+
+    /// How commands are retried that fail with a transient error
+    retry_policy: Option<RetryPolicy>,
+
+    /// How many of the most recent events of each kind are buffered for
+    /// replays
+    event_replay_capacity: usize,
+
+    /// Logs the messages exchanged with the browser, if set
+    wire_log: Option<WireLogConfig>,
+
+    /// The domains that are enabled for every new page right away
+    domains: DomainConfig,
+
+    /// Whether to hide the signs of automation from the pages
+    #[cfg(feature = "stealth")]
+    stealth: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct BrowserConfigBuilder {
-    headless: bool,
+    headless: HeadlessMode,
+    devtools: bool,
     sandbox: bool,
     window_size: Option<(u32, u32)>,
     port: u16,
@@ -228,6 +1530,31 @@ pub struct BrowserConfigBuilder {
     extensions: Vec<String>,
     process_envs: Option<HashMap<String, String>>,
     user_data_dir: Option<PathBuf>,
+    template_profile: Option<PathBuf>,
+    proxy: Option<String>,
+    proxy_bypass_list: Vec<String>,
+    proxy_credentials: Option<Credentials>,
+    request_timeout: Duration,
+    launch_timeout: Duration,
+    log_chrome_output: Option<log::Level>,
+    pipe: bool,
+    reconnect_attempts: usize,
+    reconnect_interval: Duration,
+    keep_alive_interval: Option<Duration>,
+    args: Vec<String>,
+    disable_default_args: bool,
+    removed_default_args: Vec<String>,
+    channel_capacity: usize,
+    ignore_https_errors: Option<bool>,
+    max_commands_in_flight: Option<usize>,
+    max_commands_in_flight_per_session: Option<usize>,
+    viewport: Viewport,
+    retry_policy: Option<RetryPolicy>,
+    event_replay_capacity: usize,
+    wire_log: Option<WireLogConfig>,
+    domains: DomainConfig,
+    #[cfg(feature = "stealth")]
+    stealth: bool,
 }
 
 impl BrowserConfig {
@@ -243,7 +1570,8 @@ impl BrowserConfig {
 impl Default for BrowserConfigBuilder {
     fn default() -> Self {
         Self {
-            headless: true,
+            headless: HeadlessMode::default(),
+            devtools: false,
             sandbox: true,
             window_size: None,
             port: 0,
@@ -251,6 +1579,31 @@ impl Default for BrowserConfigBuilder {
             extensions: Vec::new(),
             process_envs: None,
             user_data_dir: None,
+            template_profile: None,
+            proxy: None,
+            proxy_bypass_list: Vec::new(),
+            proxy_credentials: None,
+            request_timeout: Duration::from_millis(REQUEST_TIMEOUT),
+            launch_timeout: Duration::from_millis(LAUNCH_TIMEOUT),
+            log_chrome_output: None,
+            pipe: false,
+            reconnect_attempts: 0,
+            reconnect_interval: Duration::from_secs(1),
+            keep_alive_interval: None,
+            args: Vec::new(),
+            disable_default_args: false,
+            removed_default_args: Vec::new(),
+            channel_capacity: CHANNEL_CAPACITY,
+            ignore_https_errors: None,
+            max_commands_in_flight: None,
+            max_commands_in_flight_per_session: None,
+            viewport: Default::default(),
+            retry_policy: None,
+            event_replay_capacity: 0,
+            domains: Default::default(),
+            wire_log: None,
+            #[cfg(feature = "stealth")]
+            stealth: false,
         }
     }
 }
@@ -261,13 +1614,73 @@ impl BrowserConfigBuilder {
         self
     }
 
+    /// The viewport every new page is emulated with via
+    /// `Emulation.setDeviceMetricsOverride`, defaults to 800x600.
+    ///
+    /// Unlike the `window_size`, which only sizes the browser window, this
+    /// determines the size pages are laid out and rendered at. Single pages
+    /// can override it with `Page::set_viewport`.
+    pub fn viewport(mut self, viewport: Viewport) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Retry commands of the browser and its pages that fail with a
+    /// transient error of chromium, like `Target closed`, according to the
+    /// `policy`.
+    ///
+    /// Commands are not retried by default.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Buffer the last `capacity` events of each kind per page, so that
+    /// events which fire before a listener is registered, like early console
+    /// errors, can be replayed via `Page::subscribe_with_replay`.
+    pub fn event_replay_capacity(mut self, capacity: usize) -> Self {
+        self.event_replay_capacity = capacity;
+        self
+    }
+
+    /// The CDP domains to enable for every new page right away.
+    ///
+    /// Use [`DomainConfig::lazy`] to only enable the other domains once they
+    /// are first used, see [`DomainConfig`].
+    pub fn domains(mut self, domains: DomainConfig) -> Self {
+        self.domains = domains;
+        self
+    }
+
+    /// Log every command sent to the browser and every response and event
+    /// received from it, see [`WireLogConfig`].
+    pub fn wire_log(mut self, config: WireLogConfig) -> Self {
+        self.wire_log = Some(config);
+        self
+    }
+
     pub fn no_sandbox(mut self) -> Self {
         self.sandbox = false;
         self
     }
 
     pub fn with_head(mut self) -> Self {
-        self.headless = false;
+        self.headless = HeadlessMode::False;
+        self
+    }
+
+    /// How to run the browser, headless by default
+    pub fn headless_mode(mut self, mode: HeadlessMode) -> Self {
+        self.headless = mode;
+        self
+    }
+
+    /// Open the devtools for every tab.
+    ///
+    /// This launches the browser with a head, regardless of the configured
+    /// [`HeadlessMode`].
+    pub fn devtools(mut self, devtools: bool) -> Self {
+        self.devtools = devtools;
         self
     }
 
@@ -276,6 +1689,19 @@ impl BrowserConfigBuilder {
         self
     }
 
+    /// Copy the profile at `path`, like a user data dir with the cookies,
+    /// local storage and cache of a logged in session, into a fresh
+    /// temporary user data dir on every launch.
+    ///
+    /// This lets several browsers start from the same profile without
+    /// competing for its lock. Changes are not written back to the template
+    /// and the copy is deleted once the browser is closed. This takes
+    /// precedence over the `user_data_dir`.
+    pub fn template_profile(mut self, path: impl AsRef<Path>) -> Self {
+        self.template_profile = Some(path.as_ref().to_path_buf());
+        self
+    }
+
     pub fn chrome_executable(mut self, path: impl AsRef<Path>) -> Self {
         self.executable = Some(path.as_ref().to_path_buf());
         self
@@ -316,60 +1742,274 @@ impl BrowserConfigBuilder {
         self
     }
 
+    /// Route all traffic through the proxy server at `url`, like
+    /// `http://localhost:8080` or `socks5://localhost:1080`
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// Hosts that don't use the proxy, like `*.example.com` or `<local>`
+    pub fn proxy_bypass_list<I, S>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.proxy_bypass_list
+            .extend(hosts.into_iter().map(Into::into));
+        self
+    }
+
+    /// Answer the authentication challenges of the proxy with the `username`
+    /// and `password`.
+    ///
+    /// This intercepts all requests via the `Fetch` domain, requests that are
+    /// not handled by a `RequestInterception` are continued automatically.
+    /// Every request then waits for an additional round trip between the
+    /// browser and the `Handler`, so only set this if the proxy requires it.
+    pub fn proxy_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.proxy_credentials = Some(Credentials::new(username, password));
+        self
+    }
+
+    /// How long to wait for the response to a request before it fails with
+    /// [`CdpError::Timeout`], defaults to 30 seconds.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// How long to wait for the launched browser to report its websocket
+    /// url, defaults to 20 seconds.
+    pub fn launch_timeout(mut self, timeout: Duration) -> Self {
+        self.launch_timeout = timeout;
+        self
+    }
+
+    /// Log every line the chromium process writes to its stdout and stderr
+    /// with the `level` under the `chromiumoxide::browser::chrome` target,
+    /// e.g. to find out why the browser crashed.
+    ///
+    /// By default the output is only kept until the browser is launched, to
+    /// report why a launch failed.
+    pub fn log_chrome_output(mut self, level: log::Level) -> Self {
+        self.log_chrome_output = Some(level);
+        self
+    }
+
+    /// Launch the browser with `--remote-debugging-pipe` and exchange the
+    /// messages over the pipes of the process instead of a websocket, so
+    /// that no debugging port is opened at all.
+    ///
+    /// The port is ignored then and the browser can't be reconnected to.
+    /// Only supported on unix.
+    pub fn pipe(mut self) -> Self {
+        self.pipe = true;
+        self
+    }
+
+    /// Pass an additional argument to the chromium process, like
+    /// `--enable-gpu` or an experimental flag.
+    ///
+    /// Arguments are passed after the default arguments.
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Pass additional arguments to the chromium process
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Launch chromium without any of the [`DEFAULT_ARGS`], only with the
+    /// arguments that are configured explicitly.
+    pub fn disable_default_args(mut self) -> Self {
+        self.disable_default_args = true;
+        self
+    }
+
+    /// Don't pass the `flag` of the [`DEFAULT_ARGS`], like
+    /// `--disable-extensions`.
+    ///
+    /// Flags with a value are matched by their name, so `--enable-features`
+    /// removes `--enable-features=NetworkService,NetworkServiceInProcess`.
+    pub fn remove_default_arg(mut self, flag: impl Into<String>) -> Self {
+        self.removed_default_args.push(flag.into());
+        self
+    }
+
+    /// Ignore certificate errors, like expired or self-signed certificates,
+    /// for all targets of the browser by also launching it with
+    /// `--ignore-certificate-errors`.
+    ///
+    /// Pages ignore certificate errors by default, see
+    /// [`BrowserConfigBuilder::respect_https_errors`] to opt out.
+    pub fn ignore_https_errors(mut self) -> Self {
+        self.ignore_https_errors = Some(true);
+        self
+    }
+
+    /// Fail requests with certificate errors instead of ignoring them, which
+    /// the pages do by default.
+    pub fn respect_https_errors(mut self) -> Self {
+        self.ignore_https_errors = Some(false);
+        self
+    }
+
+    /// Hide the most common signs of an automated browser from the pages.
+    ///
+    /// This removes the `--enable-automation` flag, reports the user agent of
+    /// a regular chrome instead of `HeadlessChrome` and installs the
+    /// [`stealth::EVASIONS`](crate::stealth::EVASIONS) in every page, like
+    /// removing `navigator.webdriver`.
+    #[cfg(feature = "stealth")]
+    pub fn stealth_mode(mut self) -> Self {
+        self.stealth = true;
+        self.removed_default_args
+            .push("--enable-automation".to_string());
+        self.args
+            .push("--disable-blink-features=AutomationControlled".to_string());
+        self
+    }
+
+    /// How many messages can be queued for the `Handler` and each page before
+    /// senders have to wait, defaults to [`CHANNEL_CAPACITY`].
+    pub fn channel_capacity(mut self, capacity: usize) -> Self {
+        self.channel_capacity = capacity;
+        self
+    }
+
+    /// Allow at most `max` commands to await their response at once, further
+    /// commands are queued until responses arrive.
+    ///
+    /// Unlimited by default.
+    pub fn max_commands_in_flight(mut self, max: usize) -> Self {
+        self.max_commands_in_flight = Some(max);
+        self
+    }
+
+    /// Allow at most `max` commands of the same page to await their response
+    /// at once. Queued commands of different pages are submitted in turns.
+    ///
+    /// Unlimited by default.
+    pub fn max_commands_in_flight_per_session(mut self, max: usize) -> Self {
+        self.max_commands_in_flight_per_session = Some(max);
+        self
+    }
+
+    /// Try up to `attempts` times to re-establish the websocket connection,
+    /// waiting `interval` before each attempt, if it is lost.
+    ///
+    /// Disabled by default. See [`Browser::connection_events`].
+    pub fn reconnect(mut self, attempts: usize, interval: Duration) -> Self {
+        self.reconnect_attempts = attempts;
+        self.reconnect_interval = interval;
+        self
+    }
+
+    /// Send a ping to the browser every `interval` to keep the connection
+    /// alive and detect when it is lost.
+    pub fn keep_alive(mut self, interval: Duration) -> Self {
+        self.keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// Launch the browser with a specific debugging port, instead of a free
+    /// one that is picked on launch, see `Browser::port`.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
     pub fn build(self) -> std::result::Result<BrowserConfig, String> {
-        let executable = if let Some(e) = self.executable {
-            e
-        } else {
-            default_executable()?
+        let executable = match self.executable {
+            Some(path) => Some(path),
+            // downloaded by `Browser::launch` without blocking the executor
+            None if cfg!(feature = "fetcher") => installed_executable().ok(),
+            None => Some(installed_executable()?),
         };
 
         Ok(BrowserConfig {
             headless: self.headless,
+            devtools: self.devtools,
             sandbox: self.sandbox,
             window_size: self.window_size,
             port: self.port,
             executable,
             extensions: self.extensions,
-            process_envs: None,
-            user_data_dir: None,
+            process_envs: self.process_envs,
+            user_data_dir: self.user_data_dir,
+            template_profile: self.template_profile,
+            proxy: self.proxy,
+            proxy_bypass_list: self.proxy_bypass_list,
+            proxy_credentials: self.proxy_credentials,
+            request_timeout: self.request_timeout,
+            launch_timeout: self.launch_timeout,
+            log_chrome_output: self.log_chrome_output,
+            pipe: self.pipe,
+            reconnect_attempts: self.reconnect_attempts,
+            reconnect_interval: self.reconnect_interval,
+            keep_alive_interval: self.keep_alive_interval,
+            args: self.args,
+            disable_default_args: self.disable_default_args,
+            removed_default_args: self.removed_default_args,
+            channel_capacity: self.channel_capacity,
+            ignore_https_errors: self.ignore_https_errors,
+            max_commands_in_flight: self.max_commands_in_flight,
+            max_commands_in_flight_per_session: self.max_commands_in_flight_per_session,
+            viewport: self.viewport,
+            retry_policy: self.retry_policy,
+            event_replay_capacity: self.event_replay_capacity,
+            domains: self.domains,
+            wire_log: self.wire_log,
+            #[cfg(feature = "stealth")]
+            stealth: self.stealth,
         })
     }
 }
 
 impl BrowserConfig {
+    /// Launches the chromium process.
+    ///
+    /// This downloads the pinned chromium revision first if no executable
+    /// was detected, which blocks, see [`Browser::launch`].
     pub fn launch(&self) -> io::Result<Child> {
-        let dbg_port = format!("--remote-debugging-port={}", self.port);
-
-        let args = [
-            dbg_port.as_str(),
-            "--disable-background-networking",
-            "--enable-features=NetworkService,NetworkServiceInProcess",
-            "--disable-background-timer-throttling",
-            "--disable-backgrounding-occluded-windows",
-            "--disable-breakpad",
-            "--disable-client-side-phishing-detection",
-            "--disable-component-extensions-with-background-pages",
-            "--disable-default-apps",
-            "--disable-dev-shm-usage",
-            "--disable-extensions",
-            "--disable-features=TranslateUI",
-            "--disable-hang-monitor",
-            "--disable-ipc-flooding-protection",
-            "--disable-popup-blocking",
-            "--disable-prompt-on-repost",
-            "--disable-renderer-backgrounding",
-            "--disable-sync",
-            "--force-color-profile=srgb",
-            "--metrics-recording-only",
-            "--no-first-run",
-            "--enable-automation",
-            "--password-store=basic",
-            "--use-mock-keychain",
-            "--enable-blink-features=IdleDetection",
-        ];
-
-        let mut cmd = process::Command::new(&self.executable);
-        cmd.args(&args).args(&DEFAULT_ARGS).args(
+        self.command()?.spawn()
+    }
+
+    /// The command that launches the chromium process
+    fn command(&self) -> io::Result<process::Command> {
+        let executable = match &self.executable {
+            Some(path) => path.clone(),
+            None => {
+                fetch_executable().map_err(|err| io::Error::new(io::ErrorKind::NotFound, err))?
+            }
+        };
+        let mut cmd = process::Command::new(executable);
+        if self.pipe {
+            cmd.arg("--remote-debugging-pipe");
+        } else {
+            cmd.arg(format!("--remote-debugging-port={}", self.port));
+        }
+        if !self.disable_default_args {
+            cmd.args(DEFAULT_ARGS.iter().filter(|arg| {
+                !self
+                    .removed_default_args
+                    .iter()
+                    .any(|flag| is_same_flag(arg, flag))
+            }));
+        }
+        cmd.args(
             self.extensions
                 .iter()
                 .map(|e| format!("--load-extension={}", e)),
@@ -383,18 +2023,46 @@ impl BrowserConfig {
             cmd.arg(format!("--window-size={},{}", width, height));
         }
 
+        if let Some(ref proxy) = self.proxy {
+            cmd.arg(format!("--proxy-server={}", proxy));
+        }
+
+        if !self.proxy_bypass_list.is_empty() {
+            cmd.arg(format!(
+                "--proxy-bypass-list={}",
+                self.proxy_bypass_list.join(";")
+            ));
+        }
+
+        if self.ignore_https_errors == Some(true) {
+            cmd.arg("--ignore-certificate-errors");
+        }
+
         if !self.sandbox {
-            cmd.args(&["--no-sandbox", "--disable-setuid-sandbox"]);
+            cmd.args(["--no-sandbox", "--disable-setuid-sandbox"]);
         }
 
-        if self.headless {
-            cmd.args(&["--headless", "--hide-scrollbars", "--mute-audio"]);
+        if self.devtools {
+            cmd.arg("--auto-open-devtools-for-tabs");
+        } else {
+            match self.headless {
+                HeadlessMode::True => {
+                    cmd.args(["--headless", "--hide-scrollbars", "--mute-audio"]);
+                }
+                HeadlessMode::New => {
+                    cmd.args(["--headless=new", "--hide-scrollbars", "--mute-audio"]);
+                }
+                HeadlessMode::False => {}
+            }
         }
 
+        cmd.args(&self.args);
+
         if let Some(ref envs) = self.process_envs {
             cmd.envs(envs);
         }
-        cmd.stderr(Stdio::piped()).spawn()
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+        Ok(cmd)
     }
 }
 
@@ -405,8 +2073,20 @@ impl BrowserConfig {
 /// `chromium`, `chromium-browser`, `chrome` and `chrome-browser` are
 /// searched for in standard places. If that fails,
 /// `/Applications/Google Chrome.app/...` (on MacOS) or the registry (on
-/// Windows) is consulted. If all of the above fail, an error is returned.
+/// Windows) is consulted. If all of the above fail and the `fetcher` feature
+/// is enabled, the pinned chromium revision is downloaded to the cache
+/// directory, see [`crate::fetcher::BrowserFetcher`]. Otherwise an error is
+/// returned.
+///
+/// Downloading blocks the current thread, [`BrowserConfigBuilder::build`]
+/// leaves it to [`Browser::launch`] instead, which downloads in the
+/// background.
 pub fn default_executable() -> Result<std::path::PathBuf, String> {
+    installed_executable().or_else(|_| fetch_executable())
+}
+
+/// The path to an installed Chrome executable, see [`default_executable`]
+fn installed_executable() -> Result<std::path::PathBuf, String> {
     if let Ok(path) = std::env::var("CHROME") {
         if std::path::Path::new(&path).exists() {
             return Ok(path.into());
@@ -449,9 +2129,27 @@ pub fn default_executable() -> Result<std::path::PathBuf, String> {
     Err("Could not auto detect a chrome executable".to_string())
 }
 
-/// These are passed to the Chrome binary by default.
+/// Downloads the pinned chromium revision, unless it was downloaded already.
+#[cfg(feature = "fetcher")]
+fn fetch_executable() -> Result<std::path::PathBuf, String> {
+    use crate::fetcher::{BrowserFetcher, CURRENT_REVISION};
+
+    let info = BrowserFetcher::with_cache_dir()
+        .and_then(|fetcher| fetcher.fetch(CURRENT_REVISION))
+        .map_err(|err| format!("Could not download chromium: {}", err))?;
+    Ok(info.executable_path)
+}
+
+#[cfg(not(feature = "fetcher"))]
+fn fetch_executable() -> Result<std::path::PathBuf, String> {
+    Err("Could not auto detect a chrome executable".to_string())
+}
+
+/// These are passed to the Chrome binary by default, see
+/// [`BrowserConfigBuilder::remove_default_arg`] and
+/// [`BrowserConfigBuilder::disable_default_args`].
 /// Via https://github.com/puppeteer/puppeteer/blob/4846b8723cf20d3551c0d755df394cc5e0c82a94/src/node/Launcher.ts#L157
-static DEFAULT_ARGS: [&str; 23] = [
+pub static DEFAULT_ARGS: [&str; 24] = [
     "--disable-background-networking",
     "--enable-features=NetworkService,NetworkServiceInProcess",
     "--disable-background-timer-throttling",
@@ -475,4 +2173,10 @@ static DEFAULT_ARGS: [&str; 23] = [
     "--enable-automation",
     "--password-store=basic",
     "--use-mock-keychain",
+    "--enable-blink-features=IdleDetection",
 ];
+
+/// Whether the `arg` is the flag, with or without a value
+fn is_same_flag(arg: &str, flag: &str) -> bool {
+    arg == flag || arg.split('=').next() == Some(flag)
+}
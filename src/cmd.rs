@@ -1,17 +1,47 @@
-use futures::channel::oneshot::Sender as OneshotSender;
-use futures::task::Poll;
+use futures::channel::oneshot::{Receiver as OneshotReceiver, Sender as OneshotSender};
+use futures::task::{Context, Poll};
+use futures::Future;
 use serde::Serialize;
 use std::borrow::Cow;
 use std::collections::VecDeque;
 use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::pin::Pin;
 use std::time::{Duration, Instant};
 
 use chromiumoxide_types::{Command, CommandResponse, Method, Request, Response};
 
 use crate::error::{CdpError, DeadlineExceeded, Result};
 use crate::handler::REQUEST_TIMEOUT;
-use chromiumoxide_cdp::cdp::browser_protocol::page::NavigateParams;
+use chromiumoxide_cdp::cdp::browser_protocol::fetch::{
+    ContinueRequestParams, ContinueWithAuthParams, FailRequestParams, FulfillRequestParams,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::page::{
+    HandleJavaScriptDialogParams, NavigateParams,
+};
 use chromiumoxide_cdp::cdp::browser_protocol::target::SessionId;
+use chromiumoxide_cdp::cdp::js_protocol::debugger::ResumeParams;
+use chromiumoxide_cdp::cdp::js_protocol::runtime::RunIfWaitingForDebuggerParams;
+
+/// The commands that resume the browser after it paused on an event, like a
+/// paused request or an open dialog.
+///
+/// Other commands, like a navigation, may only complete once these were
+/// sent, so they are never queued behind other commands.
+const RESUMING_COMMANDS: &[&str] = &[
+    ContinueRequestParams::IDENTIFIER,
+    FulfillRequestParams::IDENTIFIER,
+    FailRequestParams::IDENTIFIER,
+    ContinueWithAuthParams::IDENTIFIER,
+    HandleJavaScriptDialogParams::IDENTIFIER,
+    RunIfWaitingForDebuggerParams::IDENTIFIER,
+    ResumeParams::IDENTIFIER,
+];
+
+/// Whether the command `method` resumes the browser, see `RESUMING_COMMANDS`
+pub(crate) fn is_resuming(method: &str) -> bool {
+    RESUMING_COMMANDS.contains(&method)
+}
 
 /// Deserialize a response
 pub(crate) fn to_command_response<T: Command>(
@@ -26,7 +56,80 @@ pub(crate) fn to_command_response<T: Command>(
             method,
         })
     } else if let Some(err) = resp.error {
-        Err(err.into())
+        Err(response_error(err, &method))
+    } else {
+        Err(CdpError::NoResponse)
+    }
+}
+
+/// The JSON-RPC error code chromium responds with to unknown methods
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Maps the error response to the `method` to a `CdpError`
+fn response_error(err: chromiumoxide_types::Error, method: &str) -> CdpError {
+    if err.code == METHOD_NOT_FOUND {
+        CdpError::NotSupported {
+            method: method.to_string(),
+        }
+    } else {
+        err.into()
+    }
+}
+
+/// A command that was sent to the browser and resolves to its response.
+///
+/// Dropping the handle, or calling [`CommandHandle::cancel`], stops waiting
+/// for the response. The `Handler` then discards the pending command, so
+/// that it no longer takes up a slot of the in flight commands. The browser
+/// still executes the command if it was sent already.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct CommandHandle<T: Command> {
+    method: Cow<'static, str>,
+    rx: OneshotReceiver<Result<Response>>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Command> CommandHandle<T> {
+    pub(crate) fn new(method: Cow<'static, str>, rx: OneshotReceiver<Result<Response>>) -> Self {
+        Self {
+            method,
+            rx,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The identifier of the command, like `Page.navigate`
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Stops waiting for the response
+    pub fn cancel(self) {}
+}
+
+impl<T: Command> Future for CommandHandle<T> {
+    type Output = Result<CommandResponse<T::Response>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let pin = self.get_mut();
+        match Pin::new(&mut pin.rx).poll(cx) {
+            Poll::Ready(Ok(Ok(resp))) => {
+                Poll::Ready(to_command_response::<T>(resp, pin.method.clone()))
+            }
+            Poll::Ready(Ok(Err(err))) => Poll::Ready(Err(err)),
+            Poll::Ready(Err(err)) => Poll::Ready(Err(err.into())),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// The raw result of a response
+pub(crate) fn to_raw_response(resp: Response, method: &str) -> Result<serde_json::Value> {
+    if let Some(res) = resp.result {
+        Ok(res)
+    } else if let Some(err) = resp.error {
+        Err(response_error(err, method))
     } else {
         Err(CdpError::NoResponse)
     }
@@ -42,6 +145,10 @@ pub(crate) struct CommandMessage<T = Result<Response>> {
     pub params: serde_json::Value,
     #[serde(skip_serializing)]
     pub sender: OneshotSender<T>,
+    /// How long to wait for the response, overrides the request timeout of
+    /// the `Handler`
+    #[serde(skip_serializing)]
+    pub timeout: Option<Duration>,
 }
 
 impl<T> CommandMessage<T> {
@@ -51,14 +158,42 @@ impl<T> CommandMessage<T> {
             session_id: None,
             params: serde_json::to_value(cmd)?,
             sender,
+            timeout: None,
         })
     }
 
+    /// A command that is not generated from the PDL
+    pub fn raw(
+        method: impl Into<Cow<'static, str>>,
+        params: serde_json::Value,
+        sender: OneshotSender<T>,
+        session_id: Option<SessionId>,
+    ) -> Self {
+        Self {
+            method: method.into(),
+            session_id,
+            params,
+            sender,
+            timeout: None,
+        }
+    }
+
+    /// Fail with a timeout if no response arrives within `timeout`
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
     /// Whether this command is a navigation
     pub fn is_navigation(&self) -> bool {
         self.method.as_ref() == NavigateParams::IDENTIFIER
     }
 
+    /// Whether this command resumes the browser after it paused on an event
+    pub fn is_resuming(&self) -> bool {
+        is_resuming(&self.method)
+    }
+
     pub fn with_session<C: Command>(
         cmd: C,
         sender: OneshotSender<T>,
@@ -69,6 +204,7 @@ impl<T> CommandMessage<T> {
             session_id,
             params: serde_json::to_value(cmd)?,
             sender,
+            timeout: None,
         })
     }
 
@@ -126,12 +262,12 @@ impl CommandChain {
     /// Removes the waiting state if the identifier matches that of the last
     /// issued command
     pub fn received_response(&mut self, identifier: &str) -> bool {
-        return if self.waiting.as_ref().map(|(c, _)| c.as_ref()) == Some(identifier) {
+        if self.waiting.as_ref().map(|(c, _)| c.as_ref()) == Some(identifier) {
             self.waiting.take();
             true
         } else {
             false
-        };
+        }
     }
 
     /// Return the next command to process or `None` if done.
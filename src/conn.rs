@@ -1,9 +1,17 @@
 use std::borrow::Cow;
 use std::collections::VecDeque;
+use std::io::{self, Read, Write};
 use std::marker::PhantomData;
 use std::pin::Pin;
+use std::process::ExitStatus;
 
+use async_tungstenite::tungstenite::client::IntoClientRequest;
+use async_tungstenite::tungstenite::handshake::client::Request;
+use async_tungstenite::tungstenite::http::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use async_tungstenite::tungstenite::http::Uri;
+use async_tungstenite::tungstenite::Message as WsMessage;
 use async_tungstenite::WebSocketStream;
+use futures::io::{AsyncReadExt, AsyncWriteExt};
 use futures::stream::Stream;
 use futures::task::{Context, Poll};
 use futures::Sink;
@@ -13,6 +21,8 @@ use chromiumoxide_types::{CallId, Event, Message, MethodCall};
 
 use crate::error::CdpError;
 use crate::error::Result;
+use crate::pipe::PipeTransport;
+use crate::wire_log::WireLog;
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "async-std-runtime")] {
@@ -21,44 +31,533 @@ cfg_if::cfg_if! {
         use async_tungstenite::tokio::ConnectStream;
     }
 }
-/// Exchanges the messages with the websocket
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "native-tls")] {
+        /// The stream to a chromium instance, encrypted for `wss://` urls
+        #[derive(Debug)]
+        pub(crate) enum MaybeTlsStream {
+            Plain(ConnectStream),
+            Tls(async_native_tls::TlsStream<ConnectStream>),
+        }
+
+        impl futures::io::AsyncRead for MaybeTlsStream {
+            fn poll_read(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &mut [u8],
+            ) -> Poll<io::Result<usize>> {
+                match self.get_mut() {
+                    MaybeTlsStream::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+                    MaybeTlsStream::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+                }
+            }
+        }
+
+        impl futures::io::AsyncWrite for MaybeTlsStream {
+            fn poll_write(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &[u8],
+            ) -> Poll<io::Result<usize>> {
+                match self.get_mut() {
+                    MaybeTlsStream::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+                    MaybeTlsStream::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+                }
+            }
+
+            fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+                match self.get_mut() {
+                    MaybeTlsStream::Plain(stream) => Pin::new(stream).poll_flush(cx),
+                    MaybeTlsStream::Tls(stream) => Pin::new(stream).poll_flush(cx),
+                }
+            }
+
+            fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+                match self.get_mut() {
+                    MaybeTlsStream::Plain(stream) => Pin::new(stream).poll_close(cx),
+                    MaybeTlsStream::Tls(stream) => Pin::new(stream).poll_close(cx),
+                }
+            }
+        }
+    } else {
+        /// The stream to a chromium instance, `wss://` urls require the
+        /// `native-tls` feature
+        pub(crate) type MaybeTlsStream = ConnectStream;
+    }
+}
+
+/// The websocket to a chromium instance
+pub(crate) type WsStream = WebSocketStream<MaybeTlsStream>;
+
+/// Options for opening the websocket to a remote chromium instance, like
+/// one behind a reverse proxy that requires authentication.
+///
+/// The options also apply to the `/json/version` request that resolves the
+/// websocket url of the browser, see
+/// [`Browser::connect`](crate::browser::Browser::connect).
+///
+/// `wss://` urls require the `native-tls` feature, which verifies the
+/// certificate of the host against the system's trust store and the
+/// [`ConnectOptions::root_certificate`]s.
+/// # Example
+///
+/// ```no_run
+/// # use chromiumoxide::browser::Browser;
+/// # use chromiumoxide::conn::ConnectOptions;
+/// # use chromiumoxide::error::Result;
+/// # async fn demo() -> Result<()> {
+///     let options = ConnectOptions::default()
+///         .basic_auth("user", "secret")
+///         .header("X-Tenant", "crawler")
+///         .proxy("10.0.0.1:3128");
+///     let (browser, handler) =
+///         Browser::connect_with_options("ws://chrome.internal/devtools/browser/<id>", options)
+///             .await?;
+///     # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConnectOptions {
+    /// Additional headers of the websocket handshake request
+    pub headers: Vec<(String, String)>,
+    /// The address of an HTTP proxy, `host:port`, the websocket is tunneled
+    /// through via `CONNECT`
+    pub proxy: Option<String>,
+    /// Whether to accept any certificate of `wss://` hosts, like expired or
+    /// self-signed certificates
+    pub accept_invalid_certs: bool,
+    /// Additional PEM encoded certificates to trust for `wss://` hosts
+    pub root_certificates: Vec<Vec<u8>>,
+}
+
+impl ConnectOptions {
+    /// Add a header to the websocket handshake request
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Authenticate the websocket handshake request with the HTTP basic
+    /// authentication `username` and `password`
+    pub fn basic_auth(self, username: impl AsRef<str>, password: impl AsRef<str>) -> Self {
+        let credentials = format!("{}:{}", username.as_ref(), password.as_ref());
+        self.header(
+            AUTHORIZATION.as_str(),
+            format!("Basic {}", base64::encode(credentials)),
+        )
+    }
+
+    /// Tunnel the websocket through the HTTP proxy at `addr`, `host:port`
+    pub fn proxy(mut self, addr: impl Into<String>) -> Self {
+        self.proxy = Some(addr.into());
+        self
+    }
+
+    /// Accept any certificate of `wss://` hosts, like expired or self-signed
+    /// certificates.
+    ///
+    /// This makes the connection vulnerable to man-in-the-middle attacks,
+    /// prefer trusting the certificate via
+    /// [`ConnectOptions::root_certificate`].
+    pub fn accept_invalid_certs(mut self) -> Self {
+        self.accept_invalid_certs = true;
+        self
+    }
+
+    /// Trust the PEM encoded certificate for `wss://` hosts, like the
+    /// self-signed certificate of a remote chromium instance
+    pub fn root_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates.push(pem.into());
+        self
+    }
+
+    /// The handshake request for the websocket at `url` with the headers
+    fn request(&self, url: &str) -> Result<Request> {
+        let mut request = url.into_client_request()?;
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|err| CdpError::msg(format!("Invalid header {:?}: {}", name, err)))?;
+            let value = HeaderValue::from_str(value).map_err(|err| {
+                CdpError::msg(format!("Invalid header value {:?}: {}", value, err))
+            })?;
+            request.headers_mut().append(name, value);
+        }
+        Ok(request)
+    }
+}
+
+/// Opens a new websocket to the chromium instance at `debug_ws_url`
+pub(crate) async fn connect_ws(debug_ws_url: &str, options: &ConnectOptions) -> Result<WsStream> {
+    let request = options.request(debug_ws_url)?;
+    let stream = connect_stream(request.uri(), options).await?;
+    let (ws, _) = async_tungstenite::client_async(request, stream).await?;
+    Ok(ws)
+}
+
+/// Requests the JSON document at the `http://` or `https://` `url`, with the
+/// headers and through the proxy of the `options`
+pub(crate) async fn get_json(url: &str, options: &ConnectOptions) -> Result<serde_json::Value> {
+    let uri: Uri = url
+        .parse()
+        .map_err(|err| CdpError::msg(format!("Invalid url {:?}: {}", url, err)))?;
+    let mut stream = connect_stream(&uri, options).await?;
+    let mut request = format!(
+        "GET {} HTTP/1.0\r\nHost: {}\r\n",
+        uri.path_and_query()
+            .map(|path| path.as_str())
+            .unwrap_or("/"),
+        uri.authority()
+            .map(|authority| authority.as_str())
+            .unwrap_or_default()
+    );
+    for (name, value) in &options.headers {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+    stream.flush().await?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await?;
+    let body = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .map(|pos| &response[pos + 4..])
+        .ok_or_else(|| CdpError::msg(format!("Invalid response from {}", url)))?;
+    Ok(serde_json::from_slice(body)?)
+}
+
+/// Opens a stream to the host of the `uri`, through the proxy of the
+/// `options` if set, which is encrypted for `wss://` and `https://` urls
+async fn connect_stream(uri: &Uri, options: &ConnectOptions) -> Result<MaybeTlsStream> {
+    let tls = match uri.scheme_str() {
+        Some("ws") | Some("http") => false,
+        Some("wss") | Some("https") => true,
+        _ => return Err(CdpError::msg(format!("Unsupported url: {}", uri))),
+    };
+    let host = uri
+        .host()
+        .ok_or_else(|| CdpError::msg(format!("No host in url: {}", uri)))?
+        .to_string();
+    let target = format!(
+        "{}:{}",
+        host,
+        uri.port_u16().unwrap_or(if tls { 443 } else { 80 })
+    );
+    let stream = match options.proxy.clone() {
+        Some(proxy) => {
+            let stream = crate::utils::spawn_blocking(move || tunnel(&proxy, &target)).await?;
+            stream.set_nonblocking(true)?;
+            cfg_if::cfg_if! {
+                if #[cfg(feature = "async-std-runtime")] {
+                    async_std::net::TcpStream::from(stream)
+                } else if #[cfg(feature = "tokio-runtime")] {
+                    async_tungstenite::tokio::TokioAdapter(tokio::net::TcpStream::from_std(stream)?)
+                }
+            }
+        }
+        None => {
+            cfg_if::cfg_if! {
+                if #[cfg(feature = "async-std-runtime")] {
+                    async_std::net::TcpStream::connect(&target).await?
+                } else if #[cfg(feature = "tokio-runtime")] {
+                    async_tungstenite::tokio::TokioAdapter(tokio::net::TcpStream::connect(&target).await?)
+                }
+            }
+        }
+    };
+    if tls {
+        encrypt(stream, &host, options).await
+    } else {
+        Ok(plain(stream))
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "native-tls")] {
+        fn plain(stream: ConnectStream) -> MaybeTlsStream {
+            MaybeTlsStream::Plain(stream)
+        }
+
+        /// Performs the TLS handshake with the `host`
+        async fn encrypt(
+            stream: ConnectStream,
+            host: &str,
+            options: &ConnectOptions,
+        ) -> Result<MaybeTlsStream> {
+            let mut connector = async_native_tls::TlsConnector::new()
+                .danger_accept_invalid_certs(options.accept_invalid_certs);
+            for pem in &options.root_certificates {
+                let certificate = async_native_tls::Certificate::from_pem(pem)
+                    .map_err(|err| CdpError::msg(format!("Invalid root certificate: {}", err)))?;
+                connector = connector.add_root_certificate(certificate);
+            }
+            let stream = connector.connect(host, stream).await.map_err(|err| {
+                CdpError::msg(format!("TLS handshake with {} failed: {}", host, err))
+            })?;
+            Ok(MaybeTlsStream::Tls(stream))
+        }
+    } else {
+        fn plain(stream: ConnectStream) -> MaybeTlsStream {
+            stream
+        }
+
+        async fn encrypt(
+            _: ConnectStream,
+            host: &str,
+            _: &ConnectOptions,
+        ) -> Result<MaybeTlsStream> {
+            Err(CdpError::msg(format!(
+                "Connecting to {} via TLS requires the `native-tls` feature",
+                host
+            )))
+        }
+    }
+}
+
+/// Opens a tunnel to the `target`, `host:port`, through the HTTP proxy at
+/// `proxy`
+fn tunnel(proxy: &str, target: &str) -> io::Result<std::net::TcpStream> {
+    let mut stream = std::net::TcpStream::connect(proxy)?;
+    write!(
+        stream,
+        "CONNECT {} HTTP/1.1\r\nHost: {}\r\n\r\n",
+        target, target
+    )?;
+    // read the response byte by byte, so that nothing after its head is
+    // consumed
+    let mut head = Vec::new();
+    let mut byte = [0; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if stream.read(&mut byte)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        head.push(byte[0]);
+    }
+    let status = String::from_utf8_lossy(&head);
+    let status = status.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(io::Error::other(format!(
+            "The proxy {} refused the tunnel: {}",
+            proxy, status
+        )));
+    }
+    Ok(stream)
+}
+
+/// How the messages are exchanged with the chromium instance
+#[derive(Debug)]
+pub(crate) enum Transport {
+    /// The websocket of the debugging port
+    Ws(Box<WsStream>),
+    /// The pipes of a browser launched with `--remote-debugging-pipe`
+    Pipe(PipeTransport),
+}
+
+impl Transport {
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        match self {
+            Transport::Ws(ws) => Sink::poll_ready(Pin::new(ws), cx).map_err(Into::into),
+            Transport::Pipe(_) => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn start_send(&mut self, msg: String) -> Result<()> {
+        match self {
+            Transport::Ws(ws) => Ok(Sink::start_send(Pin::new(ws), msg.into())?),
+            Transport::Pipe(pipe) => Ok(pipe.send(msg)?),
+        }
+    }
+
+    /// Sends a ping frame, pipes don't need to be kept alive
+    fn start_ping(&mut self) -> Result<bool> {
+        match self {
+            Transport::Ws(ws) => {
+                Sink::start_send(Pin::new(ws), WsMessage::Ping(Vec::new()))?;
+                Ok(true)
+            }
+            Transport::Pipe(_) => Ok(false),
+        }
+    }
+
+    fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        match self {
+            Transport::Ws(ws) => Sink::poll_flush(Pin::new(ws), cx).map_err(Into::into),
+            Transport::Pipe(_) => Poll::Ready(Ok(())),
+        }
+    }
+
+    /// Sends the close frame of the websocket, the pipes are closed once the
+    /// transport is dropped
+    fn poll_close(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        match self {
+            Transport::Ws(ws) => Sink::poll_close(Pin::new(ws), cx).map_err(Into::into),
+            Transport::Pipe(_) => Poll::Ready(Ok(())),
+        }
+    }
+
+    /// Reads the payload of the next message, `None` once the transport was
+    /// closed
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Vec<u8>>>> {
+        match self {
+            Transport::Ws(ws) => loop {
+                return match Stream::poll_next(Pin::new(&mut *ws), cx) {
+                    Poll::Ready(Some(Ok(msg))) => {
+                        if msg.is_ping() || msg.is_pong() || msg.is_close() {
+                            continue;
+                        }
+                        Poll::Ready(Some(Ok(msg.into_data())))
+                    }
+                    Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(CdpError::Ws(err)))),
+                    Poll::Ready(None) => Poll::Ready(None),
+                    Poll::Pending => Poll::Pending,
+                };
+            },
+            Transport::Pipe(pipe) => Stream::poll_next(Pin::new(pipe), cx)
+                .map(|msg| msg.map(|msg| msg.map_err(CdpError::Io))),
+        }
+    }
+}
+
+/// Changes of the state of the websocket connection to the chromium instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionEvent {
+    /// The websocket connection was lost, all requests that were awaiting a
+    /// response failed with `CdpError::Disconnected`
+    Disconnected,
+    /// The websocket connection was re-established after `attempts` tries and
+    /// the targets are attached again.
+    ///
+    /// The pages restore their enabled domains, routes and emulated media,
+    /// but other state of their previous sessions is lost, like scripts
+    /// added via `Page::evaluate_on_new_document` or emulation overrides, and
+    /// needs to be set up again.
+    Reconnected { attempts: usize },
+    /// The connection could not be re-established, the `Handler` is done
+    Closed,
+    /// The process of the launched browser exited unexpectedly, all requests
+    /// that were awaiting a response failed with `CdpError::BrowserCrashed`.
+    /// Followed by `Closed`
+    Crashed(ExitStatus),
+}
+/// Exchanges the messages with the chromium instance, over its websocket or
+/// the pipes of a browser launched with `BrowserConfigBuilder::pipe`
 #[must_use = "streams do nothing unless polled"]
 #[derive(Debug)]
 pub struct Connection<T: Event> {
     /// Queue of commands to send.
     pending_commands: VecDeque<MethodCall>,
-    /// The websocket of the chromium instance
-    ws: WebSocketStream<ConnectStream>,
+    /// The transport to the chromium instance
+    transport: Transport,
+    /// The url of the websocket, empty for pipes
+    url: String,
+    /// How the websocket is opened, again when reconnecting
+    options: ConnectOptions,
+    /// Whether a ping frame should be sent next
+    needs_ping: bool,
     /// The identifier for a specific command
     next_id: usize,
     needs_flush: bool,
     /// The message that is currently being proceessed
     pending_flush: Option<MethodCall>,
+    /// Logs all the messages, if enabled
+    wire_log: Option<WireLog>,
     _marker: PhantomData<T>,
 }
 
 impl<T: Event + Unpin> Connection<T> {
     pub async fn connect(debug_ws_url: impl AsRef<str>) -> Result<Self> {
-        cfg_if::cfg_if! {
-            if #[cfg(feature = "async-std-runtime")] {
-               let (ws, _) = async_tungstenite::async_std::connect_async(debug_ws_url.as_ref()).await?;
-            } else if #[cfg(feature = "tokio-runtime")] {
-                 let (ws, _) = async_tungstenite::tokio::connect_async(debug_ws_url.as_ref()).await?;
-            }
-        }
+        Self::connect_with_options(debug_ws_url, ConnectOptions::default()).await
+    }
+
+    /// Opens the websocket at `debug_ws_url` with the `options`
+    pub async fn connect_with_options(
+        debug_ws_url: impl AsRef<str>,
+        options: ConnectOptions,
+    ) -> Result<Self> {
+        let ws = connect_ws(debug_ws_url.as_ref(), &options).await?;
+        let mut conn = Self::new(
+            Transport::Ws(Box::new(ws)),
+            debug_ws_url.as_ref().to_string(),
+        );
+        conn.options = options;
+        Ok(conn)
+    }
 
-        Ok(Self {
+    /// A connection over the pipes of a launched browser
+    pub(crate) fn pipe(pipe: PipeTransport) -> Self {
+        Self::new(Transport::Pipe(pipe), String::new())
+    }
+
+    fn new(transport: Transport, url: String) -> Self {
+        Self {
             pending_commands: Default::default(),
-            ws,
+            transport,
+            url,
+            options: Default::default(),
+            needs_ping: false,
             next_id: 0,
             needs_flush: false,
             pending_flush: None,
+            wire_log: None,
             _marker: Default::default(),
-        })
+        }
     }
 }
 
 impl<T: Event> Connection<T> {
+    /// The url of the websocket this connection is connected to, empty if
+    /// connected via pipes
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// How the websocket is opened
+    pub fn options(&self) -> &ConnectOptions {
+        &self.options
+    }
+
+    /// Whether the connection can be re-established after it was lost, which
+    /// is only possible for websockets
+    pub fn is_reconnectable(&self) -> bool {
+        matches!(self.transport, Transport::Ws(_))
+    }
+
+    /// Replace the websocket after the previous one was disconnected.
+    ///
+    /// Commands that are still queued are sent over the new websocket.
+    pub(crate) fn set_ws(&mut self, ws: WsStream) {
+        if let Some(call) = self.pending_flush.take() {
+            self.pending_commands.push_front(call);
+        }
+        self.needs_flush = false;
+        self.needs_ping = false;
+        self.transport = Transport::Ws(Box::new(ws));
+    }
+
+    /// Closes the websocket with a close frame
+    pub(crate) fn poll_close(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.transport.poll_close(cx)
+    }
+
+    /// Log all the messages exchanged over this connection
+    pub(crate) fn set_wire_log(&mut self, wire_log: Option<WireLog>) {
+        self.wire_log = wire_log;
+    }
+
+    /// The command will not receive a response anymore, like after it timed
+    /// out
+    pub(crate) fn on_command_expired(&mut self, id: CallId) {
+        if let Some(wire_log) = self.wire_log.as_mut() {
+            wire_log.on_expired(id);
+        }
+    }
+
+    /// Queue in a ping frame that keeps the websocket alive
+    pub fn ping(&mut self) {
+        self.needs_ping = true;
+    }
+
     fn next_call_id(&mut self) -> CallId {
         let id = CallId::new(self.next_id);
         self.next_id = self.next_id.wrapping_add(1);
@@ -88,14 +587,21 @@ impl<T: Event> Connection<T> {
     /// sink
     fn start_send_next(&mut self, cx: &mut Context<'_>) -> Result<()> {
         if self.needs_flush {
-            if let Poll::Ready(Ok(())) = Sink::poll_flush(Pin::new(&mut self.ws), cx) {
+            if let Poll::Ready(Ok(())) = self.transport.poll_flush(cx) {
                 self.needs_flush = false;
             }
         }
+        if self.needs_ping && self.pending_flush.is_none() && !self.needs_flush {
+            self.needs_flush = self.transport.start_ping()?;
+            self.needs_ping = false;
+        }
         if self.pending_flush.is_none() && !self.needs_flush {
             if let Some(cmd) = self.pending_commands.pop_front() {
                 let msg = serde_json::to_string(&cmd)?;
-                Sink::start_send(Pin::new(&mut self.ws), msg.into())?;
+                if let Some(wire_log) = self.wire_log.as_mut() {
+                    wire_log.on_send(&cmd);
+                }
+                self.transport.start_send(msg)?;
                 self.pending_flush = Some(cmd);
             }
         }
@@ -116,25 +622,27 @@ impl<T: Event + Unpin> Stream for Connection<T> {
 
         // send the message
         if let Some(call) = pin.pending_flush.take() {
-            if Sink::poll_ready(Pin::new(&mut pin.ws), cx).is_ready() {
+            if pin.transport.poll_ready(cx).is_ready() {
                 pin.needs_flush = true;
             } else {
                 pin.pending_flush = Some(call);
             }
         }
-        // read from the ws
-        match Stream::poll_next(Pin::new(&mut pin.ws), cx) {
-            Poll::Ready(Some(Ok(msg))) => {
-                return match serde_json::from_slice::<Message<T>>(&msg.into_data()) {
+        // read from the transport
+        match pin.transport.poll_next(cx) {
+            Poll::Ready(Some(Ok(data))) => {
+                if let Some(wire_log) = pin.wire_log.as_mut() {
+                    wire_log.on_receive(&data);
+                }
+                match serde_json::from_slice::<Message<T>>(&data) {
                     Ok(msg) => Poll::Ready(Some(Ok(msg))),
                     Err(err) => Poll::Ready(Some(Err(err.into()))),
-                };
-            }
-            Poll::Ready(Some(Err(err))) => {
-                return Poll::Ready(Some(Err(CdpError::Ws(err))));
+                }
             }
-            _ => {}
+            Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(err))),
+            // the websocket or pipe was closed
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
         }
-        Poll::Pending
     }
 }
@@ -0,0 +1,172 @@
+use std::pin::Pin;
+
+use futures::task::{Context, Poll};
+use futures::Stream;
+
+use chromiumoxide_cdp::cdp::browser_protocol::log::{
+    EventEntryAdded, LogEntryLevel, LogEntrySource,
+};
+use chromiumoxide_cdp::cdp::js_protocol::runtime::{
+    ConsoleApiCalledType, EventConsoleApiCalled, EventExceptionThrown, RemoteObject, StackTrace,
+};
+
+use crate::listeners::EventStream;
+
+/// Where a `ConsoleMessage` originates from
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConsoleMessageSource {
+    /// A call of the `console` api, like `console.log`, via
+    /// `Runtime.consoleAPICalled`
+    ConsoleApi(ConsoleApiCalledType),
+    /// An entry of the browser's log, like a network error or a violation,
+    /// via `Log.entryAdded`
+    Log(LogEntrySource),
+    /// An uncaught exception via `Runtime.exceptionThrown`
+    Exception,
+}
+
+/// A message that was logged to the console of a page
+#[derive(Debug, Clone)]
+pub struct ConsoleMessage {
+    /// The origin of the message
+    pub source: ConsoleMessageSource,
+    /// The severity of the message
+    pub level: LogEntryLevel,
+    /// The text of the message
+    pub text: String,
+    /// The time the message was logged at in milliseconds since epoch
+    pub timestamp: f64,
+    /// The url of the resource that logged the message, if known
+    pub url: Option<String>,
+    /// The line within the resource that logged the message, if known
+    pub line_number: Option<i64>,
+    /// The javascript stack trace at the time the message was logged
+    pub stack_trace: Option<StackTrace>,
+    /// The arguments that were passed to the `console` api call
+    pub args: Vec<RemoteObject>,
+}
+
+impl From<&EventConsoleApiCalled> for ConsoleMessage {
+    fn from(ev: &EventConsoleApiCalled) -> Self {
+        let level = match ev.r#type {
+            ConsoleApiCalledType::Debug => LogEntryLevel::Verbose,
+            ConsoleApiCalledType::Error | ConsoleApiCalledType::Assert => LogEntryLevel::Error,
+            ConsoleApiCalledType::Warning => LogEntryLevel::Warning,
+            _ => LogEntryLevel::Info,
+        };
+        let text = ev
+            .args
+            .iter()
+            .map(remote_object_to_string)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let top_frame = ev
+            .stack_trace
+            .as_ref()
+            .and_then(|trace| trace.call_frames.first());
+        Self {
+            source: ConsoleMessageSource::ConsoleApi(ev.r#type.clone()),
+            level,
+            text,
+            timestamp: *ev.timestamp.inner(),
+            url: top_frame.map(|frame| frame.url.clone()),
+            line_number: top_frame.map(|frame| frame.line_number),
+            stack_trace: ev.stack_trace.clone(),
+            args: ev.args.clone(),
+        }
+    }
+}
+
+impl From<&EventEntryAdded> for ConsoleMessage {
+    fn from(ev: &EventEntryAdded) -> Self {
+        let entry = &ev.entry;
+        Self {
+            source: ConsoleMessageSource::Log(entry.source.clone()),
+            level: entry.level.clone(),
+            text: entry.text.clone(),
+            timestamp: *entry.timestamp.inner(),
+            url: entry.url.clone(),
+            line_number: entry.line_number,
+            stack_trace: entry.stack_trace.clone(),
+            args: entry.args.clone().unwrap_or_default(),
+        }
+    }
+}
+
+impl From<&EventExceptionThrown> for ConsoleMessage {
+    fn from(ev: &EventExceptionThrown) -> Self {
+        let details = &ev.exception_details;
+        // the description of the exception includes its message and the stack
+        let text = details
+            .exception
+            .as_ref()
+            .and_then(|exception| exception.description.clone())
+            .unwrap_or_else(|| details.text.clone());
+        Self {
+            source: ConsoleMessageSource::Exception,
+            level: LogEntryLevel::Error,
+            text,
+            timestamp: *ev.timestamp.inner(),
+            url: details.url.clone(),
+            line_number: Some(details.line_number),
+            stack_trace: details.stack_trace.clone(),
+            args: details.exception.clone().into_iter().collect(),
+        }
+    }
+}
+
+/// A human readable representation of the object
+fn remote_object_to_string(obj: &RemoteObject) -> String {
+    match &obj.value {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(value) => value.to_string(),
+        None => obj
+            .unserializable_value
+            .as_ref()
+            .map(|value| value.inner().clone())
+            .or_else(|| obj.description.clone())
+            .unwrap_or_else(|| format!("{:?}", obj.r#type).to_lowercase()),
+    }
+}
+
+/// A `Stream` over all the `ConsoleMessage`s of a `Page` or a `Worker`.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct ConsoleMessages {
+    console_api: EventStream<EventConsoleApiCalled>,
+    log: EventStream<EventEntryAdded>,
+    exceptions: EventStream<EventExceptionThrown>,
+}
+
+impl ConsoleMessages {
+    pub(crate) fn new(
+        console_api: EventStream<EventConsoleApiCalled>,
+        log: EventStream<EventEntryAdded>,
+        exceptions: EventStream<EventExceptionThrown>,
+    ) -> Self {
+        Self {
+            console_api,
+            log,
+            exceptions,
+        }
+    }
+}
+
+impl Stream for ConsoleMessages {
+    type Item = ConsoleMessage;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        if let Poll::Ready(Some(ev)) = Pin::new(&mut pin.console_api).poll_next(cx) {
+            return Poll::Ready(Some(ev.as_ref().into()));
+        }
+        if let Poll::Ready(Some(ev)) = Pin::new(&mut pin.exceptions).poll_next(cx) {
+            return Poll::Ready(Some(ev.as_ref().into()));
+        }
+        match Pin::new(&mut pin.log).poll_next(cx) {
+            Poll::Ready(Some(ev)) => Poll::Ready(Some(ev.as_ref().into())),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! Descriptors of common devices to emulate with `Page::emulate`.
+//!
+//! The values are taken from [puppeteer's device descriptors](https://github.com/puppeteer/puppeteer/blob/main/src/common/DeviceDescriptors.ts)
+
+use crate::handler::viewport::Viewport;
+
+/// Describes a device with its user agent and viewport
+#[derive(Debug, Clone)]
+pub struct Device {
+    pub name: &'static str,
+    pub user_agent: &'static str,
+    pub viewport: Viewport,
+}
+
+impl Device {
+    /// Returns the same device, rotated by 90 degrees
+    pub fn landscape(&self) -> Self {
+        let mut device = self.clone();
+        device.viewport.width = self.viewport.height;
+        device.viewport.height = self.viewport.width;
+        device.viewport.is_landscape = !self.viewport.is_landscape;
+        device
+    }
+}
+
+const fn mobile_viewport(width: u32, height: u32, device_scale_factor: f64) -> Viewport {
+    Viewport {
+        width,
+        height,
+        device_scale_factor: Some(device_scale_factor),
+        is_mobile: true,
+        is_landscape: false,
+        has_touch: true,
+    }
+}
+
+pub const DEVICES: [Device; 10] = [
+    Device {
+        name: "iPhone SE",
+        user_agent: "Mozilla/5.0 (iPhone; CPU iPhone OS 10_3_1 like Mac OS X) AppleWebKit/603.1.30 (KHTML, like Gecko) Version/10.0 Mobile/14E304 Safari/602.1",
+        viewport: mobile_viewport(320, 568, 2.),
+    },
+    Device {
+        name: "iPhone 8",
+        user_agent: "Mozilla/5.0 (iPhone; CPU iPhone OS 11_0 like Mac OS X) AppleWebKit/604.1.38 (KHTML, like Gecko) Version/11.0 Mobile/15A372 Safari/604.1",
+        viewport: mobile_viewport(375, 667, 2.),
+    },
+    Device {
+        name: "iPhone X",
+        user_agent: "Mozilla/5.0 (iPhone; CPU iPhone OS 11_0 like Mac OS X) AppleWebKit/604.1.38 (KHTML, like Gecko) Version/11.0 Mobile/15A372 Safari/604.1",
+        viewport: mobile_viewport(375, 812, 3.),
+    },
+    Device {
+        name: "iPhone 11",
+        user_agent: "Mozilla/5.0 (iPhone; CPU iPhone OS 13_7 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/13.1 Mobile/15E148 Safari/604.1",
+        viewport: mobile_viewport(414, 828, 2.),
+    },
+    Device {
+        name: "iPad",
+        user_agent: "Mozilla/5.0 (iPad; CPU OS 11_0 like Mac OS X) AppleWebKit/604.1.34 (KHTML, like Gecko) Version/11.0 Mobile/15A5341f Safari/604.1",
+        viewport: mobile_viewport(768, 1024, 2.),
+    },
+    Device {
+        name: "iPad Pro",
+        user_agent: "Mozilla/5.0 (iPad; CPU OS 11_0 like Mac OS X) AppleWebKit/604.1.34 (KHTML, like Gecko) Version/11.0 Mobile/15A5341f Safari/604.1",
+        viewport: mobile_viewport(1024, 1366, 2.),
+    },
+    Device {
+        name: "Pixel 2",
+        user_agent: "Mozilla/5.0 (Linux; Android 8.0; Pixel 2 Build/OPD3.170816.012) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/75.0.3765.0 Mobile Safari/537.36",
+        viewport: mobile_viewport(411, 731, 2.625),
+    },
+    Device {
+        name: "Pixel 2 XL",
+        user_agent: "Mozilla/5.0 (Linux; Android 8.0.0; Pixel 2 XL Build/OPD1.170816.004) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/75.0.3765.0 Mobile Safari/537.36",
+        viewport: mobile_viewport(411, 823, 3.5),
+    },
+    Device {
+        name: "Galaxy S5",
+        user_agent: "Mozilla/5.0 (Linux; Android 5.0; SM-G900P Build/LRX21T) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/75.0.3765.0 Mobile Safari/537.36",
+        viewport: mobile_viewport(360, 640, 3.),
+    },
+    Device {
+        name: "Nexus 10",
+        user_agent: "Mozilla/5.0 (Linux; Android 6.0.1; Nexus 10 Build/MOB31T) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/75.0.3765.0 Safari/537.36",
+        viewport: mobile_viewport(800, 1280, 2.),
+    },
+];
+
+/// Returns the `Device` by its name
+/// # Example return the `iPhone X`: `get_device("iPhone X").unwrap()`
+pub fn get_device(name: impl AsRef<str>) -> Option<&'static Device> {
+    let name = name.as_ref();
+    DEVICES.iter().find(|device| device.name == name)
+}
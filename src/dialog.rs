@@ -0,0 +1,128 @@
+use std::pin::Pin;
+use std::sync::Arc;
+
+use futures::task::{Context, Poll};
+use futures::Stream;
+
+use chromiumoxide_cdp::cdp::browser_protocol::page::{
+    DialogType, EventJavascriptDialogOpening, HandleJavaScriptDialogParams,
+};
+
+use crate::error::Result;
+use crate::handler::PageInner;
+use crate::listeners::EventStream;
+
+/// How a `Page` handles javascript dialogs (alert, confirm, prompt or
+/// onbeforeunload) while there is no `DialogStream` of the page.
+///
+/// An open dialog blocks the page, so that pending navigations never finish.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum DialogPolicy {
+    /// Leave the dialog open until it is handled manually.
+    Manual,
+    /// Accept the dialog, prompts are answered with their default value.
+    #[default]
+    Accept,
+    /// Dismiss the dialog.
+    Dismiss,
+}
+
+impl DialogPolicy {
+    /// Whether to accept the dialog, `None` if the dialog should stay open
+    pub(crate) fn accept(&self) -> Option<bool> {
+        match self {
+            DialogPolicy::Manual => None,
+            DialogPolicy::Accept => Some(true),
+            DialogPolicy::Dismiss => Some(false),
+        }
+    }
+}
+
+/// A `Stream` over all javascript dialogs that are opened by a `Page`.
+///
+/// As long as this stream exists, dialogs are not handled by the
+/// `DialogPolicy` of the page and need to be resolved via `Dialog::accept`
+/// or `Dialog::dismiss`.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct DialogStream {
+    page: Arc<PageInner>,
+    events: EventStream<EventJavascriptDialogOpening>,
+}
+
+impl DialogStream {
+    pub(crate) fn new(
+        page: Arc<PageInner>,
+        events: EventStream<EventJavascriptDialogOpening>,
+    ) -> Self {
+        page.set_dialog_stream(true);
+        Self { page, events }
+    }
+}
+
+impl Drop for DialogStream {
+    fn drop(&mut self) {
+        self.page.set_dialog_stream(false);
+    }
+}
+
+impl Stream for DialogStream {
+    type Item = Dialog;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        match Pin::new(&mut pin.events).poll_next(cx) {
+            Poll::Ready(Some(event)) => Poll::Ready(Some(Dialog {
+                page: Arc::clone(&pin.page),
+                event,
+            })),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// A javascript dialog that is currently open.
+#[derive(Debug)]
+pub struct Dialog {
+    page: Arc<PageInner>,
+    event: Arc<EventJavascriptDialogOpening>,
+}
+
+impl Dialog {
+    /// The kind of the dialog
+    pub fn r#type(&self) -> &DialogType {
+        &self.event.r#type
+    }
+
+    /// The message that is displayed in the dialog
+    pub fn message(&self) -> &str {
+        &self.event.message
+    }
+
+    /// The default value of a prompt dialog
+    pub fn default_prompt(&self) -> Option<&str> {
+        self.event.default_prompt.as_deref()
+    }
+
+    /// The url of the frame that opened the dialog
+    pub fn url(&self) -> &str {
+        &self.event.url
+    }
+
+    /// Accepts the dialog, a prompt dialog is answered with the `prompt_text`
+    pub async fn accept(self, prompt_text: Option<String>) -> Result<()> {
+        let mut params = HandleJavaScriptDialogParams::new(true);
+        params.prompt_text = prompt_text;
+        self.page.execute(params).await?;
+        Ok(())
+    }
+
+    /// Dismisses the dialog
+    pub async fn dismiss(self) -> Result<()> {
+        self.page
+            .execute(HandleJavaScriptDialogParams::new(false))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+use std::pin::Pin;
+
+use futures::task::{Context, Poll};
+use futures::Stream;
+
+use chromiumoxide_cdp::cdp::browser_protocol::dom::{
+    EventAttributeModified, EventAttributeRemoved, EventCharacterDataModified,
+    EventChildNodeInserted, EventChildNodeRemoved, EventDocumentUpdated, Node, NodeId,
+};
+
+use crate::listeners::EventStream;
+
+/// A change of the DOM of a page
+#[derive(Debug, Clone, PartialEq)]
+pub enum DomChange {
+    /// The attribute `name` of the node was set to `value`, via
+    /// `DOM.attributeModified`
+    AttributeModified {
+        node_id: NodeId,
+        name: String,
+        value: String,
+    },
+    /// The attribute `name` was removed from the node, via
+    /// `DOM.attributeRemoved`
+    AttributeRemoved { node_id: NodeId, name: String },
+    /// The text of the node changed, via `DOM.characterDataModified`
+    CharacterDataModified {
+        node_id: NodeId,
+        character_data: String,
+    },
+    /// The `node` was inserted into the parent after the previous node, via
+    /// `DOM.childNodeInserted`
+    ChildNodeInserted {
+        parent_node_id: NodeId,
+        previous_node_id: NodeId,
+        node: Box<Node>,
+    },
+    /// The node was removed from the parent, via `DOM.childNodeRemoved`
+    ChildNodeRemoved {
+        parent_node_id: NodeId,
+        node_id: NodeId,
+    },
+    /// The document was replaced, all the node ids that were known before are
+    /// invalid, via `DOM.documentUpdated`
+    DocumentUpdated,
+}
+
+impl From<&EventAttributeModified> for DomChange {
+    fn from(ev: &EventAttributeModified) -> Self {
+        DomChange::AttributeModified {
+            node_id: ev.node_id,
+            name: ev.name.clone(),
+            value: ev.value.clone(),
+        }
+    }
+}
+
+impl From<&EventAttributeRemoved> for DomChange {
+    fn from(ev: &EventAttributeRemoved) -> Self {
+        DomChange::AttributeRemoved {
+            node_id: ev.node_id,
+            name: ev.name.clone(),
+        }
+    }
+}
+
+impl From<&EventCharacterDataModified> for DomChange {
+    fn from(ev: &EventCharacterDataModified) -> Self {
+        DomChange::CharacterDataModified {
+            node_id: ev.node_id,
+            character_data: ev.character_data.clone(),
+        }
+    }
+}
+
+impl From<&EventChildNodeInserted> for DomChange {
+    fn from(ev: &EventChildNodeInserted) -> Self {
+        DomChange::ChildNodeInserted {
+            parent_node_id: ev.parent_node_id,
+            previous_node_id: ev.previous_node_id,
+            node: Box::new(ev.node.clone()),
+        }
+    }
+}
+
+impl From<&EventChildNodeRemoved> for DomChange {
+    fn from(ev: &EventChildNodeRemoved) -> Self {
+        DomChange::ChildNodeRemoved {
+            parent_node_id: ev.parent_node_id,
+            node_id: ev.node_id,
+        }
+    }
+}
+
+/// A `Stream` over all the `DomChange`s of a `Page`.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct DomChanges {
+    attribute_modified: EventStream<EventAttributeModified>,
+    attribute_removed: EventStream<EventAttributeRemoved>,
+    character_data_modified: EventStream<EventCharacterDataModified>,
+    child_node_inserted: EventStream<EventChildNodeInserted>,
+    child_node_removed: EventStream<EventChildNodeRemoved>,
+    document_updated: EventStream<EventDocumentUpdated>,
+}
+
+impl DomChanges {
+    pub(crate) fn new(
+        attribute_modified: EventStream<EventAttributeModified>,
+        attribute_removed: EventStream<EventAttributeRemoved>,
+        character_data_modified: EventStream<EventCharacterDataModified>,
+        child_node_inserted: EventStream<EventChildNodeInserted>,
+        child_node_removed: EventStream<EventChildNodeRemoved>,
+        document_updated: EventStream<EventDocumentUpdated>,
+    ) -> Self {
+        Self {
+            attribute_modified,
+            attribute_removed,
+            character_data_modified,
+            child_node_inserted,
+            child_node_removed,
+            document_updated,
+        }
+    }
+}
+
+impl Stream for DomChanges {
+    type Item = DomChange;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        if let Poll::Ready(Some(_)) = Pin::new(&mut pin.document_updated).poll_next(cx) {
+            return Poll::Ready(Some(DomChange::DocumentUpdated));
+        }
+        if let Poll::Ready(Some(ev)) = Pin::new(&mut pin.attribute_modified).poll_next(cx) {
+            return Poll::Ready(Some(ev.as_ref().into()));
+        }
+        if let Poll::Ready(Some(ev)) = Pin::new(&mut pin.attribute_removed).poll_next(cx) {
+            return Poll::Ready(Some(ev.as_ref().into()));
+        }
+        if let Poll::Ready(Some(ev)) = Pin::new(&mut pin.character_data_modified).poll_next(cx) {
+            return Poll::Ready(Some(ev.as_ref().into()));
+        }
+        if let Poll::Ready(Some(ev)) = Pin::new(&mut pin.child_node_inserted).poll_next(cx) {
+            return Poll::Ready(Some(ev.as_ref().into()));
+        }
+        match Pin::new(&mut pin.child_node_removed).poll_next(cx) {
+            Poll::Ready(Some(ev)) => Poll::Ready(Some(ev.as_ref().into())),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
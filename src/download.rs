@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+
+use futures::task::{Context, Poll};
+use futures::{Stream, StreamExt};
+
+use chromiumoxide_cdp::cdp::browser_protocol::page::{
+    DownloadProgressState, EventDownloadProgress, EventDownloadWillBegin,
+};
+
+use crate::error::{CdpError, Result};
+use crate::listeners::EventStream;
+
+/// The state of a download of a `Page`
+#[derive(Debug, Clone)]
+pub struct Download {
+    /// The event that started this download
+    begin: Arc<EventDownloadWillBegin>,
+    /// The directory the file is downloaded to
+    download_path: Arc<PathBuf>,
+    /// Total expected bytes to download
+    total_bytes: f64,
+    /// Total bytes received so far
+    received_bytes: f64,
+    state: DownloadProgressState,
+}
+
+impl Download {
+    /// Global unique identifier of the download
+    pub fn guid(&self) -> &str {
+        &self.begin.guid
+    }
+
+    /// URL of the resource being downloaded
+    pub fn url(&self) -> &str {
+        &self.begin.url
+    }
+
+    /// Suggested file name of the resource
+    pub fn suggested_filename(&self) -> &str {
+        &self.begin.suggested_filename
+    }
+
+    /// The path of the downloaded file.
+    ///
+    /// Chromium saves the file with its suggested file name, which may differ
+    /// if a file with the same name already exists.
+    pub fn path(&self) -> PathBuf {
+        self.download_path.join(self.suggested_filename())
+    }
+
+    /// Total expected bytes to download
+    pub fn total_bytes(&self) -> f64 {
+        self.total_bytes
+    }
+
+    /// Total bytes received so far
+    pub fn received_bytes(&self) -> f64 {
+        self.received_bytes
+    }
+
+    pub fn state(&self) -> &DownloadProgressState {
+        &self.state
+    }
+
+    /// Whether the download completed successfully
+    pub fn is_completed(&self) -> bool {
+        self.state == DownloadProgressState::Completed
+    }
+
+    /// Whether the download was canceled
+    pub fn is_canceled(&self) -> bool {
+        self.state == DownloadProgressState::Canceled
+    }
+}
+
+/// Keeps track of all the downloads of a `Page`.
+///
+/// This is a `Stream` that yields the current state of a `Download` whenever
+/// a download started or made progress. Completed and canceled downloads are
+/// yielded one last time and are not tracked afterwards.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct DownloadManager {
+    download_path: Arc<PathBuf>,
+    will_begin: EventStream<EventDownloadWillBegin>,
+    progress: EventStream<EventDownloadProgress>,
+    /// All the downloads that are in progress
+    downloads: HashMap<String, Download>,
+}
+
+impl DownloadManager {
+    pub(crate) fn new(
+        download_path: PathBuf,
+        will_begin: EventStream<EventDownloadWillBegin>,
+        progress: EventStream<EventDownloadProgress>,
+    ) -> Self {
+        Self {
+            download_path: Arc::new(download_path),
+            will_begin,
+            progress,
+            downloads: Default::default(),
+        }
+    }
+
+    /// The directory the downloads are saved to
+    pub fn download_path(&self) -> &Path {
+        &self.download_path
+    }
+
+    /// All downloads that are currently in progress
+    pub fn downloads(&self) -> impl Iterator<Item = &Download> + '_ {
+        self.downloads.values()
+    }
+
+    /// Waits until the next download completed.
+    ///
+    /// Fails if the download was canceled.
+    pub async fn wait_for_download(&mut self) -> Result<Download> {
+        while let Some(download) = self.next().await {
+            if download.is_completed() {
+                return Ok(download);
+            }
+            if download.is_canceled() {
+                return Err(CdpError::msg(format!(
+                    "Download of {} was canceled",
+                    download.url()
+                )));
+            }
+        }
+        Err(CdpError::NotFound)
+    }
+}
+
+impl Stream for DownloadManager {
+    type Item = Download;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        // process the start of a download first so that its progress can be
+        // tracked
+        if let Poll::Ready(Some(begin)) = Pin::new(&mut pin.will_begin).poll_next(cx) {
+            let download = Download {
+                begin,
+                download_path: Arc::clone(&pin.download_path),
+                total_bytes: 0.,
+                received_bytes: 0.,
+                state: DownloadProgressState::InProgress,
+            };
+            pin.downloads
+                .insert(download.guid().to_string(), download.clone());
+            return Poll::Ready(Some(download));
+        }
+        loop {
+            match Pin::new(&mut pin.progress).poll_next(cx) {
+                Poll::Ready(Some(progress)) => {
+                    let done = progress.state != DownloadProgressState::InProgress;
+                    let download = if done {
+                        pin.downloads.remove(&progress.guid)
+                    } else {
+                        pin.downloads.get_mut(&progress.guid).map(|d| d.clone())
+                    };
+                    // ignore progress of downloads that started before
+                    if let Some(mut download) = download {
+                        download.total_bytes = progress.total_bytes;
+                        download.received_bytes = progress.received_bytes;
+                        download.state = progress.state.clone();
+                        if let Some(tracked) = pin.downloads.get_mut(&progress.guid) {
+                            *tracked = download.clone();
+                        }
+                        return Poll::Ready(Some(download));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
@@ -1,13 +1,18 @@
 use std::collections::HashMap;
+use std::path::Path;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
-use futures::{future, Future, FutureExt, Stream};
+use futures::{future, stream, Future, FutureExt, Stream, StreamExt};
 
 use chromiumoxide_cdp::cdp::browser_protocol::dom::{
-    BackendNodeId, DescribeNodeParams, GetBoxModelParams, GetContentQuadsParams, Node, NodeId,
-    ResolveNodeParams,
+    BackendNodeId, DescribeNodeParams, EnableParams, EventAttributeModified, EventAttributeRemoved,
+    EventDocumentUpdated, GetBoxModelParams, GetContentQuadsParams, Node, NodeId,
+    ResolveNodeParams, SetFileInputFilesParams,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::page::{
+    CaptureScreenshotFormat, CaptureScreenshotParams, Viewport,
 };
 use chromiumoxide_cdp::cdp::js_protocol::runtime::{
     CallFunctionOnReturns, GetPropertiesParams, PropertyDescriptor, RemoteObjectId,
@@ -17,6 +22,8 @@ use chromiumoxide_cdp::cdp::js_protocol::runtime::{
 use crate::error::{CdpError, Result};
 use crate::handler::PageInner;
 use crate::layout::{BoundingBox, BoxModel, ElementQuad, Point};
+use crate::page::Page;
+use crate::utils;
 
 /// Represents a [DOM Element](https://developer.mozilla.org/en-US/docs/Web/API/Element).
 #[derive(Debug)]
@@ -66,7 +73,7 @@ impl Element {
 
     /// Convert a slice of `NodeId`s into a `Vec` of `Element`s
     pub(crate) async fn from_nodes(tab: &Arc<PageInner>, node_ids: &[NodeId]) -> Result<Vec<Self>> {
-        Ok(future::join_all(
+        future::join_all(
             node_ids
                 .iter()
                 .copied()
@@ -74,23 +81,23 @@ impl Element {
         )
         .await
         .into_iter()
-        .collect::<Result<Vec<_>, _>>()?)
+        .collect::<Result<Vec<_>, _>>()
     }
 
     /// Returns the first element in the document which matches the given CSS
     /// selector.
     pub async fn find_element(&self, selector: impl Into<String>) -> Result<Self> {
         let node_id = self.tab.find_element(selector, self.node_id).await?;
-        Ok(Element::new(Arc::clone(&self.tab), node_id).await?)
+        Element::new(Arc::clone(&self.tab), node_id).await
     }
 
     /// Return all `Element`s in the document that match the given selector
     pub async fn find_elements(&self, selector: impl Into<String>) -> Result<Vec<Element>> {
-        Ok(Element::from_nodes(
+        Element::from_nodes(
             &self.tab,
             &self.tab.find_elements(selector, self.node_id).await?,
         )
-        .await?)
+        .await
     }
 
     async fn box_model(&self) -> Result<BoxModel> {
@@ -146,13 +153,82 @@ impl Element {
             .quads
             .iter()
             .filter(|q| q.inner().len() == 8)
-            .map(|q| ElementQuad::from_quad(q))
+            .map(ElementQuad::from_quad)
             .filter(|q| q.quad_area() > 1.)
             .map(|q| q.quad_center())
             .next()
             .ok_or_else(|| CdpError::msg("Node is either not visible or not an HTMLElement"))
     }
 
+    /// Scrolls the element into view and takes a screenshot of the area the
+    /// element covers, according to its box model.
+    ///
+    /// The image has the resolution of the device, the clip is aligned to
+    /// whole device pixels. Fails if the element has no visible area.
+    pub async fn screenshot(&self, format: CaptureScreenshotFormat) -> Result<Vec<u8>> {
+        let bounding_box = self.scroll_into_view().await?.bounding_box().await?;
+        if bounding_box.width < 1. || bounding_box.height < 1. {
+            return Err(CdpError::msg("Node has no visible area"));
+        }
+        // the bounding box is relative to the viewport, the clip to the document
+        let viewport = self.tab.layout_metrics().await?.layout_viewport;
+        let x = bounding_box.x + viewport.page_x as f64;
+        let y = bounding_box.y + viewport.page_y as f64;
+
+        // the image has the resolution of the device, align the clip to whole
+        // device pixels so that the edges of the element are not blurred
+        let scale_factor = self.device_scale_factor().await?;
+        let align = |start: f64, len: f64| {
+            let aligned = (start * scale_factor).floor() / scale_factor;
+            let end = ((start + len) * scale_factor).ceil() / scale_factor;
+            (aligned, end - aligned)
+        };
+        let (x, width) = align(x, bounding_box.width);
+        let (y, height) = align(y, bounding_box.height);
+        let clip = Viewport {
+            x,
+            y,
+            width,
+            height,
+            scale: 1.,
+        };
+
+        self.tab
+            .screenshot(
+                CaptureScreenshotParams::builder()
+                    .format(format)
+                    .clip(clip)
+                    .build()
+                    .into(),
+            )
+            .await
+    }
+
+    /// The ratio of device pixels to css pixels of the element's window
+    async fn device_scale_factor(&self) -> Result<f64> {
+        let resp = self
+            .call_js_fn("function() { return window.devicePixelRatio; }", false)
+            .await?;
+        Ok(resp
+            .result
+            .value
+            .and_then(|value| value.as_f64())
+            .filter(|factor| *factor > 0.)
+            .unwrap_or(1.))
+    }
+
+    /// Save a screenshot of the element as file to the `output` path and
+    /// return the image.
+    pub async fn save_screenshot(
+        &self,
+        format: CaptureScreenshotFormat,
+        output: impl AsRef<Path>,
+    ) -> Result<Vec<u8>> {
+        let img = self.screenshot(format).await?;
+        utils::write(output.as_ref(), &img).await?;
+        Ok(img)
+    }
+
     /// Submits a javascript function to the page and returns the evaluated
     /// result
     ///
@@ -184,14 +260,13 @@ impl Element {
         function_declaration: impl Into<String>,
         await_promise: bool,
     ) -> Result<CallFunctionOnReturns> {
-        Ok(self
-            .tab
+        self.tab
             .call_js_fn(
                 function_declaration,
                 await_promise,
                 self.remote_object_id.clone(),
             )
-            .await?)
+            .await
     }
 
     /// Returns a JSON representation of this element.
@@ -199,7 +274,7 @@ impl Element {
         let element_json = self
             .call_js_fn("function() { return this; }", false)
             .await?;
-        Ok(element_json.result.value.ok_or(CdpError::NotFound)?)
+        element_json.result.value.ok_or(CdpError::NotFound)
     }
 
     /// Calls [focus](https://developer.mozilla.org/en-US/docs/Web/API/HTMLElement/focus) on the element.
@@ -209,6 +284,21 @@ impl Element {
         Ok(self)
     }
 
+    /// Selects the files at the `paths` in this `input[type=file]` element.
+    ///
+    /// Relative paths are resolved against the current working directory.
+    pub async fn upload_files<I, P>(&self, paths: I) -> Result<&Self>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let files = utils::absolute_paths(paths)?;
+        let mut params = SetFileInputFilesParams::new(files);
+        params.object_id = Some(self.remote_object_id.clone());
+        self.tab.execute(params).await?;
+        Ok(self)
+    }
+
     /// Scrolls the element into view and uses a mouse event to move the mouse
     /// over the center of this element.
     pub async fn hover(&self) -> Result<&Self> {
@@ -267,6 +357,16 @@ impl Element {
         Ok(self)
     }
 
+    /// Scrolls the element into view and taps it in the center of its
+    /// visible area.
+    ///
+    /// Requires touch emulation, see `Page::set_viewport`.
+    pub async fn tap(&self) -> Result<&Self> {
+        let center = self.scroll_into_view().await?.clickable_point().await?;
+        self.tab.tap(center).await?;
+        Ok(self)
+    }
+
     /// Type the input
     ///
     /// # Example type text into an input element
@@ -285,6 +385,126 @@ impl Element {
         Ok(self)
     }
 
+    /// Clears the value of this input or textarea element and types the
+    /// `text` into it.
+    ///
+    /// The element is focused first, clearing dispatches an `input` event
+    /// like a user deleting the content would.
+    pub async fn fill(&self, text: impl AsRef<str>) -> Result<&Self> {
+        let resp = self
+            .call_js_fn(
+                "function() {
+                if (!this.isConnected)
+                    return 'Node is detached from document';
+                if (this.isContentEditable) {
+                    this.focus();
+                    this.textContent = '';
+                } else if (this instanceof HTMLInputElement || this instanceof HTMLTextAreaElement) {
+                    this.focus();
+                    this.select();
+                    this.value = '';
+                } else {
+                    return 'Node is not an input, textarea or contenteditable element';
+                }
+                this.dispatchEvent(new Event('input', { bubbles: true }));
+                return false;
+            }",
+                false,
+            )
+            .await?;
+        if resp.result.r#type == RemoteObjectType::String {
+            let error_text = resp.result.value.unwrap().as_str().unwrap().to_string();
+            return Err(CdpError::msg(error_text));
+        }
+        self.type_str(text).await
+    }
+
+    /// Selects the options of this `select` element whose values are in
+    /// `values` and returns the values of all selected options.
+    ///
+    /// All other options are deselected, a single-choice `select` selects
+    /// the first matching option. Afterwards the `input` and `change` events
+    /// are dispatched, like after a selection by the user.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let selected = page
+    ///         .find_element("select#color")
+    ///         .await?
+    ///         .select_option(vec!["blue"])
+    ///         .await?;
+    ///     assert_eq!(selected, vec!["blue".to_string()]);
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn select_option<I, S>(&self, values: I) -> Result<Vec<String>>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let values: Vec<_> = values
+            .into_iter()
+            .map(|value| value.as_ref().to_string())
+            .collect();
+        let js_fn = format!(
+            "function() {{
+                const values = {};
+                if (!(this instanceof HTMLSelectElement))
+                    return 'Node is not a select element';
+                let matched = false;
+                for (const option of this.options) {{
+                    option.selected = values.includes(option.value) && (this.multiple || !matched);
+                    matched = matched || option.selected;
+                }}
+                this.dispatchEvent(new Event('input', {{ bubbles: true }}));
+                this.dispatchEvent(new Event('change', {{ bubbles: true }}));
+                return Array.from(this.selectedOptions).map(option => option.value);
+            }}",
+            serde_json::to_string(&values)?
+        );
+        let resp = self.call_js_fn(js_fn, false).await?;
+        match resp.result.value {
+            Some(serde_json::Value::String(error_text)) => Err(CdpError::msg(error_text)),
+            Some(selected) => Ok(serde_json::from_value(selected)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Checks this checkbox or radio button by clicking it, if it is not
+    /// checked already.
+    pub async fn check(&self) -> Result<&Self> {
+        self.set_checked(true).await
+    }
+
+    /// Unchecks this checkbox by clicking it, if it is checked.
+    pub async fn uncheck(&self) -> Result<&Self> {
+        self.set_checked(false).await
+    }
+
+    async fn set_checked(&self, checked: bool) -> Result<&Self> {
+        if self.is_checked().await? != checked {
+            self.click().await?;
+            if self.is_checked().await? != checked {
+                return Err(CdpError::msg(format!(
+                    "Clicking the element did not change its state to checked = {}",
+                    checked
+                )));
+            }
+        }
+        Ok(self)
+    }
+
+    async fn is_checked(&self) -> Result<bool> {
+        match self.property("checked").await? {
+            Some(serde_json::Value::Bool(checked)) => Ok(checked),
+            _ => Err(CdpError::msg("Node is not a checkbox or radio button")),
+        }
+    }
+
     /// Presses the key.
     ///
     /// # Example type text into an input element and hit enter
@@ -329,8 +549,8 @@ impl Element {
     /// Returns the value of the element's attribute
     pub async fn attribute(&self, attribute: impl AsRef<str>) -> Result<Option<String>> {
         let js_fn = format!(
-            "function() {{ return this.getAttribute('{}'); }}",
-            attribute.as_ref()
+            "function() {{ return this.getAttribute({}); }}",
+            serde_json::to_string(attribute.as_ref())?
         );
         let resp = self.call_js_fn(js_fn, false).await?;
         if let Some(value) = resp.result.value {
@@ -340,6 +560,68 @@ impl Element {
         }
     }
 
+    /// Waits until the attribute `name` of this element has the `value`.
+    ///
+    /// Instead of polling, this re-checks the attribute whenever the browser
+    /// reports a change of the element's attributes, the `DOM` domain is
+    /// enabled for that. Returns right away if the attribute already has the
+    /// value. This waits forever if the value is never set, unless the page
+    /// has a default timeout, see `Page::set_default_timeout`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let button = page.find_element("button#submit").await?;
+    ///     button.wait_for_attribute("aria-busy", "false").await?.click().await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn wait_for_attribute(
+        &self,
+        name: impl AsRef<str>,
+        value: impl AsRef<str>,
+    ) -> Result<&Self> {
+        utils::timeout(
+            self.tab.default_timeout(),
+            self.wait_for_attribute_inner(name.as_ref(), value.as_ref()),
+        )
+        .await?;
+        Ok(self)
+    }
+
+    async fn wait_for_attribute_inner(&self, name: &str, value: &str) -> Result<()> {
+        let page = Page::from(Arc::clone(&self.tab));
+        // subscribe before the first check, so that no change is missed
+        let modified = page.event_listener::<EventAttributeModified>().await?;
+        let removed = page.event_listener::<EventAttributeRemoved>().await?;
+        let document = page.event_listener::<EventDocumentUpdated>().await?;
+        self.tab.execute(EnableParams::default()).await?;
+
+        let node_id = self.node_id;
+        let modified = modified
+            .filter(move |ev| future::ready(ev.node_id == node_id && ev.name == name))
+            .map(|_| ());
+        let removed = removed
+            .filter(move |ev| future::ready(ev.node_id == node_id && ev.name == name))
+            .map(|_| ());
+        let mut changes = stream::select(stream::select(modified, removed), document.map(|_| ()));
+
+        loop {
+            if self.attribute(name).await?.as_deref() == Some(value) {
+                return Ok(());
+            }
+            if changes.next().await.is_none() {
+                return Err(CdpError::msg(format!(
+                    "Stopped waiting for attribute `{}`, the page was closed",
+                    name
+                )));
+            }
+        }
+    }
+
     /// A `Stream` over all attributes and their values
     pub async fn iter_attributes(
         &self,
@@ -354,17 +636,17 @@ impl Element {
 
     /// The inner text of this element.
     pub async fn inner_text(&self) -> Result<Option<String>> {
-        Ok(self.string_property("innerText").await?)
+        self.string_property("innerText").await
     }
 
     /// The inner HTML of this element.
     pub async fn inner_html(&self) -> Result<Option<String>> {
-        Ok(self.string_property("innerHTML").await?)
+        self.string_property("innerHTML").await
     }
 
     /// The outer HTML of this element.
     pub async fn outer_html(&self) -> Result<Option<String>> {
-        Ok(self.string_property("outerHTML").await?)
+        self.string_property("outerHTML").await
     }
 
     /// Returns the string property of the element.
@@ -375,9 +657,9 @@ impl Element {
         let value = self.property(property).await?.ok_or(CdpError::NotFound)?;
         let txt: String = serde_json::from_value(value)?;
         if txt.is_empty() {
-            Ok(Some(txt))
-        } else {
             Ok(None)
+        } else {
+            Ok(Some(txt))
         }
     }
 
@@ -4,8 +4,10 @@ use crate::handler::frame::NavigationError;
 use async_tungstenite::tungstenite;
 use base64::DecodeError;
 use chromiumoxide_cdp::cdp::browser_protocol::page::FrameId;
+use chromiumoxide_cdp::cdp::browser_protocol::target::TargetId;
 use futures::channel::mpsc::SendError;
 use futures::channel::oneshot::Canceled;
+use std::process::ExitStatus;
 use std::time::Instant;
 use thiserror::Error;
 
@@ -39,11 +41,42 @@ pub enum CdpError {
     ScrollingFailed(String),
     #[error("Requested value not found.")]
     NotFound,
+    #[error("The connection to the browser was lost.")]
+    Disconnected,
+    /// The launched browser did not report its websocket url in time,
+    /// contains the output of the process
+    #[error("Browser did not report its websocket url in time, output:\n{0}")]
+    LaunchTimeout(String),
+    /// The launched browser exited before it reported its websocket url
+    #[error("Browser exited with {0} before it reported its websocket url, output:\n{1}")]
+    LaunchExit(ExitStatus, String),
+    /// The process of the launched browser exited unexpectedly
+    #[error("Browser crashed, the process exited with {0}")]
+    BrowserCrashed(ExitStatus),
+    /// The renderer process of the target the command was sent to crashed
+    #[error("Target {0:?} crashed.")]
+    TargetCrashed(TargetId),
+    /// The connected browser does not know the method, e.g. an experimental
+    /// command of a newer protocol version
+    #[error("The browser does not support {method}.")]
+    NotSupported { method: String },
+    /// The screenshot of a page differs from the baseline image, see
+    /// `Page::expect_visual_match`
+    #[error("Screenshot differs from {baseline:?} by {mismatch_percentage:.2}% of the pixels")]
+    VisualMismatch {
+        baseline: std::path::PathBuf,
+        mismatch_percentage: f64,
+    },
 }
 impl CdpError {
     pub fn msg(msg: impl Into<String>) -> Self {
         CdpError::ChromeMessage(msg.into())
     }
+
+    /// Whether the browser does not support the method that failed
+    pub fn is_not_supported(&self) -> bool {
+        matches!(self, CdpError::NotSupported { .. })
+    }
 }
 
 #[derive(Debug, Error)]
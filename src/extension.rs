@@ -0,0 +1,73 @@
+use std::ops::Deref;
+
+use chromiumoxide_cdp::cdp::browser_protocol::target::TargetInfo;
+
+use crate::worker::Worker;
+
+/// The scheme of the urls of extension pages and scripts
+const EXTENSION_SCHEME: &str = "chrome-extension://";
+
+/// A handle to the background page or service worker of an extension.
+///
+/// Obtained via
+/// [`Browser::extension_targets`](crate::Browser::extension_targets).
+/// Dereferences to the [`Worker`] handle of its session, so that commands
+/// are executed and scripts are evaluated within the extension, with access
+/// to its `chrome.*` APIs.
+#[derive(Debug, Clone)]
+pub struct ExtensionTarget {
+    /// The id of the extension, the host of its urls
+    extension_id: String,
+    worker: Worker,
+}
+
+impl ExtensionTarget {
+    pub(crate) fn new(worker: Worker) -> Option<Self> {
+        let extension_id = extension_id(worker.url())?.to_string();
+        Some(Self {
+            extension_id,
+            worker,
+        })
+    }
+
+    /// Whether the target is the background page or service worker of an
+    /// extension
+    pub(crate) fn is_extension_target(info: &TargetInfo) -> bool {
+        matches!(info.r#type.as_str(), "background_page" | "service_worker")
+            && extension_id(&info.url).is_some()
+    }
+
+    /// The id of the extension, like `cjpalhdlnbpafiamejdnhcphjbkeiagm`
+    pub fn extension_id(&self) -> &str {
+        &self.extension_id
+    }
+
+    /// Whether this is the service worker of a manifest V3 extension, rather
+    /// than the background page of a manifest V2 extension
+    pub fn is_service_worker(&self) -> bool {
+        self.worker.info().r#type == "service_worker"
+    }
+
+    /// The handle of the session attached to the extension's target
+    pub fn worker(&self) -> &Worker {
+        &self.worker
+    }
+}
+
+impl Deref for ExtensionTarget {
+    type Target = Worker;
+
+    fn deref(&self) -> &Self::Target {
+        &self.worker
+    }
+}
+
+/// The id of the extension the `url` belongs to
+fn extension_id(url: &str) -> Option<&str> {
+    let id = url.strip_prefix(EXTENSION_SCHEME)?.split('/').next()?;
+    if id.is_empty() {
+        None
+    } else {
+        Some(id)
+    }
+}
@@ -0,0 +1,353 @@
+//! Downloads a pinned Chromium revision, like puppeteer's `BrowserFetcher`.
+//!
+//! All operations of the [`BrowserFetcher`] are blocking, so they should be
+//! run in a dedicated thread, like via `spawn_blocking`, from within an async
+//! context.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+/// The Chromium revision that is downloaded by default
+pub const CURRENT_REVISION: &str = "818858";
+
+/// The default host the Chromium snapshots are downloaded from
+pub const DEFAULT_HOST: &str = "https://storage.googleapis.com";
+
+#[derive(Debug, Error)]
+pub enum FetcherError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("Failed to download {0}: {1}")]
+    Download(String, String),
+    #[error("Downloading Chromium is not supported on this platform")]
+    UnsupportedPlatform,
+    #[error("Could not determine a cache directory to download Chromium to")]
+    NoCacheDir,
+    #[error("The downloaded revision contains no executable at {0}")]
+    ExecutableNotFound(PathBuf),
+    #[error("The checksum of the archive downloaded from {0} does not match")]
+    ChecksumMismatch(String),
+}
+
+/// The platforms Chromium snapshots are available for
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Platform {
+    Linux,
+    Mac,
+    Win32,
+    Win64,
+}
+
+impl Platform {
+    /// The platform of the current machine, if supported
+    pub fn current() -> Option<Self> {
+        if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+            Some(Platform::Linux)
+        } else if cfg!(target_os = "macos") {
+            Some(Platform::Mac)
+        } else if cfg!(all(windows, target_pointer_width = "64")) {
+            Some(Platform::Win64)
+        } else if cfg!(windows) {
+            Some(Platform::Win32)
+        } else {
+            None
+        }
+    }
+
+    /// The name of the folder the snapshots of this platform are stored in
+    fn folder(&self) -> &'static str {
+        match self {
+            Platform::Linux => "Linux_x64",
+            Platform::Mac => "Mac",
+            Platform::Win32 => "Win",
+            Platform::Win64 => "Win_x64",
+        }
+    }
+
+    /// The name of the archive of a snapshot, without the `.zip` extension
+    fn archive_name(&self, revision: &str) -> &'static str {
+        match self {
+            Platform::Linux => "chrome-linux",
+            Platform::Mac => "chrome-mac",
+            // Windows archive name changed at r591479.
+            Platform::Win32 | Platform::Win64 => {
+                if revision.parse::<u32>().map(|r| r > 591_479).unwrap_or(true) {
+                    "chrome-win"
+                } else {
+                    "chrome-win32"
+                }
+            }
+        }
+    }
+
+    /// The path of the executable relative to the extracted archive
+    fn relative_executable_path(&self, revision: &str) -> PathBuf {
+        let archive = Path::new(self.archive_name(revision));
+        match self {
+            Platform::Linux => archive.join("chrome"),
+            Platform::Mac => archive.join("Chromium.app/Contents/MacOS/Chromium"),
+            Platform::Win32 | Platform::Win64 => archive.join("chrome.exe"),
+        }
+    }
+}
+
+/// A revision that was downloaded
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RevisionInfo {
+    /// The revision of the snapshot
+    pub revision: String,
+    /// The folder the snapshot was extracted to
+    pub folder_path: PathBuf,
+    /// The path of the chromium executable
+    pub executable_path: PathBuf,
+}
+
+/// Downloads and manages Chromium snapshots in a local directory.
+#[derive(Debug, Clone)]
+pub struct BrowserFetcher {
+    /// The directory that contains all the downloaded revisions
+    path: PathBuf,
+    /// The host to download the snapshots from
+    host: String,
+    /// The platform to download the snapshots for
+    platform: Platform,
+}
+
+impl BrowserFetcher {
+    /// Create a new fetcher that manages its revisions in `path` and
+    /// downloads the snapshots for the current platform.
+    pub fn new(path: impl Into<PathBuf>) -> Result<Self, FetcherError> {
+        Ok(Self {
+            path: path.into(),
+            host: DEFAULT_HOST.to_string(),
+            platform: Platform::current().ok_or(FetcherError::UnsupportedPlatform)?,
+        })
+    }
+
+    /// Create a new fetcher that manages its revisions in the `chromiumoxide`
+    /// folder of the user's cache directory.
+    pub fn with_cache_dir() -> Result<Self, FetcherError> {
+        let path = dirs::cache_dir()
+            .ok_or(FetcherError::NoCacheDir)?
+            .join("chromiumoxide");
+        Self::new(path)
+    }
+
+    /// Download from a mirror instead of the [`DEFAULT_HOST`]
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    /// Download the snapshots of a different platform
+    pub fn platform(mut self, platform: Platform) -> Self {
+        self.platform = platform;
+        self
+    }
+
+    /// The directory that contains all the downloaded revisions
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The url to download the snapshot of the `revision` from
+    pub fn download_url(&self, revision: &str) -> String {
+        format!(
+            "{}/chromium-browser-snapshots/{}/{}/{}.zip",
+            self.host.trim_end_matches('/'),
+            self.platform.folder(),
+            revision,
+            self.platform.archive_name(revision)
+        )
+    }
+
+    /// The info of the `revision`, regardless whether it was downloaded.
+    pub fn revision_info(&self, revision: &str) -> RevisionInfo {
+        let folder_path = self
+            .path
+            .join(format!("{:?}-{}", self.platform, revision).to_lowercase());
+        let executable_path = folder_path.join(self.platform.relative_executable_path(revision));
+        RevisionInfo {
+            revision: revision.to_string(),
+            folder_path,
+            executable_path,
+        }
+    }
+
+    /// Returns the info of the `revision` if it was already downloaded
+    pub fn local_revision(&self, revision: &str) -> Option<RevisionInfo> {
+        let info = self.revision_info(revision);
+        if info.executable_path.exists() {
+            Some(info)
+        } else {
+            None
+        }
+    }
+
+    /// Downloads and extracts the snapshot of the `revision`, unless it was
+    /// downloaded already.
+    ///
+    /// The downloaded archive is discarded if its CRC32C checksum doesn't
+    /// match the one the host reports in the `x-goog-hash` header, or if it
+    /// doesn't contain a chromium executable. Hosts that don't report a
+    /// checksum, like some mirrors, are trusted.
+    pub fn fetch(&self, revision: &str) -> Result<RevisionInfo, FetcherError> {
+        if let Some(info) = self.local_revision(revision) {
+            return Ok(info);
+        }
+        let info = self.revision_info(revision);
+        fs::create_dir_all(&self.path)?;
+
+        let archive_path = info.folder_path.with_extension("zip");
+        let res = self
+            .download(revision, &archive_path)
+            .and_then(|_| extract(&archive_path, &info.folder_path));
+        let _ = fs::remove_file(&archive_path);
+        if let Err(err) = res {
+            let _ = fs::remove_dir_all(&info.folder_path);
+            return Err(err);
+        }
+
+        if !info.executable_path.exists() {
+            let _ = fs::remove_dir_all(&info.folder_path);
+            return Err(FetcherError::ExecutableNotFound(info.executable_path));
+        }
+        Ok(info)
+    }
+
+    /// Deletes the downloaded `revision`
+    pub fn remove(&self, revision: &str) -> Result<(), FetcherError> {
+        let info = self.revision_info(revision);
+        if info.folder_path.exists() {
+            fs::remove_dir_all(&info.folder_path)?;
+        }
+        Ok(())
+    }
+
+    /// Downloads the archive of the `revision` to `dest` and verifies its
+    /// checksum
+    fn download(&self, revision: &str, dest: &Path) -> Result<(), FetcherError> {
+        let url = self.download_url(revision);
+        let resp = ureq::get(&url)
+            .call()
+            .map_err(|err| FetcherError::Download(url.clone(), err.to_string()))?;
+        let expected = resp
+            .all("x-goog-hash")
+            .into_iter()
+            .flat_map(|value| value.split(','))
+            .find_map(|hash| hash.trim().strip_prefix("crc32c="))
+            .and_then(|hash| base64::decode(hash).ok())
+            .filter(|hash| hash.len() == 4)
+            .map(|hash| u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]]));
+
+        let mut file = fs::File::create(dest)?;
+        let mut reader = resp.into_reader();
+        let mut crc = !0;
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            crc = crc32c_update(crc, &buf[..n]);
+            file.write_all(&buf[..n])?;
+        }
+        if expected
+            .map(|expected| expected != !crc)
+            .unwrap_or_default()
+        {
+            return Err(FetcherError::ChecksumMismatch(url));
+        }
+        Ok(())
+    }
+}
+
+/// The lookup table of the CRC32C (Castagnoli) checksum, which Google Cloud
+/// Storage reports for the snapshots
+const CRC32C_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0x82F6_3B78
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Continues the CRC32C checksum `crc`, which starts at `!0` and is inverted
+/// once all the data was processed
+fn crc32c_update(crc: u32, data: &[u8]) -> u32 {
+    data.iter().fold(crc, |crc, byte| {
+        CRC32C_TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Extracts the zip archive at `archive` into the `dest` directory.
+///
+/// Unlike `ZipArchive::extract` this restores symlinks, which the mac
+/// snapshots rely on.
+fn extract(archive: &Path, dest: &Path) -> Result<(), FetcherError> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(archive)?)?;
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let path = match file.enclosed_name() {
+            Some(path) => dest.join(path),
+            None => continue,
+        };
+        if file.is_dir() {
+            fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        #[cfg(unix)]
+        {
+            use std::io::Read;
+            use std::os::unix::fs::PermissionsExt;
+            const S_IFLNK: u32 = 0o120000;
+            if let Some(mode) = file.unix_mode() {
+                if mode & 0o170000 == S_IFLNK {
+                    let mut target = String::new();
+                    file.read_to_string(&mut target)?;
+                    std::os::unix::fs::symlink(target, &path)?;
+                    continue;
+                }
+                io::copy(&mut file, &mut fs::File::create(&path)?)?;
+                fs::set_permissions(&path, fs::Permissions::from_mode(mode))?;
+                continue;
+            }
+        }
+
+        io::copy(&mut file, &mut fs::File::create(&path)?)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32c_check_value() {
+        assert_eq!(!crc32c_update(!0, b"123456789"), 0xE306_9283);
+        let crc = crc32c_update(!0, b"12345");
+        assert_eq!(!crc32c_update(crc, b"6789"), 0xE306_9283);
+    }
+}
@@ -0,0 +1,89 @@
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use futures::task::{Context, Poll};
+use futures::Stream;
+
+use chromiumoxide_cdp::cdp::browser_protocol::dom::{BackendNodeId, SetFileInputFilesParams};
+use chromiumoxide_cdp::cdp::browser_protocol::page::{
+    EventFileChooserOpened, FileChooserOpenedMode, FrameId,
+};
+
+use crate::error::Result;
+use crate::handler::PageInner;
+use crate::listeners::EventStream;
+use crate::utils;
+
+/// A `Stream` over all the file chooser dialogs a `Page` tried to open while
+/// file chooser interception is enabled.
+///
+/// Intercepted dialogs are not shown, instead the files are selected via
+/// [`FileChooser::accept`]. Dropping a `FileChooser` selects no files.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct FileChooserStream {
+    page: Arc<PageInner>,
+    events: EventStream<EventFileChooserOpened>,
+}
+
+impl FileChooserStream {
+    pub(crate) fn new(page: Arc<PageInner>, events: EventStream<EventFileChooserOpened>) -> Self {
+        Self { page, events }
+    }
+}
+
+impl Stream for FileChooserStream {
+    type Item = FileChooser;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        match Pin::new(&mut pin.events).poll_next(cx) {
+            Poll::Ready(Some(event)) => Poll::Ready(Some(FileChooser {
+                page: Arc::clone(&pin.page),
+                event,
+            })),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// A file chooser dialog that was intercepted, like after clicking an
+/// `input[type=file]` element.
+#[derive(Debug)]
+pub struct FileChooser {
+    page: Arc<PageInner>,
+    event: Arc<EventFileChooserOpened>,
+}
+
+impl FileChooser {
+    /// Whether the file chooser accepts multiple files
+    pub fn is_multiple(&self) -> bool {
+        self.event.mode == FileChooserOpenedMode::SelectMultiple
+    }
+
+    /// The id of the frame that opened the file chooser
+    pub fn frame_id(&self) -> &FrameId {
+        &self.event.frame_id
+    }
+
+    /// The input element that opened the file chooser
+    pub fn backend_node_id(&self) -> &BackendNodeId {
+        &self.event.backend_node_id
+    }
+
+    /// Selects the files at the `paths` in the input element that opened the
+    /// file chooser
+    pub async fn accept<I, P>(self, paths: I) -> Result<()>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let files = utils::absolute_paths(paths)?;
+        let mut params = SetFileInputFilesParams::new(files);
+        params.backend_node_id = Some(self.event.backend_node_id);
+        self.page.execute(params).await?;
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ use chromiumoxide_cdp::cdp::browser_protocol::browser::BrowserContextId;
 /// sessions.
 #[derive(Debug)]
 pub struct BrowserContext {
+    #[allow(unused)] // allow until implemented
     id: BrowserContextId,
 }
 
@@ -0,0 +1,246 @@
+use std::borrow::Cow;
+
+use chromiumoxide_cdp::cdp::browser_protocol::{css, dom, log, network, performance};
+use chromiumoxide_types::Command;
+
+/// The CDP domains that are enabled when a page is created.
+///
+/// The `Page` and `Runtime` domains are always enabled, since the frames and
+/// the execution contexts of a page are tracked with their events. By
+/// default the `Network`, `Log` and `Performance` domains are enabled for
+/// every page as well.
+///
+/// With [`DomainConfig::lazy`] only `Page` and `Runtime` are enabled right
+/// away, the other domains are enabled once they are first used by the page,
+/// i.e. once a command of the domain is executed or one of its events is
+/// subscribed to on the page, which spares the events of unused domains.
+/// Events that fire before a domain is enabled are not emitted, e.g. the
+/// responses of requests that were sent before the first network event was
+/// subscribed to. Subscriptions via
+/// [`Browser::event_listener`](crate::browser::Browser::event_listener) don't
+/// enable a domain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DomainConfig {
+    /// Whether to enable the `Network` domain
+    pub network: bool,
+    /// Whether to enable the `Log` domain
+    pub log: bool,
+    /// Whether to enable the `Performance` domain
+    pub performance: bool,
+    /// Whether to enable the `DOM` domain
+    pub dom: bool,
+    /// Whether to enable the `CSS` domain, which requires the `DOM` domain
+    pub css: bool,
+    /// Whether the disabled domains are enabled once they are first used by
+    /// the page
+    pub lazy: bool,
+}
+
+impl Default for DomainConfig {
+    fn default() -> Self {
+        Self {
+            network: true,
+            log: true,
+            performance: true,
+            dom: false,
+            css: false,
+            lazy: false,
+        }
+    }
+}
+
+impl DomainConfig {
+    /// Enable all domains when a page is created
+    pub fn all() -> Self {
+        Self {
+            network: true,
+            log: true,
+            performance: true,
+            dom: true,
+            css: true,
+            lazy: false,
+        }
+    }
+
+    /// Only enable the `Page` and `Runtime` domains when a page is created,
+    /// the other domains once they are first used by the page
+    pub fn lazy() -> Self {
+        Self {
+            network: false,
+            log: false,
+            performance: false,
+            dom: false,
+            css: false,
+            lazy: true,
+        }
+    }
+
+    /// The flag of the `domain`, if it is enabled lazily
+    fn flag_mut(&mut self, domain: &str) -> Option<&mut bool> {
+        match domain {
+            "Network" => Some(&mut self.network),
+            "Log" => Some(&mut self.log),
+            "Performance" => Some(&mut self.performance),
+            "DOM" => Some(&mut self.dom),
+            "CSS" => Some(&mut self.css),
+            _ => None,
+        }
+    }
+
+    /// The commands that enable the configured domains of a new page
+    pub(crate) fn init_commands(&self) -> Vec<(Cow<'static, str>, serde_json::Value)> {
+        let domains = [
+            ("Network", self.network),
+            ("Log", self.log),
+            ("Performance", self.performance),
+            ("DOM", self.dom || self.css),
+            ("CSS", self.css),
+        ];
+        domains
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .filter_map(|(domain, _)| enable_command(domain))
+            .collect()
+    }
+
+    /// Tracks the state of the domain of the command or event `method`.
+    ///
+    /// Returns the commands that need to be executed first if domains are
+    /// enabled lazily and the domain is used for the first time.
+    pub(crate) fn on_method(
+        &mut self,
+        method: &str,
+    ) -> Vec<(Cow<'static, str>, serde_json::Value)> {
+        let (domain, name) = match method.split_once('.') {
+            Some(parts) => parts,
+            None => return Vec::new(),
+        };
+        let lazy = self.lazy;
+        let enabled = match self.flag_mut(domain) {
+            Some(enabled) => enabled,
+            None => return Vec::new(),
+        };
+        match name {
+            "enable" => *enabled = true,
+            "disable" => *enabled = false,
+            _ if lazy && !*enabled => {
+                *enabled = true;
+                let mut cmds = Vec::with_capacity(2);
+                if domain == "CSS" && !self.dom {
+                    self.dom = true;
+                    cmds.extend(enable_command("DOM"));
+                }
+                cmds.extend(enable_command(domain));
+                return cmds;
+            }
+            _ => {}
+        }
+        Vec::new()
+    }
+}
+
+/// The command that enables the `domain`
+fn enable_command(domain: &str) -> Option<(Cow<'static, str>, serde_json::Value)> {
+    fn enable<T: Command>(cmd: T) -> (Cow<'static, str>, serde_json::Value) {
+        (cmd.identifier(), serde_json::to_value(cmd).unwrap())
+    }
+    let cmd = match domain {
+        "Network" => enable(network::EnableParams::default()),
+        "Log" => enable(log::EnableParams::default()),
+        "Performance" => enable(performance::EnableParams::default()),
+        "DOM" => enable(dom::EnableParams::default()),
+        "CSS" => enable(css::EnableParams::default()),
+        _ => return None,
+    };
+    Some(cmd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn methods(cmds: Vec<(Cow<'static, str>, serde_json::Value)>) -> Vec<String> {
+        cmds.into_iter()
+            .map(|(method, _)| method.into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn first_use_enables_domain() {
+        let mut config = DomainConfig::lazy();
+        assert_eq!(
+            methods(config.on_method("Network.requestWillBeSent")),
+            vec!["Network.enable"]
+        );
+        assert!(config.network);
+        assert!(config.on_method("Network.getCookies").is_empty());
+    }
+
+    #[test]
+    fn eager_config_does_not_enable_on_use() {
+        let mut config = DomainConfig::default();
+        assert!(config.on_method("DOM.getDocument").is_empty());
+        assert!(!config.dom);
+
+        config.on_method("Network.disable");
+        assert!(config.on_method("Network.getCookies").is_empty());
+        assert!(!config.network);
+    }
+
+    #[test]
+    fn css_implies_dom() {
+        let mut config = DomainConfig::lazy();
+        assert_eq!(
+            methods(config.on_method("CSS.getComputedStyleForNode")),
+            vec!["DOM.enable", "CSS.enable"]
+        );
+        assert!(config.dom && config.css);
+
+        let mut config = DomainConfig {
+            dom: true,
+            ..DomainConfig::lazy()
+        };
+        assert_eq!(
+            methods(config.on_method("CSS.getComputedStyleForNode")),
+            vec!["CSS.enable"]
+        );
+    }
+
+    #[test]
+    fn tracks_enable_and_disable() {
+        let mut config = DomainConfig::lazy();
+        assert!(config.on_method("Log.enable").is_empty());
+        assert!(config.log);
+        assert!(config.on_method("Log.clear").is_empty());
+
+        assert!(config.on_method("Log.disable").is_empty());
+        assert!(!config.log);
+        assert_eq!(methods(config.on_method("Log.clear")), vec!["Log.enable"]);
+    }
+
+    #[test]
+    fn ignores_unhandled_domains() {
+        let mut config = DomainConfig::lazy();
+        assert!(config.on_method("Page.navigate").is_empty());
+        assert!(config.on_method("Runtime.evaluate").is_empty());
+        assert!(config.on_method("invalid").is_empty());
+        assert_eq!(config, DomainConfig::lazy());
+    }
+
+    #[test]
+    fn init_commands() {
+        assert_eq!(
+            methods(DomainConfig::default().init_commands()),
+            vec!["Network.enable", "Log.enable", "Performance.enable"]
+        );
+        assert!(DomainConfig::lazy().init_commands().is_empty());
+        let config = DomainConfig {
+            css: true,
+            ..DomainConfig::lazy()
+        };
+        assert_eq!(
+            methods(config.init_commands()),
+            vec!["DOM.enable", "CSS.enable"]
+        );
+    }
+}
@@ -16,22 +16,8 @@ pub struct EmulationManager {
 
 impl EmulationManager {
     pub fn init_commands(&mut self, viewport: &Viewport) -> CommandChain {
-        let orientation = if viewport.is_landscape {
-            ScreenOrientation::new(ScreenOrientationType::LandscapePrimary, 90)
-        } else {
-            ScreenOrientation::new(ScreenOrientationType::PortraitPrimary, 0)
-        };
-
-        let set_device = SetDeviceMetricsOverrideParams::builder()
-            .mobile(viewport.is_mobile)
-            .width(viewport.width)
-            .height(viewport.height)
-            .device_scale_factor(viewport.device_scale_factor.unwrap_or(1.))
-            .screen_orientation(orientation)
-            .build()
-            .unwrap();
-
-        let set_touch = SetTouchEmulationEnabledParams::new(true);
+        let set_device = device_metrics_override(viewport);
+        let set_touch = SetTouchEmulationEnabledParams::new(viewport.has_touch);
 
         let chain = CommandChain::new(vec![
             (
@@ -49,3 +35,21 @@ impl EmulationManager {
         chain
     }
 }
+
+/// The `Emulation.setDeviceMetricsOverride` command that applies the viewport
+pub(crate) fn device_metrics_override(viewport: &Viewport) -> SetDeviceMetricsOverrideParams {
+    let orientation = if viewport.is_landscape {
+        ScreenOrientation::new(ScreenOrientationType::LandscapePrimary, 90)
+    } else {
+        ScreenOrientation::new(ScreenOrientationType::PortraitPrimary, 0)
+    };
+
+    SetDeviceMetricsOverrideParams::builder()
+        .mobile(viewport.is_mobile)
+        .width(viewport.width)
+        .height(viewport.height)
+        .device_scale_factor(viewport.device_scale_factor.unwrap_or(1.))
+        .screen_orientation(orientation)
+        .build()
+        .unwrap()
+}
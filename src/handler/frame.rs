@@ -3,17 +3,20 @@ use std::collections::VecDeque;
 use std::collections::{HashMap, HashSet};
 use std::time::{Duration, Instant};
 
+use futures::channel::oneshot::Sender;
+
 use chromiumoxide_types::{Method, Request};
 
 use crate::cmd::CommandChain;
-use crate::error::DeadlineExceeded;
+use crate::error::{CdpError, DeadlineExceeded, Result};
 use crate::handler::REQUEST_TIMEOUT;
 use chromiumoxide_cdp::cdp::browser_protocol::network::LoaderId;
 use chromiumoxide_cdp::cdp::browser_protocol::page::{
     EventFrameDetached, EventFrameStartedLoading, EventFrameStoppedLoading, EventLifecycleEvent,
     EventNavigatedWithinDocument, Frame as CdpFrame, FrameTree,
 };
-use chromiumoxide_cdp::cdp::browser_protocol::target::EventAttachedToTarget;
+use chromiumoxide_cdp::cdp::browser_protocol::target::{EventAttachedToTarget, SessionId};
+use chromiumoxide_cdp::cdp::events::CdpEvent;
 use chromiumoxide_cdp::cdp::js_protocol::runtime::*;
 use chromiumoxide_cdp::cdp::{
     browser_protocol::page::{self, FrameId},
@@ -90,6 +93,18 @@ impl Frame {
     pub fn is_loaded(&self) -> bool {
         self.lifecycle_events.contains("load")
     }
+
+    /// The lifecycle events the current document of the frame emitted
+    pub fn lifecycle(&self) -> LifecycleState {
+        LifecycleState {
+            loader_id: self.loader_id.clone(),
+            events: self
+                .lifecycle_events
+                .iter()
+                .map(|ev| ev.to_string())
+                .collect(),
+        }
+    }
 }
 
 impl From<CdpFrame> for Frame {
@@ -120,6 +135,13 @@ pub struct FrameManager {
     pending_navigations: VecDeque<(FrameNavigationRequest, NavigationWatcher)>,
     /// The currently ongoing navigation
     navigation: Option<(NavigationWatcher, Instant)>,
+    /// Listeners that wait for the next navigation of the main frame
+    navigation_listeners: Vec<NavigationListener>,
+    /// Listeners that wait for a lifecycle event of the main frame
+    lifecycle_listeners: Vec<(LifecycleEvent, Sender<LifecycleState>)>,
+    /// The default execution context of each frame, along with the session
+    /// it belongs to, which differs for out-of-process iframes
+    execution_contexts: HashMap<FrameId, FrameExecutionContext>,
 }
 
 impl FrameManager {
@@ -158,6 +180,39 @@ impl FrameManager {
         self.frames.get(id)
     }
 
+    /// The default execution context of the frame, if it was created yet
+    pub fn execution_context(&self, id: &FrameId) -> Option<&FrameExecutionContext> {
+        self.execution_contexts.get(id)
+    }
+
+    /// Updates the frames with the event, received via the session of the
+    /// page or one of its out-of-process iframes
+    pub fn on_event(&mut self, session_id: Option<&SessionId>, event: &CdpEvent) {
+        match event {
+            CdpEvent::PageFrameAttached(ev) => {
+                self.on_frame_attached(ev.frame_id.clone(), Some(ev.parent_frame_id.clone()))
+            }
+            CdpEvent::PageFrameDetached(ev) => self.on_frame_detached(ev),
+            CdpEvent::PageFrameNavigated(ev) => self.on_frame_navigated(ev.frame.clone()),
+            CdpEvent::PageNavigatedWithinDocument(ev) => {
+                self.on_frame_navigated_within_document(ev)
+            }
+            CdpEvent::RuntimeExecutionContextCreated(ev) => {
+                self.on_frame_execution_context_created(session_id, ev)
+            }
+            CdpEvent::RuntimeExecutionContextDestroyed(ev) => {
+                self.on_frame_execution_context_destroyed(session_id, ev)
+            }
+            CdpEvent::RuntimeExecutionContextsCleared(_) => {
+                self.on_execution_context_cleared(session_id)
+            }
+            CdpEvent::PageLifecycleEvent(ev) => self.on_page_lifecycle_event(ev),
+            CdpEvent::PageFrameStartedLoading(ev) => self.on_frame_started_loading(ev),
+            CdpEvent::PageFrameStoppedLoading(ev) => self.on_frame_stopped_loading(ev),
+            _ => {}
+        }
+    }
+
     fn check_lifecycle(&self, watcher: &NavigationWatcher, frame: &Frame) -> bool {
         watcher
             .expected_lifecycle
@@ -178,19 +233,26 @@ impl FrameManager {
         if !self.check_lifecycle(watcher, frame) {
             return None;
         }
-        if frame.loader_id == watcher.loader_id && !watcher.same_document_navigation {
-            return None;
-        }
         if watcher.same_document_navigation {
             return Some(NavigationOk::SameDocumentNavigation(watcher.id));
         }
-        if frame.loader_id != watcher.loader_id {
-            return Some(NavigationOk::NewDocumentNavigation(watcher.id));
+        if frame.loader_id == watcher.loader_id || !watcher.responded {
+            return None;
         }
-        None
+        // another navigation that raced this one must not complete it, unless
+        // it replaced the document this navigation committed, like a client
+        // side redirect
+        if let Some(loader_id) = watcher.navigation_loader_id.as_ref() {
+            if frame.loader_id.as_ref() != Some(loader_id) && !watcher.committed {
+                return None;
+            }
+        }
+        Some(NavigationOk::NewDocumentNavigation(watcher.id))
     }
 
     pub fn poll(&mut self, now: Instant) -> Option<FrameEvent> {
+        self.poll_navigation_listeners(now);
+        self.poll_lifecycle_listeners();
         if let Some((watcher, deadline)) = self.navigation.take() {
             if now > deadline {
                 return Some(FrameEvent::NavigationResult(Err(
@@ -215,13 +277,103 @@ impl FrameManager {
                 )));
             }
         } else if let Some((req, watcher)) = self.pending_navigations.pop_front() {
-            let deadline = Instant::now() + Duration::from_millis(REQUEST_TIMEOUT);
+            let deadline = Instant::now() + self.timeout;
             self.navigation = Some((watcher, deadline));
             return Some(FrameEvent::NavigationRequest(req.id, req.req));
         }
         None
     }
 
+    /// Registers a listener that is notified with the url of the main frame,
+    /// once the main frame navigated to a new document and emitted the
+    /// `wait_until` lifecycle event, or navigated within the same document.
+    pub fn add_navigation_listener(
+        &mut self,
+        wait_until: LifecycleEvent,
+        tx: Sender<Result<String>>,
+        now: Instant,
+    ) {
+        let loader_id = self.main_frame().and_then(|f| f.loader_id.clone());
+        self.navigation_listeners.push(NavigationListener {
+            loader_id,
+            wait_until,
+            same_document_navigation: false,
+            deadline: now + self.timeout,
+            tx,
+        });
+    }
+
+    /// Notifies all the listeners whose navigation completed or timed out
+    fn poll_navigation_listeners(&mut self, now: Instant) {
+        if self.navigation_listeners.is_empty() {
+            return;
+        }
+        let frames = &self.frames;
+        let main_frame = self.main_frame.as_ref().and_then(|id| frames.get(id));
+        for listener in std::mem::take(&mut self.navigation_listeners) {
+            if listener.tx.is_canceled() {
+                continue;
+            }
+            if now > listener.deadline {
+                let _ = listener.tx.send(Err(CdpError::Timeout));
+                continue;
+            }
+            match main_frame {
+                Some(frame) if listener.is_complete(frame) => {
+                    let _ = listener
+                        .tx
+                        .send(frame.url.clone().ok_or(CdpError::NotFound));
+                }
+                _ => self.navigation_listeners.push(listener),
+            }
+        }
+    }
+
+    /// Gives up on all the navigations of the frames and returns their ids,
+    /// the listeners waiting for a navigation fail with the error
+    pub fn fail_navigations(&mut self, err: impl Fn() -> CdpError) -> Vec<NavigationId> {
+        for listener in self.navigation_listeners.drain(..) {
+            let _ = listener.tx.send(Err(err()));
+        }
+        self.navigation
+            .take()
+            .map(|(watcher, _)| watcher.id)
+            .into_iter()
+            .chain(self.pending_navigations.drain(..).map(|(req, _)| req.id))
+            .collect()
+    }
+
+    /// Registers a listener that is notified once the current document of the
+    /// main frame emitted the lifecycle `event`, right away if it already did
+    pub fn add_lifecycle_listener(&mut self, event: LifecycleEvent, tx: Sender<LifecycleState>) {
+        self.lifecycle_listeners.push((event, tx));
+        self.poll_lifecycle_listeners();
+    }
+
+    /// Notifies all the listeners whose lifecycle event was emitted
+    fn poll_lifecycle_listeners(&mut self) {
+        if self.lifecycle_listeners.is_empty() {
+            return;
+        }
+        let frames = &self.frames;
+        let main_frame = match self.main_frame.as_ref().and_then(|id| frames.get(id)) {
+            Some(frame) => frame,
+            None => return,
+        };
+        let mut pending = Vec::new();
+        for (event, tx) in std::mem::take(&mut self.lifecycle_listeners) {
+            if tx.is_canceled() {
+                continue;
+            }
+            if main_frame.lifecycle_events.contains(event.as_str()) {
+                let _ = tx.send(main_frame.lifecycle());
+            } else {
+                pending.push((event, tx));
+            }
+        }
+        self.lifecycle_listeners = pending;
+    }
+
     /// entrypoint for page navigation
     pub fn goto(&mut self, req: FrameNavigationRequest) {
         if let Some(frame_id) = self.main_frame.clone() {
@@ -232,17 +384,40 @@ impl FrameManager {
     /// Navigate a specific frame
     pub fn navigate_frame(&mut self, frame_id: FrameId, mut req: FrameNavigationRequest) {
         let loader_id = self.frames.get(&frame_id).and_then(|f| f.loader_id.clone());
-        let watcher = NavigationWatcher::until_page_load(req.id, frame_id.clone(), loader_id);
+        let watcher =
+            NavigationWatcher::until_lifecycle(req.id, frame_id.clone(), loader_id, req.wait_until);
         // insert the frame_id in the request if not present
         req.set_frame_id(frame_id);
         self.pending_navigations.push_back((req, watcher))
     }
 
+    /// Received the response to the `Page.navigate` request of the
+    /// navigation `id`, `loader_id` is the loader of the new document or
+    /// `None` if the navigation stays within the same document
+    pub fn on_navigation_response(&mut self, id: NavigationId, loader_id: Option<LoaderId>) {
+        let frames = &self.frames;
+        if let Some((watcher, _)) = self.navigation.as_mut().filter(|(w, _)| w.id == id) {
+            watcher.responded = true;
+            watcher.committed = loader_id.is_some()
+                && frames
+                    .get(&watcher.frame_id)
+                    .and_then(|f| f.loader_id.as_ref())
+                    == loader_id.as_ref();
+            watcher.navigation_loader_id = loader_id;
+        }
+    }
+
     /// Fired when a frame moved to another session
     pub fn on_attached_to_target(&mut self, _event: &EventAttachedToTarget) {
         // _onFrameMoved
     }
 
+    /// The session of an out-of-process iframe is gone, along with its
+    /// execution contexts
+    pub fn on_detached_from_target(&mut self, session_id: &SessionId) {
+        self.on_execution_context_cleared(Some(session_id));
+    }
+
     pub fn on_frame_tree(&mut self, frame_tree: FrameTree) {
         self.on_frame_attached(
             frame_tree.frame.id.clone(),
@@ -310,6 +485,11 @@ impl FrameManager {
         if let Some((watcher, _)) = self.navigation.as_mut() {
             watcher.on_frame_navigated_within_document(event);
         }
+        if self.main_frame.as_ref() == Some(&event.frame_id) {
+            for listener in self.navigation_listeners.iter_mut() {
+                listener.same_document_navigation = true;
+            }
+        }
     }
 
     pub fn on_frame_stopped_loading(&mut self, event: &EventFrameStoppedLoading) {
@@ -325,15 +505,43 @@ impl FrameManager {
         }
     }
 
-    pub fn on_frame_execution_context_created(&mut self, _event: &EventExecutionContextCreated) {}
+    /// Tracks the default execution context of the frame, isolated worlds
+    /// are ignored
+    pub fn on_frame_execution_context_created(
+        &mut self,
+        session_id: Option<&SessionId>,
+        event: &EventExecutionContextCreated,
+    ) {
+        let aux_data = match event.context.aux_data.as_ref() {
+            Some(aux_data) => aux_data,
+            None => return,
+        };
+        let is_default = aux_data["isDefault"].as_bool().unwrap_or_default();
+        if let (Some(frame_id), true) = (aux_data["frameId"].as_str(), is_default) {
+            self.execution_contexts.insert(
+                FrameId::from(frame_id.to_string()),
+                FrameExecutionContext {
+                    session_id: session_id.cloned(),
+                    context_id: event.context.id,
+                },
+            );
+        }
+    }
 
     pub fn on_frame_execution_context_destroyed(
         &mut self,
-        _event: &EventExecutionContextDestroyed,
+        session_id: Option<&SessionId>,
+        event: &EventExecutionContextDestroyed,
     ) {
+        self.execution_contexts.retain(|_, ctx| {
+            ctx.session_id.as_ref() != session_id || ctx.context_id != event.execution_context_id
+        });
     }
 
-    pub fn on_execution_context_cleared(&mut self, _event: &EventExecutionContextsCleared) {}
+    pub fn on_execution_context_cleared(&mut self, session_id: Option<&SessionId>) {
+        self.execution_contexts
+            .retain(|_, ctx| ctx.session_id.as_ref() != session_id);
+    }
 
     /// Fired for top level page lifecycle events (nav, load, paint, etc.)
     pub fn on_page_lifecycle_event(&mut self, event: &EventLifecycleEvent) {
@@ -341,6 +549,9 @@ impl FrameManager {
             if event.name == "init" {
                 frame.loader_id = Some(event.loader_id.clone());
                 frame.lifecycle_events.clear();
+                if let Some((watcher, _)) = self.navigation.as_mut() {
+                    watcher.on_document_committed(&event.frame_id, &event.loader_id);
+                }
             }
             frame.lifecycle_events.insert(event.name.clone().into());
         }
@@ -348,6 +559,7 @@ impl FrameManager {
 
     /// Detach all child frames
     fn remove_frames_recursively(&mut self, id: &FrameId) -> Option<Frame> {
+        self.execution_contexts.remove(id);
         if let Some(mut frame) = self.frames.remove(id) {
             for child in &frame.child_frames {
                 self.remove_frames_recursively(child);
@@ -366,12 +578,53 @@ impl FrameManager {
 
 impl Default for FrameManager {
     fn default() -> Self {
+        FrameManager::new(Duration::from_millis(REQUEST_TIMEOUT))
+    }
+}
+
+impl FrameManager {
+    /// Create a new `FrameManager` whose navigations time out after `timeout`
+    pub fn new(timeout: Duration) -> Self {
         FrameManager {
             main_frame: None,
             frames: Default::default(),
-            timeout: Duration::from_millis(REQUEST_TIMEOUT),
+            timeout,
             pending_navigations: Default::default(),
             navigation: None,
+            navigation_listeners: Default::default(),
+            lifecycle_listeners: Default::default(),
+            execution_contexts: Default::default(),
+        }
+    }
+}
+
+/// The default execution context of a frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameExecutionContext {
+    /// The session the context belongs to, the session of an out-of-process
+    /// iframe for the frames it contains
+    pub session_id: Option<SessionId>,
+    pub context_id: ExecutionContextId,
+}
+
+/// A snapshot of a frame of a page, including out-of-process iframes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameInfo {
+    pub id: FrameId,
+    /// The frame that embeds this frame, `None` for the main frame
+    pub parent_id: Option<FrameId>,
+    /// The value of the `name` attribute of the frame's element
+    pub name: Option<String>,
+    pub url: Option<String>,
+}
+
+impl From<&Frame> for FrameInfo {
+    fn from(frame: &Frame) -> Self {
+        Self {
+            id: frame.id.clone(),
+            parent_id: frame.parent_frame.clone(),
+            name: frame.name.clone(),
+            url: frame.url.clone(),
         }
     }
 }
@@ -434,16 +687,37 @@ pub struct NavigationWatcher {
     /// navigating to a new document by checking if a loader was included in the
     /// response.
     same_document_navigation: bool,
+    /// Whether the response to the `Page.navigate` request was received
+    responded: bool,
+    /// The loader of the document this navigation loads, as included in the
+    /// response to the `Page.navigate` request
+    navigation_loader_id: Option<LoaderId>,
+    /// Whether the frame committed the document of this navigation
+    committed: bool,
 }
 
 impl NavigationWatcher {
     pub fn until_page_load(id: NavigationId, frame: FrameId, loader_id: Option<LoaderId>) -> Self {
+        Self::until_lifecycle(id, frame, loader_id, LifecycleEvent::Load)
+    }
+
+    /// Completes once the frame (and all its child frames) emitted the
+    /// `lifecycle` event
+    pub fn until_lifecycle(
+        id: NavigationId,
+        frame: FrameId,
+        loader_id: Option<LoaderId>,
+        lifecycle: LifecycleEvent,
+    ) -> Self {
         Self {
             id,
-            expected_lifecycle: std::iter::once("load".into()).collect(),
+            expected_lifecycle: std::iter::once(lifecycle.as_str().into()).collect(),
             loader_id,
             frame_id: frame,
             same_document_navigation: false,
+            responded: false,
+            navigation_loader_id: None,
+            committed: false,
         }
     }
 
@@ -457,6 +731,110 @@ impl NavigationWatcher {
             self.same_document_navigation = true;
         }
     }
+
+    fn on_document_committed(&mut self, frame_id: &FrameId, loader_id: &LoaderId) {
+        if &self.frame_id == frame_id && self.navigation_loader_id.as_ref() == Some(loader_id) {
+            self.committed = true;
+        }
+    }
+}
+
+/// Waits for the next navigation of the main frame, that was not necessarily
+/// initiated via `Page.navigate`, like after clicking a link.
+#[derive(Debug)]
+struct NavigationListener {
+    /// The loader of the main frame's document when the listener was added
+    loader_id: Option<LoaderId>,
+    /// The lifecycle event that completes a new document navigation
+    wait_until: LifecycleEvent,
+    /// Whether the main frame navigated within the same document
+    same_document_navigation: bool,
+    /// When to give up waiting for a navigation
+    deadline: Instant,
+    /// Receives the url of the main frame after the navigation
+    tx: Sender<Result<String>>,
+}
+
+impl NavigationListener {
+    fn is_complete(&self, main_frame: &Frame) -> bool {
+        self.same_document_navigation
+            || (main_frame.loader_id != self.loader_id
+                && main_frame
+                    .lifecycle_events
+                    .contains(self.wait_until.as_str()))
+    }
+}
+
+/// The lifecycle events the current document of a frame emitted so far.
+///
+/// The events are reset once the frame starts loading a new document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LifecycleState {
+    /// The loader of the document the events belong to
+    pub loader_id: Option<LoaderId>,
+    /// The names of the emitted events, as emitted by `Page.lifecycleEvent`
+    pub events: HashSet<String>,
+}
+
+impl LifecycleState {
+    /// Whether the document emitted the lifecycle `event`
+    pub fn has(&self, event: LifecycleEvent) -> bool {
+        self.events.contains(event.as_str())
+    }
+
+    /// The most advanced readiness level the document reached, `None` if it
+    /// did not even emit `init` yet
+    pub fn current(&self) -> Option<LifecycleEvent> {
+        [
+            LifecycleEvent::NetworkIdle,
+            LifecycleEvent::NetworkAlmostIdle,
+            LifecycleEvent::Load,
+            LifecycleEvent::DomContentLoaded,
+            LifecycleEvent::Init,
+        ]
+        .iter()
+        .copied()
+        .find(|event| self.has(*event))
+    }
+}
+
+/// The page lifecycle event after which a navigation is considered finished.
+///
+/// See also `Page.lifecycleEvent`
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Default)]
+pub enum LifecycleEvent {
+    /// Wait until the new document was committed, before any of its content
+    /// was parsed.
+    Init,
+    /// Wait until the `load` event was fired.
+    #[default]
+    Load,
+    /// Wait until the `DOMContentLoaded` event was fired.
+    DomContentLoaded,
+    /// Wait until there were no network connections for at least 500ms.
+    NetworkIdle,
+    /// Wait until there were no more than 2 network connections for at least
+    /// 500ms.
+    NetworkAlmostIdle,
+}
+
+impl LifecycleEvent {
+    /// The name of the event as emitted by `Page.lifecycleEvent`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LifecycleEvent::Init => "init",
+            LifecycleEvent::Load => "load",
+            LifecycleEvent::DomContentLoaded => "DOMContentLoaded",
+            LifecycleEvent::NetworkIdle => "networkIdle",
+            LifecycleEvent::NetworkAlmostIdle => "networkAlmostIdle",
+        }
+    }
+}
+
+impl AsRef<str> for LifecycleEvent {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
 }
 
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
@@ -467,14 +845,17 @@ pub struct FrameNavigationRequest {
     pub id: NavigationId,
     pub req: Request,
     pub timeout: Duration,
+    /// The lifecycle event that completes this navigation
+    pub wait_until: LifecycleEvent,
 }
 
 impl FrameNavigationRequest {
-    pub fn new(id: NavigationId, req: Request) -> Self {
+    pub fn new(id: NavigationId, req: Request, wait_until: LifecycleEvent) -> Self {
         Self {
             id,
             req,
             timeout: Duration::from_millis(REQUEST_TIMEOUT),
+            wait_until,
         }
     }
 
@@ -486,3 +867,77 @@ impl FrameNavigationRequest {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::channel::oneshot::channel;
+
+    use super::*;
+
+    fn main_frame_tree() -> FrameTree {
+        serde_json::from_value(serde_json::json!({
+            "frame": {
+                "id": "main",
+                "loaderId": "first",
+                "url": "https://example.com/",
+                "domainAndRegistry": "example.com",
+                "securityOrigin": "https://example.com",
+                "mimeType": "text/html",
+                "secureContextType": "Secure",
+                "crossOriginIsolatedContextType": "NotIsolated",
+            }
+        }))
+        .unwrap()
+    }
+
+    fn lifecycle_event(loader_id: &str, name: &str) -> EventLifecycleEvent {
+        serde_json::from_value(serde_json::json!({
+            "frameId": "main",
+            "loaderId": loader_id,
+            "name": name,
+            "timestamp": 0.0,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn navigation_listener_resolves_within_document() {
+        let now = Instant::now();
+        let mut manager = FrameManager::default();
+        manager.on_frame_tree(main_frame_tree());
+        let (tx, mut rx) = channel();
+        manager.add_navigation_listener(LifecycleEvent::Load, tx, now);
+        assert!(manager.poll(now).is_none());
+        assert!(rx.try_recv().unwrap().is_none());
+
+        let event = serde_json::from_value(serde_json::json!({
+            "frameId": "main",
+            "url": "https://example.com/#next",
+        }))
+        .unwrap();
+        manager.on_frame_navigated_within_document(&event);
+        manager.poll(now);
+        assert_eq!(
+            rx.try_recv().unwrap().unwrap().unwrap(),
+            "https://example.com/#next"
+        );
+    }
+
+    #[test]
+    fn navigation_listener_waits_for_lifecycle_event_of_new_document() {
+        let now = Instant::now();
+        let mut manager = FrameManager::default();
+        manager.on_frame_tree(main_frame_tree());
+        manager.on_page_lifecycle_event(&lifecycle_event("first", "load"));
+        let (tx, mut rx) = channel();
+        manager.add_navigation_listener(LifecycleEvent::Load, tx, now);
+
+        manager.on_page_lifecycle_event(&lifecycle_event("second", "init"));
+        manager.poll(now);
+        assert!(rx.try_recv().unwrap().is_none());
+
+        manager.on_page_lifecycle_event(&lifecycle_event("second", "load"));
+        manager.poll(now);
+        assert!(rx.try_recv().unwrap().unwrap().is_ok());
+    }
+}
@@ -14,6 +14,14 @@ pub(crate) struct PeriodicJob {
 }
 
 impl PeriodicJob {
+    /// Create a new job that is ready every `interval`
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            delay: Delay::new(interval),
+            interval,
+        }
+    }
+
     /// Returns `true` if the job is currently not running but ready
     /// to be run, `false` otherwise.
     pub fn is_ready(&mut self, cx: &mut Context<'_>) -> bool {
@@ -27,10 +35,6 @@ impl PeriodicJob {
 
 impl Default for PeriodicJob {
     fn default() -> Self {
-        let interval = Duration::from_millis(REQUEST_TIMEOUT);
-        Self {
-            delay: Delay::new(interval),
-            interval,
-        }
+        Self::new(Duration::from_millis(REQUEST_TIMEOUT))
     }
 }
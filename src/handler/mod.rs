@@ -1,58 +1,248 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fmt;
 use std::pin::Pin;
-use std::time::Instant;
+use std::process::ExitStatus;
+use std::time::{Duration, Instant};
 
 use fnv::FnvHashMap;
-use futures::channel::mpsc::Receiver;
-use futures::channel::oneshot::Sender as OneshotSender;
+use futures::channel::mpsc::{Receiver, UnboundedSender};
+use futures::channel::oneshot::{channel as oneshot_channel, Sender as OneshotSender};
+use futures::future::{self, BoxFuture, Either};
 use futures::stream::{Fuse, Stream, StreamExt};
 use futures::task::{Context, Poll};
+use futures::{Future, FutureExt};
+use futures_timer::Delay;
 
 use chromiumoxide_cdp::cdp::browser_protocol::browser::*;
+use chromiumoxide_cdp::cdp::browser_protocol::network::LoaderId;
+use chromiumoxide_cdp::cdp::browser_protocol::page::{
+    EnableParams, GetFrameTreeParams, SetLifecycleEventsEnabledParams,
+};
 use chromiumoxide_cdp::cdp::browser_protocol::target::*;
 use chromiumoxide_cdp::cdp::events::CdpEvent;
 use chromiumoxide_cdp::cdp::events::CdpEventMessage;
+use chromiumoxide_cdp::cdp::js_protocol::runtime::{self, RunIfWaitingForDebuggerParams};
 use chromiumoxide_types::Request as CdpRequest;
-use chromiumoxide_types::{CallId, Message, Method, Response};
+use chromiumoxide_types::{CallId, Command, Message, Method, Response};
 pub(crate) use page::PageInner;
+pub use process::BrowserProcess;
 
 use crate::cmd::{to_command_response, CommandMessage};
-use crate::conn::Connection;
-use crate::error::Result;
+use crate::conn::{self, ConnectOptions, Connection, ConnectionEvent, WsStream};
+use crate::error::{CdpError, Result};
 use crate::handler::browser::BrowserContext;
+use crate::handler::domains::DomainConfig;
 use crate::handler::frame::FrameNavigationRequest;
-use crate::handler::frame::{NavigationError, NavigationId, NavigationOk};
+use crate::handler::frame::{LifecycleEvent, NavigationError, NavigationId, NavigationOk};
 use crate::handler::job::PeriodicJob;
+use crate::handler::network::Credentials;
+use crate::handler::queue::CommandQueue;
 use crate::handler::session::Session;
 use crate::handler::target::Target;
 use crate::handler::target::TargetEvent;
+use crate::handler::viewport::Viewport;
+use crate::listeners::{EventListenerRequest, EventListeners};
 use crate::page::Page;
+use crate::retry::RetryPolicy;
+use crate::wire_log::{WireLog, WireLogConfig};
 
 /// Standard timeout in MS
 pub const REQUEST_TIMEOUT: u64 = 30_000;
 
+/// Default capacity of the channels to the `Handler` and to the targets
+pub const CHANNEL_CAPACITY: usize = 1_000;
+
+/// How often pending commands are checked for timeouts
+const EVICT_COMMANDS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often the process of a launched browser is checked for crashes
+const PROCESS_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
 mod browser;
+pub mod domains;
 pub mod emulation;
 pub mod frame;
 mod job;
 pub mod network;
 mod page;
+mod process;
+mod queue;
 mod session;
 pub mod target;
-mod viewport;
+pub mod viewport;
+
+/// Settings that apply to all the targets of a `Handler`
+///
+/// New settings may be added, so start from the default and change the ones
+/// that matter:
+///
+/// ```
+/// # use chromiumoxide::handler::HandlerConfig;
+/// let mut config = HandlerConfig::default();
+/// config.reconnect_attempts = 3;
+/// ```
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct HandlerConfig {
+    /// The credentials to answer proxy authentication challenges with.
+    ///
+    /// This pauses every request of all pages via the `Fetch` domain until
+    /// the `Handler` continues it, which slows down page loads.
+    pub proxy_credentials: Option<Credentials>,
+    /// How long to wait for the response to a request before it fails with a
+    /// timeout error
+    pub request_timeout: Duration,
+    /// How often to try to re-establish a lost connection, `0` disables
+    /// reconnecting
+    pub reconnect_attempts: usize,
+    /// How long to wait before each attempt to reconnect
+    pub reconnect_interval: Duration,
+    /// How often to send a ping frame to keep the connection alive, if at all
+    pub keep_alive_interval: Option<Duration>,
+    /// How many messages can be queued in the channels to the `Handler` and
+    /// to the targets' pages before senders have to wait
+    pub channel_capacity: usize,
+    /// Whether to ignore certificate errors of all pages, like self-signed
+    /// certificates, which is the default
+    pub ignore_https_errors: bool,
+    /// The user agent all pages report, instead of the browser's default
+    pub user_agent: Option<String>,
+    /// How many commands may await their response at once, further commands
+    /// are queued until a response arrives. Unlimited if `None`
+    pub max_commands_in_flight: Option<usize>,
+    /// How many commands of the same page may await their response at once.
+    /// Unlimited if `None`
+    pub max_commands_in_flight_per_session: Option<usize>,
+    /// The viewport every new page is emulated with
+    pub viewport: Viewport,
+    /// How the commands of pages are retried that fail with a transient
+    /// error, not at all if `None`
+    pub retry_policy: Option<RetryPolicy>,
+    /// How many of the most recent events of each kind are buffered per
+    /// session, so that subscribers that register late can request a
+    /// replay. Disabled if `0`
+    pub event_replay_capacity: usize,
+    /// Logs every command, response and event exchanged with the browser,
+    /// disabled if `None`
+    pub wire_log: Option<WireLogConfig>,
+    /// How the websocket is opened by `Browser::connect_with_config`, like
+    /// with additional headers or through a proxy
+    pub connect_options: ConnectOptions,
+    /// The domains that are enabled for every new page right away and
+    /// whether the others are enabled once they are used
+    pub domains: DomainConfig,
+    /// Whether to install the [`stealth::EVASIONS`](crate::stealth::EVASIONS)
+    /// in all pages
+    #[cfg(feature = "stealth")]
+    pub stealth: bool,
+}
+
+impl Default for HandlerConfig {
+    fn default() -> Self {
+        Self {
+            proxy_credentials: None,
+            request_timeout: Duration::from_millis(REQUEST_TIMEOUT),
+            reconnect_attempts: 0,
+            reconnect_interval: Duration::from_secs(1),
+            keep_alive_interval: None,
+            channel_capacity: CHANNEL_CAPACITY,
+            ignore_https_errors: true,
+            user_agent: None,
+            max_commands_in_flight: None,
+            max_commands_in_flight_per_session: None,
+            viewport: Default::default(),
+            retry_policy: None,
+            event_replay_capacity: 0,
+            wire_log: None,
+            connect_options: Default::default(),
+            domains: Default::default(),
+            #[cfg(feature = "stealth")]
+            stealth: false,
+        }
+    }
+}
+
+/// The state of the websocket connection of the `Handler`
+enum ConnectionState {
+    Connected,
+    /// Waiting for the next attempt to reconnect
+    Reconnecting {
+        attempt: usize,
+        delay: Delay,
+    },
+    /// Trying to open a new websocket
+    Connecting {
+        attempt: usize,
+        ws: BoxFuture<'static, Result<WsStream>>,
+    },
+    /// The connection is lost for good
+    Closed,
+}
+
+impl fmt::Debug for ConnectionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionState::Connected => f.write_str("Connected"),
+            ConnectionState::Reconnecting { attempt, .. } => f
+                .debug_struct("Reconnecting")
+                .field("attempt", attempt)
+                .finish(),
+            ConnectionState::Connecting { attempt, .. } => f
+                .debug_struct("Connecting")
+                .field("attempt", attempt)
+                .finish(),
+            ConnectionState::Closed => f.write_str("Closed"),
+        }
+    }
+}
+
+/// A snapshot of the bookkeeping of a [`Handler`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HandlerStats {
+    /// The commands that were sent and await their response
+    pub pending_commands: usize,
+    /// The commands that wait until fewer commands are in flight
+    pub queued_commands: usize,
+    /// The navigations that are in progress
+    pub navigations: usize,
+    /// How many commands were evicted before their response arrived, because
+    /// they timed out or nobody awaits the response anymore
+    pub expired_commands: u64,
+    /// How many responses arrived for commands that were not pending, like
+    /// late responses to expired commands
+    pub unknown_responses: u64,
+}
 
 /// The handler that monitors the state of the chromium browser and drives all
 /// the requests and events.
+///
+/// # Reentrancy
+///
+/// Events are delivered to the subscribers of `Page::event_listener` and
+/// `Browser::event_listener` over unbounded channels, so the handler never
+/// waits for a subscriber. Subscribers can therefore execute commands while
+/// they handle an event, as long as the handler is driven by another task,
+/// like via [`Handler::spawn`]. Awaiting a command in the same task that
+/// polls the handler never completes.
+///
+/// Commands that resume the browser after it paused on an event, like
+/// continuing an intercepted request or accepting a dialog, bypass the
+/// limits of `HandlerConfig::max_commands_in_flight` and are accepted even
+/// while the handler is backlogged, since the commands in flight, like a
+/// navigation, may wait for them.
 #[must_use = "streams do nothing unless polled"]
 #[derive(Debug)]
 pub struct Handler {
     /// Commands that are being processed await a response from the chromium
-    /// instance
+    /// instance until their deadline
     pending_commands: FnvHashMap<CallId, (PendingRequest, Cow<'static, str>, Instant)>,
+    /// Commands that wait until fewer commands are in flight
+    commands: CommandQueue,
     /// Connection to the browser instance
     from_browser: Fuse<Receiver<HandlerMessage>>,
     // default_ctx: BrowserContext,
+    #[allow(unused)] // allow until implemented
     contexts: HashMap<BrowserContextId, BrowserContext>,
     /// Used to loop over all targets in a consistent manner
     target_ids: Vec<TargetId>,
@@ -66,25 +256,48 @@ pub struct Handler {
     sessions: HashMap<SessionId, Session>,
     /// The websocket connection to the chromium instance
     conn: Connection<CdpEventMessage>,
+    /// Whether the websocket is currently connected
+    conn_state: ConnectionState,
+    /// The subscribers to changes of the connection state
+    connection_listeners: Vec<UnboundedSender<ConnectionEvent>>,
+    /// Sends ping frames periodically, if configured
+    keep_alive: Option<PeriodicJob>,
     /// Evicts timed out requests periodically
     evict_command_timeout: PeriodicJob,
+    /// The process of the launched browser, if any
+    process: Option<BrowserProcess>,
+    /// Checks periodically whether the process crashed
+    process_check: PeriodicJob,
+    /// The exit status of the browser process, if it crashed
+    crashed: Option<ExitStatus>,
     /// The internal identifier for a specific navigation
     next_navigation_id: usize,
+    /// The subscribers to events that are not tied to a target's session
+    event_listeners: EventListeners,
+    /// How the targets are set up
+    config: HandlerConfig,
+    /// How many commands were evicted before their response arrived
+    expired_commands: u64,
+    /// How many responses arrived for commands that are not pending
+    unknown_responses: u64,
 }
 
 impl Handler {
     /// Create a new `Handler` that drives the connection and listens for
     /// messages on the receiver `rx`.
-    pub(crate) fn new(mut conn: Connection<CdpEventMessage>, rx: Receiver<HandlerMessage>) -> Self {
-        let discover = SetDiscoverTargetsParams::new(true);
-        let _ = conn.submit_command(
-            discover.identifier(),
-            None,
-            serde_json::to_value(discover).unwrap(),
-        );
-
-        Self {
+    pub(crate) fn new(
+        mut conn: Connection<CdpEventMessage>,
+        rx: Receiver<HandlerMessage>,
+        config: HandlerConfig,
+    ) -> Self {
+        conn.set_wire_log(config.wire_log.as_ref().map(WireLog::new));
+        let keep_alive = config.keep_alive_interval.map(PeriodicJob::new);
+        let mut handler = Self {
             pending_commands: Default::default(),
+            commands: CommandQueue::new(
+                config.max_commands_in_flight,
+                config.max_commands_in_flight_per_session,
+            ),
             from_browser: rx.fuse(),
             contexts: Default::default(),
             target_ids: Default::default(),
@@ -92,11 +305,41 @@ impl Handler {
             navigations: Default::default(),
             sessions: Default::default(),
             conn,
-            evict_command_timeout: Default::default(),
+            conn_state: ConnectionState::Connected,
+            connection_listeners: Vec::new(),
+            keep_alive,
+            evict_command_timeout: PeriodicJob::new(EVICT_COMMANDS_INTERVAL),
+            process: None,
+            process_check: PeriodicJob::new(PROCESS_CHECK_INTERVAL),
+            crashed: None,
             next_navigation_id: 0,
+            event_listeners: EventListeners::with_replay_capacity(config.event_replay_capacity),
+            config,
+            expired_commands: 0,
+            unknown_responses: 0,
+        };
+        handler.submit_unawaited(SetDiscoverTargetsParams::new(true), None);
+        handler
+    }
+
+    /// The number of commands and navigations the handler keeps track of,
+    /// useful to detect commands that never complete
+    pub fn stats(&self) -> HandlerStats {
+        HandlerStats {
+            pending_commands: self.pending_commands.len(),
+            queued_commands: self.commands.len(),
+            navigations: self.navigations.len(),
+            expired_commands: self.expired_commands,
+            unknown_responses: self.unknown_responses,
         }
     }
 
+    /// Watch the process of the launched browser, if it exits unexpectedly all
+    /// pending commands fail with `CdpError::BrowserCrashed`
+    pub(crate) fn set_process(&mut self, process: BrowserProcess) {
+        self.process = Some(process);
+    }
+
     /// Return the target with the matching `target_id`
     pub fn get_target(&self, target_id: &TargetId) -> Option<&Target> {
         self.targets.get(target_id)
@@ -107,8 +350,36 @@ impl Handler {
         self.targets.values()
     }
 
+    /// The sessions attached to workers and background pages, one per target
+    fn workers(&self) -> Vec<(SessionId, TargetInfo)> {
+        let mut workers: Vec<(SessionId, TargetInfo)> = Vec::new();
+        for session in self
+            .sessions
+            .values()
+            .filter(|s| s.is_worker() || s.is_background_page())
+        {
+            if !workers
+                .iter()
+                .any(|(_, info)| &info.target_id == session.target_id())
+            {
+                workers.push((session.session_id().clone(), session.target_info().clone()));
+            }
+        }
+        workers
+    }
+
     /// received a response to a navigation request like `Page.navigate`
     fn on_navigation_response(&mut self, id: NavigationId, resp: Response) {
+        let loader_id = resp
+            .result
+            .as_ref()
+            .and_then(|res| res["loaderId"].as_str())
+            .map(|id| LoaderId::from(id.to_string()));
+        for target in self.targets.values_mut() {
+            target
+                .frame_manager_mut()
+                .on_navigation_response(id, loader_id.clone());
+        }
         if let Some(nav) = self.navigations.remove(&id) {
             match nav {
                 NavigationRequest::Navigate(mut nav) => {
@@ -179,32 +450,320 @@ impl Handler {
                 PendingRequest::Navigate(id) => {
                     self.on_navigation_response(id, resp);
                 }
-                PendingRequest::ExternalCommand(tx) => {
+                PendingRequest::ExternalCommand(tx, session_id) => {
                     let _ = tx.send(Ok(resp)).ok();
+                    self.commands.release(&session_id);
                 }
                 PendingRequest::InternalCommand(target_id) => {
                     if let Some(target) = self.targets.get_mut(&target_id) {
                         target.on_response(resp, method.as_ref());
                     }
                 }
+                PendingRequest::OopifFrameTree(target_id) => {
+                    if let Some(target) = self.targets.get_mut(&target_id) {
+                        target.on_oopif_frame_tree(resp);
+                    }
+                }
+                PendingRequest::Unawaited => {}
             }
+        } else {
+            // the command timed out already or the id was never issued
+            self.unknown_responses += 1;
+            log::warn!("Received a response to an unknown command {:?}", resp.id);
         }
     }
 
-    /// Submit a command initiated via channel
-    pub(crate) fn submit_external_command(
-        &mut self,
-        msg: CommandMessage,
-        now: Instant,
-    ) -> Result<()> {
-        let call_id = self
+    /// Fails all the pending commands whose deadline has passed with
+    /// `CdpError::Timeout` and discards those whose response is no longer
+    /// awaited, like a cancelled `CommandHandle` or the command of a target
+    /// that is gone
+    fn evict_timed_out_commands(&mut self, now: Instant) {
+        let targets = &self.targets;
+        let timed_out = self
+            .pending_commands
+            .iter()
+            .filter(|(_, (req, _, deadline))| {
+                now > *deadline
+                    || match req {
+                        PendingRequest::ExternalCommand(tx, _) => tx.is_canceled(),
+                        PendingRequest::InternalCommand(target_id)
+                        | PendingRequest::OopifFrameTree(target_id) => {
+                            !targets.contains_key(target_id)
+                        }
+                        _ => false,
+                    }
+            })
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for call_id in timed_out {
+            if let Some((req, method, _)) = self.pending_commands.remove(&call_id) {
+                self.expired_commands += 1;
+                self.conn.on_command_expired(call_id);
+                log::debug!("Evicted command {:?} ({})", call_id, method);
+                match req {
+                    PendingRequest::CreateTarget(tx) => {
+                        let _ = tx.send(Err(CdpError::Timeout));
+                    }
+                    PendingRequest::Navigate(id) => {
+                        if let Some(NavigationRequest::Navigate(nav)) = self.navigations.remove(&id)
+                        {
+                            let _ = nav.tx.send(Err(CdpError::Timeout));
+                        }
+                    }
+                    PendingRequest::ExternalCommand(tx, session_id) => {
+                        let _ = tx.send(Err(CdpError::Timeout));
+                        self.commands.release(&session_id);
+                    }
+                    // the target's command chain times out on its own
+                    PendingRequest::InternalCommand(_)
+                    | PendingRequest::OopifFrameTree(_)
+                    | PendingRequest::Unawaited => {}
+                }
+            }
+        }
+        for cmd in self.commands.evict(now) {
+            let _ = cmd.sender.send(Err(CdpError::Timeout));
+        }
+    }
+
+    /// Sends the event to all the subscribers of connection changes
+    fn notify_connection_listeners(&mut self, event: ConnectionEvent) {
+        self.connection_listeners
+            .retain(|tx| tx.unbounded_send(event).is_ok());
+    }
+
+    /// The websocket was closed or failed.
+    ///
+    /// All the requests that are awaiting a response fail, since the response
+    /// will never arrive, and the sessions of the targets are gone. Unless
+    /// reconnecting is enabled, the connection is closed for good.
+    fn on_disconnected(&mut self) {
+        if let Some(status) = self.process.as_ref().and_then(BrowserProcess::crashed) {
+            return self.on_browser_crashed(status);
+        }
+        self.fail_pending_commands(|| CdpError::Disconnected);
+        self.sessions.clear();
+        for target in self.targets.values_mut() {
+            target.detach();
+        }
+        self.notify_connection_listeners(ConnectionEvent::Disconnected);
+
+        if self.config.reconnect_attempts > 0 && self.conn.is_reconnectable() {
+            self.conn_state = ConnectionState::Reconnecting {
+                attempt: 1,
+                delay: Delay::new(self.config.reconnect_interval),
+            };
+        } else {
+            self.on_closed();
+        }
+    }
+
+    /// Fails all the commands that await a response or are queued and all
+    /// navigations with the error
+    fn fail_pending_commands(&mut self, err: impl Fn() -> CdpError) {
+        for (call_id, (req, _, _)) in self.pending_commands.drain() {
+            self.conn.on_command_expired(call_id);
+            match req {
+                PendingRequest::CreateTarget(tx) => {
+                    let _ = tx.send(Err(err()));
+                }
+                PendingRequest::Navigate(id) => {
+                    if let Some(NavigationRequest::Navigate(nav)) = self.navigations.remove(&id) {
+                        let _ = nav.tx.send(Err(err()));
+                    }
+                }
+                PendingRequest::ExternalCommand(tx, _) => {
+                    let _ = tx.send(Err(err()));
+                }
+                PendingRequest::InternalCommand(_)
+                | PendingRequest::OopifFrameTree(_)
+                | PendingRequest::Unawaited => {}
+            }
+        }
+        for cmd in self.commands.clear() {
+            let _ = cmd.sender.send(Err(err()));
+        }
+        // navigations that wait for a lifecycle event would only time out
+        for target in self.targets.values_mut() {
+            target.frame_manager_mut().fail_navigations(&err);
+        }
+        for (_, nav) in self.navigations.drain() {
+            match nav {
+                NavigationRequest::Navigate(nav) => {
+                    let _ = nav.tx.send(Err(err()));
+                }
+            }
+        }
+    }
+
+    /// The process of the browser exited unexpectedly, there is nothing left
+    /// to reconnect to
+    fn on_browser_crashed(&mut self, status: ExitStatus) {
+        self.crashed = Some(status);
+        self.fail_pending_commands(|| CdpError::BrowserCrashed(status));
+        self.sessions.clear();
+        self.notify_connection_listeners(ConnectionEvent::Crashed(status));
+        self.on_closed();
+    }
+
+    /// The renderer process of the target crashed, the commands sent to its
+    /// sessions and its navigations won't receive a response
+    fn on_target_crashed(&mut self, target_id: &TargetId) {
+        let sessions = self
+            .sessions
+            .values()
+            .filter(|session| session.target_id() == target_id)
+            .map(|session| Some(session.session_id().clone()))
+            .collect::<Vec<_>>();
+        let crashed = self
+            .pending_commands
+            .iter()
+            .filter(|(_, (req, _, _))| {
+                matches!(req, PendingRequest::ExternalCommand(_, session) if sessions.contains(session))
+            })
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for call_id in crashed {
+            if let Some((PendingRequest::ExternalCommand(tx, session), _, _)) =
+                self.pending_commands.remove(&call_id)
+            {
+                let _ = tx.send(Err(CdpError::TargetCrashed(target_id.clone())));
+                self.commands.release(&session);
+            }
+        }
+        for session in &sessions {
+            for cmd in self.commands.remove(session) {
+                let _ = cmd
+                    .sender
+                    .send(Err(CdpError::TargetCrashed(target_id.clone())));
+            }
+        }
+        let navigations = match self.targets.get_mut(target_id) {
+            Some(target) => target
+                .frame_manager_mut()
+                .fail_navigations(|| CdpError::TargetCrashed(target_id.clone())),
+            None => Vec::new(),
+        };
+        for id in navigations {
+            if let Some(NavigationRequest::Navigate(nav)) = self.navigations.remove(&id) {
+                let _ = nav.tx.send(Err(CdpError::TargetCrashed(target_id.clone())));
+            }
+        }
+    }
+
+    /// The error the `Handler` fails with once it is done
+    fn closed_error(&self) -> CdpError {
+        match self.crashed {
+            Some(status) => CdpError::BrowserCrashed(status),
+            None => CdpError::Disconnected,
+        }
+    }
+
+    /// The connection is lost for good
+    fn on_closed(&mut self) {
+        self.conn_state = ConnectionState::Closed;
+        self.notify_connection_listeners(ConnectionEvent::Closed);
+    }
+
+    /// A new websocket was opened after `attempts` tries, the targets need to
+    /// attach to it again.
+    fn on_reconnected(&mut self, ws: WsStream, attempts: usize) {
+        self.conn.set_ws(ws);
+        self.conn_state = ConnectionState::Connected;
+        self.submit_unawaited(SetDiscoverTargetsParams::new(true), None);
+        for target in self.targets.values_mut() {
+            target.reattach();
+        }
+        self.notify_connection_listeners(ConnectionEvent::Reconnected { attempts });
+    }
+
+    /// Drives the attempts to re-establish a lost connection.
+    ///
+    /// Resolves once the connection is up again or reconnecting failed for
+    /// good.
+    fn poll_reconnect(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        loop {
+            match &mut self.conn_state {
+                ConnectionState::Connected | ConnectionState::Closed => return Poll::Ready(Ok(())),
+                ConnectionState::Reconnecting { attempt, delay } => {
+                    if Future::poll(Pin::new(delay), cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                    let url = self.conn.url().to_string();
+                    let options = self.conn.options().clone();
+                    self.conn_state = ConnectionState::Connecting {
+                        attempt: *attempt,
+                        ws: async move { conn::connect_ws(&url, &options).await }.boxed(),
+                    };
+                }
+                ConnectionState::Connecting { attempt, ws } => {
+                    let attempt = *attempt;
+                    match ws.as_mut().poll(cx) {
+                        Poll::Pending => return Poll::Pending,
+                        Poll::Ready(Ok(ws)) => {
+                            self.on_reconnected(ws, attempt);
+                            return Poll::Ready(Ok(()));
+                        }
+                        Poll::Ready(Err(err)) => {
+                            if attempt >= self.config.reconnect_attempts {
+                                self.on_closed();
+                                return Poll::Ready(Err(err));
+                            }
+                            self.conn_state = ConnectionState::Reconnecting {
+                                attempt: attempt + 1,
+                                delay: Delay::new(self.config.reconnect_interval),
+                            };
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Submit a command initiated via channel, or queue it in if too many
+    /// commands are in flight already
+    pub(crate) fn submit_external_command(&mut self, msg: CommandMessage, now: Instant) {
+        let deadline = now + msg.timeout.unwrap_or(self.config.request_timeout);
+        if msg.is_resuming() {
+            // the commands in flight may only complete once this one was sent
+            self.commands.acquire(&msg.session_id);
+            self.send_external_command(msg, deadline);
+        } else if self.commands.try_acquire(&msg.session_id) {
+            self.send_external_command(msg, deadline);
+        } else {
+            self.commands.push(msg, deadline);
+        }
+    }
+
+    /// Submit the queued commands for which slots became available
+    fn submit_queued_commands(&mut self) {
+        while let Some((msg, deadline)) = self.commands.pop() {
+            self.send_external_command(msg, deadline);
+        }
+    }
+
+    /// Send a command whose slot is already reserved
+    fn send_external_command(&mut self, msg: CommandMessage, deadline: Instant) {
+        let session_id = msg.session_id.clone();
+        match self
             .conn
-            .submit_command(msg.method.clone(), msg.session_id, msg.params)?;
-        self.pending_commands.insert(
-            call_id,
-            (PendingRequest::ExternalCommand(msg.sender), msg.method, now),
-        );
-        Ok(())
+            .submit_command(msg.method.clone(), msg.session_id, msg.params)
+        {
+            Ok(call_id) => {
+                self.pending_commands.insert(
+                    call_id,
+                    (
+                        PendingRequest::ExternalCommand(msg.sender, session_id),
+                        msg.method,
+                        deadline,
+                    ),
+                );
+            }
+            Err(err) => {
+                self.commands.release(&session_id);
+                let _ = msg.sender.send(Err(err.into()));
+            }
+        }
     }
 
     pub(crate) fn submit_internal_command(
@@ -220,11 +779,33 @@ impl Handler {
         )?;
         self.pending_commands.insert(
             call_id,
-            (PendingRequest::InternalCommand(target_id), req.method, now),
+            (
+                PendingRequest::InternalCommand(target_id),
+                req.method,
+                now + self.config.request_timeout,
+            ),
         );
         Ok(())
     }
 
+    /// Send a command whose response is of no interest, it is only tracked
+    /// until it arrives or times out
+    fn submit_unawaited<T: Command>(&mut self, cmd: T, session_id: Option<SessionId>) {
+        let method = cmd.identifier();
+        let res = serde_json::to_value(cmd)
+            .and_then(|params| self.conn.submit_command(method.clone(), session_id, params));
+        if let Ok(call_id) = res {
+            self.pending_commands.insert(
+                call_id,
+                (
+                    PendingRequest::Unawaited,
+                    method,
+                    Instant::now() + self.config.request_timeout,
+                ),
+            );
+        }
+    }
+
     /// Send the Request over to the server and store its identifier to handle
     /// the response once received.
     fn submit_navigation(&mut self, id: NavigationId, req: CdpRequest, now: Instant) {
@@ -237,26 +818,42 @@ impl Handler {
             )
             .unwrap();
 
-        self.pending_commands
-            .insert(call_id, (PendingRequest::Navigate(id), req.method, now));
+        self.pending_commands.insert(
+            call_id,
+            (
+                PendingRequest::Navigate(id),
+                req.method,
+                now + self.config.request_timeout,
+            ),
+        );
     }
 
     /// Process a message received by the target's page via channel
     fn on_target_message(&mut self, target: &mut Target, msg: CommandMessage, now: Instant) {
-        // if let some
         if msg.is_navigation() {
-            let (req, tx) = msg.split();
-            let id = self.next_navigation_id();
-            target.goto(FrameNavigationRequest::new(id, req));
-            self.navigations.insert(
-                id,
-                NavigationRequest::Navigate(NavigationInProgress::new(tx)),
-            );
+            self.on_navigation(target, msg, LifecycleEvent::default());
         } else {
-            let _ = self.submit_external_command(msg, now);
+            self.submit_external_command(msg, now);
         }
     }
 
+    /// Queue in a navigation of the target's main frame that completes once
+    /// the `wait_until` lifecycle event was fired
+    fn on_navigation(
+        &mut self,
+        target: &mut Target,
+        msg: CommandMessage,
+        wait_until: LifecycleEvent,
+    ) {
+        let (req, tx) = msg.split();
+        let id = self.next_navigation_id();
+        target.goto(FrameNavigationRequest::new(id, req, wait_until));
+        self.navigations.insert(
+            id,
+            NavigationRequest::Navigate(NavigationInProgress::new(tx)),
+        );
+    }
+
     /// An identifier for queued `NavigationRequest`s.
     fn next_navigation_id(&mut self) -> NavigationId {
         let id = NavigationId(self.next_navigation_id);
@@ -281,7 +878,11 @@ impl Handler {
                 Ok(call_id) => {
                     self.pending_commands.insert(
                         call_id,
-                        (PendingRequest::CreateTarget(tx), method, Instant::now()),
+                        (
+                            PendingRequest::CreateTarget(tx),
+                            method,
+                            Instant::now() + self.config.request_timeout,
+                        ),
                     );
                 }
                 Err(err) => {
@@ -294,20 +895,74 @@ impl Handler {
         }
     }
 
+    /// Attach to an existing page target and send its `Page` once the target
+    /// is initialized
+    fn attach_to_target(&mut self, target_id: TargetId, tx: OneshotSender<Result<Page>>) {
+        match self.targets.get_mut(&target_id) {
+            Some(target) if target.info().r#type == "page" => target.attach(tx),
+            Some(target) => {
+                let _ = tx.send(Err(CdpError::msg(format!(
+                    "Target {:?} is a {}, not a page",
+                    target_id,
+                    target.info().r#type
+                ))));
+            }
+            None => {
+                let _ = tx.send(Err(CdpError::NotFound));
+            }
+        }
+    }
+
     /// Process an incoming event read from the websocket
+    ///
+    /// Events that carry a session id are dispatched to the `Target` the
+    /// session is attached to. Sessions attached to child targets (e.g.
+    /// out-of-process iframes or workers) are reported via the parent's
+    /// session and need to be tracked before the event is dispatched.
     fn on_event(&mut self, event: CdpEventMessage) {
+        match &event.params {
+            CdpEvent::TargetAttachedToTarget(ev) => {
+                let parent_session = event.session_id.clone().map(SessionId::from);
+                self.on_attached_to_target(ev, parent_session.as_ref())
+            }
+            CdpEvent::TargetDetachedFromTarget(ev) => self.on_detached_from_target(ev),
+            CdpEvent::TargetTargetCrashed(ev) => self.on_target_crashed(&ev.target_id),
+            CdpEvent::InspectorTargetCrashed(_) => {
+                if let Some(target_id) = event
+                    .session_id
+                    .as_ref()
+                    .and_then(|id| self.sessions.get(id))
+                    .map(|session| session.target_id().clone())
+                {
+                    self.on_target_crashed(&target_id);
+                }
+            }
+            _ => {}
+        }
         if let Some(ref session_id) = event.session_id {
-            if let Some(session) = self.sessions.get(session_id) {
+            if let Some(session) = self.sessions.get_mut(session_id) {
+                if session.is_worker() {
+                    return session.event_listeners_mut().start_send(&event);
+                }
+                if session.is_iframe() && session.page_target_id() != session.target_id() {
+                    if let Some(target) = self.targets.get_mut(session.page_target_id()) {
+                        return target.on_oopif_event(event);
+                    }
+                }
                 if let Some(target) = self.targets.get_mut(session.target_id()) {
                     return target.on_event(event);
                 }
             }
         }
+        self.event_listeners.start_send(&event);
         match event.params {
             CdpEvent::TargetTargetCreated(ev) => self.on_target_created(ev),
-            CdpEvent::TargetAttachedToTarget(ev) => self.on_attached_to_target(ev),
             CdpEvent::TargetTargetDestroyed(ev) => self.on_target_destroyed(ev),
-            CdpEvent::TargetDetachedFromTarget(ev) => self.on_detached_from_target(ev),
+            CdpEvent::TargetTargetInfoChanged(ev) => {
+                if let Some(target) = self.targets.get_mut(&ev.target_info.target_id) {
+                    target.set_info(ev.target_info);
+                }
+            }
             _ => {}
         }
     }
@@ -316,44 +971,254 @@ impl Handler {
     ///
     /// Creates a new `Target` instance and keeps track of it
     fn on_target_created(&mut self, event: EventTargetCreated) {
-        let target = Target::new(event.target_info);
+        if self.targets.contains_key(&event.target_info.target_id) {
+            // already known targets are reported again after a reconnect
+            return;
+        }
+        let target = Target::new(event.target_info, &self.config);
         self.target_ids.push(target.target_id().clone());
         self.targets.insert(target.target_id().clone(), target);
     }
 
     /// A new session is attached to a target
-    fn on_attached_to_target(&mut self, event: EventAttachedToTarget) {
-        let session = Session::new(
+    ///
+    /// All sessions are attached in flatten mode, so that all commands and
+    /// events of that session are multiplexed over the same connection and
+    /// identified by their session id.
+    ///
+    /// Out-of-process iframes are auto attached via the session of the page
+    /// (or iframe) that embeds them, their frames are merged into the frame
+    /// tree of that page.
+    fn on_attached_to_target(
+        &mut self,
+        event: &EventAttachedToTarget,
+        parent_session: Option<&SessionId>,
+    ) {
+        let mut session = Session::new(
             event.session_id.clone(),
-            event.target_info.r#type,
-            event.target_info.target_id,
+            event.target_info.clone(),
+            self.config.event_replay_capacity,
         );
-        if let Some(target) = self.targets.get_mut(session.target_id()) {
-            target.set_session_id(session.session_id().clone())
+        let page_target_id = parent_session
+            .and_then(|id| self.sessions.get(id))
+            .map(|parent| parent.page_target_id().clone());
+        match page_target_id {
+            Some(page_target_id) if session.is_iframe() => {
+                if let Some(target) = self.targets.get_mut(&page_target_id) {
+                    target.frame_manager_mut().on_attached_to_target(event);
+                }
+                self.init_oopif_session(&page_target_id, &event.session_id);
+                session.set_page_target_id(page_target_id);
+            }
+            _ if !session.is_worker() => {
+                if let Some(target) = self.targets.get_mut(session.target_id()) {
+                    target.set_session_id(session.session_id().clone())
+                }
+            }
+            _ => {}
+        }
+        if event.waiting_for_debugger {
+            // targets that were auto attached are paused until resumed
+            self.submit_unawaited(
+                RunIfWaitingForDebuggerParams::default(),
+                Some(event.session_id.clone()),
+            );
+        }
+        self.sessions.insert(event.session_id.clone(), session);
+    }
+
+    /// Enables the domains of the session of an out-of-process iframe that
+    /// track its frames, before the iframe resumes
+    fn init_oopif_session(&mut self, page_target_id: &TargetId, session_id: &SessionId) {
+        let session = Some(session_id.clone());
+        self.submit_unawaited(EnableParams::default(), session.clone());
+        let get_tree = GetFrameTreeParams::default();
+        let res = serde_json::to_value(get_tree).and_then(|params| {
+            self.conn.submit_command(
+                GetFrameTreeParams::IDENTIFIER.into(),
+                session.clone(),
+                params,
+            )
+        });
+        if let Ok(call_id) = res {
+            self.pending_commands.insert(
+                call_id,
+                (
+                    PendingRequest::OopifFrameTree(page_target_id.clone()),
+                    GetFrameTreeParams::IDENTIFIER.into(),
+                    Instant::now() + self.config.request_timeout,
+                ),
+            );
         }
-        self.sessions.insert(event.session_id, session);
+        self.submit_unawaited(SetLifecycleEventsEnabledParams::new(true), session.clone());
+        self.submit_unawaited(runtime::EnableParams::default(), session.clone());
+        // nested out-of-process iframes are attached via this session
+        let attach = SetAutoAttachParams::builder()
+            .flatten(true)
+            .auto_attach(true)
+            .wait_for_debugger_on_start(true)
+            .build()
+            .unwrap();
+        self.submit_unawaited(attach, session);
     }
 
     /// The session was detached from target.
     /// Can be issued multiple times per target if multiple session have been
     /// attached to it.
-    fn on_detached_from_target(&mut self, event: EventDetachedFromTarget) {
+    fn on_detached_from_target(&mut self, event: &EventDetachedFromTarget) {
         // remove the session
         if let Some(session) = self.sessions.remove(&event.session_id) {
+            if session.is_iframe() {
+                if let Some(target) = self.targets.get_mut(session.page_target_id()) {
+                    target
+                        .frame_manager_mut()
+                        .on_detached_from_target(session.session_id());
+                }
+            }
             if let Some(target) = self.targets.get_mut(session.target_id()) {
-                target.session_id().take();
+                if target.session_id() == Some(session.session_id()) {
+                    target.session_id_mut().take();
+                }
             }
         }
     }
 
     /// Fired when the target was destroyed in the browser
     fn on_target_destroyed(&mut self, event: EventTargetDestroyed) {
-        if let Some(target) = self.targets.remove(&event.target_id) {
+        if self.targets.remove(&event.target_id).is_some() {
             // TODO shutdown?
-            if let Some(session) = target.session_id() {
-                self.sessions.remove(session);
+            // there can be multiple sessions attached to the target
+            self.sessions
+                .retain(|_, session| session.target_id() != &event.target_id);
+        }
+    }
+
+    /// Drives the handler in the background on the configured runtime, until
+    /// the connection to the browser is closed or the handler is shut down
+    /// via [`JoinHandle::shutdown`].
+    ///
+    /// Errors of the handler are logged, the events it yields are discarded,
+    /// use [`Page::event_listener`] to receive events instead.
+    pub fn spawn(self) -> JoinHandle {
+        let (done_tx, done) = oneshot_channel();
+        let (shutdown, shutdown_rx) = oneshot_channel::<()>();
+        // only an explicit shutdown stops the handler, not dropping the handle
+        let shutdown_rx = shutdown_rx.then(|res| async move {
+            if res.is_err() {
+                future::pending::<()>().await
             }
+        });
+        crate::utils::spawn(async move {
+            self.run_until(shutdown_rx).await;
+            let _ = done_tx.send(());
+        });
+        JoinHandle {
+            done,
+            shutdown: Some(shutdown),
+        }
+    }
+
+    /// Drives the handler until the connection to the browser is closed or
+    /// the `shutdown` future resolves, then shuts the handler down, see
+    /// [`Handler::shutdown`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::browser::{Browser, BrowserConfig};
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(config: BrowserConfig) -> Result<()> {
+    ///     let (browser, handler) = Browser::launch(config).await?;
+    ///     let (stop, stopped) = futures::channel::oneshot::channel::<()>();
+    ///     async_std::task::spawn(handler.run_until(async move {
+    ///         let _ = stopped.await;
+    ///     }));
+    ///     // ...
+    ///     let _ = stop.send(());
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn run_until(mut self, shutdown: impl Future<Output = ()>) {
+        let shutdown = shutdown.fuse();
+        futures::pin_mut!(shutdown);
+        loop {
+            match future::select(self.next(), &mut shutdown).await {
+                Either::Left((Some(Err(err)), _)) => {
+                    log::debug!("Handler error: {}", err);
+                }
+                Either::Left((Some(Ok(_)), _)) => {}
+                Either::Left((None, _)) => return,
+                Either::Right(_) => break,
+            }
+        }
+        self.shutdown().await
+    }
+
+    /// Stops the handler: all the pending and queued commands fail with
+    /// [`CdpError::Disconnected`], the websocket is closed and the stream of
+    /// the handler ends.
+    ///
+    /// The browser itself keeps running, see [`Browser::close`] to close it.
+    ///
+    /// [`Browser::close`]: crate::browser::Browser::close
+    pub async fn shutdown(&mut self) {
+        if let ConnectionState::Closed = self.conn_state {
+            return;
+        }
+        // fail everything that was sent to the handler but not handled yet
+        self.from_browser.get_mut().close();
+        while let Some(msg) = self.from_browser.next().await {
+            match msg {
+                HandlerMessage::Command(cmd) => {
+                    let _ = cmd.sender.send(Err(CdpError::Disconnected));
+                }
+                HandlerMessage::CreatePage(_, tx) | HandlerMessage::AttachToTarget(_, tx) => {
+                    let _ = tx.send(Err(CdpError::Disconnected));
+                }
+                _ => {}
+            }
+        }
+        self.fail_pending_commands(|| CdpError::Disconnected);
+        self.sessions.clear();
+        if let ConnectionState::Connected = self.conn_state {
+            let conn = &mut self.conn;
+            if let Err(err) = future::poll_fn(|cx| conn.poll_close(cx)).await {
+                log::debug!("Failed to close the connection: {}", err);
+            }
+        }
+        self.on_closed();
+    }
+}
+
+/// Resolves once a [`Handler`] that runs in the background via
+/// [`Handler::spawn`] finished, i.e. once the connection to the browser was
+/// closed.
+///
+/// Dropping the handle does not stop the handler.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct JoinHandle {
+    done: futures::channel::oneshot::Receiver<()>,
+    shutdown: Option<OneshotSender<()>>,
+}
+
+impl JoinHandle {
+    /// Shuts the handler down and waits until it finished, see
+    /// [`Handler::shutdown`]
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
         }
+        self.await
+    }
+}
+
+impl Future for JoinHandle {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // a canceled channel means the task was dropped by the runtime
+        Pin::new(&mut self.done).poll(cx).map(|_| ())
     }
 }
 
@@ -363,30 +1228,60 @@ impl Stream for Handler {
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let pin = self.get_mut();
 
+        if let ConnectionState::Closed = pin.conn_state {
+            return Poll::Ready(None);
+        }
+
         loop {
             let now = Instant::now();
+            // stop accepting new commands while too many are queued, so that
+            // the bounded channels make the senders wait
+            let backlogged = pin.commands.len() >= pin.config.channel_capacity;
+
             // temporary pinning of the browser receiver should be safe as we are pinning
             // through the already pinned self. with the receivers we can also
             // safely ignore exhaustion as those are fused.
-            while let Poll::Ready(Some(msg)) = Pin::new(&mut pin.from_browser).poll_next(cx) {
+            while let Poll::Ready(Some(msg)) = if backlogged {
+                Poll::Pending
+            } else {
+                Pin::new(&mut pin.from_browser).poll_next(cx)
+            } {
                 match msg {
                     HandlerMessage::Command(cmd) => {
-                        pin.submit_external_command(cmd, now).unwrap();
+                        pin.submit_external_command(cmd, now);
                     }
                     HandlerMessage::CreatePage(params, tx) => {
                         pin.create_page(params, tx);
                     }
-                    HandlerMessage::GetPages(tx) => {
-                        let pages: Vec<_> = pin
-                            .targets
-                            .values_mut()
-                            .filter_map(|target| target.get_or_create_page())
-                            .map(|page| Page::from(page.clone()))
-                            .collect();
-                        let _ = tx.send(pages);
+                    HandlerMessage::AttachToTarget(target_id, tx) => {
+                        pin.attach_to_target(target_id, tx);
+                    }
+                    HandlerMessage::GetTargets(tx) => {
+                        let _ = tx.send(
+                            pin.targets
+                                .values()
+                                .filter(|target| !target.is_closed())
+                                .map(|target| target.info().clone())
+                                .collect(),
+                        );
+                    }
+                    HandlerMessage::GetWorkers(tx) => {
+                        let _ = tx.send(pin.workers());
+                    }
+                    HandlerMessage::GetStats(tx) => {
+                        let _ = tx.send(pin.stats());
+                    }
+                    HandlerMessage::AddEventListener(req) => {
+                        pin.event_listeners.add_listener(req);
+                    }
+                    HandlerMessage::AddSessionEventListener(session_id, req) => {
+                        // the listener is dropped if the session is already gone
+                        if let Some(session) = pin.sessions.get_mut(&session_id) {
+                            session.event_listeners_mut().add_listener(req);
+                        }
                     }
-                    HandlerMessage::Subscribe => {
-                        // TODO implement subscriptions
+                    HandlerMessage::AddConnectionListener(tx) => {
+                        pin.connection_listeners.push(tx);
                     }
                 }
             }
@@ -394,6 +1289,7 @@ impl Stream for Handler {
             for n in (0..pin.target_ids.len()).rev() {
                 let target_id = pin.target_ids.swap_remove(n);
                 if let Some((id, mut target)) = pin.targets.remove_entry(&target_id) {
+                    target.set_backlogged(backlogged);
                     while let Some(event) = target.poll(cx, now) {
                         match event {
                             TargetEvent::Request(req) => {
@@ -409,6 +1305,9 @@ impl Stream for Handler {
                             TargetEvent::Command(msg) => {
                                 pin.on_target_message(&mut target, msg, now);
                             }
+                            TargetEvent::Navigate(msg, wait_until) => {
+                                pin.on_navigation(&mut target, msg, wait_until);
+                            }
                             TargetEvent::NavigationRequest(id, req) => {
                                 pin.submit_navigation(id, req, now);
                             }
@@ -423,21 +1322,54 @@ impl Stream for Handler {
                 }
             }
 
+            // slots may have become free with the responses of the previous
+            // iteration
+            pin.submit_queued_commands();
+
             let mut done = true;
 
-            while let Poll::Ready(Some(ev)) = Pin::new(&mut pin.conn).poll_next(cx) {
-                match ev {
-                    Ok(Message::Response(resp)) => pin.on_response(resp),
-                    Ok(Message::Event(ev)) => {
-                        pin.on_event(ev);
+            if let Poll::Ready(Err(err)) = pin.poll_reconnect(cx) {
+                return Poll::Ready(Some(Err(err)));
+            }
+
+            if pin.process.is_some() && pin.process_check.is_ready(cx) {
+                if let Some(status) = pin.process.as_ref().and_then(BrowserProcess::crashed) {
+                    pin.on_browser_crashed(status);
+                    return Poll::Ready(Some(Err(CdpError::BrowserCrashed(status))));
+                }
+            }
+
+            if let ConnectionState::Connected = pin.conn_state {
+                if let Some(keep_alive) = pin.keep_alive.as_mut() {
+                    if keep_alive.is_ready(cx) {
+                        pin.conn.ping();
+                    }
+                }
+                while let Poll::Ready(ev) = Pin::new(&mut pin.conn).poll_next(cx) {
+                    match ev {
+                        Some(Ok(Message::Response(resp))) => pin.on_response(resp),
+                        Some(Ok(Message::Event(ev))) => {
+                            pin.on_event(ev);
+                        }
+                        Some(Err(CdpError::Ws(err))) => {
+                            log::debug!("Websocket connection failed: {}", err);
+                            pin.on_disconnected();
+                        }
+                        Some(Err(err)) => return Poll::Ready(Some(Err(err))),
+                        None => pin.on_disconnected(),
+                    }
+                    done = false;
+                    if let ConnectionState::Closed = pin.conn_state {
+                        return Poll::Ready(Some(Err(pin.closed_error())));
+                    }
+                    if let ConnectionState::Reconnecting { .. } = pin.conn_state {
+                        break;
                     }
-                    Err(err) => return Poll::Ready(Some(Err(err))),
                 }
-                done = false;
             }
 
             if pin.evict_command_timeout.is_ready(cx) {
-                // TODO evict all commands that timed out
+                pin.evict_timed_out_commands(now);
             }
 
             if done {
@@ -501,11 +1433,17 @@ enum PendingRequest {
     /// after the `Target` notifies the `Handler` that the `Page` has finished
     /// loading, which comes after the response.
     Navigate(NavigationId),
-    /// A common request received via a channel (`Page`).
-    ExternalCommand(OneshotSender<Result<Response>>),
+    /// A common request received via a channel (`Page`), within the session
+    /// of the page, if any.
+    ExternalCommand(OneshotSender<Result<Response>>, Option<SessionId>),
     /// Requests that are initiated directly from a `Target` (all the
     /// initialization commands).
     InternalCommand(TargetId),
+    /// The request for the frame tree of an out-of-process iframe, which is
+    /// merged into the frame tree of the page of the `Target`
+    OopifFrameTree(TargetId),
+    /// Requests of the handler itself, whose response is ignored
+    Unawaited,
 }
 
 /// Events used internally to communicate with the handler, which are executed
@@ -514,8 +1452,12 @@ enum PendingRequest {
 #[derive(Debug)]
 pub(crate) enum HandlerMessage {
     CreatePage(CreateTargetParams, OneshotSender<Result<Page>>),
-    GetPages(OneshotSender<Vec<Page>>),
+    AttachToTarget(TargetId, OneshotSender<Result<Page>>),
+    GetTargets(OneshotSender<Vec<TargetInfo>>),
+    GetWorkers(OneshotSender<Vec<(SessionId, TargetInfo)>>),
+    GetStats(OneshotSender<HandlerStats>),
     Command(CommandMessage),
-    #[allow(unused)] // allow until implemented
-    Subscribe,
+    AddEventListener(EventListenerRequest),
+    AddSessionEventListener(SessionId, EventListenerRequest),
+    AddConnectionListener(UnboundedSender<ConnectionEvent>),
 }
@@ -1,44 +1,159 @@
-use chromiumoxide_types::Method;
+use std::borrow::Cow;
+use std::collections::{HashSet, VecDeque};
+
+use chromiumoxide_types::{Command, Method};
 
 use crate::cmd::CommandChain;
-use chromiumoxide_cdp::cdp::browser_protocol::fetch::{EventAuthRequired, EventRequestPaused};
+use chromiumoxide_cdp::cdp::browser_protocol::fetch::{
+    self, AuthChallenge, AuthChallengeResponse, AuthChallengeResponseResponse, AuthChallengeSource,
+    ContinueRequestParams, ContinueWithAuthParams, EventAuthRequired, EventRequestPaused,
+    RequestId,
+};
 use chromiumoxide_cdp::cdp::browser_protocol::network::{
     EventLoadingFailed, EventLoadingFinished, EventRequestServedFromCache, EventRequestWillBeSent,
-    EventResponseReceived,
-};
-use chromiumoxide_cdp::cdp::browser_protocol::{
-    network::EnableParams, security::SetIgnoreCertificateErrorsParams,
+    EventResponseReceived, SetUserAgentOverrideParams,
 };
+use chromiumoxide_cdp::cdp::browser_protocol::security::SetIgnoreCertificateErrorsParams;
+
+/// The username and password to answer authentication challenges with
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl Credentials {
+    pub fn new(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct NetworkManager {
     ignore_httpserrors: bool,
+    /// The credentials to answer proxy authentication challenges with
+    proxy_credentials: Option<Credentials>,
+    /// The credentials to answer all authentication challenges with, set via
+    /// `Page::authenticate`
+    credentials: Option<Credentials>,
+    /// Requests for which the credentials were already provided once
+    attempted_authentications: HashSet<RequestId>,
+    /// The user agent to report instead of the browser's default
+    user_agent: Option<String>,
+    queued_events: VecDeque<NetworkEvent>,
 }
 
 impl NetworkManager {
     pub fn new(ignore_httpserrors: bool) -> Self {
-        Self { ignore_httpserrors }
+        Self {
+            ignore_httpserrors,
+            proxy_credentials: None,
+            credentials: None,
+            attempted_authentications: Default::default(),
+            user_agent: None,
+            queued_events: Default::default(),
+        }
+    }
+
+    /// Set the user agent that is overridden during initialization
+    pub fn set_user_agent(&mut self, user_agent: Option<String>) {
+        self.user_agent = user_agent;
+    }
+
+    /// Set the credentials to answer proxy authentication challenges with.
+    ///
+    /// This enables the `Fetch` domain during initialization.
+    pub fn set_proxy_credentials(&mut self, credentials: Option<Credentials>) {
+        self.proxy_credentials = credentials;
+    }
+
+    /// Set the credentials to answer server and proxy authentication
+    /// challenges with
+    pub fn set_credentials(&mut self, credentials: Credentials) {
+        self.attempted_authentications.clear();
+        self.credentials = Some(credentials);
+    }
+
+    /// Whether any credentials are configured to answer challenges with
+    pub fn has_credentials(&self) -> bool {
+        self.proxy_credentials.is_some() || self.credentials.is_some()
+    }
+
+    /// The credentials to answer the `challenge` with, if any
+    pub fn credentials_for(&self, challenge: &AuthChallenge) -> Option<&Credentials> {
+        if challenge.source == Some(AuthChallengeSource::Proxy) {
+            self.credentials
+                .as_ref()
+                .or(self.proxy_credentials.as_ref())
+        } else {
+            self.credentials.as_ref()
+        }
     }
 
+    /// The commands that apply the settings to a new page.
+    ///
+    /// With credentials, `Fetch.enable` is sent without patterns: the
+    /// browser only reports authentication challenges of requests it
+    /// intercepts, and which requests are challenged is not known upfront.
+    /// So every request of the page is paused until it is continued by
+    /// [`NetworkManager::on_fetch_request_paused`], a round trip to the
+    /// browser per request.
     pub fn init_commands(&self) -> CommandChain {
-        let enable = EnableParams::default();
+        let mut cmds = Vec::with_capacity(3);
+        if let Some(ref user_agent) = self.user_agent {
+            let ua = SetUserAgentOverrideParams::new(user_agent.clone());
+            cmds.push((ua.identifier(), serde_json::to_value(ua).unwrap()));
+        }
         if self.ignore_httpserrors {
             let ignore = SetIgnoreCertificateErrorsParams::new(true);
-            CommandChain::new(vec![
-                (enable.identifier(), serde_json::to_value(enable).unwrap()),
-                (ignore.identifier(), serde_json::to_value(ignore).unwrap()),
-            ])
-        } else {
-            CommandChain::new(vec![(
-                enable.identifier(),
-                serde_json::to_value(enable).unwrap(),
-            )])
+            cmds.push((ignore.identifier(), serde_json::to_value(ignore).unwrap()));
         }
+        if self.has_credentials() {
+            let fetch = fetch::EnableParams::builder()
+                .handle_auth_requests(true)
+                .build();
+            cmds.push((fetch.identifier(), serde_json::to_value(fetch).unwrap()));
+        }
+        CommandChain::new(cmds)
     }
 
-    pub fn on_fetch_request_paused(&mut self, _event: &EventRequestPaused) {}
+    /// Continues a request that was paused but that no one is going to handle
+    pub fn on_fetch_request_paused(&mut self, event: &EventRequestPaused) {
+        self.push_cdp_request(ContinueRequestParams::new(event.request_id.clone()));
+    }
 
-    pub fn on_fetch_auth_required(&mut self, _event: &EventAuthRequired) {}
+    /// Answers an authentication challenge.
+    ///
+    /// Challenges are answered with the configured credentials once per
+    /// request, if those get rejected the authentication is canceled.
+    /// Without credentials the browser's default behavior applies.
+    pub fn on_fetch_auth_required(&mut self, event: &EventAuthRequired) {
+        let response = match self.credentials_for(&event.auth_challenge).cloned() {
+            Some(credentials) => {
+                if self
+                    .attempted_authentications
+                    .insert(event.request_id.clone())
+                {
+                    let mut response = AuthChallengeResponse::new(
+                        AuthChallengeResponseResponse::ProvideCredentials,
+                    );
+                    response.username = Some(credentials.username);
+                    response.password = Some(credentials.password);
+                    response
+                } else {
+                    AuthChallengeResponse::new(AuthChallengeResponseResponse::CancelAuth)
+                }
+            }
+            None => AuthChallengeResponse::new(AuthChallengeResponseResponse::Default),
+        };
+        self.push_cdp_request(ContinueWithAuthParams::new(
+            event.request_id.clone(),
+            response,
+        ));
+    }
 
     pub fn on_request_will_be_sent(&mut self, _event: &EventRequestWillBeSent) {}
 
@@ -49,6 +164,17 @@ impl NetworkManager {
     pub fn on_network_loading_finished(&mut self, _event: &EventLoadingFinished) {}
 
     pub fn on_network_loading_failed(&mut self, _event: &EventLoadingFailed) {}
+
+    fn push_cdp_request<T: Command>(&mut self, cmd: T) {
+        let method = cmd.identifier();
+        let params = serde_json::to_value(cmd).expect("Command should not panic");
+        self.queued_events
+            .push_back(NetworkEvent::SendCdpRequest((method, params)));
+    }
+
+    pub fn poll(&mut self) -> Option<NetworkEvent> {
+        self.queued_events.pop_front()
+    }
 }
 
 impl Default for NetworkManager {
@@ -56,3 +182,9 @@ impl Default for NetworkManager {
         NetworkManager::new(true)
     }
 }
+
+#[derive(Debug)]
+pub enum NetworkEvent {
+    /// A request to issue to the chromium instance
+    SendCdpRequest((Cow<'static, str>, serde_json::Value)),
+}
@@ -1,45 +1,87 @@
-use std::sync::Arc;
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use futures::channel::mpsc::{channel, Receiver, Sender};
-use futures::channel::oneshot::channel as oneshot_channel;
+use futures::channel::oneshot::{channel as oneshot_channel, Receiver as OneshotReceiver};
 use futures::stream::Fuse;
 use futures::{SinkExt, StreamExt};
+use futures_timer::Delay;
 
+use chromiumoxide_cdp::cdp::browser_protocol::dom::Rgba;
 use chromiumoxide_cdp::cdp::browser_protocol::dom::{
     NodeId, QuerySelectorAllParams, QuerySelectorParams,
 };
+use chromiumoxide_cdp::cdp::browser_protocol::emulation::{
+    ClearDeviceMetricsOverrideParams, SetDefaultBackgroundColorOverrideParams,
+    SetDeviceMetricsOverrideParams, SetEmulatedMediaParams,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::fetch::{self, RequestPattern};
 use chromiumoxide_cdp::cdp::browser_protocol::input::{
-    DispatchKeyEventParams, DispatchKeyEventType, DispatchMouseEventParams, DispatchMouseEventType,
-    MouseButton,
+    DispatchKeyEventParams, DispatchKeyEventParamsBuilder, DispatchKeyEventType,
+    DispatchMouseEventParams, DispatchMouseEventType, DispatchTouchEventParams,
+    DispatchTouchEventType, InsertTextParams, MouseButton, TouchPoint,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::page::{
+    CaptureScreenshotFormat, CaptureScreenshotParams, GetLayoutMetricsParams,
+    GetLayoutMetricsReturns, NavigateParams, NavigateReturns, Viewport,
 };
 use chromiumoxide_cdp::cdp::browser_protocol::target::{SessionId, TargetId};
 use chromiumoxide_cdp::cdp::js_protocol::runtime::{
     CallFunctionOnParams, CallFunctionOnReturns, RemoteObjectId,
 };
-use chromiumoxide_types::{Command, CommandResponse};
+use chromiumoxide_types::{Command, CommandResponse, Method};
 
-use crate::cmd::{to_command_response, CommandMessage};
+use crate::cmd::{self, to_command_response, to_raw_response, CommandHandle, CommandMessage};
 use crate::error::{CdpError, Result};
+use crate::handler::frame::LifecycleEvent;
 use crate::handler::target::TargetMessage;
 use crate::keys;
 use crate::layout::Point;
+use crate::page::{FullPageStrategy, ScreenshotFormat, ScreenshotParams};
+use crate::retry::{self, RetryPolicy};
+use crate::route::{Route, Routes};
+use crate::utils;
 
 #[derive(Debug)]
 pub struct PageHandle {
     pub(crate) rx: Fuse<Receiver<TargetMessage>>,
+    /// Receives the commands that resume the browser, which are received
+    /// even while the `Handler` is backlogged
+    pub(crate) resuming_rx: Fuse<Receiver<TargetMessage>>,
     page: Arc<PageInner>,
 }
 
 impl PageHandle {
-    pub fn new(target_id: TargetId, session_id: SessionId) -> Self {
-        let (commands, rx) = channel(1);
+    pub fn new(
+        target_id: TargetId,
+        session_id: SessionId,
+        channel_capacity: usize,
+        retry_policy: Option<RetryPolicy>,
+        device_metrics: Option<SetDeviceMetricsOverrideParams>,
+        handle_auth_requests: bool,
+    ) -> Self {
+        let (commands, rx) = channel(channel_capacity);
+        let (resuming_commands, resuming_rx) = channel(channel_capacity);
         let page = PageInner {
             target_id,
-            session_id,
+            initial_session_id: session_id.clone(),
+            session_id: Mutex::new(session_id),
             sender: commands,
+            resuming_sender: resuming_commands,
+            retry_policy: Mutex::new(retry_policy.map(Arc::new)),
+            default_timeout: Mutex::new(None),
+            navigation_timeout: Mutex::new(None),
+            routes: Mutex::new(None),
+            emulated_media: Mutex::new(SetEmulatedMediaParams::default()),
+            device_metrics: Mutex::new(device_metrics),
+            handle_auth_requests: AtomicBool::new(handle_auth_requests),
+            dialog_streams: AtomicUsize::new(0),
         };
         Self {
             rx: rx.fuse(),
+            resuming_rx: resuming_rx.fuse(),
             page: Arc::new(page),
         }
     }
@@ -52,14 +94,260 @@ impl PageHandle {
 #[derive(Debug)]
 pub(crate) struct PageInner {
     target_id: TargetId,
-    session_id: SessionId,
+    /// The session the target was attached to when the page was created
+    initial_session_id: SessionId,
+    /// The current session of the target, which changes when the target is
+    /// attached again after a reconnect
+    session_id: Mutex<SessionId>,
     sender: Sender<TargetMessage>,
+    /// Sends the commands that resume the browser after it paused on an
+    /// event, so they don't wait behind commands that wait for them
+    resuming_sender: Sender<TargetMessage>,
+    /// How to retry commands that fail with a transient error
+    retry_policy: Mutex<Option<Arc<RetryPolicy>>>,
+    /// How long the `wait_for_*` helpers of the page wait by default
+    default_timeout: Mutex<Option<Duration>>,
+    /// How long navigations of the page may take
+    navigation_timeout: Mutex<Option<Duration>>,
+    /// The fixtures requests are fulfilled with, once a route was added
+    routes: Mutex<Option<Routes>>,
+    /// The emulated media type and features, `Emulation.setEmulatedMedia`
+    /// resets whatever is omitted so both are always sent together
+    emulated_media: Mutex<SetEmulatedMediaParams>,
+    /// The device metrics the page is currently emulated with, if any
+    device_metrics: Mutex<Option<SetDeviceMetricsOverrideParams>>,
+    /// Whether authentication challenges are answered with credentials, via
+    /// the `Fetch` domain
+    handle_auth_requests: AtomicBool,
+    /// How many `DialogStream`s handle the dialogs of the page manually
+    dialog_streams: AtomicUsize,
 }
 
 impl PageInner {
     /// Execute a PDL command and return its response
     pub(crate) async fn execute<T: Command>(&self, cmd: T) -> Result<CommandResponse<T::Response>> {
-        Ok(execute(cmd, self.sender.clone(), Some(self.session_id.clone())).await?)
+        execute(
+            self.command_sender(&cmd.identifier()),
+            cmd,
+            Some(self.current_session_id()),
+            None,
+            self.retry_policy().as_deref(),
+        )
+        .await
+    }
+
+    /// Execute a PDL command within another session of the page, like the
+    /// session of an out-of-process iframe
+    pub(crate) async fn execute_in_session<T: Command>(
+        &self,
+        cmd: T,
+        session_id: SessionId,
+    ) -> Result<CommandResponse<T::Response>> {
+        execute(
+            self.command_sender(&cmd.identifier()),
+            cmd,
+            Some(session_id),
+            None,
+            self.retry_policy().as_deref(),
+        )
+        .await
+    }
+
+    /// Execute a PDL command that fails with a timeout error if no response
+    /// arrives within `timeout`
+    pub(crate) async fn execute_with_timeout<T: Command>(
+        &self,
+        cmd: T,
+        timeout: Duration,
+    ) -> Result<CommandResponse<T::Response>> {
+        execute(
+            self.command_sender(&cmd.identifier()),
+            cmd,
+            Some(self.current_session_id()),
+            Some(timeout),
+            self.retry_policy().as_deref(),
+        )
+        .await
+    }
+
+    /// The channel to send the command `method` through, commands that
+    /// resume the browser are never held back
+    fn command_sender(&self, method: &str) -> Sender<TargetMessage> {
+        if cmd::is_resuming(method) {
+            self.resuming_sender.clone()
+        } else {
+            self.sender.clone()
+        }
+    }
+
+    /// How commands of this page are retried, if at all
+    pub(crate) fn retry_policy(&self) -> Option<Arc<RetryPolicy>> {
+        self.retry_policy.lock().unwrap().clone()
+    }
+
+    pub(crate) fn set_retry_policy(&self, policy: Option<RetryPolicy>) {
+        *self.retry_policy.lock().unwrap() = policy.map(Arc::new);
+    }
+
+    /// How long the `wait_for_*` helpers of the page wait if no timeout is
+    /// passed
+    pub(crate) fn default_timeout(&self) -> Option<Duration> {
+        *self.default_timeout.lock().unwrap()
+    }
+
+    pub(crate) fn set_default_timeout(&self, timeout: Option<Duration>) {
+        *self.default_timeout.lock().unwrap() = timeout;
+    }
+
+    /// How long navigations may take, falls back to the `default_timeout`
+    pub(crate) fn navigation_timeout(&self) -> Option<Duration> {
+        self.navigation_timeout
+            .lock()
+            .unwrap()
+            .or_else(|| self.default_timeout())
+    }
+
+    pub(crate) fn set_navigation_timeout(&self, timeout: Option<Duration>) {
+        *self.navigation_timeout.lock().unwrap() = timeout;
+    }
+
+    /// The routes of the page, if they are served already
+    pub(crate) fn routes(&self) -> Option<Routes> {
+        self.routes.lock().unwrap().clone()
+    }
+
+    /// The routes of the page and whether they were created by this call
+    pub(crate) fn get_or_init_routes(&self) -> (Routes, bool) {
+        let mut routes = self.routes.lock().unwrap();
+        match routes.as_ref() {
+            Some(routes) => (Arc::clone(routes), false),
+            None => (
+                Arc::clone(routes.get_or_insert_with(Default::default)),
+                true,
+            ),
+        }
+    }
+
+    /// The commands that restore the state of the page that is lost when its
+    /// target is attached to a new session, i.e. the interception of the
+    /// routes and the emulated media
+    pub(crate) fn restore_commands(&self) -> Vec<(Cow<'static, str>, serde_json::Value)> {
+        let mut cmds = Vec::new();
+        let (method, params) = self.fetch_command();
+        if method != fetch::DisableParams::IDENTIFIER {
+            cmds.push((method, params));
+        }
+        let media = self.emulated_media.lock().unwrap().clone();
+        if media.media.is_some() || media.features.is_some() {
+            cmds.push((media.identifier(), serde_json::to_value(media).unwrap()));
+        }
+        cmds
+    }
+
+    /// Keeps track of the device metrics override of the page, as set by
+    /// the command `method` sent by the page
+    pub(crate) fn on_command(&self, method: &str, params: &serde_json::Value) {
+        match method {
+            SetDeviceMetricsOverrideParams::IDENTIFIER => {
+                if let Ok(metrics) = serde_json::from_value(params.clone()) {
+                    *self.device_metrics.lock().unwrap() = Some(metrics);
+                }
+            }
+            ClearDeviceMetricsOverrideParams::IDENTIFIER => {
+                *self.device_metrics.lock().unwrap() = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Updates the emulated media with `f` and applies it
+    pub(crate) async fn emulate_media(
+        &self,
+        f: impl FnOnce(&mut SetEmulatedMediaParams),
+    ) -> Result<()> {
+        let params = {
+            let mut media = self.emulated_media.lock().unwrap();
+            f(&mut media);
+            media.clone()
+        };
+        self.execute(params).await?;
+        Ok(())
+    }
+
+    /// Sends the PDL command without waiting for its response, see
+    /// `CommandHandle`
+    pub(crate) async fn execute_cancellable<T: Command>(&self, cmd: T) -> Result<CommandHandle<T>> {
+        let (tx, rx) = oneshot_channel();
+        let method = cmd.identifier();
+        let msg = CommandMessage::with_session(cmd, tx, Some(self.current_session_id()))?;
+        self.command_sender(&method)
+            .send(TargetMessage::Command(msg))
+            .await?;
+        Ok(CommandHandle::new(method, rx))
+    }
+
+    /// Execute any method within the page's session and return its raw
+    /// result
+    pub(crate) async fn execute_raw(
+        &self,
+        method: String,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let (tx, rx) = oneshot_channel();
+        let msg = CommandMessage::raw(method.clone(), params, tx, Some(self.current_session_id()));
+        self.command_sender(&method)
+            .send(TargetMessage::Command(msg))
+            .await?;
+        to_raw_response(rx.await??, &method)
+    }
+
+    /// Execute a PDL command outside of the page's session, like commands of
+    /// the `Browser` domain
+    pub(crate) async fn execute_browser_command<T: Command>(
+        &self,
+        cmd: T,
+    ) -> Result<CommandResponse<T::Response>> {
+        execute(
+            self.sender.clone(),
+            cmd,
+            None,
+            None,
+            self.retry_policy().as_deref(),
+        )
+        .await
+    }
+
+    /// Registers or unregisters a `DialogStream`, which handles the dialogs
+    /// instead of the `DialogPolicy`
+    pub(crate) fn set_dialog_stream(&self, open: bool) {
+        if open {
+            self.dialog_streams.fetch_add(1, Ordering::SeqCst);
+        } else {
+            self.dialog_streams.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Whether the dialogs are handled manually via a `DialogStream`
+    pub(crate) fn handles_dialogs_manually(&self) -> bool {
+        self.dialog_streams.load(Ordering::SeqCst) > 0
+    }
+
+    /// Navigate the main frame and resolve once the `wait_until` lifecycle
+    /// event was fired
+    pub(crate) async fn navigate(
+        &self,
+        params: NavigateParams,
+        wait_until: LifecycleEvent,
+    ) -> Result<CommandResponse<NavigateReturns>> {
+        let (tx, rx) = oneshot_channel();
+        let method = params.identifier();
+        let msg = CommandMessage::with_session(params, tx, Some(self.current_session_id()))?;
+        self.sender
+            .clone()
+            .send(TargetMessage::Navigate(msg, wait_until))
+            .await?;
+        let resp = utils::timeout(self.navigation_timeout(), async { rx.await? }).await?;
+        to_command_response::<NavigateParams>(resp, method)
     }
 
     /// This responds with the current url of the page, once the navigation
@@ -70,7 +358,24 @@ impl PageInner {
             .clone()
             .send(TargetMessage::WaitForNavigation(tx))
             .await?;
-        Ok(rx.await??)
+        utils::timeout(self.navigation_timeout(), async { rx.await? }).await
+    }
+
+    /// Registers a listener for the next navigation of the main frame.
+    ///
+    /// The returned receiver resolves with the new url once the navigation
+    /// finished, which allows to register the listener before triggering the
+    /// navigation, e.g. via a click.
+    pub(crate) async fn watch_navigation(
+        &self,
+        wait_until: LifecycleEvent,
+    ) -> Result<OneshotReceiver<Result<String>>> {
+        let (tx, rx) = oneshot_channel();
+        self.sender
+            .clone()
+            .send(TargetMessage::WaitForNextNavigation(wait_until, tx))
+            .await?;
+        Ok(rx)
     }
 
     /// The identifier of this page's target
@@ -78,9 +383,56 @@ impl PageInner {
         &self.target_id
     }
 
-    /// The identifier of this page's target's session
+    /// The identifier of the session this page's target was attached to
+    /// when the page was created
     pub fn session_id(&self) -> &SessionId {
-        &self.session_id
+        &self.initial_session_id
+    }
+
+    /// The identifier of this page's target's current session
+    pub fn current_session_id(&self) -> SessionId {
+        self.session_id.lock().unwrap().clone()
+    }
+
+    pub(crate) fn set_session_id(&self, session_id: SessionId) {
+        *self.session_id.lock().unwrap() = session_id;
+    }
+
+    /// Answer the authentication challenges of the page with credentials
+    /// from now on, see `fetch_command`
+    pub(crate) fn set_handle_auth_requests(&self) {
+        self.handle_auth_requests.store(true, Ordering::SeqCst);
+    }
+
+    /// The command that intercepts the requests of the routes and, if
+    /// credentials are configured, the authentication challenges of all
+    /// requests. Disables the `Fetch` domain if neither is needed.
+    pub(crate) fn fetch_command(&self) -> (Cow<'static, str>, serde_json::Value) {
+        let mut patterns = self
+            .routes()
+            .map(|routes| {
+                routes
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .flat_map(Route::request_patterns)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let handle_auth_requests = self.handle_auth_requests.load(Ordering::SeqCst);
+        if handle_auth_requests {
+            // challenges are only reported for paused requests
+            patterns.push(RequestPattern::builder().url_pattern("*").build());
+        } else if patterns.is_empty() {
+            // enabling without patterns would pause all requests
+            let disable = fetch::DisableParams::default();
+            return (disable.identifier(), serde_json::to_value(disable).unwrap());
+        }
+        let enable = fetch::EnableParams::builder()
+            .patterns(patterns)
+            .handle_auth_requests(handle_auth_requests)
+            .build();
+        (enable.identifier(), serde_json::to_value(enable).unwrap())
     }
 
     pub(crate) fn sender(&self) -> &Sender<TargetMessage> {
@@ -89,11 +441,19 @@ impl PageInner {
 
     /// Returns the first element in the node which matches the given CSS
     /// selector.
+    ///
+    /// Fails with `CdpError::NotFound` if no element matches the selector.
     pub async fn find_element(&self, selector: impl Into<String>, node: NodeId) -> Result<NodeId> {
-        Ok(self
+        let node_id = self
             .execute(QuerySelectorParams::new(node, selector))
             .await?
-            .node_id)
+            .result
+            .node_id;
+        // `DOM.querySelector` responds with the id `0` if no element matched
+        if *node_id.inner() == 0 {
+            return Err(CdpError::NotFound);
+        }
+        Ok(node_id)
     }
 
     /// Return all `Element`s inside the node that match the given selector
@@ -147,53 +507,304 @@ impl PageInner {
         Ok(self)
     }
 
-    /// This simulates pressing keys on the page.
+    /// Touches the screen at the point's location and lifts the finger
+    /// again
+    pub async fn tap(&self, point: Point) -> Result<&Self> {
+        self.execute(DispatchTouchEventParams::new(
+            DispatchTouchEventType::TouchStart,
+            vec![TouchPoint::new(point.x, point.y)],
+        ))
+        .await?;
+        self.execute(DispatchTouchEventParams::new(
+            DispatchTouchEventType::TouchEnd,
+            vec![],
+        ))
+        .await?;
+        Ok(self)
+    }
+
+    /// This simulates typing the `input` on the page.
     ///
-    /// # Note The `input` is treated as series of `KeyDefinition`s, where each
-    /// char is inserted as a separate keystroke. So sending
+    /// Each char is inserted as a separate keystroke, chars that don't
+    /// exist on the keyboard layout are inserted as text instead. So sending
     /// `page.type_str("Enter")` will be processed as a series of single
     /// keystrokes:  `["E", "n", "t", "e", "r"]`. To simulate pressing the
-    /// actual Enter key instead use `page.press_key(
-    /// keys::get_key_definition("Enter").unwrap())`.
+    /// actual Enter key instead use `page.press_key("Enter")`.
     pub async fn type_str(&self, input: impl AsRef<str>) -> Result<&Self> {
-        for c in input.as_ref().split("").filter(|s| !s.is_empty()) {
-            self.press_key(c).await?;
+        self.type_str_with_delay(input, None).await
+    }
+
+    /// Same as `type_str` but waits `delay` between the keystrokes
+    pub async fn type_str_with_delay(
+        &self,
+        input: impl AsRef<str>,
+        delay: Option<Duration>,
+    ) -> Result<&Self> {
+        let mut buf = [0; 4];
+        for (idx, c) in input.as_ref().chars().enumerate() {
+            if let Some(delay) = delay.filter(|_| idx > 0) {
+                Delay::new(delay).await;
+            }
+            let c: &str = c.encode_utf8(&mut buf);
+            if keys::get_key_definition(c).is_some() {
+                self.press_key(c).await?;
+            } else {
+                self.insert_text(c).await?;
+            }
         }
         Ok(self)
     }
 
     /// Uses the `DispatchKeyEvent` mechanism to simulate pressing keyboard
     /// keys.
+    ///
+    /// Keys can be combined with modifier keys, like `Control+A` or
+    /// `Shift+ArrowDown`. All the keys are pressed in order and released in
+    /// reverse order.
     pub async fn press_key(&self, key: impl AsRef<str>) -> Result<&Self> {
-        let key = key.as_ref();
+        let keys = keys::split_key_combination(key.as_ref());
+        let mut modifiers = 0;
+        for key in &keys {
+            self.key_down(key, modifiers).await?;
+            modifiers |= keys::modifier_bit(key);
+        }
+        for key in keys.iter().rev() {
+            modifiers &= !keys::modifier_bit(key);
+            self.key_up(key, modifiers).await?;
+        }
+        Ok(self)
+    }
+
+    /// Dispatches a `keydown` event for the key, while the `modifiers` are
+    /// pressed.
+    pub(crate) async fn key_down(&self, key: &str, modifiers: i64) -> Result<&Self> {
         let key_definition = keys::get_key_definition(key)
             .ok_or_else(|| CdpError::msg(format!("Key not found: {}", key)))?;
-        let mut cmd = DispatchKeyEventParams::builder();
+        let mut cmd = key_event(key_definition, modifiers);
 
         // See https://github.com/GoogleChrome/puppeteer/blob/62da2366c65b335751896afbb0206f23c61436f1/lib/Input.js#L114-L115
         // And https://github.com/GoogleChrome/puppeteer/blob/62da2366c65b335751896afbb0206f23c61436f1/lib/Input.js#L52
-        let key_down_event_type = if let Some(txt) = key_definition.text {
-            cmd = cmd.text(txt);
-            DispatchKeyEventType::KeyDown
-        } else if key_definition.key.len() == 1 {
-            cmd = cmd.text(key_definition.key);
-            DispatchKeyEventType::KeyDown
+        let text = key_definition.text.or_else(|| {
+            if key_definition.key.chars().count() == 1 {
+                Some(key_definition.key)
+            } else {
+                None
+            }
+        });
+        // pressing any modifier other than shift suppresses the text input
+        let text = text.filter(|_| modifiers & !keys::MODIFIER_SHIFT == 0);
+        if let Some(text) = text {
+            cmd = cmd
+                .r#type(DispatchKeyEventType::KeyDown)
+                .text(text)
+                .unmodified_text(text);
         } else {
-            DispatchKeyEventType::RawKeyDown
-        };
+            cmd = cmd.r#type(DispatchKeyEventType::RawKeyDown);
+        }
+        self.execute(cmd.build().unwrap()).await?;
+        Ok(self)
+    }
+
+    /// Dispatches a `keyup` event for the key, while the `modifiers` are
+    /// pressed.
+    pub(crate) async fn key_up(&self, key: &str, modifiers: i64) -> Result<&Self> {
+        let key_definition = keys::get_key_definition(key)
+            .ok_or_else(|| CdpError::msg(format!("Key not found: {}", key)))?;
+        self.execute(
+            key_event(key_definition, modifiers)
+                .r#type(DispatchKeyEventType::KeyUp)
+                .build()
+                .unwrap(),
+        )
+        .await?;
+        Ok(self)
+    }
 
-        cmd = cmd
-            .r#type(DispatchKeyEventType::KeyDown)
-            .key(key_definition.key)
-            .code(key_definition.code)
-            .windows_virtual_key_code(key_definition.key_code)
-            .native_virtual_key_code(key_definition.key_code);
+    /// Inserts the text as if it was sent by an IME, without any key events
+    pub(crate) async fn insert_text(&self, text: impl Into<String>) -> Result<&Self> {
+        self.execute(InsertTextParams::new(text)).await?;
+        Ok(self)
+    }
+
+    /// Returns metrics relating to the layout of the page
+    pub async fn layout_metrics(&self) -> Result<GetLayoutMetricsReturns> {
+        Ok(self
+            .execute(GetLayoutMetricsParams::default())
+            .await?
+            .result)
+    }
 
-        self.execute(cmd.clone().r#type(key_down_event_type).build().unwrap())
+    /// Take a screenshot of the page and return the decoded image
+    pub(crate) async fn screenshot(&self, params: ScreenshotParams) -> Result<Vec<u8>> {
+        let omit_background = params.omit_background();
+        if omit_background {
+            let mut transparent = Rgba::new(0, 0, 0);
+            transparent.a = Some(0.);
+            self.execute(
+                SetDefaultBackgroundColorOverrideParams::builder()
+                    .color(transparent)
+                    .build(),
+            )
             .await?;
-        self.execute(cmd.r#type(DispatchKeyEventType::KeyUp).build().unwrap())
+        }
+
+        let res = self.capture(params).await;
+
+        if omit_background {
+            self.execute(SetDefaultBackgroundColorOverrideParams::default())
+                .await?;
+        }
+        res
+    }
+
+    /// Captures the screenshot with the strategy of the `params`
+    async fn capture(&self, params: ScreenshotParams) -> Result<Vec<u8>> {
+        let strategy = params.full_page_strategy();
+        let format = params.format();
+        let mut cdp_params = params.cdp_params;
+        let mut options = Vec::new();
+        cdp_params.format = match format {
+            ScreenshotFormat::Png => Some(CaptureScreenshotFormat::Png),
+            ScreenshotFormat::Jpeg => Some(CaptureScreenshotFormat::Jpeg),
+            ScreenshotFormat::Webp => {
+                options.push(("format", serde_json::Value::from("webp")));
+                None
+            }
+        };
+        match strategy {
+            None => self.capture_raw(&cdp_params, &options).await,
+            Some(FullPageStrategy::Resize) => self.capture_resized(cdp_params, &options).await,
+            Some(FullPageStrategy::CaptureBeyondViewport) => {
+                self.capture_beyond_viewport(cdp_params, options).await
+            }
+            #[cfg(feature = "stitch")]
+            Some(FullPageStrategy::Stitch) => self.capture_stitched(cdp_params, format).await,
+            #[cfg(not(feature = "stitch"))]
+            Some(strategy @ FullPageStrategy::Stitch) | Some(strategy @ FullPageStrategy::Auto) => {
+                Err(CdpError::msg(format!(
+                    "FullPageStrategy::{:?} requires the `stitch` feature",
+                    strategy
+                )))
+            }
+            #[cfg(feature = "stitch")]
+            Some(FullPageStrategy::Auto) => {
+                let metrics = self.layout_metrics().await?;
+                match self
+                    .capture_beyond_viewport(cdp_params.clone(), options)
+                    .await
+                {
+                    Ok(data) if crate::stitch::covers(&data, &metrics.content_size) => Ok(data),
+                    Ok(_) => {
+                        log::debug!("Incomplete full page screenshot, stitching it instead");
+                        self.capture_stitched(cdp_params, format).await
+                    }
+                    Err(err) => {
+                        log::debug!("Full page screenshot failed, stitching it instead: {}", err);
+                        self.capture_stitched(cdp_params, format).await
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sends the `Page.captureScreenshot` command along with the `options`
+    /// that are not part of the protocol version the commands are generated
+    /// from, which requires sending the command raw.
+    async fn capture_raw(
+        &self,
+        cdp_params: &CaptureScreenshotParams,
+        options: &[(&str, serde_json::Value)],
+    ) -> Result<Vec<u8>> {
+        if options.is_empty() {
+            return Ok(base64::decode(
+                &self.execute(cdp_params.clone()).await?.data,
+            )?);
+        }
+        let mut params = serde_json::to_value(cdp_params)?;
+        for (name, value) in options {
+            params[*name] = value.clone();
+        }
+        let res = self
+            .execute_raw(cdp_params.identifier().into_owned(), params)
             .await?;
-        Ok(self)
+        let data = res["data"]
+            .as_str()
+            .ok_or_else(|| CdpError::msg("Missing screenshot data"))?;
+        Ok(base64::decode(data)?)
+    }
+
+    /// Resizes the viewport to the size of the content for the screenshot
+    async fn capture_resized(
+        &self,
+        mut cdp_params: CaptureScreenshotParams,
+        options: &[(&str, serde_json::Value)],
+    ) -> Result<Vec<u8>> {
+        // restored afterwards, the resizing override is tracked as well
+        let device_metrics = self.device_metrics.lock().unwrap().clone();
+        let metrics = self.layout_metrics().await?;
+        let width = metrics.content_size.width.ceil();
+        let height = metrics.content_size.height.ceil();
+
+        self.execute(SetDeviceMetricsOverrideParams::new(
+            width as i64,
+            height as i64,
+            1.,
+            false,
+        ))
+        .await?;
+        cdp_params.clip = Some(Viewport {
+            x: 0.,
+            y: 0.,
+            width,
+            height,
+            scale: 1.,
+        });
+
+        let res = self.capture_raw(&cdp_params, options).await;
+
+        if let Some(device_metrics) = device_metrics {
+            self.execute(device_metrics).await?;
+        } else {
+            self.execute(ClearDeviceMetricsOverrideParams::default())
+                .await?;
+        }
+
+        res
+    }
+
+    /// Captures the content outside of the viewport without resizing it, via
+    /// the `captureBeyondViewport` option.
+    async fn capture_beyond_viewport(
+        &self,
+        mut cdp_params: CaptureScreenshotParams,
+        mut options: Vec<(&str, serde_json::Value)>,
+    ) -> Result<Vec<u8>> {
+        let metrics = self.layout_metrics().await?;
+        cdp_params.clip = Some(Viewport {
+            x: 0.,
+            y: 0.,
+            width: metrics.content_size.width.ceil(),
+            height: metrics.content_size.height.ceil(),
+            scale: 1.,
+        });
+        options.push(("captureBeyondViewport", serde_json::Value::Bool(true)));
+        self.capture_raw(&cdp_params, &options).await
+    }
+
+    /// Stitches the screenshots of the viewport together, which can only be
+    /// encoded as png or jpeg
+    #[cfg(feature = "stitch")]
+    async fn capture_stitched(
+        &self,
+        cdp_params: CaptureScreenshotParams,
+        format: ScreenshotFormat,
+    ) -> Result<Vec<u8>> {
+        if format == ScreenshotFormat::Webp {
+            return Err(CdpError::msg(
+                "Stitched screenshots can only be encoded as png or jpeg",
+            ));
+        }
+        crate::stitch::capture(self, cdp_params).await
     }
 
     /// Calls function with given declaration on the remote object with the
@@ -219,16 +830,39 @@ impl PageInner {
     }
 }
 
+/// The common parameters of all key events of the `key_definition`
+fn key_event(
+    key_definition: &keys::KeyDefinition,
+    modifiers: i64,
+) -> DispatchKeyEventParamsBuilder {
+    DispatchKeyEventParams::builder()
+        .key(key_definition.key)
+        .code(key_definition.code)
+        .windows_virtual_key_code(key_definition.key_code)
+        .native_virtual_key_code(key_definition.key_code)
+        .modifiers(modifiers)
+}
+
 pub(crate) async fn execute<T: Command>(
+    sender: Sender<TargetMessage>,
     cmd: T,
-    mut sender: Sender<TargetMessage>,
     session: Option<SessionId>,
+    timeout: Option<Duration>,
+    retry_policy: Option<&RetryPolicy>,
 ) -> Result<CommandResponse<T::Response>> {
-    let (tx, rx) = oneshot_channel();
     let method = cmd.identifier();
-    let msg = CommandMessage::with_session(cmd, tx, session)?;
-
-    sender.send(TargetMessage::Command(msg)).await?;
-    let resp = rx.await??;
-    to_command_response::<T>(resp, method)
+    let params = serde_json::to_value(cmd)?;
+    retry::with_retries(retry_policy, || {
+        let (tx, rx) = oneshot_channel();
+        let msg = CommandMessage::raw(method.clone(), params.clone(), tx, session.clone())
+            .with_timeout(timeout);
+        let mut sender = sender.clone();
+        let method = method.clone();
+        async move {
+            sender.send(TargetMessage::Command(msg)).await?;
+            let resp = rx.await??;
+            to_command_response::<T>(resp, method)
+        }
+    })
+    .await
 }
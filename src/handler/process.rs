@@ -0,0 +1,83 @@
+use std::io;
+use std::process::{Child, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use futures_timer::Delay;
+
+/// How often `BrowserProcess::wait` checks whether the process exited
+const WAIT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The process of a launched chromium instance, shared by the `Browser` that
+/// owns it and the `Handler` that watches it for crashes.
+///
+/// Killing the process via this handle is reported as crash by the
+/// `Handler`, use `Browser::close` to close the browser instead.
+#[derive(Debug, Clone)]
+pub struct BrowserProcess {
+    child: Arc<Mutex<Child>>,
+    /// Set once the browser is closed on purpose, so that its exit is not
+    /// reported as crash
+    closing: Arc<AtomicBool>,
+}
+
+impl BrowserProcess {
+    pub(crate) fn new(child: Child) -> Self {
+        Self {
+            child: Arc::new(Mutex::new(child)),
+            closing: Default::default(),
+        }
+    }
+
+    fn child(&self) -> MutexGuard<'_, Child> {
+        self.child.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// The OS-assigned identifier of the process
+    pub fn id(&self) -> u32 {
+        self.child().id()
+    }
+
+    /// The exit status of the process, if it exited already
+    pub fn try_wait(&self) -> io::Result<Option<ExitStatus>> {
+        self.child().try_wait()
+    }
+
+    /// Kills the process
+    pub fn kill(&self) -> io::Result<()> {
+        self.child().kill()
+    }
+
+    /// Waits until the process exited.
+    ///
+    /// The process is polled, so that the thread is not blocked and the
+    /// `Handler` can still check the process in the meantime.
+    pub async fn wait(&self) -> io::Result<ExitStatus> {
+        loop {
+            if let Some(status) = self.try_wait()? {
+                return Ok(status);
+            }
+            Delay::new(WAIT_INTERVAL).await;
+        }
+    }
+
+    /// Blocks the thread until the process that was just killed exited
+    pub(crate) fn wait_killed(&self) -> io::Result<ExitStatus> {
+        self.child().wait()
+    }
+
+    /// Marks the process as being closed on purpose
+    pub(crate) fn set_closing(&self) {
+        self.closing.store(true, Ordering::SeqCst);
+    }
+
+    /// The exit status of the process, if it exited without being closed on
+    /// purpose
+    pub(crate) fn crashed(&self) -> Option<ExitStatus> {
+        if self.closing.load(Ordering::SeqCst) {
+            return None;
+        }
+        self.try_wait().ok().flatten()
+    }
+}
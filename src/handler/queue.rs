@@ -0,0 +1,225 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+
+use chromiumoxide_cdp::cdp::browser_protocol::target::SessionId;
+
+use crate::cmd::CommandMessage;
+
+/// Limits the number of commands that are in flight at once and queues the
+/// commands that exceed the limits.
+///
+/// Commands are queued per session and the sessions take turns, so that a
+/// burst of commands of one page doesn't delay the commands of other pages.
+/// Commands of the browser's own session are queued under `None`.
+#[derive(Debug)]
+pub(crate) struct CommandQueue {
+    /// How many commands may be in flight over the connection
+    max_in_flight: Option<usize>,
+    /// How many commands of the same session may be in flight
+    max_in_flight_per_session: Option<usize>,
+    /// The number of commands in flight per session
+    in_flight: HashMap<Option<SessionId>, usize>,
+    /// The number of all commands in flight
+    total_in_flight: usize,
+    /// The queued commands of each session together with their deadline
+    queued: HashMap<Option<SessionId>, VecDeque<(CommandMessage, Instant)>>,
+    /// The sessions with queued commands in the order they are served
+    turns: VecDeque<Option<SessionId>>,
+    /// The number of all queued commands
+    len: usize,
+}
+
+impl CommandQueue {
+    pub fn new(max_in_flight: Option<usize>, max_in_flight_per_session: Option<usize>) -> Self {
+        Self {
+            max_in_flight,
+            max_in_flight_per_session,
+            in_flight: Default::default(),
+            total_in_flight: 0,
+            queued: Default::default(),
+            turns: Default::default(),
+            len: 0,
+        }
+    }
+
+    /// The number of queued commands
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether another command of the `session` is allowed in flight
+    fn has_capacity(&self, session: &Option<SessionId>) -> bool {
+        self.max_in_flight
+            .map(|max| self.total_in_flight < max)
+            .unwrap_or(true)
+            && self
+                .max_in_flight_per_session
+                .map(|max| self.in_flight.get(session).copied().unwrap_or_default() < max)
+                .unwrap_or(true)
+    }
+
+    /// Reserves a slot for a command of the `session` regardless of the
+    /// limits, for commands the commands in flight may wait for
+    pub fn acquire(&mut self, session: &Option<SessionId>) {
+        self.total_in_flight += 1;
+        *self.in_flight.entry(session.clone()).or_default() += 1;
+    }
+
+    /// Reserves a slot for a command of the `session`, if it can be submitted
+    /// right away without overtaking queued commands of the same session
+    pub fn try_acquire(&mut self, session: &Option<SessionId>) -> bool {
+        if self.queued.contains_key(session) || !self.has_capacity(session) {
+            return false;
+        }
+        self.acquire(session);
+        true
+    }
+
+    /// Frees the slot of a command of the `session` that is no longer in
+    /// flight
+    pub fn release(&mut self, session: &Option<SessionId>) {
+        self.total_in_flight = self.total_in_flight.saturating_sub(1);
+        if let Some(count) = self.in_flight.get_mut(session) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                self.in_flight.remove(session);
+            }
+        }
+    }
+
+    /// Queue in a command that fails with a timeout after the `deadline`
+    pub fn push(&mut self, cmd: CommandMessage, deadline: Instant) {
+        let session = cmd.session_id.clone();
+        let queue = self.queued.entry(session.clone()).or_default();
+        if queue.is_empty() {
+            self.turns.push_back(session);
+        }
+        queue.push_back((cmd, deadline));
+        self.len += 1;
+    }
+
+    /// The next queued command that can be submitted, the slot for the
+    /// command is reserved.
+    pub fn pop(&mut self) -> Option<(CommandMessage, Instant)> {
+        for _ in 0..self.turns.len() {
+            if self.max_in_flight.map(|max| self.total_in_flight >= max) == Some(true) {
+                return None;
+            }
+            let session = self.turns.pop_front()?;
+            if !self.has_capacity(&session) {
+                self.turns.push_back(session);
+                continue;
+            }
+            if let Some(queue) = self.queued.get_mut(&session) {
+                if let Some(cmd) = queue.pop_front() {
+                    if queue.is_empty() {
+                        self.queued.remove(&session);
+                    } else {
+                        self.turns.push_back(session.clone());
+                    }
+                    self.len -= 1;
+                    self.acquire(&session);
+                    return Some(cmd);
+                }
+            }
+        }
+        None
+    }
+
+    /// Removes all the queued commands of the `session`
+    pub fn remove(&mut self, session: &Option<SessionId>) -> Vec<CommandMessage> {
+        let removed = self
+            .queued
+            .remove(session)
+            .map(|queue| queue.into_iter().map(|(cmd, _)| cmd).collect())
+            .unwrap_or_default();
+        self.remove_empty();
+        removed
+    }
+
+    /// Removes all the queued commands whose deadline has passed or whose
+    /// response is no longer awaited
+    pub fn evict(&mut self, now: Instant) -> Vec<CommandMessage> {
+        let mut timed_out = Vec::new();
+        for queue in self.queued.values_mut() {
+            while let Some(idx) = queue
+                .iter()
+                .position(|(cmd, deadline)| now > *deadline || cmd.sender.is_canceled())
+            {
+                if let Some((cmd, _)) = queue.remove(idx) {
+                    timed_out.push(cmd);
+                }
+            }
+        }
+        self.remove_empty();
+        timed_out
+    }
+
+    /// Removes all queued commands and resets the commands in flight
+    pub fn clear(&mut self) -> Vec<CommandMessage> {
+        self.in_flight.clear();
+        self.total_in_flight = 0;
+        self.turns.clear();
+        self.len = 0;
+        self.queued
+            .drain()
+            .flat_map(|(_, queue)| queue.into_iter().map(|(cmd, _)| cmd))
+            .collect()
+    }
+
+    fn remove_empty(&mut self) {
+        self.queued.retain(|_, queue| !queue.is_empty());
+        let queued = &self.queued;
+        self.turns.retain(|session| queued.contains_key(session));
+        self.len = self.queued.values().map(VecDeque::len).sum();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::channel::oneshot::channel;
+
+    use chromiumoxide_cdp::cdp::browser_protocol::fetch::ContinueRequestParams;
+    use chromiumoxide_cdp::cdp::browser_protocol::page::NavigateParams;
+    use chromiumoxide_types::Command;
+
+    use super::*;
+
+    fn command<C: Command>(cmd: C, session: &Option<SessionId>) -> CommandMessage {
+        let (tx, _) = channel();
+        CommandMessage::with_session(cmd, tx, session.clone()).unwrap()
+    }
+
+    #[test]
+    fn resuming_commands_bypass_limits() {
+        let session = Some(SessionId::from("page".to_string()));
+        let deadline = Instant::now() + Duration::from_secs(30);
+        let mut queue = CommandQueue::new(Some(1), None);
+
+        // a navigation waits for the paused request of its document
+        let nav = command(NavigateParams::new("https://example.com"), &session);
+        assert!(!nav.is_resuming());
+        assert!(queue.try_acquire(&session));
+        assert!(!queue.try_acquire(&session));
+        queue.push(
+            command(NavigateParams::new("https://example.org"), &session),
+            deadline,
+        );
+
+        let resume = command(
+            ContinueRequestParams::new("interception-1".to_string()),
+            &session,
+        );
+        assert!(resume.is_resuming());
+        queue.acquire(&session);
+
+        // the queued command still waits for the navigation
+        queue.release(&session);
+        assert!(queue.pop().is_none());
+        queue.release(&session);
+        assert!(queue.pop().is_some());
+        assert_eq!(queue.len(), 0);
+    }
+}
@@ -1,22 +1,28 @@
-use chromiumoxide_cdp::cdp::browser_protocol::target::{SessionId, TargetId};
+use chromiumoxide_cdp::cdp::browser_protocol::target::{SessionId, TargetId, TargetInfo};
+
+use crate::listeners::EventListeners;
 
 /// Represents a Session within the cpd.
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct Session {
     /// Identifier for this session.
     id: SessionId,
-    /// The type of the target this session is attached to.
+    /// Info about the target this session is attached to.
     /// Used to determine whether this is a page or worker session.
-    target_type: String,
-    /// The identifier of the target this session is attached to.
-    target_id: TargetId,
+    target_info: TargetInfo,
+    /// The subscribers to events of a worker session, events of page
+    /// sessions are dispatched to their `Target`
+    event_listeners: EventListeners,
+    /// The page an out-of-process iframe belongs to
+    page_target_id: Option<TargetId>,
 }
 impl Session {
-    pub fn new(id: SessionId, target_type: String, target_id: TargetId) -> Self {
+    pub fn new(id: SessionId, target_info: TargetInfo, event_replay_capacity: usize) -> Self {
         Self {
             id,
-            target_id,
-            target_type,
+            target_info,
+            event_listeners: EventListeners::with_replay_capacity(event_replay_capacity),
+            page_target_id: None,
         }
     }
 
@@ -25,6 +31,46 @@ impl Session {
     }
 
     pub fn target_id(&self) -> &TargetId {
-        &self.target_id
+        &self.target_info.target_id
+    }
+
+    pub fn target_info(&self) -> &TargetInfo {
+        &self.target_info
+    }
+
+    /// Whether this session is attached to a dedicated, shared or service
+    /// worker
+    pub fn is_worker(&self) -> bool {
+        matches!(
+            self.target_info.r#type.as_str(),
+            "worker" | "shared_worker" | "service_worker"
+        )
+    }
+
+    /// Whether this session is attached to an out-of-process iframe
+    pub fn is_iframe(&self) -> bool {
+        self.target_info.r#type == "iframe"
+    }
+
+    /// The target of the page this session belongs to, the page that embeds
+    /// the frame for out-of-process iframes
+    pub fn page_target_id(&self) -> &TargetId {
+        self.page_target_id
+            .as_ref()
+            .unwrap_or_else(|| self.target_id())
+    }
+
+    pub(crate) fn set_page_target_id(&mut self, target_id: TargetId) {
+        self.page_target_id = Some(target_id);
+    }
+
+    /// Whether this session is attached to the background page of an
+    /// extension
+    pub fn is_background_page(&self) -> bool {
+        self.target_info.r#type == "background_page"
+    }
+
+    pub(crate) fn event_listeners_mut(&mut self) -> &mut EventListeners {
+        &mut self.event_listeners
     }
 }
@@ -11,21 +11,28 @@ use chromiumoxide_types::{Command, Method, Request, Response};
 
 use crate::cmd::CommandChain;
 use crate::cmd::CommandMessage;
+use crate::dialog::DialogPolicy;
 use crate::error::{CdpError, DeadlineExceeded, Result};
-use crate::handler::emulation::EmulationManager;
+use crate::handler::domains::DomainConfig;
+use crate::handler::emulation::{self, EmulationManager};
 use crate::handler::frame::FrameNavigationRequest;
 use crate::handler::frame::{
-    FrameEvent, FrameManager, NavigationError, NavigationId, NavigationOk,
+    FrameEvent, FrameExecutionContext, FrameInfo, FrameManager, LifecycleEvent, LifecycleState,
+    NavigationError, NavigationId, NavigationOk,
 };
-use crate::handler::network::NetworkManager;
+use crate::handler::network::{Credentials, NetworkEvent, NetworkManager};
 use crate::handler::page::PageHandle;
 use crate::handler::viewport::Viewport;
-use crate::handler::PageInner;
+use crate::handler::{HandlerConfig, PageInner};
+use crate::listeners::{EventListenerRequest, EventListeners};
 use crate::page::Page;
-use chromiumoxide_cdp::cdp::browser_protocol::page::{FrameId, GetFrameTreeParams};
+use crate::retry::RetryPolicy;
+use chromiumoxide_cdp::cdp::browser_protocol::fetch::{EventAuthRequired, EventRequestPaused};
+use chromiumoxide_cdp::cdp::browser_protocol::page::{
+    FrameId, GetFrameTreeParams, HandleJavaScriptDialogParams,
+};
 use chromiumoxide_cdp::cdp::browser_protocol::{
     browser::BrowserContextId,
-    log as cdplog, performance,
     target::{AttachToTargetParams, SessionId, SetAutoAttachParams, TargetId, TargetInfo},
 };
 use chromiumoxide_cdp::cdp::events::CdpEvent;
@@ -66,6 +73,12 @@ pub struct Target {
     viewport: Viewport,
     /// The identifier of the session this target is attached to
     session_id: Option<SessionId>,
+    /// The previous sessions of the page, before the target was attached
+    /// again after a reconnect
+    stale_sessions: Vec<SessionId>,
+    /// Whether the state of the page needs to be restored once the target
+    /// is initialized again
+    restore_page: bool,
     /// The handle of the browser page of this target
     page: Option<PageHandle>,
     /// Drives this target towards initialization
@@ -76,32 +89,68 @@ pub struct Target {
     wait_until_frame_loaded: Vec<Sender<Result<String>>>,
     /// The sender who requested the page.
     initiator: Option<Sender<Result<Page>>>,
+    /// Senders that requested to attach to this target, they receive the
+    /// page as soon as the target is initialized, regardless of its loading
+    /// state
+    attach_requests: Vec<Sender<Result<Page>>>,
     /// Used to tracked whether this target should initialize its state
     initialize: bool,
+    /// The subscribers to events of this target
+    event_listeners: EventListeners,
+    /// How to handle dialogs nobody listens for
+    dialog_policy: DialogPolicy,
+    /// The capacity of the channel of the target's page
+    channel_capacity: usize,
+    /// Whether the messages of the page are currently not received
+    backlogged: bool,
+    /// How the commands of the page are retried
+    retry_policy: Option<RetryPolicy>,
+    /// The domains that are enabled for the page
+    domains: DomainConfig,
+    /// Whether to install the stealth evasions in the page
+    #[cfg(feature = "stealth")]
+    stealth: bool,
 }
 
 impl Target {
     /// Create a new target instance with `TargetInfo` after a
     /// `CreateTargetParams` request.
-    pub fn new(info: TargetInfo) -> Self {
+    pub fn new(info: TargetInfo, config: &HandlerConfig) -> Self {
+        let mut network_manager = NetworkManager::new(config.ignore_https_errors);
+        network_manager.set_proxy_credentials(config.proxy_credentials.clone());
+        network_manager.set_user_agent(config.user_agent.clone());
         Self {
             info,
             is_closed: false,
-            frame_manager: Default::default(),
-            network_manager: Default::default(),
+            frame_manager: FrameManager::new(config.request_timeout),
+            network_manager,
             emulation_manager: Default::default(),
-            viewport: Default::default(),
+            viewport: config.viewport.clone(),
             session_id: None,
+            stale_sessions: Vec::new(),
+            restore_page: false,
             page: None,
             init_state: TargetInit::AttachToTarget,
             wait_until_frame_loaded: Default::default(),
             queued_events: Default::default(),
             initiator: None,
+            attach_requests: Default::default(),
             initialize: false,
+            event_listeners: EventListeners::with_replay_capacity(config.event_replay_capacity),
+            dialog_policy: Default::default(),
+            channel_capacity: config.channel_capacity,
+            backlogged: false,
+            retry_policy: config.retry_policy.clone(),
+            domains: config.domains,
+            #[cfg(feature = "stealth")]
+            stealth: config.stealth,
         }
     }
 
     pub fn set_session_id(&mut self, id: SessionId) {
+        if let Some(page) = self.page.as_ref() {
+            page.inner().set_session_id(id.clone());
+        }
         self.session_id = Some(id)
     }
 
@@ -118,6 +167,17 @@ impl Target {
         &self.info.target_id
     }
 
+    /// Update the info after `Target.targetInfoChanged`, like the url or
+    /// title after a navigation
+    pub fn set_info(&mut self, info: TargetInfo) {
+        self.info = info;
+    }
+
+    /// Whether this target was marked as closed
+    pub fn is_closed(&self) -> bool {
+        self.is_closed
+    }
+
     /// Whether this target is already initialized
     pub fn is_initialized(&self) -> bool {
         matches!(self.init_state, TargetInit::Initialized)
@@ -130,7 +190,14 @@ impl Target {
     fn create_page(&mut self) {
         if self.page.is_none() {
             if let Some(session) = self.session_id.clone() {
-                let handle = PageHandle::new(self.target_id().clone(), session);
+                let handle = PageHandle::new(
+                    self.target_id().clone(),
+                    session,
+                    self.channel_capacity,
+                    self.retry_policy.clone(),
+                    Some(emulation::device_metrics_override(&self.viewport)),
+                    self.network_manager.has_credentials(),
+                );
                 self.page = Some(handle);
             }
         }
@@ -163,7 +230,6 @@ impl Target {
     }
 
     /// Received a response to a command issued by this target
-
     pub fn on_response(&mut self, resp: Response, method: &str) {
         if let Some(cmds) = self.init_state.commands_mut() {
             cmds.received_response(method);
@@ -183,42 +249,48 @@ impl Target {
     }
 
     pub fn on_event(&mut self, event: CdpEventMessage) {
-        match event.params {
-            // `FrameManager` events
-            CdpEvent::PageFrameAttached(ev) => self
-                .frame_manager
-                .on_frame_attached(ev.frame_id.clone(), Some(ev.parent_frame_id)),
-            CdpEvent::PageFrameDetached(ev) => self.frame_manager.on_frame_detached(&ev),
-            CdpEvent::PageFrameNavigated(ev) => self.frame_manager.on_frame_navigated(ev.frame),
-            CdpEvent::PageNavigatedWithinDocument(ev) => {
-                self.frame_manager.on_frame_navigated_within_document(&ev)
-            }
-            CdpEvent::RuntimeExecutionContextCreated(ev) => {
-                self.frame_manager.on_frame_execution_context_created(&ev)
-            }
-            CdpEvent::RuntimeExecutionContextDestroyed(ev) => {
-                self.frame_manager.on_frame_execution_context_destroyed(&ev)
-            }
-            CdpEvent::RuntimeExecutionContextsCleared(ev) => {
-                self.frame_manager.on_execution_context_cleared(&ev)
-            }
-            CdpEvent::PageLifecycleEvent(ev) => self.frame_manager.on_page_lifecycle_event(&ev),
-            CdpEvent::PageFrameStartedLoading(ev) => {
-                self.frame_manager.on_frame_started_loading(&ev);
+        if let CdpEvent::FetchAuthRequired(ev) = &event.params {
+            // challenges that are answered with the configured credentials are
+            // not forwarded to the request interception
+            if self
+                .network_manager
+                .credentials_for(&ev.auth_challenge)
+                .is_some()
+            {
+                self.network_manager.on_fetch_auth_required(ev);
+                return;
             }
+        }
+        self.event_listeners.start_send(&event);
+        let session_id = event.session_id.clone().map(SessionId::from);
+        self.frame_manager
+            .on_event(session_id.as_ref(), &event.params);
+        match event.params {
+            CdpEvent::PageJavascriptDialogOpening(_) => self.on_javascript_dialog_opening(),
 
             // `NetworkManager` events
-            CdpEvent::FetchRequestPaused(ev) => self.network_manager.on_fetch_request_paused(&*ev),
-            CdpEvent::FetchAuthRequired(ev) => self.network_manager.on_fetch_auth_required(&*ev),
+            // paused requests are left to the request interception, if any
+            CdpEvent::FetchRequestPaused(ev)
+                if !self
+                    .event_listeners
+                    .has_listeners(EventRequestPaused::IDENTIFIER) =>
+            {
+                self.network_manager.on_fetch_request_paused(&ev)
+            }
+            CdpEvent::FetchAuthRequired(ev)
+                if !self
+                    .event_listeners
+                    .has_listeners(EventAuthRequired::IDENTIFIER) =>
+            {
+                self.network_manager.on_fetch_auth_required(&ev)
+            }
             CdpEvent::NetworkRequestWillBeSent(ev) => {
-                self.network_manager.on_request_will_be_sent(&*ev)
+                self.network_manager.on_request_will_be_sent(&ev)
             }
             CdpEvent::NetworkRequestServedFromCache(ev) => {
                 self.network_manager.on_request_served_from_cache(&ev)
             }
-            CdpEvent::NetworkResponseReceived(ev) => {
-                self.network_manager.on_response_received(&*ev)
-            }
+            CdpEvent::NetworkResponseReceived(ev) => self.network_manager.on_response_received(&ev),
             CdpEvent::NetworkLoadingFinished(ev) => {
                 self.network_manager.on_network_loading_finished(&ev)
             }
@@ -229,6 +301,55 @@ impl Target {
         }
     }
 
+    /// Received the frame tree of an out-of-process iframe of this target's
+    /// page, which is not part of the target's own initialization
+    pub fn on_oopif_frame_tree(&mut self, resp: Response) {
+        if let Some(resp) = resp
+            .result
+            .and_then(|val| GetFrameTreeParams::response_from_value(val).ok())
+        {
+            self.frame_manager.on_frame_tree(resp.frame_tree);
+        }
+    }
+
+    /// Received an event of an out-of-process iframe of this target's page,
+    /// only its frames are merged into the frame tree of the page
+    pub fn on_oopif_event(&mut self, event: CdpEventMessage) {
+        let session_id = event.session_id.clone().map(SessionId::from);
+        self.frame_manager
+            .on_event(session_id.as_ref(), &event.params);
+    }
+
+    /// Handles the dialog according to the `DialogPolicy`, unless it is
+    /// handled manually via a `DialogStream`.
+    ///
+    /// Other subscribers of `EventJavascriptDialogOpening` only observe the
+    /// dialog.
+    fn on_javascript_dialog_opening(&mut self) {
+        if self
+            .page
+            .as_ref()
+            .map(|page| page.inner().handles_dialogs_manually())
+            .unwrap_or_default()
+        {
+            return;
+        }
+        if let Some(accept) = self.dialog_policy.accept() {
+            let params = HandleJavaScriptDialogParams::new(accept);
+            self.queued_events.push_back(TargetEvent::Request(Request {
+                method: params.identifier(),
+                session_id: self.session_id.clone().map(Into::into),
+                params: serde_json::to_value(params).unwrap(),
+            }));
+        }
+    }
+
+    /// Stop receiving the messages of the page while the `Handler` is
+    /// backlogged with commands
+    pub(crate) fn set_backlogged(&mut self, backlogged: bool) {
+        self.backlogged = backlogged;
+    }
+
     /// Advance that target's state
     pub(crate) fn poll(&mut self, cx: &mut Context<'_>, now: Instant) -> Option<TargetEvent> {
         if !self.initialize {
@@ -264,7 +385,7 @@ impl Target {
                     cx,
                     now,
                     cmds,
-                    TargetInit::InitializingPage(Self::page_init_commands())
+                    TargetInit::InitializingPage(self.page_init_commands())
                 );
             }
             TargetInit::InitializingPage(cmds) => {
@@ -282,6 +403,25 @@ impl Target {
                 advance_state!(self, cx, now, cmds, TargetInit::Initialized);
             }
             TargetInit::Initialized => {
+                if std::mem::take(&mut self.restore_page) {
+                    if let Some(page) = self.page.as_ref() {
+                        for (method, params) in page.inner().restore_commands() {
+                            self.queued_events.push_back(TargetEvent::Request(Request {
+                                method,
+                                session_id: self.session_id.clone().map(Into::into),
+                                params,
+                            }));
+                        }
+                    }
+                }
+                if !self.attach_requests.is_empty() {
+                    if let Some(page) = self.get_or_create_page() {
+                        let page = Arc::clone(page);
+                        for tx in self.attach_requests.drain(..) {
+                            let _ = tx.send(Ok(Page::from(Arc::clone(&page))));
+                        }
+                    }
+                }
                 if let Some(initiator) = self.initiator.take() {
                     // make sure that the main frame of the page has finished loading
                     if self
@@ -315,12 +455,62 @@ impl Target {
                 return Some(ev);
             }
 
+            // commands that resume the browser are accepted even while
+            // backlogged, the backlog may wait for them
             if let Some(handle) = self.page.as_mut() {
+                while let Poll::Ready(Some(msg)) = Pin::new(&mut handle.resuming_rx).poll_next(cx) {
+                    if let TargetMessage::Command(mut cmd) = msg {
+                        update_session(&mut cmd, &self.session_id, &self.stale_sessions);
+                        self.queued_events.push_back(TargetEvent::Command(cmd));
+                    }
+                }
+            }
+
+            let backlogged = self.backlogged;
+            if let Some(handle) = self.page.as_mut().filter(|_| !backlogged) {
                 while let Poll::Ready(Some(msg)) = Pin::new(&mut handle.rx).poll_next(cx) {
                     match msg {
-                        TargetMessage::Command(cmd) => {
+                        TargetMessage::Command(mut cmd) => {
+                            enable_domain_of(
+                                &mut self.domains,
+                                &mut self.queued_events,
+                                &self.session_id,
+                                &cmd.method,
+                            );
+                            update_session(&mut cmd, &self.session_id, &self.stale_sessions);
+                            handle.inner().on_command(&cmd.method, &cmd.params);
                             self.queued_events.push_back(TargetEvent::Command(cmd));
                         }
+                        TargetMessage::Navigate(mut cmd, wait_until) => {
+                            enable_domain_of(
+                                &mut self.domains,
+                                &mut self.queued_events,
+                                &self.session_id,
+                                &cmd.method,
+                            );
+                            update_session(&mut cmd, &self.session_id, &self.stale_sessions);
+                            self.queued_events
+                                .push_back(TargetEvent::Navigate(cmd, wait_until));
+                        }
+                        TargetMessage::WaitForNextNavigation(wait_until, tx) => {
+                            self.frame_manager
+                                .add_navigation_listener(wait_until, tx, now);
+                        }
+                        TargetMessage::Authenticate(credentials) => {
+                            self.network_manager.set_credentials(credentials);
+                        }
+                        TargetMessage::SetDialogPolicy(policy) => {
+                            self.dialog_policy = policy;
+                        }
+                        TargetMessage::AddEventListener(req) => {
+                            enable_domain_of(
+                                &mut self.domains,
+                                &mut self.queued_events,
+                                &self.session_id,
+                                &req.method,
+                            );
+                            self.event_listeners.add_listener(req);
+                        }
                         TargetMessage::MainFrame(tx) => {
                             let _ = tx.send(self.frame_manager.main_frame().map(|f| f.id.clone()));
                         }
@@ -328,6 +518,19 @@ impl Target {
                             let _ = tx
                                 .send(self.frame_manager.main_frame().and_then(|f| f.url.clone()));
                         }
+                        TargetMessage::Frames(tx) => {
+                            let _ = tx.send(self.frame_manager.frames().map(Into::into).collect());
+                        }
+                        TargetMessage::FrameExecutionContext(frame_id, tx) => {
+                            let _ =
+                                tx.send(self.frame_manager.execution_context(&frame_id).cloned());
+                        }
+                        TargetMessage::Lifecycle(tx) => {
+                            let _ = tx.send(self.frame_manager.main_frame().map(|f| f.lifecycle()));
+                        }
+                        TargetMessage::WaitForLifecycle(event, tx) => {
+                            self.frame_manager.add_lifecycle_listener(event, tx);
+                        }
                         TargetMessage::WaitForNavigation(tx) => {
                             if let Some(frame) = self.frame_manager.main_frame() {
                                 if frame.is_loaded() {
@@ -343,6 +546,18 @@ impl Target {
                 }
             }
 
+            while let Some(event) = self.network_manager.poll() {
+                match event {
+                    NetworkEvent::SendCdpRequest((method, params)) => {
+                        self.queued_events.push_back(TargetEvent::Request(Request {
+                            method,
+                            session_id: self.session_id.clone().map(Into::into),
+                            params,
+                        }));
+                    }
+                }
+            }
+
             while let Some(event) = self.frame_manager.poll(now) {
                 match event {
                     FrameEvent::NavigationResult(res) => {
@@ -369,32 +584,96 @@ impl Target {
         self.initialize();
     }
 
+    /// Attach to this target and send its page to `tx` once initialized
+    pub fn attach(&mut self, tx: Sender<Result<Page>>) {
+        self.attach_requests.push(tx);
+        self.initialize();
+    }
+
+    /// Forget the session of this target, which is gone with the lost
+    /// connection. Commands of the page that are still addressed to it are
+    /// sent to the new session once the target is attached again.
+    pub(crate) fn detach(&mut self) {
+        if let Some(session_id) = self.session_id.take() {
+            self.stale_sessions.push(session_id);
+        }
+    }
+
+    /// Attach to this target again after the connection was re-established,
+    /// since the previous session is gone.
+    ///
+    /// The domains, the interception of the routes and the emulated media of
+    /// the page are restored once the target is initialized again. Other
+    /// state of the old session, like scripts added via
+    /// `Page.addScriptToEvaluateOnNewDocument` or emulation overrides, is
+    /// lost and needs to be restored by the caller after
+    /// `ConnectionEvent::Reconnected`.
+    pub(crate) fn reattach(&mut self) {
+        if self.initialize {
+            self.init_state = TargetInit::AttachToTarget;
+            self.restore_page = self.page.is_some();
+        }
+    }
+
     /// Start with the initialization process
     pub fn initialize(&mut self) {
         self.initialize = true;
     }
 
     // TODO move to other location
-    pub(crate) fn page_init_commands() -> CommandChain {
+    pub(crate) fn page_init_commands(&self) -> CommandChain {
         let attach = SetAutoAttachParams::builder()
             .flatten(true)
             .auto_attach(true)
             .wait_for_debugger_on_start(true)
             .build()
             .unwrap();
-        let enable_performance = performance::EnableParams::default();
-        let enable_log = cdplog::EnableParams::default();
-        CommandChain::new(vec![
-            (attach.identifier(), serde_json::to_value(attach).unwrap()),
-            (
-                enable_performance.identifier(),
-                serde_json::to_value(enable_performance).unwrap(),
-            ),
-            (
-                enable_log.identifier(),
-                serde_json::to_value(enable_log).unwrap(),
-            ),
-        ])
+        let mut cmds = vec![(attach.identifier(), serde_json::to_value(attach).unwrap())];
+        cmds.extend(self.domains.init_commands());
+        #[cfg(feature = "stealth")]
+        if self.stealth {
+            cmds.extend(crate::stealth::init_commands());
+        }
+        CommandChain::new(cmds)
+    }
+}
+
+/// Enables the domain of the command or event `method` of the page before it
+/// is used for the first time.
+///
+/// While the target is not attached, e.g. during a reconnect, the domain is
+/// only marked as enabled and then enabled by the `page_init_commands` once
+/// the target is attached again.
+fn enable_domain_of(
+    domains: &mut DomainConfig,
+    queued_events: &mut VecDeque<TargetEvent>,
+    session_id: &Option<SessionId>,
+    method: &str,
+) {
+    let cmds = domains.on_method(method);
+    if let Some(session_id) = session_id {
+        for (method, params) in cmds {
+            queued_events.push_back(TargetEvent::Request(Request {
+                method,
+                session_id: Some(session_id.clone().into()),
+                params,
+            }));
+        }
+    }
+}
+
+/// Commands of the page are sent to the current session of the target, which
+/// changes if the target was attached again after a reconnect. Commands for
+/// the sessions of out-of-process iframes are left as they are.
+fn update_session(
+    cmd: &mut CommandMessage,
+    session_id: &Option<SessionId>,
+    stale_sessions: &[SessionId],
+) {
+    if let (Some(cmd_session), Some(session_id)) = (&cmd.session_id, session_id) {
+        if stale_sessions.contains(cmd_session) {
+            cmd.session_id = Some(session_id.clone());
+        }
     }
 }
 
@@ -407,9 +686,12 @@ pub(crate) enum TargetEvent {
     /// Indicates that a previous requested navigation has finished
     NavigationResult(Result<NavigationOk, NavigationError>),
     /// An internal request timed out
+    #[allow(unused)] // allow until implemented
     RequestTimeout(DeadlineExceeded),
     /// A new command arrived via a channel
     Command(CommandMessage),
+    /// A new navigation command arrived via a channel
+    Navigate(CommandMessage, LifecycleEvent),
 }
 
 // TODO this can be moved into the classes?
@@ -440,10 +722,32 @@ impl TargetInit {
 pub(crate) enum TargetMessage {
     /// Execute a command within the session of this target
     Command(CommandMessage),
+    /// Execute a navigation command and resolve once the lifecycle event was
+    /// fired
+    Navigate(CommandMessage, LifecycleEvent),
     /// Return the main frame of this target
     MainFrame(Sender<Option<FrameId>>),
     /// Return the url of this target's page
     Url(Sender<Option<String>>),
+    /// Return all the frames of the page, including out-of-process iframes
+    Frames(Sender<Vec<FrameInfo>>),
+    /// Return the default execution context of the frame
+    FrameExecutionContext(FrameId, Sender<Option<FrameExecutionContext>>),
+    /// Return the lifecycle state of the main frame
+    Lifecycle(Sender<Option<LifecycleState>>),
+    /// A Message that resolves once the current document of the main frame
+    /// emitted the lifecycle event
+    WaitForLifecycle(LifecycleEvent, Sender<LifecycleState>),
     /// A Message that resolves when the frame finished loading a new url
     WaitForNavigation(Sender<Result<String>>),
+    /// A Message that resolves once the main frame navigated to another
+    /// document and emitted the lifecycle event, or navigated within the
+    /// current document
+    WaitForNextNavigation(LifecycleEvent, Sender<Result<String>>),
+    /// Subscribe to events of this target
+    AddEventListener(EventListenerRequest),
+    /// Answer authentication challenges with the credentials
+    Authenticate(Credentials),
+    /// Change how dialogs are handled that nobody listens for
+    SetDialogPolicy(DialogPolicy),
 }
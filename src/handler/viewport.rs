@@ -1,10 +1,17 @@
+/// The size and the capabilities of the screen a page is emulated with
 #[derive(Debug, Clone)]
 pub struct Viewport {
+    /// The width of the viewport in css pixels
     pub width: u32,
+    /// The height of the viewport in css pixels
     pub height: u32,
+    /// The ratio of device pixels to css pixels, `1` if `None`
     pub device_scale_factor: Option<f64>,
+    /// Whether to emulate a mobile device, including the meta viewport tag
     pub is_mobile: bool,
+    /// Whether the screen is in landscape orientation
     pub is_landscape: bool,
+    /// Whether to emulate touch events
     pub has_touch: bool,
 }
 
@@ -0,0 +1,599 @@
+//! Records the network traffic of a page and exports it as
+//! [HAR](http://www.softwareishard.com/blog/har-12-spec/) file.
+//!
+//! The conversion of the devtools events follows
+//! [chrome-har](https://github.com/sitespeedio/chrome-har).
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use futures::{FutureExt, StreamExt};
+use serde::Serialize;
+
+use chromiumoxide_cdp::cdp::browser_protocol::network::{
+    EventLoadingFailed, EventLoadingFinished, EventRequestWillBeSent, EventResponseReceived,
+    GetResponseBodyParams, Headers, Request, RequestId, ResourceTiming, Response,
+};
+
+use crate::error::Result;
+use crate::handler::PageInner;
+use crate::listeners::EventStream;
+use crate::utils;
+
+/// Collects the requests of a page and their responses.
+///
+/// Created via `Page::record_network`, only requests that are sent after that
+/// are recorded.
+///
+/// # Example
+///
+/// ```no_run
+/// # use chromiumoxide::page::Page;
+/// # use chromiumoxide::error::Result;
+/// # async fn demo(page: Page) -> Result<()> {
+///     let mut recorder = page.record_network().await?.include_bodies(true);
+///     page.goto("https://example.com").await?;
+///     recorder.save_har("example.har").await?;
+///     # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct NetworkRecorder {
+    page: Arc<PageInner>,
+    requests: EventStream<EventRequestWillBeSent>,
+    responses: EventStream<EventResponseReceived>,
+    finished: EventStream<EventLoadingFinished>,
+    failed: EventStream<EventLoadingFailed>,
+    /// All recorded exchanges in the order their requests were sent
+    exchanges: Vec<Exchange>,
+    /// The index of the latest exchange of a request, redirects reuse the id
+    /// of the request
+    pending: HashMap<RequestId, usize>,
+    include_bodies: bool,
+}
+
+/// A request and its response
+#[derive(Debug)]
+struct Exchange {
+    request_id: RequestId,
+    request: Request,
+    /// Seconds since epoch when the request was sent
+    wall_time: f64,
+    /// Monotonic time when the request was sent
+    timestamp: f64,
+    response: Option<Response>,
+    /// Monotonic time when the response finished loading
+    finished: Option<f64>,
+    encoded_data_length: Option<f64>,
+    error_text: Option<String>,
+    body: Option<ResponseBody>,
+}
+
+#[derive(Debug, Clone)]
+struct ResponseBody {
+    text: String,
+    base64_encoded: bool,
+}
+
+impl NetworkRecorder {
+    pub(crate) fn new(
+        page: Arc<PageInner>,
+        requests: EventStream<EventRequestWillBeSent>,
+        responses: EventStream<EventResponseReceived>,
+        finished: EventStream<EventLoadingFinished>,
+        failed: EventStream<EventLoadingFailed>,
+    ) -> Self {
+        Self {
+            page,
+            requests,
+            responses,
+            finished,
+            failed,
+            exchanges: Vec::new(),
+            pending: HashMap::new(),
+            include_bodies: false,
+        }
+    }
+
+    /// Whether to include the bodies of the responses in the HAR.
+    ///
+    /// The bodies are fetched when the HAR is created, the browser may have
+    /// discarded the bodies of older responses by then.
+    pub fn include_bodies(mut self, include_bodies: bool) -> Self {
+        self.include_bodies = include_bodies;
+        self
+    }
+
+    /// Discard all the requests recorded so far
+    pub fn clear(&mut self) {
+        self.drain_events();
+        self.exchanges.clear();
+        self.pending.clear();
+    }
+
+    /// Processes all the events that were received so far.
+    ///
+    /// The events of each kind are processed in order, requests first, so that
+    /// responses always find their request.
+    fn drain_events(&mut self) {
+        while let Some(Some(ev)) = self.requests.next().now_or_never() {
+            self.on_request(&ev);
+        }
+        while let Some(Some(ev)) = self.responses.next().now_or_never() {
+            if let Some(exchange) = self.exchange_mut(&ev.request_id) {
+                exchange.response = Some(ev.response.clone());
+            }
+        }
+        while let Some(Some(ev)) = self.finished.next().now_or_never() {
+            if let Some(idx) = self.pending.remove(&ev.request_id) {
+                let exchange = &mut self.exchanges[idx];
+                exchange.finished = Some(*ev.timestamp.inner());
+                exchange.encoded_data_length = Some(ev.encoded_data_length);
+            }
+        }
+        while let Some(Some(ev)) = self.failed.next().now_or_never() {
+            if let Some(idx) = self.pending.remove(&ev.request_id) {
+                let exchange = &mut self.exchanges[idx];
+                exchange.finished = Some(*ev.timestamp.inner());
+                exchange.error_text = Some(ev.error_text.clone());
+            }
+        }
+    }
+
+    fn on_request(&mut self, ev: &EventRequestWillBeSent) {
+        // a redirect completes the previous exchange of this request
+        if let Some(redirect) = ev.redirect_response.as_ref() {
+            if let Some(idx) = self.pending.remove(&ev.request_id) {
+                let exchange = &mut self.exchanges[idx];
+                exchange.response = Some(redirect.clone());
+                exchange.finished = Some(*ev.timestamp.inner());
+                exchange.encoded_data_length = Some(redirect.encoded_data_length);
+            }
+        }
+        self.pending
+            .insert(ev.request_id.clone(), self.exchanges.len());
+        self.exchanges.push(Exchange {
+            request_id: ev.request_id.clone(),
+            request: ev.request.clone(),
+            wall_time: *ev.wall_time.inner(),
+            timestamp: *ev.timestamp.inner(),
+            response: None,
+            finished: None,
+            encoded_data_length: None,
+            error_text: None,
+            body: None,
+        });
+    }
+
+    fn exchange_mut(&mut self, request_id: &RequestId) -> Option<&mut Exchange> {
+        let idx = *self.pending.get(request_id)?;
+        self.exchanges.get_mut(idx)
+    }
+
+    /// Creates the HAR of all the requests recorded so far that received a
+    /// response.
+    pub async fn har(&mut self) -> Result<Har> {
+        self.drain_events();
+        if self.include_bodies {
+            for exchange in self.exchanges.iter_mut() {
+                let is_redirect = exchange
+                    .response
+                    .as_ref()
+                    .map(|resp| (300..400).contains(&resp.status))
+                    .unwrap_or(true);
+                if exchange.body.is_some() || exchange.finished.is_none() || is_redirect {
+                    continue;
+                }
+                let params = GetResponseBodyParams::new(exchange.request_id.clone());
+                // the body may not be available anymore
+                if let Ok(resp) = self.page.execute(params).await {
+                    exchange.body = Some(ResponseBody {
+                        text: resp.result.body,
+                        base64_encoded: resp.result.base64_encoded,
+                    });
+                }
+            }
+        }
+        let entries = self
+            .exchanges
+            .iter()
+            .filter_map(HarEntry::from_exchange)
+            .collect();
+        Ok(Har {
+            log: HarLog {
+                version: "1.2".to_string(),
+                creator: HarCreator {
+                    name: "chromiumoxide".to_string(),
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                },
+                entries,
+            },
+        })
+    }
+
+    /// Writes the HAR of all the requests recorded so far to the file at
+    /// `path`
+    pub async fn save_har(&mut self, path: impl AsRef<Path>) -> Result<Har> {
+        let har = self.har().await?;
+        utils::write(path.as_ref(), serde_json::to_vec_pretty(&har)?).await?;
+        Ok(har)
+    }
+}
+
+/// The root of a HAR file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Har {
+    pub log: HarLog,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HarLog {
+    pub version: String,
+    pub creator: HarCreator,
+    pub entries: Vec<HarEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HarCreator {
+    pub name: String,
+    pub version: String,
+}
+
+/// A request and its response
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarEntry {
+    /// When the request was sent, in ISO 8601 format
+    pub started_date_time: String,
+    /// The total time of the request in milliseconds
+    pub time: f64,
+    pub request: HarRequest,
+    pub response: HarResponse,
+    pub cache: HarCache,
+    pub timings: HarTimings,
+    #[serde(rename = "serverIPAddress", skip_serializing_if = "Option::is_none")]
+    pub server_ip_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarRequest {
+    pub method: String,
+    pub url: String,
+    pub http_version: String,
+    pub cookies: Vec<HarNameValue>,
+    pub headers: Vec<HarNameValue>,
+    pub query_string: Vec<HarNameValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<HarPostData>,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarResponse {
+    pub status: i64,
+    pub status_text: String,
+    pub http_version: String,
+    pub cookies: Vec<HarNameValue>,
+    pub headers: Vec<HarNameValue>,
+    pub content: HarContent,
+    #[serde(rename = "redirectURL")]
+    pub redirect_url: String,
+    pub headers_size: i64,
+    pub body_size: i64,
+    /// Why the request failed, if it did
+    #[serde(rename = "_errorText", skip_serializing_if = "Option::is_none")]
+    pub error_text: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarContent {
+    pub size: i64,
+    pub mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarPostData {
+    pub mime_type: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HarNameValue {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct HarCache {}
+
+/// The phases of a request in milliseconds, `-1` if not applicable
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HarTimings {
+    pub blocked: f64,
+    pub dns: f64,
+    pub connect: f64,
+    pub send: f64,
+    pub wait: f64,
+    pub receive: f64,
+    pub ssl: f64,
+}
+
+impl HarEntry {
+    fn from_exchange(exchange: &Exchange) -> Option<Self> {
+        let response = exchange.response.as_ref()?;
+        let request = &exchange.request;
+        let http_version = http_version(response.protocol.as_deref());
+
+        let request_headers = response
+            .request_headers
+            .as_ref()
+            .unwrap_or(&request.headers);
+        let post_data = request.post_data.as_ref().map(|text| HarPostData {
+            mime_type: header_value(request_headers, "content-type").unwrap_or_default(),
+            text: text.clone(),
+        });
+        let query_string = url::Url::parse(&request.url)
+            .map(|url| {
+                url.query_pairs()
+                    .map(|(name, value)| HarNameValue {
+                        name: name.into_owned(),
+                        value: value.into_owned(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let (text, encoding, size) = match exchange.body.clone() {
+            Some(body) if body.base64_encoded => {
+                let size = base64::decode(&body.text).ok().map(|data| data.len());
+                (Some(body.text), Some("base64".to_string()), size)
+            }
+            Some(body) => {
+                let size = body.text.len();
+                (Some(body.text), None, Some(size))
+            }
+            None => (None, None, None),
+        };
+        let body_size = exchange
+            .encoded_data_length
+            .map(|len| len as i64)
+            .unwrap_or(-1);
+
+        let total = exchange
+            .finished
+            .map(|finished| (finished - exchange.timestamp) * 1000.)
+            .unwrap_or_default();
+        let timings = HarTimings::new(response.timing.as_ref(), exchange.finished, total);
+        let time = [timings.blocked, timings.dns, timings.connect]
+            .iter()
+            .map(|t| t.max(0.))
+            .sum::<f64>()
+            + timings.send
+            + timings.wait
+            + timings.receive;
+
+        Some(HarEntry {
+            started_date_time: iso_8601(exchange.wall_time),
+            time,
+            request: HarRequest {
+                method: request.method.clone(),
+                url: request.url.clone(),
+                http_version: http_version.clone(),
+                cookies: header_value(request_headers, "cookie")
+                    .map(|cookies| request_cookies(&cookies))
+                    .unwrap_or_default(),
+                headers: har_headers(request_headers),
+                query_string,
+                body_size: request
+                    .post_data
+                    .as_ref()
+                    .map(|data| data.len() as i64)
+                    .unwrap_or_default(),
+                post_data,
+                headers_size: -1,
+            },
+            response: HarResponse {
+                status: response.status,
+                status_text: response.status_text.clone(),
+                http_version,
+                cookies: header_value(&response.headers, "set-cookie")
+                    .map(|cookies| response_cookies(&cookies))
+                    .unwrap_or_default(),
+                headers: har_headers(&response.headers),
+                content: HarContent {
+                    size: size.map(|size| size as i64).unwrap_or(body_size),
+                    mime_type: response.mime_type.clone(),
+                    text,
+                    encoding,
+                },
+                redirect_url: header_value(&response.headers, "location").unwrap_or_default(),
+                headers_size: -1,
+                body_size,
+                error_text: exchange.error_text.clone(),
+            },
+            cache: HarCache::default(),
+            timings,
+            server_ip_address: response.remote_ip_address.clone(),
+            connection: Some(response.connection_id.to_string()),
+        })
+    }
+}
+
+impl HarTimings {
+    /// Splits the `total` time of a request in milliseconds into its phases
+    fn new(timing: Option<&ResourceTiming>, finished: Option<f64>, total: f64) -> Self {
+        let timing = if let Some(timing) = timing {
+            timing
+        } else {
+            return Self {
+                blocked: -1.,
+                dns: -1.,
+                connect: -1.,
+                send: 0.,
+                wait: total.max(0.),
+                receive: 0.,
+                ssl: -1.,
+            };
+        };
+        let blocked = [timing.dns_start, timing.connect_start, timing.send_start]
+            .iter()
+            .copied()
+            .find(|start| *start >= 0.)
+            .unwrap_or(-1.);
+        let phase = |start: f64, end: f64| if start >= 0. { end - start } else { -1. };
+        let receive = finished
+            .map(|finished| (finished - timing.request_time) * 1000. - timing.receive_headers_end)
+            .unwrap_or_default();
+        Self {
+            blocked,
+            dns: phase(timing.dns_start, timing.dns_end),
+            connect: phase(timing.connect_start, timing.connect_end),
+            send: (timing.send_end - timing.send_start).max(0.),
+            wait: (timing.receive_headers_end - timing.send_end).max(0.),
+            receive: receive.max(0.),
+            ssl: phase(timing.ssl_start, timing.ssl_end),
+        }
+    }
+}
+
+/// The HTTP version as written in HAR files
+fn http_version(protocol: Option<&str>) -> String {
+    match protocol {
+        Some("h2") => "HTTP/2".to_string(),
+        Some("h3") | Some("h3-29") => "HTTP/3".to_string(),
+        Some(protocol) if protocol.starts_with("http/") => protocol.to_uppercase(),
+        Some(protocol) => protocol.to_string(),
+        None => String::new(),
+    }
+}
+
+/// Headers can have multiple values, separated by newlines
+fn har_headers(headers: &Headers) -> Vec<HarNameValue> {
+    headers
+        .inner()
+        .as_object()
+        .map(|headers| {
+            headers
+                .iter()
+                .flat_map(|(name, value)| {
+                    value
+                        .as_str()
+                        .unwrap_or_default()
+                        .split('\n')
+                        .map(move |value| HarNameValue {
+                            name: name.clone(),
+                            value: value.to_string(),
+                        })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The cookies of a `Cookie` request header, like `a=1; b=2`
+fn request_cookies(header: &str) -> Vec<HarNameValue> {
+    header.split(';').filter_map(cookie_pair).collect()
+}
+
+/// The cookies of the `Set-Cookie` response headers, which the browser
+/// merges into one value separated by newlines, like
+/// `a=1; Path=/\nb=2; HttpOnly`
+fn response_cookies(header: &str) -> Vec<HarNameValue> {
+    header
+        .split('\n')
+        .filter_map(|cookie| cookie.split(';').next().and_then(cookie_pair))
+        .collect()
+}
+
+fn cookie_pair(cookie: &str) -> Option<HarNameValue> {
+    let mut pair = cookie.splitn(2, '=');
+    let name = pair.next()?.trim();
+    if name.is_empty() {
+        return None;
+    }
+    Some(HarNameValue {
+        name: name.to_string(),
+        value: pair.next().unwrap_or_default().trim().to_string(),
+    })
+}
+
+/// The value of the header with the case insensitive `name`
+fn header_value(headers: &Headers, name: &str) -> Option<String> {
+    headers.inner().as_object().and_then(|headers| {
+        headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .and_then(|(_, value)| value.as_str())
+            .map(ToString::to_string)
+    })
+}
+
+/// Formats the seconds since epoch as UTC date time, like
+/// `2020-11-15T09:30:12.345Z`
+fn iso_8601(secs_since_epoch: f64) -> String {
+    let millis = (secs_since_epoch * 1000.) as i64;
+    let days = millis.div_euclid(86_400_000);
+    let millis_of_day = millis.rem_euclid(86_400_000);
+
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        millis_of_day / 3_600_000,
+        millis_of_day / 60_000 % 60,
+        millis_of_day / 1000 % 60,
+        millis_of_day % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(name: &str, value: &str) -> HarNameValue {
+        HarNameValue {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_request_cookies() {
+        assert_eq!(
+            request_cookies("a=1; b=x=y;  c"),
+            vec![pair("a", "1"), pair("b", "x=y"), pair("c", "")]
+        );
+    }
+
+    #[test]
+    fn parses_response_cookies() {
+        assert_eq!(
+            response_cookies("a=1; Path=/; Secure\nb=2; HttpOnly"),
+            vec![pair("a", "1"), pair("b", "2")]
+        );
+    }
+}
@@ -0,0 +1,238 @@
+//! Caches the responses of a page on disk, so repeated runs of a scraper are
+//! served from the cache instead of the network.
+//!
+//! Requests that match the pattern of [`Page::cache_responses`] are
+//! intercepted via the `Fetch` domain. Responses of `GET` requests with a
+//! `2xx` status are stored in the cache directory, keyed by their url and the
+//! request headers named by their `Vary` header. Subsequent requests for the
+//! same url are fulfilled from the cache, also in later runs and without a
+//! network connection.
+//!
+//! [`Page::cache_responses`]: crate::Page::cache_responses
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::http_cache::ResponseCache;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     page.cache_responses("https://example.com/*", ResponseCache::new(".scrape-cache"))
+//!         .await?;
+//!     // only hits the network the first time
+//!     page.goto("https://example.com/products?page=1").await?;
+//!     # Ok(())
+//! # }
+//! ```
+use std::fs;
+use std::hash::Hasher;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use fnv::FnvHasher;
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+
+use chromiumoxide_cdp::cdp::browser_protocol::fetch::HeaderEntry;
+use chromiumoxide_cdp::cdp::browser_protocol::network::Request;
+
+use crate::error::Result;
+use crate::interception::{InterceptResponse, InterceptedRequest};
+use crate::utils;
+
+/// Serializes the updates of the entry files
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Response headers that don't apply to the decoded body that is cached
+const SKIPPED_HEADERS: [&str; 3] = ["content-encoding", "content-length", "transfer-encoding"];
+
+/// A cache of responses in a directory on disk.
+///
+/// Every cached url is stored in its own file, so the cache can be shared
+/// between pages and runs. Entries never expire, remove them with
+/// [`ResponseCache::clear`] or by deleting the directory.
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    dir: PathBuf,
+}
+
+impl ResponseCache {
+    /// A cache that stores its entries in `dir`, which is created once the
+    /// first response is stored
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// The directory of the cache
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Removes all the cached responses
+    pub fn clear(&self) -> io::Result<()> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fulfills the paused `request` from the cache if possible, otherwise
+    /// continues it and stores its response once it is paused at the
+    /// response stage
+    pub(crate) async fn handle(&self, request: InterceptedRequest) -> Result<()> {
+        if request.request().method != "GET" {
+            return request.continue_request().await;
+        }
+        if request.is_response_stage() {
+            if let Err(err) = self.store(&request).await {
+                log::debug!(
+                    "Failed to cache the response of {}: {}",
+                    request.request().url,
+                    err
+                );
+            }
+            return request.continue_request().await;
+        }
+        match self.lookup(request.request()).await {
+            Some(response) => {
+                log::debug!("Serving {} from the cache", request.request().url);
+                request.fulfill(response).await
+            }
+            None => request.continue_request().await,
+        }
+    }
+
+    /// The cached response to the `request`, if any
+    async fn lookup(&self, request: &Request) -> Option<InterceptResponse> {
+        let path = self.path_of(request);
+        let entries = utils::spawn_blocking(move || read_entries(&path)).await;
+        let entry = entries
+            .into_iter()
+            .find(|entry| entry.url == request.url && entry.matches(request))?;
+        let body = base64::decode(&entry.body).ok()?;
+        let mut response = InterceptResponse::new(entry.status).body(body);
+        for (name, value) in entry.headers {
+            response = response.header(name, value);
+        }
+        Some(response)
+    }
+
+    /// Stores the response of the `request` that is paused at the response
+    /// stage, if it is cacheable
+    async fn store(&self, request: &InterceptedRequest) -> Result<()> {
+        let status = request.response_status_code().unwrap_or_default();
+        if !(200..300).contains(&status) || status == 204 || status == 206 {
+            return Ok(());
+        }
+        let headers = request.response_headers().unwrap_or_default();
+        let vary = match header(headers, "vary") {
+            Some(vary) if vary.trim() == "*" => return Ok(()),
+            Some(vary) => vary
+                .split(',')
+                .map(|name| name.trim().to_ascii_lowercase())
+                .filter(|name| !name.is_empty())
+                .map(|name| {
+                    let value = request_header(request.request(), &name);
+                    (name, value)
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        let body = request.response_body().await?;
+        let entry = CacheEntry {
+            url: request.request().url.clone(),
+            vary,
+            status,
+            headers: headers
+                .iter()
+                .filter(|h| !SKIPPED_HEADERS.contains(&h.name.to_ascii_lowercase().as_str()))
+                .map(|h| (h.name.clone(), h.value.clone()))
+                .collect(),
+            body: base64::encode(body),
+        };
+
+        let dir = self.dir.clone();
+        let path = self.path_of(request.request());
+        utils::spawn_blocking(move || {
+            // the entries of a url are read, updated and written as a whole,
+            // which is serialized within this process. Concurrent runs may
+            // still overwrite each other's entries for the same url, the
+            // response is then fetched again by the next run
+            let _guard = STORE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+            let mut entries = read_entries(&path);
+            entries.retain(|e| e.url != entry.url || e.vary != entry.vary);
+            entries.push(entry);
+            fs::create_dir_all(&dir)?;
+            // write to a unique temporary file first and move it in place, so
+            // that readers never see a partially written file
+            let mut tmp = NamedTempFile::new_in(&dir)?;
+            tmp.write_all(&serde_json::to_vec(&entries)?)?;
+            tmp.persist(path).map_err(|err| err.error)?;
+            Ok::<_, io::Error>(())
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// The file of the entries for the url of the `request`
+    fn path_of(&self, request: &Request) -> PathBuf {
+        let mut hasher = FnvHasher::default();
+        hasher.write(request.url.as_bytes());
+        self.dir.join(format!("{:016x}.json", hasher.finish()))
+    }
+}
+
+/// A cached response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    url: String,
+    /// The lowercase names of the request headers named by the `Vary` header
+    /// of the response and their values
+    vary: Vec<(String, Option<String>)>,
+    status: i64,
+    headers: Vec<(String, String)>,
+    /// The base64 encoded body
+    body: String,
+}
+
+impl CacheEntry {
+    /// Whether the response applies to the `request`, according to its
+    /// `Vary` header
+    fn matches(&self, request: &Request) -> bool {
+        self.vary
+            .iter()
+            .all(|(name, value)| request_header(request, name) == *value)
+    }
+}
+
+/// The entries stored in the file at `path`, an unreadable file counts as
+/// empty
+fn read_entries(path: &Path) -> Vec<CacheEntry> {
+    fs::read(path)
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+/// The value of the response header `name`, ignoring its case
+fn header<'a>(headers: &'a [HeaderEntry], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|h| h.name.eq_ignore_ascii_case(name))
+        .map(|h| h.value.as_str())
+}
+
+/// The value of the request header `name`, ignoring its case
+fn request_header(request: &Request, name: &str) -> Option<String> {
+    request
+        .headers
+        .inner()
+        .as_object()?
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .and_then(|(_, value)| value.as_str())
+        .map(str::to_string)
+}
@@ -0,0 +1,223 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chromiumoxide_cdp::cdp::browser_protocol::input::{
+    DispatchMouseEventParams, DispatchMouseEventType, MouseButton,
+};
+
+use crate::error::Result;
+use crate::handler::PageInner;
+use crate::keys;
+use crate::layout::Point;
+
+/// A virtual keyboard of a `Page` that keeps track of the currently pressed
+/// modifier keys.
+///
+/// # Example
+///
+/// Select all the text of the focused input and replace it
+///
+/// ```no_run
+/// # use chromiumoxide::page::Page;
+/// # use chromiumoxide::error::Result;
+/// # async fn demo(page: Page) -> Result<()> {
+///     let mut keyboard = page.keyboard();
+///     keyboard.down("Control").await?;
+///     keyboard.press("a").await?;
+///     keyboard.up("Control").await?;
+///     keyboard.type_str("replaced").await?;
+///     # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Keyboard {
+    page: Arc<PageInner>,
+    /// The bitmask of the currently pressed modifier keys
+    modifiers: i64,
+}
+
+impl Keyboard {
+    pub(crate) fn new(page: Arc<PageInner>) -> Self {
+        Self { page, modifiers: 0 }
+    }
+
+    /// The bitmask of the currently pressed modifiers, see
+    /// `keys::MODIFIER_*`
+    pub fn modifiers(&self) -> i64 {
+        self.modifiers
+    }
+
+    /// Dispatches a `keydown` event, modifier keys stay pressed until
+    /// released via `Keyboard::up`.
+    pub async fn down(&mut self, key: impl AsRef<str>) -> Result<&mut Self> {
+        let key = key.as_ref();
+        self.page.key_down(key, self.modifiers).await?;
+        self.modifiers |= keys::modifier_bit(key);
+        Ok(self)
+    }
+
+    /// Dispatches a `keyup` event
+    pub async fn up(&mut self, key: impl AsRef<str>) -> Result<&mut Self> {
+        let key = key.as_ref();
+        self.modifiers &= !keys::modifier_bit(key);
+        self.page.key_up(key, self.modifiers).await?;
+        Ok(self)
+    }
+
+    /// Presses and releases the key or combination of keys like `Control+A`
+    /// while the currently held modifiers stay pressed.
+    pub async fn press(&mut self, key: impl AsRef<str>) -> Result<&mut Self> {
+        let keys = keys::split_key_combination(key.as_ref());
+        for key in &keys {
+            self.down(key).await?;
+        }
+        for key in keys.iter().rev() {
+            self.up(key).await?;
+        }
+        Ok(self)
+    }
+
+    /// Inserts the text without emitting any key events
+    pub async fn send_character(&mut self, text: impl Into<String>) -> Result<&mut Self> {
+        self.page.insert_text(text).await?;
+        Ok(self)
+    }
+
+    /// Types each char of the `input` as a separate keystroke
+    pub async fn type_str(&mut self, input: impl AsRef<str>) -> Result<&mut Self> {
+        self.page.type_str(input).await?;
+        Ok(self)
+    }
+
+    /// Types each char of the `input` as a separate keystroke and waits
+    /// `delay` between them
+    pub async fn type_str_with_delay(
+        &mut self,
+        input: impl AsRef<str>,
+        delay: Duration,
+    ) -> Result<&mut Self> {
+        self.page.type_str_with_delay(input, Some(delay)).await?;
+        Ok(self)
+    }
+}
+
+/// A virtual mouse of a `Page` that keeps track of its current position and
+/// the pressed button.
+///
+/// # Example
+///
+/// Drag the mouse from one point to another
+///
+/// ```no_run
+/// # use chromiumoxide::page::Page;
+/// # use chromiumoxide::error::Result;
+/// # use chromiumoxide::layout::Point;
+/// # use chromiumoxide_cdp::cdp::browser_protocol::input::MouseButton;
+/// # async fn demo(page: Page) -> Result<()> {
+///     let mut mouse = page.mouse();
+///     mouse.move_to(Point::new(10., 10.)).await?;
+///     mouse.down(MouseButton::Left).await?;
+///     mouse.move_to_with_steps(Point::new(200., 10.), 10).await?;
+///     mouse.up(MouseButton::Left).await?;
+///     # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Mouse {
+    page: Arc<PageInner>,
+    /// The last position the mouse was moved to
+    position: Point,
+    /// The currently pressed button
+    button: MouseButton,
+}
+
+impl Mouse {
+    pub(crate) fn new(page: Arc<PageInner>) -> Self {
+        Self {
+            page,
+            position: Point::new(0., 0.),
+            button: MouseButton::None,
+        }
+    }
+
+    /// The last position the mouse was moved to
+    pub fn position(&self) -> Point {
+        self.position
+    }
+
+    /// Dispatches a `mousemove` event to the `point`
+    pub async fn move_to(&mut self, point: Point) -> Result<&mut Self> {
+        self.move_to_with_steps(point, 1).await
+    }
+
+    /// Moves the mouse to the `point` in `steps` intermediate `mousemove`
+    /// events
+    pub async fn move_to_with_steps(&mut self, point: Point, steps: usize) -> Result<&mut Self> {
+        let steps = steps.max(1);
+        let start = self.position;
+        for step in 1..=steps {
+            let progress = step as f64 / steps as f64;
+            self.position = Point::new(
+                start.x + (point.x - start.x) * progress,
+                start.y + (point.y - start.y) * progress,
+            );
+            self.dispatch(
+                DispatchMouseEventType::MouseMoved,
+                self.button.clone(),
+                None,
+            )
+            .await?;
+        }
+        Ok(self)
+    }
+
+    /// Dispatches a `mousedown` event at the current position
+    pub async fn down(&mut self, button: MouseButton) -> Result<&mut Self> {
+        self.button = button.clone();
+        self.dispatch(DispatchMouseEventType::MousePressed, button, Some(1))
+            .await?;
+        Ok(self)
+    }
+
+    /// Dispatches a `mouseup` event at the current position
+    pub async fn up(&mut self, button: MouseButton) -> Result<&mut Self> {
+        self.button = MouseButton::None;
+        self.dispatch(DispatchMouseEventType::MouseReleased, button, Some(1))
+            .await?;
+        Ok(self)
+    }
+
+    /// Moves the mouse to the `point` and performs a left click
+    pub async fn click(&mut self, point: Point) -> Result<&mut Self> {
+        self.move_to(point).await?;
+        self.down(MouseButton::Left).await?;
+        self.up(MouseButton::Left).await
+    }
+
+    /// Dispatches a `mousewheel` event at the current position
+    pub async fn wheel(&mut self, delta_x: f64, delta_y: f64) -> Result<&mut Self> {
+        let cmd = DispatchMouseEventParams::builder()
+            .r#type(DispatchMouseEventType::MouseWheel)
+            .x(self.position.x)
+            .y(self.position.y)
+            .delta_x(delta_x)
+            .delta_y(delta_y)
+            .build()
+            .unwrap();
+        self.page.execute(cmd).await?;
+        Ok(self)
+    }
+
+    async fn dispatch(
+        &self,
+        r#type: DispatchMouseEventType,
+        button: MouseButton,
+        click_count: Option<i64>,
+    ) -> Result<()> {
+        let mut cmd = DispatchMouseEventParams::new(r#type, self.position.x, self.position.y);
+        cmd.button = Some(button);
+        cmd.click_count = click_count;
+        self.page.execute(cmd).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,315 @@
+use std::pin::Pin;
+use std::sync::Arc;
+
+use futures::task::{Context, Poll};
+use futures::Stream;
+
+use chromiumoxide_cdp::cdp::browser_protocol::fetch::{
+    AuthChallenge, AuthChallengeResponse, AuthChallengeResponseResponse, ContinueRequestParams,
+    ContinueWithAuthParams, EventAuthRequired, EventRequestPaused, FailRequestParams,
+    FulfillRequestParams, GetResponseBodyParams, HeaderEntry, RequestId,
+    TakeResponseBodyAsStreamParams,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::network::{self, ErrorReason, ResourceType};
+use chromiumoxide_cdp::cdp::browser_protocol::page::FrameId;
+
+use crate::error::Result;
+use crate::handler::PageInner;
+use crate::io_stream::IoStream;
+use crate::listeners::EventStream;
+
+/// A `Stream` over all the requests that were paused due to an active
+/// request interception of a `Page`.
+///
+/// Every `InterceptedRequest` must be resolved by either continuing,
+/// fulfilling or failing it, otherwise the request stays paused.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct RequestInterception {
+    page: Arc<PageInner>,
+    paused: EventStream<EventRequestPaused>,
+    auth_required: EventStream<EventAuthRequired>,
+}
+
+impl RequestInterception {
+    pub(crate) fn new(
+        page: Arc<PageInner>,
+        paused: EventStream<EventRequestPaused>,
+        auth_required: EventStream<EventAuthRequired>,
+    ) -> Self {
+        Self {
+            page,
+            paused,
+            auth_required,
+        }
+    }
+}
+
+impl Stream for RequestInterception {
+    type Item = InterceptedRequest;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        if let Poll::Ready(Some(ev)) = Pin::new(&mut pin.auth_required).poll_next(cx) {
+            return Poll::Ready(Some(InterceptedRequest {
+                page: Arc::clone(&pin.page),
+                event: InterceptedEvent::AuthRequired(ev),
+            }));
+        }
+        match Pin::new(&mut pin.paused).poll_next(cx) {
+            Poll::Ready(Some(ev)) => Poll::Ready(Some(InterceptedRequest {
+                page: Arc::clone(&pin.page),
+                event: InterceptedEvent::Paused(ev),
+            })),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum InterceptedEvent {
+    /// `Fetch.requestPaused`
+    Paused(Arc<EventRequestPaused>),
+    /// `Fetch.authRequired`
+    AuthRequired(Arc<EventAuthRequired>),
+}
+
+/// A request that was paused by the browser and waits for a decision on how
+/// to proceed.
+#[derive(Debug)]
+pub struct InterceptedRequest {
+    page: Arc<PageInner>,
+    event: InterceptedEvent,
+}
+
+impl InterceptedRequest {
+    /// The identifier of the paused request
+    pub fn request_id(&self) -> &RequestId {
+        match &self.event {
+            InterceptedEvent::Paused(ev) => &ev.request_id,
+            InterceptedEvent::AuthRequired(ev) => &ev.request_id,
+        }
+    }
+
+    /// The details of the request
+    pub fn request(&self) -> &network::Request {
+        match &self.event {
+            InterceptedEvent::Paused(ev) => &ev.request,
+            InterceptedEvent::AuthRequired(ev) => &ev.request,
+        }
+    }
+
+    /// The id of the frame that initiated the request
+    pub fn frame_id(&self) -> &FrameId {
+        match &self.event {
+            InterceptedEvent::Paused(ev) => &ev.frame_id,
+            InterceptedEvent::AuthRequired(ev) => &ev.frame_id,
+        }
+    }
+
+    /// How the requested resource will be used
+    pub fn resource_type(&self) -> &ResourceType {
+        match &self.event {
+            InterceptedEvent::Paused(ev) => &ev.resource_type,
+            InterceptedEvent::AuthRequired(ev) => &ev.resource_type,
+        }
+    }
+
+    /// The `Fetch.requestPaused` event, if this request was not paused due to
+    /// an auth challenge
+    pub fn paused_event(&self) -> Option<&EventRequestPaused> {
+        match &self.event {
+            InterceptedEvent::Paused(ev) => Some(ev),
+            InterceptedEvent::AuthRequired(_) => None,
+        }
+    }
+
+    /// The auth challenge that needs to be answered with
+    /// [`InterceptedRequest::continue_with_auth`], if any
+    pub fn auth_challenge(&self) -> Option<&AuthChallenge> {
+        match &self.event {
+            InterceptedEvent::Paused(_) => None,
+            InterceptedEvent::AuthRequired(ev) => Some(&ev.auth_challenge),
+        }
+    }
+
+    /// Whether the request was paused at the response stage, after the
+    /// response headers were received, see `RequestStage::Response`
+    pub fn is_response_stage(&self) -> bool {
+        self.paused_event()
+            .map(|ev| ev.response_status_code.is_some() || ev.response_error_reason.is_some())
+            .unwrap_or_default()
+    }
+
+    /// The status code of the response, if the request was paused at the
+    /// response stage
+    pub fn response_status_code(&self) -> Option<i64> {
+        self.paused_event()?.response_status_code
+    }
+
+    /// The headers of the response, if the request was paused at the
+    /// response stage
+    pub fn response_headers(&self) -> Option<&[HeaderEntry]> {
+        self.paused_event()?.response_headers.as_deref()
+    }
+
+    /// Returns the decoded body of a request that was paused at the response
+    /// stage, once it was received completely.
+    ///
+    /// Unlike [`InterceptedRequest::take_response_body`] the request can
+    /// still be continued unmodified afterwards.
+    pub async fn response_body(&self) -> Result<Vec<u8>> {
+        let resp = self
+            .page
+            .execute(GetResponseBodyParams::new(self.request_id().clone()))
+            .await?
+            .result;
+        if resp.base64_encoded {
+            Ok(base64::decode(&resp.body)?)
+        } else {
+            Ok(resp.body.into_bytes())
+        }
+    }
+
+    /// Takes the body of a request that was paused at the response stage as
+    /// a stream, that is read in chunks via `IO.read`.
+    ///
+    /// The body is read while it is still being received, so server-sent
+    /// events and large downloads can be consumed incrementally, without
+    /// waiting for the complete response.
+    ///
+    /// The request can't be continued unmodified afterwards, it needs to be
+    /// fulfilled or failed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::cdp::browser_protocol::fetch::{RequestPattern, RequestStage};
+    /// # use chromiumoxide::cdp::browser_protocol::network::ErrorReason;
+    /// # use futures::{AsyncReadExt, StreamExt};
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let pattern = RequestPattern::builder()
+    ///         .url_pattern("*/events")
+    ///         .request_stage(RequestStage::Response)
+    ///         .build();
+    ///     let mut requests = page.enable_request_interception(vec![pattern]).await?;
+    ///     if let Some(request) = requests.next().await {
+    ///         let mut body = request.take_response_body().await?;
+    ///         let mut chunk = vec![0; 4096];
+    ///         loop {
+    ///             let n = body.read(&mut chunk).await?;
+    ///             if n == 0 {
+    ///                 break;
+    ///             }
+    ///             println!("{}", String::from_utf8_lossy(&chunk[..n]));
+    ///         }
+    ///         request.fail(ErrorReason::Aborted).await?;
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn take_response_body(&self) -> Result<IoStream> {
+        let stream = self
+            .page
+            .execute(TakeResponseBodyAsStreamParams::new(
+                self.request_id().clone(),
+            ))
+            .await?
+            .result
+            .stream;
+        Ok(IoStream::page(Arc::clone(&self.page), stream))
+    }
+
+    /// Continue the request unmodified.
+    ///
+    /// For auth challenges this defers to the browser's default behavior.
+    pub async fn continue_request(self) -> Result<()> {
+        if self.auth_challenge().is_some() {
+            let response = AuthChallengeResponse::new(AuthChallengeResponseResponse::Default);
+            return self.continue_with_auth(response).await;
+        }
+        let params = ContinueRequestParams::new(self.request_id().clone());
+        self.page.execute(params).await?;
+        Ok(())
+    }
+
+    /// Continue the request with modified url, method, post data or headers.
+    ///
+    /// The `request_id` of `params` is replaced by the id of this request.
+    pub async fn continue_with(self, mut params: ContinueRequestParams) -> Result<()> {
+        params.request_id = self.request_id().clone();
+        self.page.execute(params).await?;
+        Ok(())
+    }
+
+    /// Provide a response to the request without sending it to the network
+    pub async fn fulfill(self, response: InterceptResponse) -> Result<()> {
+        let mut params = FulfillRequestParams::new(self.request_id().clone(), response.status);
+        if !response.headers.is_empty() {
+            params.response_headers = Some(response.headers);
+        }
+        if let Some(body) = response.body {
+            params.body = Some(base64::encode(body).into());
+        }
+        params.response_phrase = response.phrase;
+        self.page.execute(params).await?;
+        Ok(())
+    }
+
+    /// Abort the request with the given reason
+    pub async fn fail(self, reason: ErrorReason) -> Result<()> {
+        let params = FailRequestParams::new(self.request_id().clone(), reason);
+        self.page.execute(params).await?;
+        Ok(())
+    }
+
+    /// Answer an auth challenge via `Fetch.continueWithAuth`
+    pub async fn continue_with_auth(self, response: AuthChallengeResponse) -> Result<()> {
+        let params = ContinueWithAuthParams::new(self.request_id().clone(), response);
+        self.page.execute(params).await?;
+        Ok(())
+    }
+}
+
+/// The response to fulfill an `InterceptedRequest` with
+#[derive(Debug, Clone, Default)]
+pub struct InterceptResponse {
+    /// The HTTP response code
+    pub status: i64,
+    /// The response headers
+    pub headers: Vec<HeaderEntry>,
+    /// The raw, not yet encoded body of the response
+    pub body: Option<Vec<u8>>,
+    /// A textual representation of the status code, like `OK`
+    pub phrase: Option<String>,
+}
+
+impl InterceptResponse {
+    pub fn new(status: i64) -> Self {
+        Self {
+            status,
+            ..Default::default()
+        }
+    }
+
+    /// Add a response header
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push(HeaderEntry::new(name, value));
+        self
+    }
+
+    /// Set the body of the response
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Set the status text of the response
+    pub fn phrase(mut self, phrase: impl Into<String>) -> Self {
+        self.phrase = Some(phrase.into());
+        self
+    }
+}
@@ -0,0 +1,274 @@
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use futures::channel::mpsc::Sender;
+use futures::channel::oneshot::channel as oneshot_channel;
+use futures::future::BoxFuture;
+use futures::io::AsyncRead;
+use futures::task::{Context, Poll};
+use futures::{FutureExt, SinkExt};
+
+use chromiumoxide_cdp::cdp::browser_protocol::io::{
+    CloseParams, ReadParams, ReadReturns, StreamHandle,
+};
+use chromiumoxide_types::Command;
+
+use crate::cmd::{to_command_response, CommandMessage};
+use crate::error::{CdpError, Result};
+use crate::handler::target::TargetMessage;
+use crate::handler::{HandlerMessage, PageInner};
+
+/// Where the stream was opened, streams are only valid within the session
+/// they were returned in
+#[derive(Debug, Clone)]
+enum Session {
+    Page(Arc<PageInner>),
+    Browser(Sender<HandlerMessage>),
+}
+
+impl Session {
+    async fn execute<T: Command>(self, cmd: T) -> Result<T::Response> {
+        match self {
+            Session::Page(page) => Ok(page.execute(cmd).await?.result),
+            Session::Browser(mut sender) => {
+                let (tx, rx) = oneshot_channel();
+                let method = cmd.identifier();
+                let msg = CommandMessage::new(cmd, tx)?;
+                sender.send(HandlerMessage::Command(msg)).await?;
+                let resp = rx.await??;
+                Ok(to_command_response::<T>(resp, method)?.result)
+            }
+        }
+    }
+
+    /// Sends the command without waiting for its response, or for room in
+    /// the channel, so it can be used where nothing can be awaited.
+    ///
+    /// Nobody awaits the response, so the command is discarded if it is
+    /// queued because of `max_commands_in_flight`.
+    fn send<T: Command>(self, cmd: T) -> Result<()> {
+        let (tx, _) = oneshot_channel();
+        match self {
+            Session::Page(page) => {
+                let msg = CommandMessage::with_session(cmd, tx, Some(page.current_session_id()))?;
+                page.sender()
+                    .clone()
+                    .try_send(TargetMessage::Command(msg))
+                    .map_err(|err| err.into_send_error())?;
+            }
+            Session::Browser(mut sender) => {
+                let msg = CommandMessage::new(cmd, tx)?;
+                sender
+                    .try_send(HandlerMessage::Command(msg))
+                    .map_err(|err| err.into_send_error())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+enum ReadState {
+    /// Waiting for the next call of `poll_read`
+    Idle,
+    /// Waiting for the next chunk via `IO.read`
+    Reading(BoxFuture<'static, Result<ReadReturns>>),
+    /// Waiting for `IO.close` after the last chunk
+    Closing(BoxFuture<'static, Result<()>>),
+    /// The stream is exhausted and closed
+    Done,
+}
+
+/// Reads the data of a stream of the browser, like a pdf or a trace that was
+/// returned with `transferMode: ReturnAsStream`, chunk by chunk via
+/// `IO.read`.
+///
+/// The stream is closed via `IO.close` once it is exhausted or dropped.
+///
+/// # Example
+///
+/// ```no_run
+/// # use chromiumoxide::page::Page;
+/// # use chromiumoxide::error::Result;
+/// # use chromiumoxide::cdp::browser_protocol::page::PrintToPdfParams;
+/// # use futures::AsyncReadExt;
+/// # async fn demo(page: Page) -> Result<()> {
+///     let mut pdf = page.pdf_stream(PrintToPdfParams::default()).await?;
+///     let mut chunk = vec![0; 64 * 1024];
+///     loop {
+///         let n = pdf.read(&mut chunk).await?;
+///         if n == 0 {
+///             break;
+///         }
+///         // process &chunk[..n]
+///     }
+///     # Ok(())
+/// # }
+/// ```
+pub struct IoStream {
+    session: Session,
+    handle: StreamHandle,
+    /// The decoded data of the current chunk
+    chunk: Vec<u8>,
+    /// How much of the current chunk was already read
+    pos: usize,
+    /// Whether the browser reported the end of the stream
+    eof: bool,
+    state: ReadState,
+}
+
+impl IoStream {
+    pub(crate) fn page(page: Arc<PageInner>, handle: StreamHandle) -> Self {
+        Self::new(Session::Page(page), handle)
+    }
+
+    pub(crate) fn browser(sender: Sender<HandlerMessage>, handle: StreamHandle) -> Self {
+        Self::new(Session::Browser(sender), handle)
+    }
+
+    fn new(session: Session, handle: StreamHandle) -> Self {
+        Self {
+            session,
+            handle,
+            chunk: Vec::new(),
+            pos: 0,
+            eof: false,
+            state: ReadState::Idle,
+        }
+    }
+
+    /// The handle of the stream in the browser
+    pub fn handle(&self) -> &StreamHandle {
+        &self.handle
+    }
+
+    /// Reads the remaining content of the stream and closes it
+    pub async fn into_bytes(mut self) -> Result<Vec<u8>> {
+        let mut data = self.chunk.split_off(self.pos);
+        match std::mem::replace(&mut self.state, ReadState::Idle) {
+            ReadState::Idle => {}
+            ReadState::Reading(fut) => {
+                // the chunk that was requested by the last `poll_read`
+                let chunk = fut.await?;
+                self.eof = chunk.eof;
+                data.extend(decode(chunk)?);
+            }
+            ReadState::Closing(fut) => {
+                self.state = ReadState::Done;
+                fut.await?;
+                return Ok(data);
+            }
+            ReadState::Done => {
+                self.state = ReadState::Done;
+                return Ok(data);
+            }
+        }
+        while !self.eof {
+            let chunk = self
+                .session
+                .clone()
+                .execute(ReadParams::new(self.handle.clone()))
+                .await?;
+            self.eof = chunk.eof;
+            data.extend(decode(chunk)?);
+        }
+        self.state = ReadState::Done;
+        self.session
+            .clone()
+            .execute(CloseParams::new(self.handle.clone()))
+            .await?;
+        Ok(data)
+    }
+}
+
+/// The data of the chunk
+fn decode(chunk: ReadReturns) -> Result<Vec<u8>> {
+    if chunk.base64_encoded.unwrap_or_default() {
+        Ok(base64::decode(&chunk.data)?)
+    } else {
+        Ok(chunk.data.into_bytes())
+    }
+}
+
+fn to_io_error(err: CdpError) -> io::Error {
+    match err {
+        CdpError::Io(err) => err,
+        err => io::Error::other(err),
+    }
+}
+
+impl AsyncRead for IoStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let pin = self.get_mut();
+        loop {
+            if pin.pos < pin.chunk.len() {
+                let n = buf.len().min(pin.chunk.len() - pin.pos);
+                buf[..n].copy_from_slice(&pin.chunk[pin.pos..pin.pos + n]);
+                pin.pos += n;
+                return Poll::Ready(Ok(n));
+            }
+            match &mut pin.state {
+                ReadState::Idle => {
+                    let session = pin.session.clone();
+                    let handle = pin.handle.clone();
+                    pin.state = if pin.eof {
+                        ReadState::Closing(
+                            async move { session.execute(CloseParams::new(handle)).await.map(drop) }
+                                .boxed(),
+                        )
+                    } else {
+                        ReadState::Reading(session.execute(ReadParams::new(handle)).boxed())
+                    };
+                }
+                ReadState::Reading(fut) => match fut.poll_unpin(cx) {
+                    Poll::Ready(Ok(chunk)) => {
+                        pin.state = ReadState::Idle;
+                        pin.eof = chunk.eof;
+                        pin.chunk = decode(chunk).map_err(to_io_error)?;
+                        pin.pos = 0;
+                    }
+                    Poll::Ready(Err(err)) => {
+                        pin.state = ReadState::Idle;
+                        return Poll::Ready(Err(to_io_error(err)));
+                    }
+                    Poll::Pending => return Poll::Pending,
+                },
+                ReadState::Closing(fut) => match fut.poll_unpin(cx) {
+                    Poll::Ready(res) => {
+                        pin.state = ReadState::Done;
+                        return Poll::Ready(res.map(|_| 0).map_err(to_io_error));
+                    }
+                    Poll::Pending => return Poll::Pending,
+                },
+                ReadState::Done => return Poll::Ready(Ok(0)),
+            }
+        }
+    }
+}
+
+impl Drop for IoStream {
+    fn drop(&mut self) {
+        if !matches!(self.state, ReadState::Done) {
+            // release the stream in the browser, without a runtime to spawn
+            // a task on the response is not awaited
+            let _ = self
+                .session
+                .clone()
+                .send(CloseParams::new(self.handle.clone()));
+        }
+    }
+}
+
+impl std::fmt::Debug for IoStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IoStream")
+            .field("handle", &self.handle)
+            .field("pos", &self.pos)
+            .field("eof", &self.eof)
+            .finish()
+    }
+}
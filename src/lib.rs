@@ -61,21 +61,65 @@
 
 #![warn(missing_debug_implementations, rust_2018_idioms)]
 
+pub(crate) mod binding;
 pub mod browser;
 pub(crate) mod cmd;
 pub mod conn;
+pub mod console;
+pub mod device;
+pub mod dialog;
+pub mod dom;
+pub mod download;
 pub mod element;
 pub mod error;
+pub mod extension;
+#[cfg(feature = "fetcher")]
+pub mod fetcher;
+pub mod file_chooser;
 pub mod handler;
+pub mod har;
+#[cfg(feature = "http-cache")]
+pub mod http_cache;
+pub mod input;
+pub mod interception;
+pub mod io_stream;
 pub mod keys;
 pub mod layout;
+pub mod listeners;
+pub mod metrics;
+pub mod network_conditions;
 pub mod page;
+pub mod pdf;
+pub(crate) mod pipe;
+pub mod pool;
+pub mod replay;
+pub mod response;
+pub mod retry;
+pub mod route;
+pub mod scrape;
+pub mod screencast;
+pub mod snapshot;
+#[cfg(feature = "stealth")]
+pub mod stealth;
+#[cfg(feature = "stitch")]
+pub(crate) mod stitch;
+pub mod storage;
+pub mod tracing;
+pub(crate) mod utils;
+#[cfg(feature = "visual-diff")]
+pub mod visual_diff;
+pub mod webauthn;
+pub mod websocket;
+pub mod wire_log;
+pub mod worker;
 
-pub use crate::browser::{Browser, BrowserConfig};
-pub use crate::conn::Connection;
+pub use crate::browser::{Browser, BrowserConfig, BrowserContext, BrowserEvent, HeadlessMode};
+pub use crate::conn::{Connection, ConnectionEvent};
 pub use crate::element::Element;
-pub use crate::handler::Handler;
+pub use crate::handler::network::Credentials;
+pub use crate::handler::{Handler, HandlerConfig, HandlerStats};
 pub use crate::page::Page;
+pub use crate::worker::Worker;
 
 /// reexport all the generated cdp types
 pub use chromiumoxide_cdp::cdp;
@@ -0,0 +1,157 @@
+use std::any::Any;
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use futures::channel::mpsc::{UnboundedReceiver, UnboundedSender};
+use futures::task::{Context, Poll};
+use futures::Stream;
+
+use chromiumoxide_cdp::cdp::CdpEventMessage;
+use chromiumoxide_types::EventKind;
+
+/// A type erased event that is shared between all the listeners of that event
+pub(crate) type SharedEvent = Arc<dyn Any + Send + Sync>;
+
+/// Request to subscribe to all events with the `method` identifier
+#[derive(Debug)]
+pub(crate) struct EventListenerRequest {
+    /// The identifier of the event, like `Page.loadEventFired`
+    pub method: Cow<'static, str>,
+    /// The sender half of the subscriber's channel
+    pub sender: UnboundedSender<SharedEvent>,
+    /// How many of the most recent buffered events to send to the subscriber
+    /// before the new ones
+    pub replay: usize,
+}
+
+impl EventListenerRequest {
+    pub fn new<T: EventKind>(sender: UnboundedSender<SharedEvent>) -> Self {
+        Self::with_replay::<T>(sender, 0)
+    }
+
+    pub fn with_replay<T: EventKind>(sender: UnboundedSender<SharedEvent>, replay: usize) -> Self {
+        Self {
+            method: T::method_id(),
+            sender,
+            replay,
+        }
+    }
+}
+
+/// Keeps track of all the subscribers of events
+#[derive(Debug, Default)]
+pub(crate) struct EventListeners {
+    /// All the subscribed listeners keyed by the identifier of the event
+    listeners: HashMap<Cow<'static, str>, Vec<UnboundedSender<SharedEvent>>>,
+    /// How many of the most recent events of each kind are buffered for
+    /// subscribers that request a replay
+    replay_capacity: usize,
+    /// The most recent events keyed by the identifier of the event, oldest
+    /// first
+    history: HashMap<Cow<'static, str>, VecDeque<SharedEvent>>,
+}
+
+impl EventListeners {
+    /// Creates listeners that buffer the last `replay_capacity` events of
+    /// each kind, `0` disables the buffer
+    pub fn with_replay_capacity(replay_capacity: usize) -> Self {
+        Self {
+            replay_capacity,
+            ..Default::default()
+        }
+    }
+
+    /// Register a new subscriber, replays the buffered events it requested
+    pub fn add_listener(&mut self, req: EventListenerRequest) {
+        if req.replay > 0 {
+            if let Some(history) = self.history.get(&req.method) {
+                let skip = history.len().saturating_sub(req.replay);
+                for ev in history.iter().skip(skip) {
+                    if req.sender.unbounded_send(Arc::clone(ev)).is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+        self.listeners
+            .entry(req.method)
+            .or_default()
+            .push(req.sender);
+    }
+
+    /// Whether there is at least one active subscriber of the event
+    pub fn has_listeners(&self, method: &str) -> bool {
+        self.listeners
+            .get(method)
+            .map(|subscribers| subscribers.iter().any(|tx| !tx.is_closed()))
+            .unwrap_or_default()
+    }
+
+    /// Sends the event to all of its subscribers.
+    ///
+    /// The event is only cloned if there is at least one subscriber or it is
+    /// buffered for replays. Listeners
+    /// whose receiver was dropped are removed.
+    pub fn start_send(&mut self, event: &CdpEventMessage) {
+        let subscribers = self.listeners.get_mut(event.method.as_ref());
+        if subscribers.is_none() && self.replay_capacity == 0 {
+            return;
+        }
+        let ev: SharedEvent = Arc::from(event.params.clone().into_any());
+        if let Some(subscribers) = subscribers {
+            subscribers.retain(|tx| tx.unbounded_send(Arc::clone(&ev)).is_ok());
+            if subscribers.is_empty() {
+                self.listeners.remove(event.method.as_ref());
+            }
+        }
+        if self.replay_capacity > 0 {
+            let history = self.history.entry(event.method.clone()).or_default();
+            if history.len() >= self.replay_capacity {
+                history.pop_front();
+            }
+            history.push_back(ev);
+        }
+    }
+}
+
+/// A `Stream` over all the typed events `T` of a subscription.
+///
+/// The events are shared between all the subscribers of the same event type.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct EventStream<T: EventKind> {
+    events: UnboundedReceiver<SharedEvent>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: EventKind> EventStream<T> {
+    pub(crate) fn new(events: UnboundedReceiver<SharedEvent>) -> Self {
+        Self {
+            events,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: EventKind> Stream for EventStream<T> {
+    type Item = Arc<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        loop {
+            match Stream::poll_next(Pin::new(&mut pin.events), cx) {
+                Poll::Ready(Some(ev)) => {
+                    if let Ok(ev) = ev.downcast::<T>() {
+                        return Poll::Ready(Some(ev));
+                    }
+                    // skip events that can't be restored as `T`
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use futures::task::{Context, Poll};
+use futures::{Future, FutureExt, Stream};
+use futures_timer::Delay;
+
+use chromiumoxide_cdp::cdp::browser_protocol::performance::{GetMetricsParams, Metric};
+
+use crate::error::Result;
+use crate::handler::PageInner;
+
+/// The run-time metrics of a page as reported by `Performance.getMetrics`.
+///
+/// Durations are in seconds and sizes in bytes. Metrics that the browser
+/// doesn't report are `0`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metrics {
+    /// The time the metrics were taken at, in seconds
+    pub timestamp: f64,
+    /// The number of documents in the page
+    pub documents: f64,
+    /// The number of frames in the page
+    pub frames: f64,
+    /// The number of event listeners in the page
+    pub js_event_listeners: f64,
+    /// The number of DOM nodes in the page
+    pub nodes: f64,
+    /// The number of full or partial page layouts
+    pub layout_count: f64,
+    /// The number of style recalculations
+    pub recalc_style_count: f64,
+    /// The combined duration of all page layouts
+    pub layout_duration: f64,
+    /// The combined duration of all style recalculations
+    pub recalc_style_duration: f64,
+    /// The combined duration of the JavaScript execution
+    pub script_duration: f64,
+    /// The combined duration of all tasks the browser performed
+    pub task_duration: f64,
+    /// The used JavaScript heap size
+    pub js_heap_used_size: f64,
+    /// The total JavaScript heap size
+    pub js_heap_total_size: f64,
+    /// All the other metrics by their name, like `LayoutObjects`
+    pub other: HashMap<String, f64>,
+}
+
+impl From<Vec<Metric>> for Metrics {
+    fn from(metrics: Vec<Metric>) -> Self {
+        let mut m = Metrics::default();
+        for metric in metrics {
+            let value = metric.value;
+            match metric.name.as_str() {
+                "Timestamp" => m.timestamp = value,
+                "Documents" => m.documents = value,
+                "Frames" => m.frames = value,
+                "JSEventListeners" => m.js_event_listeners = value,
+                "Nodes" => m.nodes = value,
+                "LayoutCount" => m.layout_count = value,
+                "RecalcStyleCount" => m.recalc_style_count = value,
+                "LayoutDuration" => m.layout_duration = value,
+                "RecalcStyleDuration" => m.recalc_style_duration = value,
+                "ScriptDuration" => m.script_duration = value,
+                "TaskDuration" => m.task_duration = value,
+                "JSHeapUsedSize" => m.js_heap_used_size = value,
+                "JSHeapTotalSize" => m.js_heap_total_size = value,
+                _ => {
+                    m.other.insert(metric.name, value);
+                }
+            }
+        }
+        m
+    }
+}
+
+pub(crate) async fn get_metrics(page: &PageInner) -> Result<Metrics> {
+    let resp = page.execute(GetMetricsParams::default()).await?;
+    Ok(resp.result.metrics.into())
+}
+
+/// A `Stream` over the `Metrics` of a page sampled every `interval`.
+///
+/// The first sample is taken right away, the stream ends once the page is
+/// closed.
+#[must_use = "streams do nothing unless polled"]
+pub struct MetricsStream {
+    page: Arc<PageInner>,
+    interval: Duration,
+    delay: Delay,
+    sample: Option<BoxFuture<'static, Result<Metrics>>>,
+}
+
+impl MetricsStream {
+    pub(crate) fn new(page: Arc<PageInner>, interval: Duration) -> Self {
+        Self {
+            page,
+            interval,
+            delay: Delay::new(Duration::default()),
+            sample: None,
+        }
+    }
+}
+
+impl Stream for MetricsStream {
+    type Item = Result<Metrics>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        loop {
+            if let Some(sample) = pin.sample.as_mut() {
+                return match sample.poll_unpin(cx) {
+                    Poll::Ready(res) => {
+                        pin.sample = None;
+                        pin.delay.reset(pin.interval);
+                        if pin.page.sender().is_closed() {
+                            Poll::Ready(None)
+                        } else {
+                            Poll::Ready(Some(res))
+                        }
+                    }
+                    Poll::Pending => Poll::Pending,
+                };
+            }
+            if Future::poll(Pin::new(&mut pin.delay), cx).is_pending() {
+                return Poll::Pending;
+            }
+            let page = Arc::clone(&pin.page);
+            pin.sample = Some(async move { get_metrics(&page).await }.boxed());
+        }
+    }
+}
+
+impl std::fmt::Debug for MetricsStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MetricsStream")
+            .field("interval", &self.interval)
+            .finish()
+    }
+}
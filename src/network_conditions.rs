@@ -0,0 +1,64 @@
+//! Presets of network conditions to emulate with
+//! `Page::emulate_network_conditions`.
+//!
+//! The values are taken from [puppeteer's network conditions](https://github.com/puppeteer/puppeteer/blob/main/src/common/NetworkConditions.ts)
+
+use chromiumoxide_cdp::cdp::browser_protocol::network::EmulateNetworkConditionsParams;
+
+/// Describes the network a page is connected to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NetworkConditions {
+    /// Whether the page is disconnected from the network
+    pub offline: bool,
+    /// Additional latency of every request in milliseconds
+    pub latency: f64,
+    /// Maximum download throughput in bytes per second, `-1` disables
+    /// throttling
+    pub download_throughput: f64,
+    /// Maximum upload throughput in bytes per second, `-1` disables
+    /// throttling
+    pub upload_throughput: f64,
+}
+
+/// No throttling at all, this disables a previous emulation
+pub const NO_THROTTLING: NetworkConditions = NetworkConditions {
+    offline: false,
+    latency: 0.,
+    download_throughput: -1.,
+    upload_throughput: -1.,
+};
+
+/// The page has no network connection
+pub const OFFLINE: NetworkConditions = NetworkConditions {
+    offline: true,
+    latency: 0.,
+    download_throughput: -1.,
+    upload_throughput: -1.,
+};
+
+/// A slow 3G connection
+pub const SLOW_3G: NetworkConditions = NetworkConditions {
+    offline: false,
+    latency: 400. * 5.,
+    download_throughput: 500. * 1000. / 8. * 0.8,
+    upload_throughput: 500. * 1000. / 8. * 0.8,
+};
+
+/// A fast 3G connection
+pub const FAST_3G: NetworkConditions = NetworkConditions {
+    offline: false,
+    latency: 150. * 3.75,
+    download_throughput: 1.6 * 1000. * 1000. / 8. * 0.9,
+    upload_throughput: 750. * 1000. / 8. * 0.9,
+};
+
+impl From<NetworkConditions> for EmulateNetworkConditionsParams {
+    fn from(conditions: NetworkConditions) -> Self {
+        EmulateNetworkConditionsParams::new(
+            conditions.offline,
+            conditions.latency,
+            conditions.download_throughput,
+            conditions.upload_throughput,
+        )
+    }
+}
@@ -1,26 +1,106 @@
-use std::path::Path;
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use futures::channel::mpsc::unbounded;
 use futures::channel::oneshot::channel as oneshot_channel;
-use futures::SinkExt;
+use futures::{stream, Future, FutureExt, SinkExt, StreamExt};
+use futures_timer::Delay;
 
 use chromiumoxide_cdp::cdp::browser_protocol;
+use chromiumoxide_cdp::cdp::browser_protocol::browser;
+use chromiumoxide_cdp::cdp::browser_protocol::device_orientation::{
+    ClearDeviceOrientationOverrideParams, SetDeviceOrientationOverrideParams,
+};
 use chromiumoxide_cdp::cdp::browser_protocol::dom::*;
+use chromiumoxide_cdp::cdp::browser_protocol::emulation::{
+    ClearIdleOverrideParams, EventVirtualTimeBudgetExpired, MediaFeature,
+    SetCpuThrottlingRateParams, SetEmulatedVisionDeficiencyParams, SetGeolocationOverrideParams,
+    SetIdleOverrideParams, SetLocaleOverrideParams, SetTimezoneOverrideParams,
+    SetTouchEmulationEnabledParams, SetVirtualTimePolicyParams, VirtualTimePolicy,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::fetch::{
+    self, EventAuthRequired, EventRequestPaused, RequestPattern,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::input::{
+    GestureSourceType, SynthesizePinchGestureParams, SynthesizeScrollGestureParams,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::log::EventEntryAdded;
 use chromiumoxide_cdp::cdp::browser_protocol::network::{
-    Cookie, GetCookiesParams, SetUserAgentOverrideParams,
+    ClearBrowserCacheParams, Cookie, EmulateNetworkConditionsParams, EventLoadingFailed,
+    EventLoadingFinished, EventRequestWillBeSent, EventResponseReceived, GetCertificateParams,
+    GetCookiesParams, Headers, LoaderId, RequestId, ResourceType, SetCacheDisabledParams,
+    SetExtraHttpHeadersParams, SetUserAgentOverrideParams,
 };
 use chromiumoxide_cdp::cdp::browser_protocol::page::*;
-use chromiumoxide_cdp::cdp::browser_protocol::target::{ActivateTargetParams, SessionId, TargetId};
+use chromiumoxide_cdp::cdp::browser_protocol::security::{
+    self, EventSecurityStateChanged, SetIgnoreCertificateErrorsParams,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::target::{
+    ActivateTargetParams, GetTargetInfoParams, SessionId, TargetId,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::web_authn::{
+    self, AddVirtualAuthenticatorParams, VirtualAuthenticatorOptions,
+};
 use chromiumoxide_cdp::cdp::js_protocol;
 use chromiumoxide_cdp::cdp::js_protocol::debugger::GetScriptSourceParams;
-use chromiumoxide_cdp::cdp::js_protocol::runtime::{EvaluateParams, RemoteObject, ScriptId};
+use chromiumoxide_cdp::cdp::js_protocol::runtime::{
+    AddBindingParams, EvaluateParams, EventBindingCalled, EventConsoleApiCalled,
+    EventExceptionThrown, RemoteObject, ScriptId,
+};
 use chromiumoxide_types::*;
 
+use crate::binding;
+use crate::console::ConsoleMessages;
+use crate::device::Device;
+use crate::dialog::{DialogPolicy, DialogStream};
+use crate::dom::DomChanges;
+use crate::download::{Download, DownloadManager};
 use crate::element::Element;
 use crate::error::{CdpError, Result};
+use crate::file_chooser::FileChooserStream;
+use crate::handler::frame::{FrameInfo, LifecycleEvent, LifecycleState};
+use crate::handler::network::Credentials;
 use crate::handler::target::TargetMessage;
 use crate::handler::PageInner;
+use crate::handler::{emulation, viewport};
+use crate::har::NetworkRecorder;
+use crate::input::{Keyboard, Mouse};
+use crate::interception::RequestInterception;
+use crate::io_stream::IoStream;
 use crate::layout::Point;
+use crate::listeners::{EventListenerRequest, EventStream};
+use crate::metrics::{self, Metrics, MetricsStream};
+use crate::network_conditions;
+use crate::response::{NavigationResult, Response};
+use crate::retry::RetryPolicy;
+use crate::route::{self, Fixture, Route, Routes};
+use crate::scrape::{self, Link};
+use crate::screencast::Screencast;
+use crate::snapshot::DomSnapshot;
+use crate::storage::Storage;
+use crate::utils;
+#[cfg(feature = "visual-diff")]
+use crate::visual_diff;
+use crate::webauthn::VirtualAuthenticator;
+use crate::websocket::WebSocketEvents;
+
+pub use crate::cmd::CommandHandle;
+/// The vision deficiencies `Page::emulate_vision_deficiency` can simulate
+pub use chromiumoxide_cdp::cdp::browser_protocol::emulation::SetEmulatedVisionDeficiencyType as VisionDeficiency;
+
+/// How often the `wait_for_*` functions check the page
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long the `wait_for_*` functions wait if neither a timeout is passed
+/// nor the page has a default timeout
+const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How many requests `Page::wait_for_response` remembers that finished
+/// before their response was processed
+const MAX_LOADED_BEFORE_RESPONSE: usize = 64;
 
 #[derive(Debug)]
 pub struct Page {
@@ -30,14 +110,125 @@ pub struct Page {
 impl Page {
     /// Execute a command and return the `Command::Response`
     pub async fn execute<T: Command>(&self, cmd: T) -> Result<CommandResponse<T::Response>> {
-        Ok(self.inner.execute(cmd).await?)
+        self.inner.execute(cmd).await
+    }
+
+    /// Sends the command and returns a handle that resolves to its response.
+    ///
+    /// Unlike `Page::execute`, dropping or cancelling the handle discards the
+    /// pending command in the `Handler`, e.g. when the caller gave up on it
+    /// after an external timeout. Commands sent this way are not retried.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide_cdp::cdp::browser_protocol::page::NavigateParams;
+    /// # async fn demo(page: Page, abort: bool) -> Result<()> {
+    ///     let navigation = page
+    ///         .execute_cancellable(NavigateParams::new("https://example.com"))
+    ///         .await?;
+    ///     if abort {
+    ///         navigation.cancel();
+    ///         page.stop_loading().await?;
+    ///     } else {
+    ///         navigation.await?;
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_cancellable<T: Command>(&self, cmd: T) -> Result<CommandHandle<T>> {
+        self.inner.execute_cancellable(cmd).await
+    }
+
+    /// Execute any method of the devtools protocol within the page's session
+    /// and return its raw result.
+    ///
+    /// This is useful for methods that are not generated for the protocol
+    /// version chromiumoxide is built with.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let metrics = page
+    ///         .execute_raw("Performance.getMetrics", serde_json::json!({}))
+    ///         .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_raw(
+        &self,
+        method: impl Into<String>,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        self.inner.execute_raw(method.into(), params).await
+    }
+
+    /// Whether the page supports the protocol `domain`, like `WebAuthn`, to
+    /// feature-detect experimental domains before using them.
+    ///
+    /// Commands the browser does not know fail with
+    /// [`CdpError::NotSupported`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     if page.supports_domain("WebAuthn").await? {
+    ///         page.execute_raw("WebAuthn.enable", serde_json::json!({}))
+    ///             .await?;
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn supports_domain(&self, domain: &str) -> Result<bool> {
+        let res = self
+            .execute_raw("Schema.getDomains", serde_json::json!({}))
+            .await?;
+        let supported = res["domains"]
+            .as_array()
+            .map(|domains| domains.iter().any(|d| d["name"].as_str() == Some(domain)))
+            .unwrap_or_default();
+        Ok(supported)
+    }
+
+    /// Execute a command and return the `Command::Response`, or fail with
+    /// [`CdpError::Timeout`] if chromium doesn't respond within `timeout`.
+    ///
+    /// This overrides the request timeout of the `Handler` for this command.
+    pub async fn execute_with_timeout<T: Command>(
+        &self,
+        cmd: T,
+        timeout: Duration,
+    ) -> Result<CommandResponse<T::Response>> {
+        self.inner.execute_with_timeout(cmd, timeout).await
+    }
+
+    /// Retry the commands of this page that fail with a transient error of
+    /// chromium according to the `policy`, or never if `None`.
+    ///
+    /// This overrides the policy of the `BrowserConfig` and applies to all
+    /// handles of this page.
+    pub fn set_retry_policy(&self, policy: Option<RetryPolicy>) -> &Self {
+        self.inner.set_retry_policy(policy);
+        self
     }
 
     /// This resolves once the navigation finished and the page is loaded.
     ///
     /// This is necessary after an interaction with the page that may trigger a
     /// navigation (`click`, `press_key`) in order to wait until the new browser
-    /// page is loaded
+    /// page is loaded.
+    ///
+    /// If the navigation didn't start yet when this is called, this resolves
+    /// immediately with the current page. Use `Page::click_and_wait` to
+    /// reliably wait for navigations triggered by a click.
     pub async fn wait_for_navigation(&self) -> Result<&Self> {
         self.inner.wait_for_navigation().await?;
         Ok(self)
@@ -47,7 +238,31 @@ impl Page {
     ///
     /// This resolves directly after the requested URL is fully loaded.
     pub async fn goto(&self, params: impl Into<NavigateParams>) -> Result<&Self> {
-        let res = self.execute(params.into()).await?;
+        self.goto_until(params, LifecycleEvent::Load).await
+    }
+
+    /// Navigate directly to the given URL and resolve once the page emitted
+    /// the `wait_until` lifecycle event.
+    ///
+    /// # Example
+    ///
+    /// Navigate and wait until the network is idle
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::handler::frame::LifecycleEvent;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.goto_until("https://example.com", LifecycleEvent::NetworkIdle).await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn goto_until(
+        &self,
+        params: impl Into<NavigateParams>,
+        wait_until: LifecycleEvent,
+    ) -> Result<&Self> {
+        let res = self.inner.navigate(params.into(), wait_until).await?;
         if let Some(err) = res.result.error_text {
             return Err(CdpError::ChromeMessage(err));
         }
@@ -55,172 +270,2066 @@ impl Page {
         Ok(self)
     }
 
-    /// The identifier of the `Target` this page belongs to
-    pub fn target_id(&self) -> &TargetId {
-        self.inner.target_id()
+    /// Navigate directly to the given URL and resolve with the outcome of
+    /// the navigation once the page emitted the `wait_until` lifecycle event.
+    ///
+    /// The navigation is tied to the loader of the document chromium started
+    /// loading for this request, so other navigations that race it, like a
+    /// `location` change via javascript, don't resolve it early.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::handler::frame::LifecycleEvent;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let nav = page.goto_with_result("http://example.com", LifecycleEvent::Load).await?;
+    ///     for redirect in &nav.redirect_chain {
+    ///         println!("{} redirected with {}", redirect.url, redirect.status);
+    ///     }
+    ///     println!("{:?} loaded with {:?}", nav.url, nav.status());
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn goto_with_result(
+        &self,
+        params: impl Into<NavigateParams>,
+        wait_until: LifecycleEvent,
+    ) -> Result<NavigationResult> {
+        let mut requests = self.event_listener::<EventRequestWillBeSent>().await?;
+        let mut responses = self.event_listener::<EventResponseReceived>().await?;
+        let res = self.inner.navigate(params.into(), wait_until).await?;
+        if let Some(err) = res.result.error_text {
+            return Err(CdpError::ChromeMessage(err));
+        }
+
+        let mut result = NavigationResult {
+            loader_id: res.result.loader_id,
+            url: self.url().await?,
+            redirect_chain: Vec::new(),
+            response: None,
+        };
+        let loader_id = match result.loader_id.as_ref() {
+            Some(loader_id) => loader_id,
+            None => return Ok(result),
+        };
+        // the events of the navigation were dispatched before it completed,
+        // the request of the document is identified by the loader's id
+        let is_document = |request_id: &RequestId, loader: &LoaderId| {
+            loader == loader_id && request_id.as_ref() == loader_id.as_ref()
+        };
+        while let Some(Some(ev)) = requests.next().now_or_never() {
+            if is_document(&ev.request_id, &ev.loader_id) {
+                result.redirect_chain.extend(ev.redirect_response.clone());
+            }
+        }
+        while let Some(Some(ev)) = responses.next().now_or_never() {
+            if is_document(&ev.request_id, &ev.loader_id) {
+                result.response = Some(Response::new(Arc::clone(&self.inner), ev));
+            }
+        }
+        Ok(result)
     }
 
-    /// The identifier of the `Session` target of this page is attached to
-    pub fn session_id(&self) -> &SessionId {
-        self.inner.session_id()
+    /// Returns a stream over all the events of type `T` emitted by this page.
+    ///
+    /// # Example
+    ///
+    /// Listen for all the responses the page receives
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide_cdp::cdp::browser_protocol::network::EventResponseReceived;
+    /// # use futures::StreamExt;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let mut responses = page.event_listener::<EventResponseReceived>().await?;
+    ///     while let Some(event) = responses.next().await {
+    ///         println!("{}", event.response.url);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn event_listener<T: EventKind>(&self) -> Result<EventStream<T>> {
+        let (tx, rx) = unbounded();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::AddEventListener(
+                EventListenerRequest::new::<T>(tx),
+            ))
+            .await?;
+        Ok(EventStream::new(rx))
     }
 
-    /// Returns the current url of the page
-    pub async fn url(&self) -> Result<Option<String>> {
-        let (tx, rx) = oneshot_channel();
+    /// Returns a stream over the events of type `T` emitted by this page that
+    /// starts with up to `replay` of the most recent events that fired before
+    /// subscribing.
+    ///
+    /// Events are only buffered if enabled via
+    /// `BrowserConfigBuilder::event_replay_capacity` or
+    /// `HandlerConfig::event_replay_capacity`, otherwise this behaves like
+    /// `Page::event_listener`.
+    ///
+    /// # Example
+    ///
+    /// Get the console messages logged while the page was loading
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide_cdp::cdp::js_protocol::runtime::EventConsoleApiCalled;
+    /// # use futures::StreamExt;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.goto("https://example.com").await?;
+    ///     let mut messages = page
+    ///         .subscribe_with_replay::<EventConsoleApiCalled>(100)
+    ///         .await?;
+    ///     while let Some(message) = messages.next().await {
+    ///         println!("{:?}", message.args);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn subscribe_with_replay<T: EventKind>(
+        &self,
+        replay: usize,
+    ) -> Result<EventStream<T>> {
+        let (tx, rx) = unbounded();
         self.inner
             .sender()
             .clone()
-            .send(TargetMessage::Url(tx))
+            .send(TargetMessage::AddEventListener(
+                EventListenerRequest::with_replay::<T>(tx, replay),
+            ))
             .await?;
-        Ok(rx.await?)
+        Ok(EventStream::new(rx))
     }
 
-    /// Return the main frame of the page
-    pub async fn mainframe(&self) -> Result<Option<FrameId>> {
-        let (tx, rx) = oneshot_channel();
+    /// Pause all requests that match any of the `patterns` and return a
+    /// stream over the intercepted requests.
+    ///
+    /// An empty list of patterns intercepts all requests. Auth challenges are
+    /// reported as `InterceptedRequest` as well and can be answered with
+    /// [`InterceptedRequest::continue_with_auth`].
+    ///
+    /// # Example
+    ///
+    /// Block all images
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide_cdp::cdp::browser_protocol::network::{ErrorReason, ResourceType};
+    /// # use futures::StreamExt;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let mut requests = page.enable_request_interception(vec![]).await?;
+    ///     while let Some(request) = requests.next().await {
+    ///         if request.resource_type() == &ResourceType::Image {
+    ///             request.fail(ErrorReason::BlockedByClient).await?;
+    ///         } else {
+    ///             request.continue_request().await?;
+    ///         }
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`InterceptedRequest::continue_with_auth`]: crate::interception::InterceptedRequest::continue_with_auth
+    pub async fn enable_request_interception(
+        &self,
+        patterns: Vec<RequestPattern>,
+    ) -> Result<RequestInterception> {
+        // subscribe before enabling so that no paused request is missed
+        let paused = self.event_listener::<EventRequestPaused>().await?;
+        let auth_required = self.event_listener::<EventAuthRequired>().await?;
+        let mut enable = fetch::EnableParams::builder().handle_auth_requests(true);
+        if !patterns.is_empty() {
+            enable = enable.patterns(patterns);
+        }
+        self.execute(enable.build()).await?;
+        Ok(RequestInterception::new(
+            Arc::clone(&self.inner),
+            paused,
+            auth_required,
+        ))
+    }
+
+    /// Answer all authentication challenges, of servers (HTTP 401) and proxies
+    /// (HTTP 407), with the `credentials`.
+    ///
+    /// This intercepts all requests via the `Fetch` domain, so this should be
+    /// called before [`Page::enable_request_interception`], otherwise the
+    /// patterns of the interception are reset. Challenges are no longer
+    /// yielded by the `RequestInterception`.
+    pub async fn authenticate(&self, credentials: Credentials) -> Result<&Self> {
         self.inner
             .sender()
             .clone()
-            .send(TargetMessage::MainFrame(tx))
+            .send(TargetMessage::Authenticate(credentials))
             .await?;
-        Ok(rx.await?)
+        self.inner.set_handle_auth_requests();
+        self.enable_routes().await?;
+        Ok(self)
     }
 
-    /// Allows overriding user agent with the given string.
-    pub async fn set_user_agent(
+    /// Disables the request interception, all pending intercepted requests
+    /// are continued.
+    pub async fn disable_request_interception(&self) -> Result<&Self> {
+        self.execute(fetch::DisableParams::default()).await?;
+        Ok(self)
+    }
+
+    /// Fulfill all requests whose url matches the glob `pattern` with the
+    /// `fixture`, instead of sending them to the network.
+    ///
+    /// In the `pattern`, `*` matches zero or more characters and `?` exactly
+    /// one. If several routes match a request, the most recently added one is
+    /// used. Requests are intercepted via the `Fetch` domain, so routes
+    /// conflict with [`Page::enable_request_interception`] and
+    /// [`Page::authenticate`].
+    ///
+    /// See [`Fixture`](crate::route::Fixture) for an example.
+    pub async fn route(&self, pattern: impl Into<String>, fixture: Fixture) -> Result<&Self> {
+        let routes = self.serve_routes().await?;
+        routes
+            .lock()
+            .unwrap()
+            .push(Route::fulfill(pattern.into(), fixture));
+        self.enable_routes().await?;
+        Ok(self)
+    }
+
+    /// Serve the requests whose url matches the glob `pattern` from the
+    /// `cache` and store the responses that are not cached yet.
+    ///
+    /// Patterns are matched like the ones of [`Page::route`], which takes
+    /// precedence if it was added later. The cache is removed again with
+    /// [`Page::unroute`].
+    ///
+    /// See [`ResponseCache`](crate::http_cache::ResponseCache) for an
+    /// example.
+    #[cfg(feature = "http-cache")]
+    pub async fn cache_responses(
         &self,
-        params: impl Into<SetUserAgentOverrideParams>,
+        pattern: impl Into<String>,
+        cache: crate::http_cache::ResponseCache,
     ) -> Result<&Self> {
-        self.execute(params.into()).await?;
+        let routes = self.serve_routes().await?;
+        routes
+            .lock()
+            .unwrap()
+            .push(Route::cache(pattern.into(), cache));
+        self.enable_routes().await?;
         Ok(self)
     }
 
-    /// Returns the root DOM node (and optionally the subtree) of the page.
+    /// Remove all the routes that were added with the `pattern`
+    pub async fn unroute(&self, pattern: impl AsRef<str>) -> Result<&Self> {
+        let routes = match self.inner.routes() {
+            Some(routes) => routes,
+            None => return Ok(self),
+        };
+        routes
+            .lock()
+            .unwrap()
+            .retain(|route| route.resource_type.is_some() || route.pattern != pattern.as_ref());
+        self.enable_routes().await?;
+        Ok(self)
+    }
+
+    /// Fails all requests for resources of the `resource_types`, like images
+    /// or fonts, which speeds up scraping pages considerably. Replaces the
+    /// previously blocked resource types, an empty list unblocks all of them.
     ///
-    /// # Note: This does not return the actual HTML document of the page. To
-    /// retrieve the HTML content of the page see `Page::content`.
-    pub async fn get_document(&self) -> Result<Node> {
-        let resp = self.execute(GetDocumentParams::default()).await?;
-        Ok(resp.result.root)
+    /// Requests are intercepted via the `Fetch` domain like the ones of
+    /// [`Page::route`], a route added later takes precedence over blocking.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::cdp::browser_protocol::network::ResourceType;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.block_resources(vec![
+    ///         ResourceType::Image,
+    ///         ResourceType::Font,
+    ///         ResourceType::Media,
+    ///     ])
+    ///     .await?;
+    ///     page.goto("https://example.com").await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn block_resources(
+        &self,
+        resource_types: impl IntoIterator<Item = ResourceType>,
+    ) -> Result<&Self> {
+        let routes = self.serve_routes().await?;
+        {
+            let mut routes = routes.lock().unwrap();
+            routes.retain(|route| route.resource_type.is_none());
+            // blocking goes first so that routes take precedence
+            let blocked: Vec<_> = resource_types.into_iter().map(Route::block).collect();
+            routes.splice(0..0, blocked);
+        }
+        self.enable_routes().await?;
+        Ok(self)
     }
 
-    /// Returns the first element in the document which matches the given CSS
-    /// selector.
+    /// Blocks all requests whose url matches any of the `patterns`, in which
+    /// `*` matches any number of characters. Replaces the previously blocked
+    /// urls, an empty list unblocks all of them.
     ///
-    /// Execute a query selector on the document's node.
-    pub async fn find_element(&self, selector: impl Into<String>) -> Result<Element> {
-        let root = self.get_document().await?.node_id;
-        let node_id = self.inner.find_element(selector, root).await?;
-        Ok(Element::new(Arc::clone(&self.inner), node_id).await?)
+    /// Unlike [`Page::block_resources`] this doesn't need to intercept the
+    /// requests, the browser blocks them via `Network.setBlockedURLs`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.block_urls(vec!["*.doubleclick.net/*", "*/analytics.js"])
+    ///         .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn block_urls<I, S>(&self, patterns: I) -> Result<&Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let urls: Vec<String> = patterns.into_iter().map(Into::into).collect();
+        // the generated params omit an empty list, which chromium rejects
+        self.execute_raw(
+            "Network.setBlockedURLs",
+            serde_json::json!({ "urls": urls }),
+        )
+        .await?;
+        Ok(self)
     }
 
-    /// Return all `Element`s in the document that match the given selector
-    pub async fn find_elements(&self, selector: impl Into<String>) -> Result<Vec<Element>> {
-        let root = self.get_document().await?.node_id;
-        let node_ids = self.inner.find_elements(selector, root).await?;
-        Ok(Element::from_nodes(&self.inner, &node_ids).await?)
+    /// The routes of the page, starts serving them when called first
+    async fn serve_routes(&self) -> Result<Routes> {
+        if let Some(routes) = self.inner.routes() {
+            return Ok(routes);
+        }
+        // subscribe before enabling so that no paused request is missed
+        let paused = self.event_listener::<EventRequestPaused>().await?;
+        let auth_required = self.event_listener::<EventAuthRequired>().await?;
+        // only create the routes together with the task that serves them, a
+        // concurrent call may have won the race in the meantime
+        let (routes, created) = self.inner.get_or_init_routes();
+        if created {
+            let interception =
+                RequestInterception::new(Arc::clone(&self.inner), paused, auth_required);
+            utils::spawn(route::serve(interception, Arc::clone(&routes)));
+        }
+        Ok(routes)
     }
 
-    /// Describes node given its id
-    pub async fn describe_node(&self, node_id: NodeId) -> Result<Node> {
-        let resp = self
-            .execute(
-                DescribeNodeParams::builder()
-                    .node_id(node_id)
-                    .depth(100)
-                    .build(),
-            )
-            .await?;
-        Ok(resp.result.node)
+    /// Intercepts the requests that match any of the routes, keeps answering
+    /// authentication challenges if credentials are configured
+    async fn enable_routes(&self) -> Result<()> {
+        let (method, params) = self.inner.fetch_command();
+        self.execute_raw(method, params).await?;
+        Ok(())
     }
 
-    pub async fn close(self) {
-        todo!()
+    /// Waits for the first response whose url matches the `url_predicate`.
+    ///
+    /// This resolves once the response was loaded completely, so that its
+    /// body can be retrieved via `Response::body`. Fails with
+    /// [`CdpError::Timeout`] after the page's default timeout, if one is set
+    /// via `Page::set_default_timeout`.
+    ///
+    /// # Example
+    ///
+    /// Capture the payload of an api call triggered by the page
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let response = page.wait_for_response(|url| url.contains("/api/")).await?;
+    ///     let json = response.text().await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn wait_for_response(
+        &self,
+        url_predicate: impl FnMut(&str) -> bool,
+    ) -> Result<Response> {
+        utils::timeout(
+            self.inner.default_timeout(),
+            self.wait_for_response_inner(url_predicate),
+        )
+        .await
+    }
+
+    async fn wait_for_response_inner(
+        &self,
+        mut url_predicate: impl FnMut(&str) -> bool,
+    ) -> Result<Response> {
+        let responses = self
+            .event_listener::<EventResponseReceived>()
+            .await?
+            .map(NetworkEvent::Response);
+        let finished = self
+            .event_listener::<EventLoadingFinished>()
+            .await?
+            .map(|ev| NetworkEvent::Finished(ev.request_id.clone()));
+        let failed = self
+            .event_listener::<EventLoadingFailed>()
+            .await?
+            .map(|ev| NetworkEvent::Finished(ev.request_id.clone()));
+        let mut events = stream::select(responses, stream::select(finished, failed));
+
+        // the responses that match, but are not loaded yet
+        let mut pending = HashMap::new();
+        // the most recent requests that finished before their response was
+        // processed, the events arrive via separate streams
+        let mut loaded = VecDeque::with_capacity(MAX_LOADED_BEFORE_RESPONSE);
+        while let Some(event) = events.next().await {
+            match event {
+                NetworkEvent::Response(ev) => {
+                    let finished = loaded
+                        .iter()
+                        .position(|id| *id == ev.request_id)
+                        .and_then(|idx| loaded.remove(idx))
+                        .is_some();
+                    if url_predicate(&ev.response.url) {
+                        if finished {
+                            return Ok(Response::new(Arc::clone(&self.inner), ev));
+                        }
+                        pending.insert(ev.request_id.clone(), ev);
+                    }
+                }
+                NetworkEvent::Finished(request_id) => {
+                    if let Some(ev) = pending.remove(&request_id) {
+                        return Ok(Response::new(Arc::clone(&self.inner), ev));
+                    }
+                    if loaded.len() == MAX_LOADED_BEFORE_RESPONSE {
+                        loaded.pop_front();
+                    }
+                    loaded.push_back(request_id);
+                }
+            }
+        }
+        Err(CdpError::NotFound)
+    }
+
+    /// Allows downloads of this page and saves them to the `download_path`
+    /// directory.
+    ///
+    /// # Note This applies to all the pages of this page's browser context.
+    pub async fn set_download_path(&self, download_path: impl AsRef<Path>) -> Result<&Self> {
+        let params = GetTargetInfoParams::builder()
+            .target_id(self.target_id().clone())
+            .build();
+        let info = self
+            .inner
+            .execute_browser_command(params)
+            .await?
+            .result
+            .target_info;
+        let mut behavior =
+            browser::SetDownloadBehaviorParams::new(browser::SetDownloadBehaviorBehavior::Allow);
+        behavior.browser_context_id = info.browser_context_id;
+        behavior.download_path = Some(download_path.as_ref().to_string_lossy().into_owned());
+        self.inner.execute_browser_command(behavior).await?;
+        Ok(self)
+    }
+
+    /// Allows downloads to the `download_path` directory and returns a
+    /// `DownloadManager` that tracks the progress of all downloads of this
+    /// page.
+    pub async fn download_manager(
+        &self,
+        download_path: impl Into<PathBuf>,
+    ) -> Result<DownloadManager> {
+        let download_path = download_path.into();
+        let will_begin = self.event_listener::<EventDownloadWillBegin>().await?;
+        let progress = self.event_listener::<EventDownloadProgress>().await?;
+        self.set_download_path(&download_path).await?;
+        Ok(DownloadManager::new(download_path, will_begin, progress))
+    }
+
+    /// Allows downloads to the `download_path` directory and waits until the
+    /// next download of this page completed.
+    ///
+    /// Fails if the download was canceled.
+    ///
+    /// # Note Only downloads that begin after this was called are observed, to
+    /// wait for a download triggered by an action on the page, create the
+    /// `DownloadManager` before:
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let mut downloads = page.download_manager("/tmp/downloads").await?;
+    ///     page.click_selector("a#download").await?;
+    ///     let path = downloads.wait_for_download().await?.path();
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn wait_for_download(&self, download_path: impl Into<PathBuf>) -> Result<Download> {
+        self.download_manager(download_path)
+            .await?
+            .wait_for_download()
+            .await
+    }
+
+    /// Starts recording the requests of the page and their responses, which
+    /// can be exported as HAR.
+    pub async fn record_network(&self) -> Result<NetworkRecorder> {
+        Ok(NetworkRecorder::new(
+            Arc::clone(&self.inner),
+            self.event_listener().await?,
+            self.event_listener().await?,
+            self.event_listener().await?,
+            self.event_listener().await?,
+        ))
+    }
+
+    /// Whether to ignore certificate errors, like expired or self-signed
+    /// certificates, when loading resources.
+    pub async fn set_ignore_certificate_errors(&self, ignore: bool) -> Result<&Self> {
+        self.execute(SetIgnoreCertificateErrorsParams::new(ignore))
+            .await?;
+        Ok(self)
+    }
+
+    /// Returns a stream over the events of all the websockets the page opens,
+    /// like the frames it sends and receives with their decoded payloads.
+    ///
+    /// The `Network` domain is enabled for every page, unless it is
+    /// disabled via [`DomainConfig`](crate::handler::domains::DomainConfig),
+    /// in which case it is enabled by this call if domains are enabled
+    /// lazily.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::websocket::{WebSocketEvent, WebSocketPayload};
+    /// # use chromiumoxide::error::Result;
+    /// # use futures::StreamExt;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let mut events = page.websocket_events().await?;
+    ///     while let Some(event) = events.next().await {
+    ///         if let WebSocketEvent::FrameReceived {
+    ///             payload: WebSocketPayload::Text(text),
+    ///             ..
+    ///         } = event
+    ///         {
+    ///             println!("received {}", text);
+    ///         }
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn websocket_events(&self) -> Result<WebSocketEvents> {
+        Ok(WebSocketEvents::new(
+            self.event_listener().await?,
+            self.event_listener().await?,
+            self.event_listener().await?,
+            self.event_listener().await?,
+            self.event_listener().await?,
+        ))
+    }
+
+    /// Whether to bypass the Content Security Policy of the page, so that
+    /// injected scripts run on sites with a strict policy.
+    ///
+    /// This applies to documents loaded afterwards, so it should be set
+    /// before navigating.
+    pub async fn set_bypass_csp(&self, bypass: bool) -> Result<&Self> {
+        self.execute(SetBypassCspParams::new(bypass)).await?;
+        Ok(self)
+    }
+
+    /// Whether the page may load resources from the browser's cache,
+    /// disabling the cache makes every request hit the network.
+    pub async fn set_cache_enabled(&self, enabled: bool) -> Result<&Self> {
+        self.execute(SetCacheDisabledParams::new(!enabled)).await?;
+        Ok(self)
+    }
+
+    /// Clears the browser's cache of all resources
+    pub async fn clear_browser_cache(&self) -> Result<&Self> {
+        self.execute(ClearBrowserCacheParams::default()).await?;
+        Ok(self)
+    }
+
+    /// Returns a stream over the changes of the security state of the page,
+    /// like loading a page with an invalid certificate.
+    ///
+    /// This enables the `Security` domain.
+    pub async fn security_state_changes(&self) -> Result<EventStream<EventSecurityStateChanged>> {
+        let events = self.event_listener().await?;
+        self.execute(security::EnableParams::default()).await?;
+        Ok(events)
+    }
+
+    /// Returns the certificate chain the browser received for the `origin`,
+    /// like `https://example.com`, leaf certificate first.
+    ///
+    /// Each certificate is DER encoded. The chain is empty if the page did not
+    /// load any resources from the `origin` via a secure connection.
+    pub async fn certificate(&self, origin: impl Into<String>) -> Result<Vec<Vec<u8>>> {
+        self.execute(GetCertificateParams::new(origin))
+            .await?
+            .result
+            .table_names
+            .iter()
+            .map(|cert| Ok(base64::decode(cert)?))
+            .collect()
+    }
+
+    /// Returns a stream over all the messages that are logged to the console
+    /// of this page: calls of the `console` api, entries of the browser's log
+    /// and uncaught exceptions.
+    ///
+    /// The `Runtime` and `Log` domains are enabled for every page, unless
+    /// `Log` is disabled via
+    /// [`DomainConfig`](crate::handler::domains::DomainConfig), in which case
+    /// it is enabled by this call if domains are enabled lazily.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use futures::StreamExt;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let mut messages = page.console_messages().await?;
+    ///     while let Some(msg) = messages.next().await {
+    ///         println!("{:?}: {}", msg.level, msg.text);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn console_messages(&self) -> Result<ConsoleMessages> {
+        let console_api = self.event_listener::<EventConsoleApiCalled>().await?;
+        let log = self.event_listener::<EventEntryAdded>().await?;
+        let exceptions = self.event_listener::<EventExceptionThrown>().await?;
+        Ok(ConsoleMessages::new(console_api, log, exceptions))
+    }
+
+    /// Returns a stream over the changes of the DOM of this page, like
+    /// modified attributes and inserted or removed nodes.
+    ///
+    /// This enables the `DOM` domain and requests the entire document, so
+    /// that the browser reports changes of all nodes.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::dom::DomChange;
+    /// # use chromiumoxide::error::Result;
+    /// # use futures::StreamExt;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let mut changes = page.dom_changes().await?;
+    ///     while let Some(change) = changes.next().await {
+    ///         if let DomChange::AttributeModified { name, value, .. } = change {
+    ///             println!("{} = {}", name, value);
+    ///         }
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn dom_changes(&self) -> Result<DomChanges> {
+        let changes = DomChanges::new(
+            self.event_listener().await?,
+            self.event_listener().await?,
+            self.event_listener().await?,
+            self.event_listener().await?,
+            self.event_listener().await?,
+            self.event_listener().await?,
+        );
+        self.execute(browser_protocol::dom::EnableParams::default())
+            .await?;
+        self.execute(GetDocumentParams::builder().depth(-1).build())
+            .await?;
+        Ok(changes)
+    }
+
+    /// Intercepts the file chooser dialogs of this page and returns a stream
+    /// over them, so that files can be selected programmatically.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use futures::StreamExt;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let mut choosers = page.intercept_file_chooser().await?;
+    ///     page.click_selector("#upload").await?;
+    ///     if let Some(chooser) = choosers.next().await {
+    ///         chooser.accept(vec!["./image.png"]).await?;
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn intercept_file_chooser(&self) -> Result<FileChooserStream> {
+        let events = self.event_listener::<EventFileChooserOpened>().await?;
+        self.execute(SetInterceptFileChooserDialogParams::new(true))
+            .await?;
+        Ok(FileChooserStream::new(Arc::clone(&self.inner), events))
+    }
+
+    /// Stops intercepting file chooser dialogs, they are shown again.
+    pub async fn disable_file_chooser_interception(&self) -> Result<&Self> {
+        self.execute(SetInterceptFileChooserDialogParams::new(false))
+            .await?;
+        Ok(self)
+    }
+
+    /// Returns a stream over all the javascript dialogs this page opens.
+    ///
+    /// While the stream exists, all dialogs must be handled manually.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use futures::StreamExt;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let mut dialogs = page.dialogs().await?;
+    ///     while let Some(dialog) = dialogs.next().await {
+    ///         println!("{}", dialog.message());
+    ///         dialog.accept(Some("answer".to_string())).await?;
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn dialogs(&self) -> Result<DialogStream> {
+        let events = self
+            .event_listener::<EventJavascriptDialogOpening>()
+            .await?;
+        Ok(DialogStream::new(Arc::clone(&self.inner), events))
+    }
+
+    /// Sets how this page handles javascript dialogs while they are not
+    /// handled via `Page::dialogs`. Other subscribers of
+    /// `EventJavascriptDialogOpening` don't affect the policy.
+    ///
+    /// By default all dialogs are accepted, so that they don't block the page.
+    pub async fn set_dialog_policy(&self, policy: DialogPolicy) -> Result<&Self> {
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::SetDialogPolicy(policy))
+            .await?;
+        Ok(self)
+    }
+
+    /// The identifier of the `Target` this page belongs to
+    pub fn target_id(&self) -> &TargetId {
+        self.inner.target_id()
+    }
+
+    /// The identifier of the `Session` target of this page is attached to
+    ///
+    /// This is the session the page was created with, see
+    /// [`Page::current_session_id`] for the session after a reconnect.
+    pub fn session_id(&self) -> &SessionId {
+        self.inner.session_id()
+    }
+
+    /// The identifier of the `Session` target of this page is currently
+    /// attached to.
+    ///
+    /// The target is attached to a new session after the connection was
+    /// re-established, see
+    /// [`ConnectionEvent::Reconnected`](crate::conn::ConnectionEvent::Reconnected).
+    pub fn current_session_id(&self) -> SessionId {
+        self.inner.current_session_id()
+    }
+
+    /// Returns the current url of the page
+    pub async fn url(&self) -> Result<Option<String>> {
+        let (tx, rx) = oneshot_channel();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::Url(tx))
+            .await?;
+        Ok(rx.await?)
+    }
+
+    /// Return the main frame of the page
+    pub async fn mainframe(&self) -> Result<Option<FrameId>> {
+        let (tx, rx) = oneshot_channel();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::MainFrame(tx))
+            .await?;
+        Ok(rx.await?)
+    }
+
+    /// Returns all the frames of the page, including cross-origin iframes
+    /// that run in a separate process.
+    ///
+    /// Only [`Page::evaluate_in_frame`] is routed to the session of an
+    /// out-of-process iframe. The other queries of the page, like
+    /// `find_element`, only cover the frames that run in the page's own
+    /// process.
+    pub async fn frames(&self) -> Result<Vec<FrameInfo>> {
+        let (tx, rx) = oneshot_channel();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::Frames(tx))
+            .await?;
+        Ok(rx.await?)
+    }
+
+    /// Evaluates the expression in the frame, which is routed to the session
+    /// of the frame if it is an out-of-process iframe.
+    ///
+    /// Fails with [`CdpError::FrameNotFound`] if the frame has no document
+    /// that can be evaluated in yet. Objects of out-of-process iframes belong
+    /// to their session, so prefer `return_by_value`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::cdp::js_protocol::runtime::EvaluateParams;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     for frame in page.frames().await? {
+    ///         let title = page
+    ///             .evaluate_in_frame(
+    ///                 frame.id,
+    ///                 EvaluateParams::builder()
+    ///                     .expression("document.title")
+    ///                     .return_by_value(true)
+    ///                     .build()
+    ///                     .unwrap(),
+    ///             )
+    ///             .await?;
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn evaluate_in_frame(
+        &self,
+        frame_id: FrameId,
+        evaluate: impl Into<EvaluateParams>,
+    ) -> Result<RemoteObject> {
+        let (tx, rx) = oneshot_channel();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::FrameExecutionContext(frame_id.clone(), tx))
+            .await?;
+        let context = rx.await?.ok_or(CdpError::FrameNotFound(frame_id))?;
+        let mut params = evaluate.into();
+        params.context_id = Some(context.context_id);
+        let res = match context.session_id {
+            Some(session_id) => self.inner.execute_in_session(params, session_id).await?,
+            None => self.execute(params).await?,
+        };
+        Ok(res.result.result)
+    }
+
+    /// Returns the lifecycle events the current document of the main frame
+    /// emitted so far, `None` if the page has no main frame yet
+    pub async fn lifecycle(&self) -> Result<Option<LifecycleState>> {
+        let (tx, rx) = oneshot_channel();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::Lifecycle(tx))
+            .await?;
+        Ok(rx.await?)
+    }
+
+    /// Waits until the current document of the main frame emitted the
+    /// lifecycle `event`, resolves right away if it already did.
+    ///
+    /// Fails with [`CdpError::Timeout`] after the navigation timeout of the
+    /// page.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::handler::frame::LifecycleEvent;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.click_and_wait("a#next", LifecycleEvent::DomContentLoaded)
+    ///         .await?;
+    ///     // the new document is parsed, wait until its network settled too
+    ///     page.wait_for_lifecycle(LifecycleEvent::NetworkIdle).await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn wait_for_lifecycle(&self, event: LifecycleEvent) -> Result<LifecycleState> {
+        let (tx, rx) = oneshot_channel();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::WaitForLifecycle(event, tx))
+            .await?;
+        utils::timeout(self.inner.navigation_timeout(), async { Ok(rx.await?) }).await
+    }
+
+    /// Allows overriding user agent with the given string.
+    ///
+    /// The `Accept-Language` header and the `navigator.platform` can be
+    /// overridden as well.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide_cdp::cdp::browser_protocol::network::SetUserAgentOverrideParams;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.set_user_agent(
+    ///         SetUserAgentOverrideParams::builder()
+    ///             .user_agent("Mozilla/5.0 (X11; Linux x86_64)")
+    ///             .accept_language("de-DE,de;q=0.9")
+    ///             .platform("Linux x86_64")
+    ///             .build()
+    ///             .unwrap(),
+    ///     )
+    ///     .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn set_user_agent(
+        &self,
+        params: impl Into<SetUserAgentOverrideParams>,
+    ) -> Result<&Self> {
+        self.execute(params.into()).await?;
+        Ok(self)
+    }
+
+    /// Sends the `headers` with every request of the page, including
+    /// navigations, like a `Referer` or `Accept-Language`.
+    ///
+    /// This replaces the headers of previous calls, an empty map removes
+    /// them.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.set_extra_headers(vec![("Referer", "https://www.google.com/")])
+    ///         .await?
+    ///         .goto("https://example.com")
+    ///         .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn set_extra_headers<K, V>(
+        &self,
+        headers: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<&Self>
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let headers: serde_json::Map<_, _> = headers
+            .into_iter()
+            .map(|(name, value)| (name.into(), serde_json::Value::String(value.into())))
+            .collect();
+        let headers: Headers = serde_json::from_value(serde_json::Value::Object(headers))?;
+        self.execute(SetExtraHttpHeadersParams::new(headers))
+            .await?;
+        Ok(self)
+    }
+
+    /// Emulates the `device` by overriding the viewport, touch support and the
+    /// user agent of the page.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::device;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.emulate(device::get_device("iPhone X").unwrap()).await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn emulate(&self, device: &Device) -> Result<&Self> {
+        self.set_viewport(&device.viewport)
+            .await?
+            .set_user_agent(SetUserAgentOverrideParams::new(device.user_agent))
+            .await
+    }
+
+    /// Overrides the dimensions of the page, the device scale factor and
+    /// whether it supports touch events.
+    ///
+    /// Changing `is_mobile` or `has_touch` of a page requires a reload to
+    /// take effect.
+    pub async fn set_viewport(&self, viewport: &viewport::Viewport) -> Result<&Self> {
+        self.execute(emulation::device_metrics_override(viewport))
+            .await?;
+        self.execute(SetTouchEmulationEnabledParams::new(viewport.has_touch))
+            .await?;
+        Ok(self)
+    }
+
+    /// Overrides the geolocation of the page, the `accuracy` is in meters.
+    ///
+    /// The page also needs the permission to access the geolocation.
+    pub async fn set_geolocation(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        accuracy: f64,
+    ) -> Result<&Self> {
+        self.execute(geolocation_override(latitude, longitude, accuracy))
+            .await?;
+        Ok(self)
+    }
+
+    /// Overrides the timezone of the page with a timezone id of the ICU
+    /// database, like `Europe/Berlin`.
+    pub async fn set_timezone(&self, timezone_id: impl Into<String>) -> Result<&Self> {
+        self.execute(SetTimezoneOverrideParams::new(timezone_id))
+            .await?;
+        Ok(self)
+    }
+
+    /// Overrides the locale of the page with an ICU style C locale, like
+    /// `en_US`.
+    pub async fn set_locale(&self, locale: impl Into<String>) -> Result<&Self> {
+        self.execute(SetLocaleOverrideParams::builder().locale(locale).build())
+            .await?;
+        Ok(self)
+    }
+
+    /// Overrides the orientation of the device, as reported by the
+    /// `deviceorientation` event, with the rotation angles in degrees.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     // the device lies flat on a table, pointing north
+    ///     page.set_device_orientation(0., 0., 0.).await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn set_device_orientation(&self, alpha: f64, beta: f64, gamma: f64) -> Result<&Self> {
+        self.execute(SetDeviceOrientationOverrideParams::new(alpha, beta, gamma))
+            .await?;
+        Ok(self)
+    }
+
+    /// Clears the override of the device orientation
+    pub async fn clear_device_orientation(&self) -> Result<&Self> {
+        self.execute(ClearDeviceOrientationOverrideParams::default())
+            .await?;
+        Ok(self)
+    }
+
+    /// Overrides the state reported to the `IdleDetector` API of the page,
+    /// whether the user interacts with the device and whether its screen is
+    /// unlocked.
+    ///
+    /// The page also needs the permission to detect the idle state.
+    pub async fn set_idle_state(
+        &self,
+        is_user_active: bool,
+        is_screen_unlocked: bool,
+    ) -> Result<&Self> {
+        self.execute(SetIdleOverrideParams::new(
+            is_user_active,
+            is_screen_unlocked,
+        ))
+        .await?;
+        Ok(self)
+    }
+
+    /// Clears the override of the idle state
+    pub async fn clear_idle_state(&self) -> Result<&Self> {
+        self.execute(ClearIdleOverrideParams::default()).await?;
+        Ok(self)
+    }
+
+    /// Emulates the CSS media type of the page, like `print` to render the
+    /// page with its print styles. `None` disables the emulation.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::{Page, MediaType, ScreenshotParams};
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.emulate_media_type(MediaType::Print).await?;
+    ///     let screenshot = page
+    ///         .screenshot(ScreenshotParams::builder().full_page(true).build())
+    ///         .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn emulate_media_type(&self, media: impl Into<Option<MediaType>>) -> Result<&Self> {
+        let media = media.into().map(|media| media.as_ref().to_string());
+        self.inner
+            .emulate_media(|params| params.media = media)
+            .await?;
+        Ok(self)
+    }
+
+    /// Emulates the CSS media features of the page, like
+    /// `prefers-color-scheme`. The features replace the previously emulated
+    /// ones, an empty list disables the emulation.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.emulate_media_features(vec![
+    ///         ("prefers-color-scheme", "dark"),
+    ///         ("prefers-reduced-motion", "reduce"),
+    ///     ])
+    ///     .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn emulate_media_features<I, K, V>(&self, features: I) -> Result<&Self>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let features: Vec<_> = features
+            .into_iter()
+            .map(|(name, value)| MediaFeature::new(name, value))
+            .collect();
+        let features = if features.is_empty() {
+            None
+        } else {
+            Some(features)
+        };
+        self.inner
+            .emulate_media(|params| params.features = features)
+            .await?;
+        Ok(self)
+    }
+
+    /// Simulates how the page is perceived with the vision `deficiency`,
+    /// `VisionDeficiency::None` disables the simulation.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::{Page, VisionDeficiency};
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.emulate_vision_deficiency(VisionDeficiency::Protanopia)
+    ///         .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn emulate_vision_deficiency(&self, deficiency: VisionDeficiency) -> Result<&Self> {
+        self.execute(SetEmulatedVisionDeficiencyParams::new(deficiency))
+            .await?;
+        Ok(self)
+    }
+
+    /// Applies all the overrides of the `options` at once.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::{Page, EmulationOptions};
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.set_emulation_options(
+    ///         EmulationOptions::builder()
+    ///             .geolocation(48.8584, 2.2945, 10.)
+    ///             .timezone("Europe/Paris")
+    ///             .locale("fr_FR")
+    ///             .build(),
+    ///     )
+    ///     .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn set_emulation_options(&self, options: EmulationOptions) -> Result<&Self> {
+        if let Some(geolocation) = options.geolocation {
+            self.execute(geolocation).await?;
+        }
+        if let Some(timezone) = options.timezone {
+            self.execute(timezone).await?;
+        }
+        if let Some(locale) = options.locale {
+            self.execute(locale).await?;
+        }
+        Ok(self)
+    }
+
+    /// Emulates the network conditions, like a slow connection.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::network_conditions::SLOW_3G;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.emulate_network_conditions(SLOW_3G).await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn emulate_network_conditions(
+        &self,
+        conditions: impl Into<EmulateNetworkConditionsParams>,
+    ) -> Result<&Self> {
+        self.execute(conditions.into()).await?;
+        Ok(self)
+    }
+
+    /// Disconnects the page from the network or restores its connection
+    pub async fn set_offline(&self, offline: bool) -> Result<&Self> {
+        let conditions = if offline {
+            network_conditions::OFFLINE
+        } else {
+            network_conditions::NO_THROTTLING
+        };
+        self.emulate_network_conditions(conditions).await
+    }
+
+    /// Slows down the CPU by the factor `rate`, `1` disables the throttling
+    pub async fn set_cpu_throttling_rate(&self, rate: f64) -> Result<&Self> {
+        self.execute(SetCpuThrottlingRateParams::new(rate)).await?;
+        Ok(self)
+    }
+
+    /// Switches the page to virtual time, which runs timers and animations
+    /// independent of the wall clock, according to the `policy`.
+    ///
+    /// With a `budget`, virtual time pauses after it advanced by the budget,
+    /// which is reported by the returned stream. The stream is subscribed
+    /// before the policy is applied, so the expiration is never missed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::cdp::browser_protocol::emulation::VirtualTimePolicy;
+    /// # use futures::StreamExt;
+    /// # use std::time::Duration;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let mut expired = page
+    ///         .enable_virtual_time(
+    ///             VirtualTimePolicy::PauseIfNetworkFetchesPending,
+    ///             Some(Duration::from_secs(10)),
+    ///         )
+    ///         .await?;
+    ///     // the timers of the next 10 virtual seconds fire right away
+    ///     expired.next().await;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn enable_virtual_time(
+        &self,
+        policy: VirtualTimePolicy,
+        budget: Option<Duration>,
+    ) -> Result<EventStream<EventVirtualTimeBudgetExpired>> {
+        let expired = self.event_listener().await?;
+        let mut params = SetVirtualTimePolicyParams::new(policy);
+        params.budget = budget.map(|budget| budget.as_secs_f64() * 1000.);
+        self.execute(params).await?;
+        Ok(expired)
+    }
+
+    /// Advances the virtual time of the page by the `budget` and resolves
+    /// once the budget expired, virtual time is paused afterwards.
+    ///
+    /// Unlike sleeping, this runs all the timers and animations due within
+    /// the budget as fast as possible and deterministically.
+    pub async fn advance_virtual_time(&self, budget: Duration) -> Result<&Self> {
+        let mut expired = self
+            .enable_virtual_time(VirtualTimePolicy::Advance, Some(budget))
+            .await?;
+        expired.next().await;
+        Ok(self)
+    }
+
+    /// Returns the root DOM node (and optionally the subtree) of the page.
+    ///
+    /// # Note: This does not return the actual HTML document of the page. To
+    /// retrieve the HTML content of the page see `Page::content`.
+    pub async fn get_document(&self) -> Result<Node> {
+        let resp = self.execute(GetDocumentParams::default()).await?;
+        Ok(resp.result.root)
+    }
+
+    /// Returns the first element in the document which matches the given CSS
+    /// selector.
+    ///
+    /// Execute a query selector on the document's node.
+    ///
+    /// Fails with `CdpError::NotFound` if no element matches the selector.
+    pub async fn find_element(&self, selector: impl Into<String>) -> Result<Element> {
+        let root = self.get_document().await?.node_id;
+        let node_id = self.inner.find_element(selector, root).await?;
+        Element::new(Arc::clone(&self.inner), node_id).await
+    }
+
+    /// Return all `Element`s in the document that match the given selector
+    pub async fn find_elements(&self, selector: impl Into<String>) -> Result<Vec<Element>> {
+        let root = self.get_document().await?.node_id;
+        let node_ids = self.inner.find_elements(selector, root).await?;
+        Element::from_nodes(&self.inner, &node_ids).await
+    }
+
+    /// Waits until an element matches the `selector` and returns it.
+    ///
+    /// The document is checked every 100ms, this fails with
+    /// [`CdpError::Timeout`] if no element matches within `timeout`.
+    /// `None` waits for the page's default timeout, see
+    /// `Page::set_default_timeout`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use std::time::Duration;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let results = page
+    ///         .wait_for_element("#results", Duration::from_secs(10))
+    ///         .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn wait_for_element(
+        &self,
+        selector: impl Into<String>,
+        timeout: impl Into<Option<Duration>>,
+    ) -> Result<Element> {
+        let selector = selector.into();
+        let deadline = Instant::now() + self.wait_timeout(timeout);
+        loop {
+            match self.find_element(selector.clone()).await {
+                Ok(element) => return Ok(element),
+                Err(CdpError::NotFound) => {}
+                // the document may be replaced by a navigation in between
+                Err(err) if is_context_destroyed(&err) => {}
+                Err(err) => return Err(err),
+            }
+            if Instant::now() >= deadline {
+                return Err(CdpError::Timeout);
+            }
+            Delay::new(WAIT_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Waits until an element matches the `selector` and is visible, i.e. it
+    /// has a non empty bounding box and is neither hidden via `display` nor
+    /// `visibility`.
+    ///
+    /// Fails with [`CdpError::Timeout`] if this takes longer than `timeout`.
+    /// `None` waits for the page's default timeout, see
+    /// `Page::set_default_timeout`.
+    pub async fn wait_for_visible_element(
+        &self,
+        selector: impl Into<String>,
+        timeout: impl Into<Option<Duration>>,
+    ) -> Result<Element> {
+        let selector = selector.into();
+        let predicate = format!(
+            r#"(() => {{
+                const element = document.querySelector({});
+                if (!element) return false;
+                const style = window.getComputedStyle(element);
+                const rect = element.getBoundingClientRect();
+                return style.display !== 'none' && style.visibility !== 'hidden'
+                    && rect.width > 0 && rect.height > 0;
+            }})()"#,
+            serde_json::Value::from(selector.as_str())
+        );
+        self.wait_for_function(predicate, timeout.into()).await?;
+        self.find_element(selector).await
+    }
+
+    /// Waits until the javascript expression `predicate` evaluates to a
+    /// truthy value and returns that value.
+    ///
+    /// The expression is evaluated every 100ms in the page, promises are
+    /// awaited and an expression that throws is evaluated again. Fails with
+    /// [`CdpError::Timeout`] if this takes longer than `timeout` and right
+    /// away if chromium rejects the expression, e.g. due to a syntax error.
+    /// `None` waits for the page's default timeout, see
+    /// `Page::set_default_timeout`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use std::time::Duration;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.wait_for_function(
+    ///         "document.querySelectorAll('li').length >= 10",
+    ///         Duration::from_secs(10),
+    ///     )
+    ///     .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn wait_for_function(
+        &self,
+        predicate: impl Into<String>,
+        timeout: impl Into<Option<Duration>>,
+    ) -> Result<serde_json::Value> {
+        let mut params = EvaluateParams::new(predicate);
+        params.await_promise = Some(true);
+        params.return_by_value = Some(true);
+        let deadline = Instant::now() + self.wait_timeout(timeout);
+        loop {
+            match self.execute(params.clone()).await {
+                Ok(resp) => match resp.result.exception_details.clone() {
+                    // a predicate that throws, e.g. because an element doesn't
+                    // exist yet, is evaluated again, unless it can't be parsed
+                    Some(details) => {
+                        if let Some(exception) = details
+                            .exception
+                            .filter(|ex| ex.class_name.as_deref() == Some("SyntaxError"))
+                        {
+                            return Err(CdpError::msg(
+                                exception.description.unwrap_or(details.text),
+                            ));
+                        }
+                    }
+                    None => {
+                        if let Some(value) = resp.result.result.value.as_ref() {
+                            if is_truthy(value) {
+                                return Ok(value.clone());
+                            }
+                        }
+                    }
+                },
+                // the execution context may be destroyed by a navigation
+                Err(err) if is_context_destroyed(&err) => {}
+                Err(err) => return Err(err),
+            }
+            if Instant::now() >= deadline {
+                return Err(CdpError::Timeout);
+            }
+            Delay::new(WAIT_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Sets how long the `wait_for_*` helpers of this page wait if no
+    /// timeout is passed to them, including `wait_for_response` and
+    /// `Element::wait_for_attribute`, and how long navigations may take
+    /// unless a `default_navigation_timeout` is set.
+    ///
+    /// Fails the helpers with [`CdpError::Timeout`] once it elapsed. `None`
+    /// restores the defaults: 30 seconds for the helpers that take a timeout,
+    /// no timeout for the others.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use std::time::Duration;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.set_default_timeout(Duration::from_secs(5));
+    ///     let results = page.wait_for_element("#results", None).await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub fn set_default_timeout(&self, timeout: impl Into<Option<Duration>>) -> &Self {
+        self.inner.set_default_timeout(timeout.into());
+        self
+    }
+
+    /// Sets how long navigations of this page may take, via `goto`,
+    /// `wait_for_navigation`, `reload` and `click_and_wait`, overriding the
+    /// `default_timeout` for those.
+    ///
+    /// Fails the navigation with [`CdpError::Timeout`] once it elapsed.
+    pub fn set_default_navigation_timeout(&self, timeout: impl Into<Option<Duration>>) -> &Self {
+        self.inner.set_navigation_timeout(timeout.into());
+        self
+    }
+
+    /// The timeout of a `wait_for_*` helper
+    fn wait_timeout(&self, timeout: impl Into<Option<Duration>>) -> Duration {
+        timeout
+            .into()
+            .or_else(|| self.inner.default_timeout())
+            .unwrap_or(DEFAULT_WAIT_TIMEOUT)
+    }
+
+    /// Describes node given its id
+    pub async fn describe_node(&self, node_id: NodeId) -> Result<Node> {
+        let resp = self
+            .execute(
+                DescribeNodeParams::builder()
+                    .node_id(node_id)
+                    .depth(100)
+                    .build(),
+            )
+            .await?;
+        Ok(resp.result.node)
+    }
+
+    pub async fn close(self) {
+        todo!()
+    }
+
+    /// Performs a single mouse click event at the point's location.
+    ///
+    /// This scrolls the point into view first, then executes a
+    /// `DispatchMouseEventParams` command of type `MouseLeft` with
+    /// `MousePressed` as single click and then releases the mouse with an
+    /// additional `DispatchMouseEventParams` of type `MouseLeft` with
+    /// `MouseReleased`
+    ///
+    /// Bear in mind that if `click()` triggers a navigation the new page is not
+    /// immediately loaded when `click()` resolves. To wait until navigation is
+    /// finished an additional `wait_for_navigation()` is required:
+    ///
+    /// # Example
+    ///
+    /// Trigger a navigation and wait until the triggered navigation is finished
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::layout::Point;
+    /// # async fn demo(page: Page, point: Point) -> Result<()> {
+    ///     let html = page.click(point).await?.wait_for_navigation().await?.content();
+    ///     # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Example
+    ///
+    /// Perform custom click
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::layout::Point;
+    /// # use chromiumoxide_cdp::cdp::browser_protocol::input::{DispatchMouseEventParams, MouseButton, DispatchMouseEventType};
+    /// # async fn demo(page: Page, point: Point) -> Result<()> {
+    ///      // double click
+    ///      let cmd = DispatchMouseEventParams::builder()
+    ///             .x(point.x)
+    ///             .y(point.y)
+    ///             .button(MouseButton::Left)
+    ///             .click_count(2);
+    ///
+    ///         page.move_mouse(point).await?.execute(
+    ///             cmd.clone()
+    ///                 .r#type(DispatchMouseEventType::MousePressed)
+    ///                 .build()
+    ///                 .unwrap(),
+    ///         )
+    ///         .await?;
+    ///
+    ///         page.execute(
+    ///             cmd.r#type(DispatchMouseEventType::MouseReleased)
+    ///                 .build()
+    ///                 .unwrap(),
+    ///         )
+    ///         .await?;
+    ///
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn click(&self, point: Point) -> Result<&Self> {
+        self.inner.click(point).await?;
+        Ok(self)
+    }
+
+    /// Clicks the first element that matches the `selector`.
+    ///
+    /// The element is scrolled into view first and then clicked in the center
+    /// of its visible area, see `Element::click`.
+    ///
+    /// Fails with `CdpError::NotFound` if no element matches the selector.
+    pub async fn click_selector(&self, selector: impl Into<String>) -> Result<&Self> {
+        self.find_element(selector).await?.click().await?;
+        Ok(self)
+    }
+
+    /// Clicks the first element that matches the `selector` and waits until
+    /// the navigation triggered by the click finished with the `wait_until`
+    /// lifecycle event.
+    ///
+    /// Unlike a `click_selector` followed by `wait_for_navigation`, this is
+    /// guaranteed to observe the navigation, as the page is watched before
+    /// the click is dispatched.
+    ///
+    /// # Example
+    ///
+    /// Follow a link and wait until the new page's network is idle
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::handler::frame::LifecycleEvent;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let html = page
+    ///         .click_and_wait("a#next", LifecycleEvent::NetworkIdle)
+    ///         .await?
+    ///         .content()
+    ///         .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn click_and_wait(
+        &self,
+        selector: impl Into<String>,
+        wait_until: LifecycleEvent,
+    ) -> Result<&Self> {
+        let element = self.find_element(selector).await?;
+        let navigated = self.inner.watch_navigation(wait_until).await?;
+        element.click().await?;
+        utils::timeout(self.inner.navigation_timeout(), async { navigated.await? }).await?;
+        Ok(self)
+    }
+
+    /// Scrolls the first element that matches the `selector` into view and
+    /// moves the mouse over its center.
+    pub async fn hover(&self, selector: impl Into<String>) -> Result<&Self> {
+        self.find_element(selector).await?.hover().await?;
+        Ok(self)
+    }
+
+    /// Scrolls the first element that matches the `selector` into view and
+    /// taps its center.
+    ///
+    /// Touch events are only dispatched if touch emulation is enabled, via
+    /// `Viewport::has_touch` or an emulated `Device`.
+    pub async fn tap(&self, selector: impl Into<String>) -> Result<&Self> {
+        self.find_element(selector).await?.tap().await?;
+        Ok(self)
+    }
+
+    /// Swipes with a finger from the point `from` to the point `to` within
+    /// the `duration`, scrolling the page or a scrollable element under the
+    /// finger.
+    ///
+    /// # Example
+    ///
+    /// Swipe up to scroll down the page
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::layout::Point;
+    /// # use std::time::Duration;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.swipe(
+    ///         Point::new(200., 600.),
+    ///         Point::new(200., 100.),
+    ///         Duration::from_millis(300),
+    ///     )
+    ///     .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn swipe(&self, from: Point, to: Point, duration: Duration) -> Result<&Self> {
+        let x_distance = to.x - from.x;
+        let y_distance = to.y - from.y;
+        let distance = (x_distance * x_distance + y_distance * y_distance).sqrt();
+        // the speed of the gesture in pixels per second
+        let speed = (distance / duration.as_secs_f64().max(0.001)).round() as i64;
+        self.execute(
+            SynthesizeScrollGestureParams::builder()
+                .x(from.x)
+                .y(from.y)
+                .x_distance(x_distance)
+                .y_distance(y_distance)
+                .speed(speed.max(1))
+                .prevent_fling(true)
+                .gesture_source_type(GestureSourceType::Touch)
+                .build()
+                .unwrap(),
+        )
+        .await?;
+        Ok(self)
+    }
+
+    /// Pinches in the center of the viewport, zooming in for a `scale`
+    /// greater than `1` and out for a `scale` less than `1`.
+    pub async fn pinch(&self, scale: f64) -> Result<&Self> {
+        let viewport = self.inner.layout_metrics().await?.layout_viewport;
+        let mut params = SynthesizePinchGestureParams::new(
+            viewport.client_width as f64 / 2.,
+            viewport.client_height as f64 / 2.,
+            scale,
+        );
+        params.gesture_source_type = Some(GestureSourceType::Touch);
+        self.execute(params).await?;
+        Ok(self)
+    }
+
+    /// Returns the virtual mouse of this page
+    pub fn mouse(&self) -> Mouse {
+        Mouse::new(Arc::clone(&self.inner))
+    }
+
+    /// Dispatches a `mousemove` event and moves the mouse to the position of
+    /// the `point` where `Point.x` is the horizontal position of the mouse and
+    /// `Point.y` the vertical position of the mouse.
+    pub async fn move_mouse(&self, point: Point) -> Result<&Self> {
+        self.inner.move_mouse(point).await?;
+        Ok(self)
+    }
+
+    /// Returns the current run-time metrics of the page, like the number of
+    /// DOM nodes and the size of the JavaScript heap.
+    ///
+    /// The `Performance` domain is enabled for every page, unless it is
+    /// disabled via [`DomainConfig`](crate::handler::domains::DomainConfig),
+    /// in which case it is enabled by this call if domains are enabled
+    /// lazily.
+    pub async fn metrics(&self) -> Result<Metrics> {
+        metrics::get_metrics(&self.inner).await
+    }
+
+    /// Returns a stream that samples the `metrics` of the page every
+    /// `interval`, e.g. to monitor the memory of a long-running session.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use futures::StreamExt;
+    /// # use std::time::Duration;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let mut metrics = page.enable_metrics(Duration::from_secs(10));
+    ///     while let Some(metrics) = metrics.next().await {
+    ///         println!("heap: {} bytes", metrics?.js_heap_used_size);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub fn enable_metrics(&self, interval: Duration) -> MetricsStream {
+        MetricsStream::new(Arc::clone(&self.inner), interval)
+    }
+
+    /// Captures a snapshot of the documents of the page, including iframes,
+    /// with their DOM trees, layouts, the requested computed styles and the
+    /// boxes of the laid out text.
+    ///
+    /// This is a lot faster than walking the DOM with multiple commands.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide_cdp::cdp::browser_protocol::dom_snapshot::CaptureSnapshotParams;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let snapshot = page
+    ///         .dom_snapshot(CaptureSnapshotParams::new(vec!["display".to_string()]))
+    ///         .await?;
+    ///     for layout in &snapshot.documents[0].layouts {
+    ///         println!("{:?} {:?}", layout.styles.get("display"), layout.bounds);
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn dom_snapshot(
+        &self,
+        params: impl Into<browser_protocol::dom_snapshot::CaptureSnapshotParams>,
+    ) -> Result<DomSnapshot> {
+        let params = params.into();
+        let computed_styles = params.computed_styles.clone();
+        let resp = self.execute(params).await?;
+        Ok(DomSnapshot::new(resp.result, &computed_styles))
+    }
+
+    /// Returns access to the `localStorage`, `sessionStorage` and IndexedDB
+    /// databases of the origins within this page's browser context
+    pub fn storage(&self) -> Storage {
+        Storage::new(Arc::clone(&self.inner))
+    }
+
+    /// Adds a virtual WebAuthn authenticator with the `options` to the page,
+    /// to test passkey and security key flows without a real device.
+    ///
+    /// See [`VirtualAuthenticator`] for an example.
+    pub async fn add_virtual_authenticator(
+        &self,
+        options: VirtualAuthenticatorOptions,
+    ) -> Result<VirtualAuthenticator> {
+        self.execute(web_authn::EnableParams::default()).await?;
+        let id = self
+            .execute(AddVirtualAuthenticatorParams::new(options))
+            .await?
+            .result
+            .authenticator_id;
+        Ok(VirtualAuthenticator::new(Arc::clone(&self.inner), id))
+    }
+
+    /// Returns the virtual keyboard of this page
+    pub fn keyboard(&self) -> Keyboard {
+        Keyboard::new(Arc::clone(&self.inner))
+    }
+
+    /// Presses and releases the key or combination of keys like `Control+A`
+    /// on the focused element.
+    pub async fn press_key(&self, key: impl AsRef<str>) -> Result<&Self> {
+        self.inner.press_key(key).await?;
+        Ok(self)
+    }
+
+    /// Types the `input` into the focused element, where each char is
+    /// inserted as a separate keystroke.
+    pub async fn type_str(&self, input: impl AsRef<str>) -> Result<&Self> {
+        self.inner.type_str(input).await?;
+        Ok(self)
+    }
+
+    /// Clears the first input element that matches the `selector` and types
+    /// the `text` into it, see `Element::fill`.
+    ///
+    /// Fails with `CdpError::NotFound` if no element matches the selector.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.fill("input[name=email]", "user@example.com").await?
+    ///         .fill("input[name=password]", "secret").await?
+    ///         .click_selector("button[type=submit]").await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn fill(&self, selector: impl Into<String>, text: impl AsRef<str>) -> Result<&Self> {
+        self.find_element(selector).await?.fill(text).await?;
+        Ok(self)
+    }
+
+    /// Same as `Page::type_str` but waits `delay` between the keystrokes, e.g.
+    /// to mimic a human typing.
+    pub async fn type_str_with_delay(
+        &self,
+        input: impl AsRef<str>,
+        delay: Duration,
+    ) -> Result<&Self> {
+        self.inner.type_str_with_delay(input, Some(delay)).await?;
+        Ok(self)
+    }
+
+    /// Take a screenshot of the current page and return the decoded image.
+    ///
+    /// # Example
+    ///
+    /// Capture the whole page as jpeg
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::{Page, ScreenshotParams};
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide_cdp::cdp::browser_protocol::page::CaptureScreenshotFormat;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let jpeg = page
+    ///         .screenshot(
+    ///             ScreenshotParams::builder()
+    ///                 .format(CaptureScreenshotFormat::Jpeg)
+    ///                 .quality(80)
+    ///                 .full_page(true)
+    ///                 .build(),
+    ///         )
+    ///         .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn screenshot(&self, params: impl Into<ScreenshotParams>) -> Result<Vec<u8>> {
+        self.inner.screenshot(params.into()).await
+    }
+
+    /// Save a screenshot of the page as file to the `output` path and return
+    /// the image.
+    pub async fn save_screenshot(
+        &self,
+        params: impl Into<ScreenshotParams>,
+        output: impl AsRef<Path>,
+    ) -> Result<Vec<u8>> {
+        let img = self.screenshot(params).await?;
+        utils::write(output.as_ref(), &img).await?;
+        Ok(img)
     }
 
-    /// Performs a single mouse click event at the point's location.
-    ///
-    /// This scrolls the point into view first, then executes a
-    /// `DispatchMouseEventParams` command of type `MouseLeft` with
-    /// `MousePressed` as single click and then releases the mouse with an
-    /// additional `DispatchMouseEventParams` of type `MouseLeft` with
-    /// `MouseReleased`
+    /// Compares a png screenshot of the viewport with the `baseline` png,
+    /// see [`visual_diff::compare_screenshots`].
     ///
-    /// Bear in mind that if `click()` triggers a navigation the new page is not
-    /// immediately loaded when `click()` resolves. To wait until navigation is
-    /// finished an additional `wait_for_navigation()` is required:
+    /// If the baseline does not exist yet, the screenshot is saved as the
+    /// new baseline. On a mismatch, the screenshot and the diff image are
+    /// saved next to the baseline as `<name>.actual.png` and
+    /// `<name>.diff.png` and [`CdpError::VisualMismatch`] is returned.
     ///
     /// # Example
     ///
-    /// Trigger a navigation and wait until the triggered navigation is finished
-    ///
     /// ```no_run
     /// # use chromiumoxide::page::Page;
     /// # use chromiumoxide::error::Result;
-    /// # use chromiumoxide::layout::Point;
-    /// # async fn demo(page: Page, point: Point) -> Result<()> {
-    ///     let html = page.click(point).await?.wait_for_navigation().await?.content();
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.goto("https://example.com").await?;
+    ///     page.expect_visual_match("tests/baselines/example.png").await?;
     ///     # Ok(())
     /// # }
     /// ```
+    #[cfg(feature = "visual-diff")]
+    pub async fn expect_visual_match(&self, baseline: impl AsRef<Path>) -> Result<()> {
+        let baseline = baseline.as_ref().to_path_buf();
+        let actual = self.screenshot(ScreenshotParams::builder().build()).await?;
+        utils::spawn_blocking(move || {
+            let expected = match std::fs::read(&baseline) {
+                Ok(expected) => expected,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    if let Some(dir) = baseline.parent() {
+                        std::fs::create_dir_all(dir)?;
+                    }
+                    std::fs::write(&baseline, &actual)?;
+                    return Ok(());
+                }
+                Err(err) => return Err(err.into()),
+            };
+            let diff = visual_diff::compare_screenshots(
+                &expected,
+                &actual,
+                visual_diff::DEFAULT_THRESHOLD,
+            )?;
+            if diff.is_match() {
+                return Ok(());
+            }
+            std::fs::write(baseline.with_extension("actual.png"), &actual)?;
+            std::fs::write(baseline.with_extension("diff.png"), &diff.diff_image)?;
+            Err(CdpError::VisualMismatch {
+                baseline,
+                mismatch_percentage: diff.mismatch_percentage(),
+            })
+        })
+        .await
+    }
+
+    /// Takes a screenshot of only the first element that matches the
+    /// `selector`, see `Element::screenshot`.
     ///
-    /// # Example
+    /// Fails with `CdpError::NotFound` if no element matches the selector.
     ///
-    /// Perform custom click
+    /// # Example
     ///
     /// ```no_run
     /// # use chromiumoxide::page::Page;
     /// # use chromiumoxide::error::Result;
-    /// # use chromiumoxide::layout::Point;
-    /// # use chromiumoxide_cdp::cdp::browser_protocol::input::{DispatchMouseEventParams, MouseButton, DispatchMouseEventType};
-    /// # async fn demo(page: Page, point: Point) -> Result<()> {
-    ///      // double click
-    ///      let cmd = DispatchMouseEventParams::builder()
-    ///             .x(point.x)
-    ///             .y(point.y)
-    ///             .button(MouseButton::Left)
-    ///             .click_count(2);
-    ///
-    ///         page.move_mouse(point).await?.execute(
-    ///             cmd.clone()
-    ///                 .r#type(DispatchMouseEventType::MousePressed)
-    ///                 .build()
-    ///                 .unwrap(),
-    ///         )
+    /// # use chromiumoxide::cdp::browser_protocol::page::CaptureScreenshotFormat;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let png = page
+    ///         .element_screenshot("#header", CaptureScreenshotFormat::Png)
     ///         .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn element_screenshot(
+        &self,
+        selector: impl Into<String>,
+        format: CaptureScreenshotFormat,
+    ) -> Result<Vec<u8>> {
+        self.find_element(selector).await?.screenshot(format).await
+    }
+
+    /// Starts a screencast of the page and returns a stream over its frames.
     ///
-    ///         page.execute(
-    ///             cmd.r#type(DispatchMouseEventType::MouseReleased)
-    ///                 .build()
-    ///                 .unwrap(),
+    /// Frames are only sent while the page is visible and its content
+    /// changes.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide_cdp::cdp::browser_protocol::page::{StartScreencastFormat, StartScreencastParams};
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let mut screencast = page
+    ///         .screencast(
+    ///             StartScreencastParams::builder()
+    ///                 .format(StartScreencastFormat::Png)
+    ///                 .build(),
     ///         )
     ///         .await?;
-    ///
+    ///     let frames = screencast.save_frames("frames", 100).await?;
+    ///     screencast.stop().await?;
     ///     # Ok(())
     /// # }
     /// ```
-    pub async fn click(&self, point: Point) -> Result<&Self> {
-        self.inner.click(point).await?;
-        Ok(self)
+    pub async fn screencast(&self, params: impl Into<StartScreencastParams>) -> Result<Screencast> {
+        let params = params.into();
+        let format = params.format.clone().unwrap_or(StartScreencastFormat::Jpeg);
+        let frames = self.event_listener::<EventScreencastFrame>().await?;
+        self.execute(params).await?;
+        Ok(Screencast::new(Arc::clone(&self.inner), format, frames))
     }
 
-    /// Dispatches a `mousemove` event and moves the mouse to the position of
-    /// the `point` where `Point.x` is the horizontal position of the mouse and
-    /// `Point.y` the vertical position of the mouse.
-    pub async fn move_mouse(&self, point: Point) -> Result<&Self> {
-        self.inner.move_mouse(point).await?;
-        Ok(self)
+    /// Print the current page as pdf.
+    ///
+    /// See [`PdfParams`](crate::pdf::PdfParams) for options that are checked
+    /// before they are sent to the browser, or [`PrintToPdfParams`]
+    ///
+    /// If the `transfer_mode` is set to
+    /// [`PrintToPdfTransferMode::ReturnAsStream`], the pdf is read in chunks
+    /// via `IO.read` instead of a single base64 encoded response, which is
+    /// preferable for very large documents.
+    ///
+    /// # Note Generating a pdf is currently only supported in Chrome headless.
+    pub async fn pdf(&self, opts: impl Into<PrintToPdfParams>) -> Result<Vec<u8>> {
+        let res = self.execute(opts.into()).await?.result;
+        if let Some(stream) = res.stream {
+            IoStream::page(Arc::clone(&self.inner), stream)
+                .into_bytes()
+                .await
+        } else {
+            Ok(base64::decode(&res.data)?)
+        }
     }
 
-    /// Print the current page as pdf.
+    /// Print the current page as pdf and return a stream over the pdf's
+    /// content, that is read in chunks via `IO.read`.
     ///
-    /// See [`PrintToPdfParams`]
+    /// This avoids holding the entire, base64 encoded pdf in memory.
     ///
     /// # Note Generating a pdf is currently only supported in Chrome headless.
-    pub async fn pdf(&self, opts: PrintToPdfParams) -> Result<Vec<u8>> {
-        let res = self.execute(opts).await?;
-        Ok(base64::decode(&res.data)?)
+    pub async fn pdf_stream(&self, opts: impl Into<PrintToPdfParams>) -> Result<IoStream> {
+        let mut opts = opts.into();
+        opts.transfer_mode = Some(PrintToPdfTransferMode::ReturnAsStream);
+        let res = self.execute(opts).await?.result;
+        let stream = res
+            .stream
+            .ok_or_else(|| CdpError::msg("Pdf was not returned as stream"))?;
+        Ok(IoStream::page(Arc::clone(&self.inner), stream))
     }
 
     /// Save the current page as pdf as file to the `output` path and return the
@@ -229,11 +2338,11 @@ impl Page {
     /// # Note Generating a pdf is currently only supported in Chrome headless.
     pub async fn save_pdf(
         &self,
-        opts: PrintToPdfParams,
+        opts: impl Into<PrintToPdfParams>,
         output: impl AsRef<Path>,
     ) -> Result<Vec<u8>> {
         let pdf = self.pdf(opts).await?;
-        async_std::fs::write(output.as_ref(), &pdf).await?;
+        utils::write(output.as_ref(), &pdf).await?;
         Ok(pdf)
     }
 
@@ -243,22 +2352,81 @@ impl Page {
         Ok(self)
     }
 
-    /// Reloads given page
+    /// Reloads the page, bypassing the cache if `ignore_cache` is set, like
+    /// pressing shift and refresh.
     ///
-    /// To reload ignoring cache run:
-    /// ```no_run
-    /// # use chromiumoxide::page::Page;
-    /// # use chromiumoxide::error::Result;
-    /// # use chromiumoxide_cdp::cdp::browser_protocol::page::ReloadParams;
-    /// # async fn demo(page: Page) -> Result<()> {
-    ///     page.execute(ReloadParams::builder().ignore_cache(true).build()).await?;
-    ///     page.wait_for_navigation().await?;
-    ///     # Ok(())
-    /// # }
-    /// ```
-    pub async fn reload(&self) -> Result<&Self> {
-        self.execute(ReloadParams::default()).await?;
-        Ok(self.wait_for_navigation().await?)
+    /// This resolves after the reloaded page is loaded.
+    pub async fn reload(&self, ignore_cache: bool) -> Result<&Self> {
+        self.navigate_with(
+            ReloadParams::builder().ignore_cache(ignore_cache).build(),
+            LifecycleEvent::Load,
+        )
+        .await
+    }
+
+    /// Navigates to the previous entry of the session history, like pressing
+    /// the back button of the browser.
+    ///
+    /// This resolves after the previous page is loaded, or once the main
+    /// frame navigated within the document if the entry belongs to the
+    /// current document, like a fragment or a `pushState` entry. Returns
+    /// `false` without navigating if there is no previous entry.
+    pub async fn go_back(&self) -> Result<bool> {
+        self.go_history(-1).await
+    }
+
+    /// Navigates to the next entry of the session history, like pressing
+    /// the forward button of the browser.
+    ///
+    /// This resolves after the next page is loaded, or once the main frame
+    /// navigated within the document if the entry belongs to the current
+    /// document, like a fragment or a `pushState` entry. Returns `false`
+    /// without navigating if there is no next entry.
+    pub async fn go_forward(&self) -> Result<bool> {
+        self.go_history(1).await
+    }
+
+    /// Navigates `delta` entries back or forth in the session history
+    async fn go_history(&self, delta: i64) -> Result<bool> {
+        let history = self
+            .execute(GetNavigationHistoryParams::default())
+            .await?
+            .result;
+        let entry = usize::try_from(history.current_index + delta)
+            .ok()
+            .and_then(|idx| history.entries.get(idx));
+        if let Some(entry) = entry {
+            // same-document entries never emit `load`, the navigation is
+            // complete with their `Page.navigatedWithinDocument` instead
+            self.navigate_with(
+                NavigateToHistoryEntryParams::new(entry.id),
+                LifecycleEvent::Load,
+            )
+            .await?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Executes the command that navigates the main frame and waits until
+    /// the new document emitted the `wait_until` event, or until the main
+    /// frame navigated within the current document
+    async fn navigate_with<T: Command>(&self, cmd: T, wait_until: LifecycleEvent) -> Result<&Self> {
+        let navigated = self.inner.watch_navigation(wait_until).await?;
+        self.execute(cmd).await?;
+        utils::timeout(self.inner.navigation_timeout(), async { navigated.await? }).await?;
+        Ok(self)
+    }
+
+    /// Stops loading the page, like pressing the stop button of the
+    /// browser.
+    ///
+    /// A pending `goto` may still wait for its lifecycle event until it
+    /// times out, see `Page::set_default_navigation_timeout`.
+    pub async fn stop_loading(&self) -> Result<&Self> {
+        self.execute(StopLoadingParams::default()).await?;
+        Ok(self)
     }
 
     /// Enables log domain. Enabled by default.
@@ -343,45 +2511,172 @@ impl Page {
         }
     }
 
+    /// Evaluates the script in every frame upon creation, before any of the
+    /// frame's scripts run.
+    ///
+    /// Returns the identifier of the script, which can be used to remove it
+    /// again with [`Page::remove_script_to_evaluate_on_new_document`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.evaluate_on_new_document(
+    ///         "Object.defineProperty(navigator, 'webdriver', { get: () => undefined })",
+    ///     )
+    ///     .await?;
+    ///     page.goto("https://example.com").await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn evaluate_on_new_document(
+        &self,
+        script: impl Into<AddScriptToEvaluateOnNewDocumentParams>,
+    ) -> Result<ScriptIdentifier> {
+        Ok(self.execute(script.into()).await?.result.identifier)
+    }
+
+    /// Stops evaluating the script added via
+    /// [`Page::evaluate_on_new_document`] in new frames.
+    pub async fn remove_script_to_evaluate_on_new_document(
+        &self,
+        identifier: impl Into<ScriptIdentifier>,
+    ) -> Result<&Self> {
+        self.execute(RemoveScriptToEvaluateOnNewDocumentParams::new(identifier))
+            .await?;
+        Ok(self)
+    }
+
+    /// Exposes the rust `function` as `window[name]` to the page, also in all
+    /// the documents the page navigates to.
+    ///
+    /// Calling it from the page returns a promise. The arguments of the call
+    /// are passed to `function` as JSON values, the promise resolves to its
+    /// result or rejects with its error message.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.expose_function("add", |args| async move {
+    ///         let sum: i64 = args.iter().filter_map(|arg| arg.as_i64()).sum();
+    ///         Ok(sum.into())
+    ///     })
+    ///     .await?;
+    ///     let sum = page.evaluate("window.add(1, 2)").await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn expose_function<F, Fut>(
+        &self,
+        name: impl Into<String>,
+        function: F,
+    ) -> Result<&Self>
+    where
+        F: Fn(Vec<serde_json::Value>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = std::result::Result<serde_json::Value, String>> + Send + 'static,
+    {
+        let name = name.into();
+        let calls = self.event_listener::<EventBindingCalled>().await?;
+        self.execute(AddBindingParams::new(name.clone())).await?;
+        let script = binding::binding_script(&name);
+        self.evaluate_on_new_document(script.clone()).await?;
+        self.evaluate(script).await?;
+        binding::serve(Arc::clone(&self.inner), name, calls, function);
+        Ok(self)
+    }
+
     /// Evaluates expression on global object.
     pub async fn evaluate(&self, evaluate: impl Into<EvaluateParams>) -> Result<RemoteObject> {
         Ok(self.execute(evaluate.into()).await?.result.result)
     }
 
+    /// Replaces the content of the page with the `html` and resolves once it
+    /// is loaded.
     pub async fn set_content(&self, html: impl AsRef<str>) -> Result<&Self> {
-        let js = format!(
-            "(html) => {{
-      document.open();
-      document.write(html);
-      document.close();
-    }}, {})",
-            html.as_ref()
+        self.set_content_until(html, LifecycleEvent::Load).await
+    }
+
+    /// Replaces the content of the page with the `html` and resolves once the
+    /// page emitted the `wait_until` lifecycle event.
+    ///
+    /// The html is loaded via a `data:` url, so that the regular navigation
+    /// lifecycle applies.
+    ///
+    /// # Example
+    ///
+    /// Render html to a pdf once all its resources are loaded
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::handler::frame::LifecycleEvent;
+    /// # use chromiumoxide::cdp::browser_protocol::page::PrintToPdfParams;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let html = "<h1>Invoice</h1><img src=\"https://example.com/logo.png\">";
+    ///     page.set_content_until(html, LifecycleEvent::NetworkIdle)
+    ///         .await?;
+    ///     let pdf = page.pdf(PrintToPdfParams::default()).await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn set_content_until(
+        &self,
+        html: impl AsRef<str>,
+        wait_until: LifecycleEvent,
+    ) -> Result<&Self> {
+        let url = format!(
+            "data:text/html;charset=utf-8;base64,{}",
+            base64::encode(html.as_ref())
         );
-        self.evaluate(js).await?;
-        // relying that document.open() will reset frame lifecycle with "init"
-        // lifecycle event. @see https://crrev.com/608658
-        Ok(self.wait_for_navigation().await?)
+        self.goto_until(url, wait_until).await
     }
 
-    /// Returns the HTML content of the page
+    /// Returns the HTML content of the page, including the doctype
     pub async fn content(&self) -> Result<String> {
-        let resp = self
-            .evaluate(
-                "{
-          let retVal = '';
-          if (document.doctype) {
-            retVal = new XMLSerializer().serializeToString(document.doctype);
-          }
-          if (document.documentElement) {
-            retVal += document.documentElement.outerHTML;
-          }
-          retVal
-      }
-      ",
-            )
-            .await?;
-        let value = resp.value.ok_or(CdpError::NotFound)?;
-        Ok(serde_json::from_value(value)?)
+        let root = self.get_document().await?;
+        let params = GetOuterHtmlParams::builder().node_id(root.node_id).build();
+        Ok(self.execute(params).await?.result.outer_html)
+    }
+
+    /// Returns all the links of the document, with their absolute urls and
+    /// visible texts, in document order.
+    pub async fn extract_links(&self) -> Result<Vec<Link>> {
+        scrape::extract_links(&self.inner).await
+    }
+
+    /// Returns the text of the cells of the first table that matches the
+    /// `selector`, row by row, including the header rows.
+    ///
+    /// Fails with `CdpError::NotFound` if no element matches the selector.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let rows = page.extract_table("table.prices").await?;
+    ///     for row in rows.iter().skip(1) {
+    ///         println!("{}", row.join(", "));
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn extract_table(&self, selector: impl AsRef<str>) -> Result<Vec<Vec<String>>> {
+        scrape::extract_table(&self.inner, selector.as_ref()).await
+    }
+
+    /// Returns the rendered text of the first element that matches the
+    /// `selector`, as the user sees it.
+    ///
+    /// Fails with `CdpError::NotFound` if no element matches the selector.
+    pub async fn inner_text(&self, selector: impl AsRef<str>) -> Result<String> {
+        scrape::inner_text(&self.inner, selector.as_ref()).await
     }
 
     /// Returns source for the script with given id.
@@ -396,8 +2691,287 @@ impl Page {
     }
 }
 
+/// Error messages of chromium for commands that were sent while the
+/// document of the page was being replaced
+const CONTEXT_DESTROYED_ERRORS: [&str; 5] = [
+    "Execution context was destroyed",
+    "Cannot find context with specified id",
+    "Cannot find default execution context",
+    "Inspected target navigated or closed",
+    "Could not find node with given id",
+];
+
+/// Whether the command failed because a navigation replaced the document,
+/// so that it may succeed once the new document is loaded
+fn is_context_destroyed(err: &CdpError) -> bool {
+    match err {
+        CdpError::Chrome(err) => CONTEXT_DESTROYED_ERRORS
+            .iter()
+            .any(|msg| err.message.contains(msg)),
+        _ => false,
+    }
+}
+
+/// Whether the javascript value converts to `true`
+fn is_truthy(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Null => false,
+        serde_json::Value::Bool(b) => *b,
+        serde_json::Value::Number(n) => n.as_f64().map(|n| n != 0.).unwrap_or(true),
+        serde_json::Value::String(s) => !s.is_empty(),
+        _ => true,
+    }
+}
+
 impl From<Arc<PageInner>> for Page {
     fn from(inner: Arc<PageInner>) -> Self {
         Self { inner }
     }
 }
+
+/// The image format of a screenshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenshotFormat {
+    Png,
+    Jpeg,
+    /// Not part of the protocol version the commands are generated from, so
+    /// webp screenshots are requested with a raw command
+    Webp,
+}
+
+impl From<CaptureScreenshotFormat> for ScreenshotFormat {
+    fn from(format: CaptureScreenshotFormat) -> Self {
+        match format {
+            CaptureScreenshotFormat::Png => ScreenshotFormat::Png,
+            CaptureScreenshotFormat::Jpeg => ScreenshotFormat::Jpeg,
+        }
+    }
+}
+
+/// The options for taking a screenshot of a `Page`
+#[derive(Debug, Clone, Default)]
+pub struct ScreenshotParams {
+    /// The parameters of the `Page.captureScreenshot` command
+    pub cdp_params: CaptureScreenshotParams,
+    /// The image format, overrides the format of the `cdp_params`
+    pub format: Option<ScreenshotFormat>,
+    /// Capture the full scrollable page instead of the current viewport
+    pub full_page: Option<bool>,
+    /// How the full page is captured, defaults to
+    /// [`FullPageStrategy::Resize`], see
+    /// [`ScreenshotParamsBuilder::full_page_strategy`]
+    full_page_strategy: Option<FullPageStrategy>,
+    /// Hide the default white background to capture screenshots with
+    /// transparency, this is ignored for jpeg screenshots
+    pub omit_background: Option<bool>,
+}
+
+impl ScreenshotParams {
+    pub fn builder() -> ScreenshotParamsBuilder {
+        ScreenshotParamsBuilder::default()
+    }
+
+    /// How the full page is captured, `None` if only the viewport is
+    pub(crate) fn full_page_strategy(&self) -> Option<FullPageStrategy> {
+        if self.full_page.unwrap_or_default() {
+            Some(self.full_page_strategy.unwrap_or_default())
+        } else {
+            None
+        }
+    }
+
+    /// The image format of the screenshot, png by default
+    pub(crate) fn format(&self) -> ScreenshotFormat {
+        self.format
+            .or_else(|| self.cdp_params.format.clone().map(Into::into))
+            .unwrap_or(ScreenshotFormat::Png)
+    }
+
+    pub(crate) fn omit_background(&self) -> bool {
+        self.omit_background.unwrap_or_default() && self.format() != ScreenshotFormat::Jpeg
+    }
+}
+
+impl From<CaptureScreenshotParams> for ScreenshotParams {
+    fn from(cdp_params: CaptureScreenshotParams) -> Self {
+        Self {
+            cdp_params,
+            ..Default::default()
+        }
+    }
+}
+
+/// How a screenshot of the full scrollable page is taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FullPageStrategy {
+    /// Resize the viewport to the size of the content and capture it at once
+    #[default]
+    Resize,
+    /// Let chromium capture the content beyond the viewport via the
+    /// `captureBeyondViewport` option, without resizing the viewport. This
+    /// option is ignored by browsers that don't support it
+    CaptureBeyondViewport,
+    /// Scroll through the page and stitch the screenshots of the viewport
+    /// together into a single image.
+    ///
+    /// Elements with a fixed position, like sticky headers, appear on every
+    /// tile. Screenshots can only be encoded as png or jpeg.
+    ///
+    /// Requires the `stitch` feature, the screenshot fails without it.
+    Stitch,
+    /// Try `CaptureBeyondViewport` first and fall back to `Stitch` if the
+    /// captured image does not cover the content of the page
+    ///
+    /// Requires the `stitch` feature, the screenshot fails without it.
+    Auto,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ScreenshotParamsBuilder {
+    cdp_params: CaptureScreenshotParams,
+    format: Option<ScreenshotFormat>,
+    full_page: Option<bool>,
+    full_page_strategy: Option<FullPageStrategy>,
+    omit_background: Option<bool>,
+}
+
+impl ScreenshotParamsBuilder {
+    /// The image format of the screenshot (defaults to png)
+    pub fn format(mut self, format: impl Into<ScreenshotFormat>) -> Self {
+        self.format = Some(format.into());
+        self
+    }
+
+    /// Compression quality from range [0..100] (jpeg and webp only)
+    pub fn quality(mut self, quality: impl Into<i64>) -> Self {
+        self.cdp_params.quality = Some(quality.into());
+        self
+    }
+
+    /// Capture the screenshot of a given region only
+    pub fn clip(mut self, clip: impl Into<Viewport>) -> Self {
+        self.cdp_params.clip = Some(clip.into());
+        self
+    }
+
+    /// Capture the screenshot from the surface, rather than the view
+    pub fn from_surface(mut self, from_surface: impl Into<bool>) -> Self {
+        self.cdp_params.from_surface = Some(from_surface.into());
+        self
+    }
+
+    /// Capture the full scrollable page instead of the current viewport
+    pub fn full_page(mut self, full_page: impl Into<bool>) -> Self {
+        self.full_page = Some(full_page.into());
+        self
+    }
+
+    /// Capture the full scrollable page with the `strategy`
+    pub fn full_page_strategy(mut self, strategy: FullPageStrategy) -> Self {
+        self.full_page = Some(true);
+        self.full_page_strategy = Some(strategy);
+        self
+    }
+
+    /// Capture the screenshot with a transparent background
+    pub fn omit_background(mut self, omit_background: impl Into<bool>) -> Self {
+        self.omit_background = Some(omit_background.into());
+        self
+    }
+
+    pub fn build(self) -> ScreenshotParams {
+        ScreenshotParams {
+            cdp_params: self.cdp_params,
+            format: self.format,
+            full_page: self.full_page,
+            full_page_strategy: self.full_page_strategy,
+            omit_background: self.omit_background,
+        }
+    }
+}
+
+/// The CSS media types a page can be rendered for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaType {
+    Screen,
+    Print,
+}
+
+impl AsRef<str> for MediaType {
+    fn as_ref(&self) -> &str {
+        match self {
+            MediaType::Screen => "screen",
+            MediaType::Print => "print",
+        }
+    }
+}
+
+/// Overrides to pretend the page is located in another region
+#[derive(Debug, Clone, Default)]
+pub struct EmulationOptions {
+    /// The `Emulation.setGeolocationOverride` command
+    pub geolocation: Option<SetGeolocationOverrideParams>,
+    /// The `Emulation.setTimezoneOverride` command
+    pub timezone: Option<SetTimezoneOverrideParams>,
+    /// The `Emulation.setLocaleOverride` command
+    pub locale: Option<SetLocaleOverrideParams>,
+}
+
+impl EmulationOptions {
+    pub fn builder() -> EmulationOptionsBuilder {
+        EmulationOptionsBuilder::default()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EmulationOptionsBuilder {
+    geolocation: Option<SetGeolocationOverrideParams>,
+    timezone: Option<SetTimezoneOverrideParams>,
+    locale: Option<SetLocaleOverrideParams>,
+}
+
+impl EmulationOptionsBuilder {
+    /// The geolocation of the page, the `accuracy` is in meters
+    pub fn geolocation(mut self, latitude: f64, longitude: f64, accuracy: f64) -> Self {
+        self.geolocation = Some(geolocation_override(latitude, longitude, accuracy));
+        self
+    }
+
+    /// A timezone id of the ICU database, like `Europe/Berlin`
+    pub fn timezone(mut self, timezone_id: impl Into<String>) -> Self {
+        self.timezone = Some(SetTimezoneOverrideParams::new(timezone_id));
+        self
+    }
+
+    /// An ICU style C locale, like `en_US`
+    pub fn locale(mut self, locale: impl Into<String>) -> Self {
+        self.locale = Some(SetLocaleOverrideParams::builder().locale(locale).build());
+        self
+    }
+
+    pub fn build(self) -> EmulationOptions {
+        EmulationOptions {
+            geolocation: self.geolocation,
+            timezone: self.timezone,
+            locale: self.locale,
+        }
+    }
+}
+
+fn geolocation_override(
+    latitude: f64,
+    longitude: f64,
+    accuracy: f64,
+) -> SetGeolocationOverrideParams {
+    SetGeolocationOverrideParams::builder()
+        .latitude(latitude)
+        .longitude(longitude)
+        .accuracy(accuracy)
+        .build()
+}
+
+/// The network events relevant for `Page::wait_for_response`
+enum NetworkEvent {
+    Response(Arc<EventResponseReceived>),
+    Finished(RequestId),
+}
@@ -0,0 +1,370 @@
+//! Typed options for printing a page as pdf with
+//! [`Page::pdf`](crate::Page::pdf).
+//!
+//! [`PdfParams`] are checked when they are built, so invalid options are
+//! reported with a descriptive message instead of the generic error chromium
+//! responds with.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::pdf::{Length, PaperFormat, PdfParams};
+//! # async fn demo(page: Page) -> Result<()> {
+//!     let params = PdfParams::builder()
+//!         .format(PaperFormat::A4)
+//!         .margin(Length::Mm(20.))
+//!         .footer_template(
+//!             r#"<div style="font-size: 8px; margin: auto">
+//!                 <span class="pageNumber"></span> / <span class="totalPages"></span>
+//!             </div>"#,
+//!         )
+//!         .page_ranges("1-3, 5")
+//!         .build()
+//!         .unwrap();
+//!     page.save_pdf(params, "report.pdf").await?;
+//!     # Ok(())
+//! # }
+//! ```
+use chromiumoxide_cdp::cdp::browser_protocol::page::{PrintToPdfParams, PrintToPdfTransferMode};
+
+/// The width of the default paper, US letter, in inches
+const DEFAULT_PAPER_WIDTH: f64 = 8.5;
+
+/// The height of the default paper, US letter, in inches
+const DEFAULT_PAPER_HEIGHT: f64 = 11.;
+
+/// The default margin of every side, 1cm, in inches
+const DEFAULT_MARGIN: f64 = 0.4;
+
+/// A length on the printed paper
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Length {
+    /// Inches
+    In(f64),
+    /// Centimeters
+    Cm(f64),
+    /// Millimeters
+    Mm(f64),
+    /// CSS pixels, 96 per inch
+    Px(f64),
+}
+
+impl Length {
+    /// The length in inches, the unit chromium expects
+    pub fn to_inches(self) -> f64 {
+        match self {
+            Length::In(v) => v,
+            Length::Cm(v) => v / 2.54,
+            Length::Mm(v) => v / 25.4,
+            Length::Px(v) => v / 96.,
+        }
+    }
+}
+
+/// Common paper sizes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaperFormat {
+    Letter,
+    Legal,
+    Tabloid,
+    Ledger,
+    A3,
+    A4,
+    A5,
+}
+
+impl PaperFormat {
+    /// The width and height of the paper in portrait orientation
+    pub fn size(self) -> (Length, Length) {
+        match self {
+            PaperFormat::Letter => (Length::In(8.5), Length::In(11.)),
+            PaperFormat::Legal => (Length::In(8.5), Length::In(14.)),
+            PaperFormat::Tabloid => (Length::In(11.), Length::In(17.)),
+            PaperFormat::Ledger => (Length::In(17.), Length::In(11.)),
+            PaperFormat::A3 => (Length::Mm(297.), Length::Mm(420.)),
+            PaperFormat::A4 => (Length::Mm(210.), Length::Mm(297.)),
+            PaperFormat::A5 => (Length::Mm(148.), Length::Mm(210.)),
+        }
+    }
+}
+
+/// The validated options for printing a page as pdf
+#[derive(Debug, Clone, Default)]
+pub struct PdfParams {
+    /// The parameters of the `Page.printToPDF` command
+    pub cdp_params: PrintToPdfParams,
+}
+
+impl PdfParams {
+    pub fn builder() -> PdfParamsBuilder {
+        PdfParamsBuilder::default()
+    }
+}
+
+impl From<PdfParams> for PrintToPdfParams {
+    fn from(params: PdfParams) -> Self {
+        params.cdp_params
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PdfParamsBuilder {
+    cdp_params: PrintToPdfParams,
+}
+
+impl PdfParamsBuilder {
+    /// Print in landscape orientation (defaults to false)
+    pub fn landscape(mut self, landscape: bool) -> Self {
+        self.cdp_params.landscape = Some(landscape);
+        self
+    }
+
+    /// Print the background graphics (defaults to false)
+    pub fn print_background(mut self, print_background: bool) -> Self {
+        self.cdp_params.print_background = Some(print_background);
+        self
+    }
+
+    /// The scale of the rendering, between `0.1` and `2` (defaults to 1)
+    pub fn scale(mut self, scale: f64) -> Self {
+        self.cdp_params.scale = Some(scale);
+        self
+    }
+
+    /// The size of the paper (defaults to US letter)
+    pub fn paper_size(mut self, width: Length, height: Length) -> Self {
+        self.cdp_params.paper_width = Some(width.to_inches());
+        self.cdp_params.paper_height = Some(height.to_inches());
+        self
+    }
+
+    /// Print on paper of the `format`
+    pub fn format(self, format: PaperFormat) -> Self {
+        let (width, height) = format.size();
+        self.paper_size(width, height)
+    }
+
+    /// Sets all four margins to `margin` (defaults to 1cm)
+    pub fn margin(self, margin: Length) -> Self {
+        self.margins(margin, margin, margin, margin)
+    }
+
+    /// Sets the margins in the order of CSS: top, right, bottom, left
+    pub fn margins(mut self, top: Length, right: Length, bottom: Length, left: Length) -> Self {
+        self.cdp_params.margin_top = Some(top.to_inches());
+        self.cdp_params.margin_right = Some(right.to_inches());
+        self.cdp_params.margin_bottom = Some(bottom.to_inches());
+        self.cdp_params.margin_left = Some(left.to_inches());
+        self
+    }
+
+    /// The pages to print, one based, like `1-5, 8, 11-13`. Open ranges
+    /// like `3-` print up to the last page (defaults to all pages)
+    pub fn page_ranges(mut self, page_ranges: impl Into<String>) -> Self {
+        self.cdp_params.page_ranges = Some(page_ranges.into());
+        self
+    }
+
+    /// Whether to show the header and footer, this is enabled by setting a
+    /// template
+    pub fn display_header_footer(mut self, display_header_footer: bool) -> Self {
+        self.cdp_params.display_header_footer = Some(display_header_footer);
+        self
+    }
+
+    /// The HTML template of the header.
+    ///
+    /// Elements with the classes `date`, `title`, `url`, `pageNumber` and
+    /// `totalPages` are filled with the respective values. The template
+    /// does not inherit the styles of the page, so it needs a font size to
+    /// be visible.
+    pub fn header_template(mut self, template: impl Into<String>) -> Self {
+        self.cdp_params.header_template = Some(template.into());
+        self
+    }
+
+    /// The HTML template of the footer, see
+    /// [`header_template`](Self::header_template)
+    pub fn footer_template(mut self, template: impl Into<String>) -> Self {
+        self.cdp_params.footer_template = Some(template.into());
+        self
+    }
+
+    /// Prefer the page size defined by the CSS `@page` rule of the page over
+    /// the paper size
+    pub fn prefer_css_page_size(mut self, prefer_css_page_size: bool) -> Self {
+        self.cdp_params.prefer_css_page_size = Some(prefer_css_page_size);
+        self
+    }
+
+    /// Return the pdf as a stream instead of a single base64 encoded
+    /// response
+    pub fn transfer_mode(mut self, transfer_mode: PrintToPdfTransferMode) -> Self {
+        self.cdp_params.transfer_mode = Some(transfer_mode);
+        self
+    }
+
+    /// Validates the options
+    pub fn build(mut self) -> Result<PdfParams, String> {
+        let params = &mut self.cdp_params;
+        if let Some(scale) = params.scale {
+            if !(0.1..=2.).contains(&scale) {
+                return Err(format!("Scale must be between 0.1 and 2, got {}", scale));
+            }
+        }
+
+        let width = params.paper_width.unwrap_or(DEFAULT_PAPER_WIDTH);
+        let height = params.paper_height.unwrap_or(DEFAULT_PAPER_HEIGHT);
+        if width <= 0. || height <= 0. {
+            return Err(format!(
+                "Paper size must be positive, got {}in x {}in",
+                width, height
+            ));
+        }
+
+        let margins = [
+            ("top", params.margin_top),
+            ("right", params.margin_right),
+            ("bottom", params.margin_bottom),
+            ("left", params.margin_left),
+        ];
+        if let Some((side, _)) = margins.iter().find(|(_, m)| m.unwrap_or_default() < 0.) {
+            return Err(format!("The {} margin must not be negative", side));
+        }
+        let (width, height) = if params.landscape.unwrap_or_default() {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        let margin = |margin: Option<f64>| margin.unwrap_or(DEFAULT_MARGIN);
+        if margin(params.margin_left) + margin(params.margin_right) >= width
+            || margin(params.margin_top) + margin(params.margin_bottom) >= height
+        {
+            return Err("The margins leave no space for the content of the page".to_string());
+        }
+
+        if let Some(ranges) = params.page_ranges.as_deref() {
+            validate_page_ranges(ranges)?;
+        }
+
+        if params.display_header_footer.is_none()
+            && (params.header_template.is_some() || params.footer_template.is_some())
+        {
+            params.display_header_footer = Some(true);
+        }
+
+        Ok(PdfParams {
+            cdp_params: self.cdp_params,
+        })
+    }
+}
+
+/// Checks that the `ranges` are in the format chromium accepts, like
+/// `1-5, 8, 11-`
+fn validate_page_ranges(ranges: &str) -> Result<(), String> {
+    let page = |page: &str| -> Result<Option<u64>, String> {
+        let page = page.trim();
+        if page.is_empty() {
+            return Ok(None);
+        }
+        match page.parse::<u64>() {
+            Ok(0) | Err(_) => Err(format!(
+                "Invalid page `{}` in page ranges `{}`, pages are one based numbers",
+                page, ranges
+            )),
+            Ok(page) => Ok(Some(page)),
+        }
+    };
+
+    for range in ranges.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+        let (start, end) = match range.find('-') {
+            Some(idx) => (page(&range[..idx])?, page(&range[idx + 1..])?),
+            None => {
+                page(range)?;
+                continue;
+            }
+        };
+        match (start, end) {
+            (None, None) => return Err(format!("Invalid page range `{}`", range)),
+            (Some(start), Some(end)) if start > end => {
+                return Err(format!(
+                    "Invalid page range `{}`, the start is greater than the end",
+                    range
+                ))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_page_ranges() {
+        assert!(validate_page_ranges("1-3, 5").is_ok());
+        assert!(validate_page_ranges("3-").is_ok());
+        assert!(validate_page_ranges("-2").is_ok());
+    }
+
+    #[test]
+    fn invalid_page_ranges() {
+        assert!(validate_page_ranges("0").is_err());
+        assert!(validate_page_ranges("5-2").is_err());
+        assert!(validate_page_ranges("-").is_err());
+        assert!(validate_page_ranges("1, a").is_err());
+    }
+
+    #[test]
+    fn rejects_negative_margins() {
+        let err = PdfParams::builder()
+            .margins(
+                Length::In(0.),
+                Length::Mm(-1.),
+                Length::In(0.),
+                Length::In(0.),
+            )
+            .build()
+            .unwrap_err();
+        assert!(err.contains("right"));
+    }
+
+    #[test]
+    fn rejects_margins_without_space_in_landscape() {
+        let margins = |builder: PdfParamsBuilder| {
+            builder.margins(
+                Length::In(4.3),
+                Length::Cm(1.),
+                Length::In(4.3),
+                Length::Cm(1.),
+            )
+        };
+        assert!(margins(PdfParams::builder()).build().is_ok());
+        assert!(margins(PdfParams::builder().landscape(true))
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn templates_display_header_footer() {
+        let params = PdfParams::builder()
+            .footer_template("<span class=pageNumber></span>")
+            .build()
+            .unwrap();
+        assert_eq!(params.cdp_params.display_header_footer, Some(true));
+
+        let params = PdfParams::builder()
+            .display_header_footer(false)
+            .header_template("<span class=title></span>")
+            .build()
+            .unwrap();
+        assert_eq!(params.cdp_params.display_header_footer, Some(false));
+
+        let params = PdfParams::builder().build().unwrap();
+        assert_eq!(params.cdp_params.display_header_footer, None);
+    }
+}
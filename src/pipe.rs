@@ -0,0 +1,162 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::pin::Pin;
+use std::process::{Child, Command};
+use std::sync::mpsc;
+
+use futures::channel::mpsc::{unbounded, UnboundedReceiver};
+use futures::task::{Context, Poll};
+use futures::Stream;
+
+/// The messages of the pipe transport are separated by a NUL byte
+const MESSAGE_DELIMITER: u8 = 0;
+
+/// The file descriptor chromium reads the commands from
+#[cfg(unix)]
+const CHROME_READ_FD: i32 = 3;
+
+/// The file descriptor chromium writes the responses and events to
+#[cfg(unix)]
+const CHROME_WRITE_FD: i32 = 4;
+
+/// The pipes to a chromium instance launched with `--remote-debugging-pipe`.
+///
+/// Reading and writing happens on separate threads, since the pipes are
+/// blocking files that the async runtimes can't poll.
+#[derive(Debug)]
+pub(crate) struct PipeTransport {
+    /// Sends the messages to the writing thread
+    tx: mpsc::Sender<Vec<u8>>,
+    /// The messages read by the reading thread
+    rx: UnboundedReceiver<io::Result<Vec<u8>>>,
+}
+
+impl PipeTransport {
+    fn new(writer: File, reader: File) -> Self {
+        let (tx, commands) = mpsc::channel::<Vec<u8>>();
+        std::thread::spawn(move || {
+            let mut writer = writer;
+            for msg in commands {
+                let res = writer
+                    .write_all(&msg)
+                    .and_then(|_| writer.write_all(&[MESSAGE_DELIMITER]))
+                    .and_then(|_| writer.flush());
+                if res.is_err() {
+                    return;
+                }
+            }
+        });
+
+        let (messages, rx) = unbounded();
+        std::thread::spawn(move || {
+            let mut reader = BufReader::new(reader);
+            loop {
+                let mut msg = Vec::new();
+                let res = match reader.read_until(MESSAGE_DELIMITER, &mut msg) {
+                    // the pipe was closed
+                    Ok(0) => return,
+                    Ok(_) => {
+                        if msg.last() == Some(&MESSAGE_DELIMITER) {
+                            msg.pop();
+                        }
+                        Ok(msg)
+                    }
+                    Err(err) => Err(err),
+                };
+                let failed = res.is_err();
+                if messages.unbounded_send(res).is_err() || failed {
+                    return;
+                }
+            }
+        });
+        Self { tx, rx }
+    }
+
+    /// Sends the message to chromium
+    pub fn send(&self, msg: String) -> io::Result<()> {
+        self.tx
+            .send(msg.into_bytes())
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "The pipe was closed"))
+    }
+}
+
+impl Stream for PipeTransport {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().rx).poll_next(cx)
+    }
+}
+
+/// Spawns the `cmd` with the pipes chromium uses for
+/// `--remote-debugging-pipe` as file descriptors 3 and 4
+#[cfg(unix)]
+pub(crate) fn spawn(cmd: &mut Command) -> io::Result<(Child, PipeTransport)> {
+    use std::os::unix::io::FromRawFd;
+    use std::os::unix::process::CommandExt;
+
+    let (chrome_read, writer) = pipe()?;
+    let (reader, chrome_write) = match pipe() {
+        Ok(fds) => fds,
+        Err(err) => {
+            // SAFETY: the descriptors were just created and are owned by
+            // nothing else
+            unsafe {
+                libc::close(chrome_read);
+                libc::close(writer);
+            }
+            return Err(err);
+        }
+    };
+    // SAFETY: only async-signal-safe functions are called in the child
+    unsafe {
+        cmd.pre_exec(move || {
+            // move both ends out of the way first, in case they already are
+            // 3 or 4
+            let read = libc::fcntl(chrome_read, libc::F_DUPFD, 10);
+            let write = libc::fcntl(chrome_write, libc::F_DUPFD, 10);
+            if read < 0
+                || write < 0
+                || libc::dup2(read, CHROME_READ_FD) < 0
+                || libc::dup2(write, CHROME_WRITE_FD) < 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+            libc::close(read);
+            libc::close(write);
+            Ok(())
+        });
+    }
+    let child = cmd.spawn();
+    // SAFETY: the descriptors were just created and are owned by nothing else
+    let (writer, reader) = unsafe {
+        libc::close(chrome_read);
+        libc::close(chrome_write);
+        (File::from_raw_fd(writer), File::from_raw_fd(reader))
+    };
+    Ok((child?, PipeTransport::new(writer, reader)))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn spawn(_: &mut Command) -> io::Result<(Child, PipeTransport)> {
+    Err(io::Error::other(
+        "The pipe transport is only supported on unix",
+    ))
+}
+
+/// Creates a pipe whose descriptors are closed on exec, returns the read and
+/// the write end
+#[cfg(unix)]
+fn pipe() -> io::Result<(i32, i32)> {
+    let mut fds = [0; 2];
+    // SAFETY: `fds` has room for the two descriptors
+    unsafe {
+        if libc::pipe(fds.as_mut_ptr()) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        for fd in &fds {
+            libc::fcntl(*fd, libc::F_SETFD, libc::FD_CLOEXEC);
+        }
+    }
+    Ok((fds[0], fds[1]))
+}
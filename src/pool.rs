@@ -0,0 +1,433 @@
+use std::collections::VecDeque;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::channel::mpsc::Sender;
+use futures::channel::oneshot::{
+    channel as oneshot_channel, Receiver as OneshotReceiver, Sender as OneshotSender,
+};
+use futures::{FutureExt, SinkExt, StreamExt};
+
+use chromiumoxide_cdp::cdp::browser_protocol::browser::BrowserContextId;
+use chromiumoxide_cdp::cdp::browser_protocol::page::EventFrameNavigated;
+use chromiumoxide_cdp::cdp::browser_protocol::target::{CloseTargetParams, CreateTargetParams};
+use chromiumoxide_cdp::cdp::js_protocol::runtime::EvaluateParams;
+
+use crate::error::{CdpError, Result};
+use crate::handler::HandlerMessage;
+use crate::listeners::EventStream;
+use crate::page::Page;
+
+/// Settings of a [`BrowserPool`]
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// How many pages the pool opens at most
+    pub size: usize,
+    /// After how many navigations a page is closed and replaced with a fresh
+    /// one, never if `None`
+    pub max_navigations: Option<usize>,
+    /// How long a page may take to respond to the health check before it is
+    /// considered broken and replaced
+    pub health_check_timeout: Duration,
+    /// The url pages are reset to when they are checked in
+    pub blank_url: String,
+    /// The browser context to open the pages in, the default context if
+    /// `None`
+    pub browser_context_id: Option<BrowserContextId>,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            size: 4,
+            max_navigations: None,
+            health_check_timeout: Duration::from_secs(5),
+            blank_url: "about:blank".to_string(),
+            browser_context_id: None,
+        }
+    }
+}
+
+/// A page of the pool together with its navigation history
+#[derive(Debug)]
+struct PoolEntry {
+    page: Page,
+    /// How often the main frame navigated since the page was opened
+    navigations: usize,
+    frame_navigated: EventStream<EventFrameNavigated>,
+}
+
+impl PoolEntry {
+    /// Counts the navigations of the main frame since the last call
+    fn count_navigations(&mut self) {
+        while let Some(Some(ev)) = self.frame_navigated.next().now_or_never() {
+            if ev.frame.parent_id.is_none() {
+                self.navigations += 1;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PoolState {
+    /// The pages that are currently not checked out
+    idle: VecDeque<PoolEntry>,
+    /// The number of open pages, idle or checked out
+    open: usize,
+    /// Whether the pool no longer hands out pages
+    draining: bool,
+    /// Tasks waiting for a change of the pool, like a checked in page
+    waiters: Vec<OneshotSender<()>>,
+}
+
+impl PoolState {
+    fn notify(&mut self) {
+        for waiter in self.waiters.drain(..) {
+            let _ = waiter.send(());
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    config: PoolConfig,
+    /// The `Sender` to send messages to the connection handler
+    sender: Sender<HandlerMessage>,
+    state: Mutex<PoolState>,
+}
+
+impl Shared {
+    /// Puts the page back into the pool
+    fn release(&self, entry: PoolEntry) {
+        let mut state = self.state.lock().unwrap();
+        state.idle.push_back(entry);
+        state.notify();
+    }
+
+    /// Removes a page, that is about to be closed, from the pool
+    fn discard(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.open = state.open.saturating_sub(1);
+        state.notify();
+    }
+}
+
+/// A slot of the pool that is reserved by a checkout, it is given back if
+/// the checkout is cancelled before it hands out a page
+struct Reservation<'a> {
+    shared: &'a Shared,
+    /// The idle page that is checked out, `None` if a new page is opened
+    entry: Option<PoolEntry>,
+    /// Whether the slot is still held by the checkout
+    reserved: bool,
+}
+
+impl<'a> Reservation<'a> {
+    fn new(shared: &'a Shared, entry: Option<PoolEntry>) -> Self {
+        Self {
+            shared,
+            entry,
+            reserved: true,
+        }
+    }
+
+    /// Hands over the slot, along with the idle page if any
+    fn complete(mut self) -> Option<PoolEntry> {
+        self.reserved = false;
+        self.entry.take()
+    }
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        if !self.reserved {
+            return;
+        }
+        match self.entry.take() {
+            // the next checkout checks the idle page again
+            Some(entry) => self.shared.release(entry),
+            None => self.shared.discard(),
+        }
+    }
+}
+
+/// What `BrowserPool::checkout` does next
+enum Checkout {
+    /// Hand out the idle page after a health check
+    Idle(PoolEntry),
+    /// Open a new page, its slot is already reserved
+    Open,
+    /// Wait until a page is returned
+    Wait(OneshotReceiver<()>),
+}
+
+/// A pool of pages that are reused for many tasks, like scraping a large
+/// number of urls concurrently.
+///
+/// Pages are opened lazily up to [`PoolConfig::size`] and handed out via
+/// [`BrowserPool::checkout`]. A checked out page is returned to the pool via
+/// [`BrowserPool::checkin`], which resets it to a blank page, or by dropping
+/// it. Broken pages and pages that exceeded
+/// [`PoolConfig::max_navigations`] are closed and replaced with fresh ones.
+///
+/// The pool is cheap to clone, all clones share the same pages.
+///
+/// # Example
+///
+/// ```no_run
+/// # use chromiumoxide::browser::Browser;
+/// # use chromiumoxide::pool::{BrowserPool, PoolConfig};
+/// # use chromiumoxide::error::Result;
+/// # async fn demo(browser: Browser) -> Result<()> {
+///     let pool = browser.pool(PoolConfig {
+///         size: 8,
+///         max_navigations: Some(50),
+///         ..Default::default()
+///     });
+///     let page = pool.checkout().await?;
+///     page.goto("https://example.com").await?;
+///     let html = page.content().await?;
+///     pool.checkin(page).await?;
+///     // wait for all pages to be returned and close them
+///     pool.drain().await?;
+///     # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BrowserPool {
+    shared: Arc<Shared>,
+}
+
+impl BrowserPool {
+    pub(crate) fn new(sender: Sender<HandlerMessage>, config: PoolConfig) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                config,
+                sender,
+                state: Default::default(),
+            }),
+        }
+    }
+
+    /// The settings of this pool
+    pub fn config(&self) -> &PoolConfig {
+        &self.shared.config
+    }
+
+    /// The number of pages that are open, idle or checked out
+    pub fn open(&self) -> usize {
+        self.shared.state.lock().unwrap().open
+    }
+
+    /// The number of pages that are open but not checked out
+    pub fn idle(&self) -> usize {
+        self.shared.state.lock().unwrap().idle.len()
+    }
+
+    /// Hands out an idle page of the pool or opens a new one if the pool is
+    /// not full yet, otherwise waits until a page is checked in.
+    ///
+    /// Idle pages are health checked first, broken pages are replaced.
+    ///
+    /// Fails once the pool is [drained](BrowserPool::drain).
+    pub async fn checkout(&self) -> Result<PooledPage> {
+        loop {
+            match self.try_checkout()? {
+                Checkout::Idle(mut entry) => {
+                    // pages that were dropped instead of checked in may have
+                    // navigated too often
+                    if self.is_exhausted(&mut entry) {
+                        self.close(entry).await;
+                        continue;
+                    }
+                    let reservation = Reservation::new(&self.shared, Some(entry));
+                    let page = &reservation.entry.as_ref().expect("idle page").page;
+                    let healthy = self.is_healthy(page).await;
+                    if let Some(entry) = reservation.complete() {
+                        if healthy {
+                            return Ok(self.pooled(entry));
+                        }
+                        self.close(entry).await;
+                    }
+                }
+                Checkout::Open => {
+                    let reservation = Reservation::new(&self.shared, None);
+                    let entry = self.open_page().await?;
+                    reservation.complete();
+                    return Ok(self.pooled(entry));
+                }
+                Checkout::Wait(rx) => {
+                    let _ = rx.await;
+                }
+            }
+        }
+    }
+
+    fn try_checkout(&self) -> Result<Checkout> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.draining {
+            return Err(CdpError::msg("The pool is draining"));
+        }
+        if let Some(entry) = state.idle.pop_front() {
+            return Ok(Checkout::Idle(entry));
+        }
+        if state.open < self.shared.config.size.max(1) {
+            state.open += 1;
+            return Ok(Checkout::Open);
+        }
+        let (tx, rx) = oneshot_channel();
+        state.waiters.push(tx);
+        Ok(Checkout::Wait(rx))
+    }
+
+    /// Returns the page to the pool.
+    ///
+    /// The page is navigated to the [`PoolConfig::blank_url`] so that the
+    /// next task starts from a clean page. Pages that exceeded the
+    /// [`PoolConfig::max_navigations`] or fail to reset are closed instead.
+    pub async fn checkin(&self, mut page: PooledPage) -> Result<()> {
+        let mut entry = match page.entry.take() {
+            Some(entry) => entry,
+            None => return Ok(()),
+        };
+        if self.is_exhausted(&mut entry) || self.shared.state.lock().unwrap().draining {
+            self.close(entry).await;
+            return Ok(());
+        }
+        let navigations = entry.navigations;
+        match entry.page.goto(self.shared.config.blank_url.clone()).await {
+            Ok(_) => {
+                // resetting the page doesn't count
+                entry.count_navigations();
+                entry.navigations = navigations;
+                self.shared.release(entry);
+                Ok(())
+            }
+            Err(err) => {
+                self.close(entry).await;
+                Err(err)
+            }
+        }
+    }
+
+    /// Stops handing out pages, waits until all checked out pages are
+    /// returned and closes all pages of the pool.
+    pub async fn drain(&self) -> Result<()> {
+        while let Some(rx) = self.try_drain() {
+            let _ = rx.await;
+        }
+        let idle: Vec<_> = self.shared.state.lock().unwrap().idle.drain(..).collect();
+        for entry in idle {
+            self.close(entry).await;
+        }
+        Ok(())
+    }
+
+    /// Marks the pool as draining and returns a receiver to wait on if pages
+    /// are still checked out
+    fn try_drain(&self) -> Option<OneshotReceiver<()>> {
+        let mut state = self.shared.state.lock().unwrap();
+        state.draining = true;
+        // wake all waiting checkouts, so that they fail
+        state.notify();
+        if state.idle.len() >= state.open {
+            return None;
+        }
+        let (tx, rx) = oneshot_channel();
+        state.waiters.push(tx);
+        Some(rx)
+    }
+
+    fn pooled(&self, entry: PoolEntry) -> PooledPage {
+        PooledPage {
+            entry: Some(entry),
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    async fn open_page(&self) -> Result<PoolEntry> {
+        let mut params = CreateTargetParams::new(self.shared.config.blank_url.clone());
+        params.browser_context_id = self.shared.config.browser_context_id.clone();
+        let (tx, rx) = oneshot_channel();
+        self.shared
+            .sender
+            .clone()
+            .send(HandlerMessage::CreatePage(params, tx))
+            .await?;
+        let page = rx.await??;
+        let frame_navigated = page.event_listener().await?;
+        Ok(PoolEntry {
+            page,
+            navigations: 0,
+            frame_navigated,
+        })
+    }
+
+    /// Whether the page exceeded the `max_navigations`
+    fn is_exhausted(&self, entry: &mut PoolEntry) -> bool {
+        entry.count_navigations();
+        self.shared
+            .config
+            .max_navigations
+            .map(|max| entry.navigations >= max)
+            .unwrap_or_default()
+    }
+
+    /// Whether the page still responds in time
+    async fn is_healthy(&self, page: &Page) -> bool {
+        page.execute_with_timeout(
+            EvaluateParams::new("1"),
+            self.shared.config.health_check_timeout,
+        )
+        .await
+        .is_ok()
+    }
+
+    async fn close(&self, entry: PoolEntry) {
+        self.shared.discard();
+        let target_id = entry.page.target_id().clone();
+        let _ = entry.page.execute(CloseTargetParams::new(target_id)).await;
+    }
+}
+
+/// A page that is checked out of a [`BrowserPool`].
+///
+/// Dereferences to the [`Page`]. Dropping it returns the page to the pool
+/// as it is, without resetting it, use [`BrowserPool::checkin`] instead.
+/// Pages that exceeded the [`PoolConfig::max_navigations`] are replaced on
+/// their next checkout.
+#[derive(Debug)]
+pub struct PooledPage {
+    entry: Option<PoolEntry>,
+    shared: Arc<Shared>,
+}
+
+impl PooledPage {
+    /// How often the main frame of the page navigated since it was opened
+    pub fn navigations(&mut self) -> usize {
+        match self.entry.as_mut() {
+            Some(entry) => {
+                entry.count_navigations();
+                entry.navigations
+            }
+            None => 0,
+        }
+    }
+}
+
+impl Deref for PooledPage {
+    type Target = Page;
+
+    fn deref(&self) -> &Self::Target {
+        &self.entry.as_ref().expect("checked in").page
+    }
+}
+
+impl Drop for PooledPage {
+    fn drop(&mut self) {
+        if let Some(entry) = self.entry.take() {
+            self.shared.release(entry);
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//! Exports requests observed in the browser, so they can be replayed without
+//! it, like with `curl` or any http client built on the [`http`] crate.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use futures::StreamExt;
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::replay::RequestExt;
+//! # use chromiumoxide::cdp::browser_protocol::network::EventRequestWillBeSent;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     let mut requests = page.event_listener::<EventRequestWillBeSent>().await?;
+//!     page.goto("https://example.com").await?;
+//!     while let Some(event) = requests.next().await {
+//!         println!("{}", event.request.as_curl()?);
+//!         let request = event.request.as_http_parts()?;
+//!     }
+//!     # Ok(())
+//! # }
+//! ```
+use chromiumoxide_cdp::cdp::browser_protocol::network::Request;
+
+pub use http;
+
+use crate::error::{CdpError, Result};
+
+/// Converts a request of the devtools protocol into formats that can be
+/// replayed outside of the browser.
+///
+/// Implemented for the [`Request`] of `Network` events and of intercepted
+/// requests, see
+/// [`InterceptedRequest::request`](crate::interception::InterceptedRequest::request).
+pub trait RequestExt {
+    /// The request as `curl` command line, quoted for POSIX shells.
+    ///
+    /// Request bodies that are not valid UTF-8 are converted lossily. Fails
+    /// if the request has a body that is not part of the request, see
+    /// [`Request::has_post_data`].
+    fn as_curl(&self) -> Result<String>;
+
+    /// The request as [`http::Request`], with the post data as body
+    ///
+    /// Fails if the request has a body that is not part of the request, see
+    /// [`Request::has_post_data`].
+    fn as_http_parts(&self) -> Result<http::Request<Vec<u8>>>;
+}
+
+impl RequestExt for Request {
+    fn as_curl(&self) -> Result<String> {
+        let body = body(self)?;
+        let mut args = vec![shell_quote(&self.url)];
+        if (self.method != "GET" || body.is_some()) && (self.method != "POST" || body.is_none()) {
+            args.push(format!("-X {}", shell_quote(&self.method)));
+        }
+        for (name, value) in headers(self) {
+            args.push(format!(
+                "-H {}",
+                shell_quote(&format!("{}: {}", name, value))
+            ));
+        }
+        if let Some(body) = body {
+            args.push(format!(
+                "--data-raw {}",
+                shell_quote(&String::from_utf8_lossy(&body))
+            ));
+        }
+        Ok(format!("curl {}", args.join(" \\\n  ")))
+    }
+
+    fn as_http_parts(&self) -> Result<http::Request<Vec<u8>>> {
+        let mut builder = http::Request::builder()
+            .method(self.method.as_str())
+            .uri(self.url.as_str());
+        for (name, value) in headers(self) {
+            builder = builder.header(name, value);
+        }
+        builder
+            .body(body(self)?.unwrap_or_default())
+            .map_err(|err| CdpError::msg(format!("Invalid request: {}", err)))
+    }
+}
+
+/// The headers of the request, values that were merged by the browser are
+/// split again. Pseudo headers of HTTP/2, like `:authority`, are omitted.
+fn headers(request: &Request) -> Vec<(&str, &str)> {
+    let mut headers = Vec::new();
+    if let Some(map) = request.headers.inner().as_object() {
+        for (name, value) in map.iter().filter(|(name, _)| !name.starts_with(':')) {
+            if let Some(value) = value.as_str() {
+                headers.extend(value.split('\n').map(|value| (name.as_str(), value)));
+            }
+        }
+    }
+    headers
+}
+
+/// The post data of the request, `None` if it has none.
+///
+/// Fails if the post data was not included in the event, like large bodies
+/// that need to be requested via `Network.getRequestPostData`.
+fn body(request: &Request) -> Result<Option<Vec<u8>>> {
+    if let Some(data) = request.post_data.as_ref() {
+        return Ok(Some(data.clone().into_bytes()));
+    }
+    if let Some(entries) = request.post_data_entries.as_ref() {
+        let mut body = Vec::new();
+        for bytes in entries.iter().filter_map(|entry| entry.bytes.as_ref()) {
+            let bytes: &str = bytes.as_ref();
+            body.extend(base64::decode(bytes)?);
+        }
+        return Ok(Some(body));
+    }
+    if request.has_post_data.unwrap_or_default() {
+        return Err(CdpError::msg(format!(
+            "The post data of the request to {} was not included",
+            request.url
+        )));
+    }
+    Ok(None)
+}
+
+/// Quotes the `s` in single quotes, so a POSIX shell passes it on verbatim
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r#"'\''"#))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chromiumoxide_cdp::cdp::browser_protocol::network::PostDataEntry;
+
+    fn request(method: &str, headers: serde_json::Value, post_data: Option<&str>) -> Request {
+        serde_json::from_value(serde_json::json!({
+            "url": "https://example.com/",
+            "method": method,
+            "headers": headers,
+            "postData": post_data,
+            "initialPriority": "High",
+            "referrerPolicy": "no-referrer",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn body_from_post_data_entries() {
+        let mut req = request("POST", serde_json::json!({}), None);
+        req.has_post_data = Some(true);
+        req.post_data_entries = Some(vec![
+            PostDataEntry::builder().bytes(base64::encode("a=")).build(),
+            PostDataEntry::builder().bytes(base64::encode("1")).build(),
+        ]);
+        assert_eq!(body(&req).unwrap(), Some(b"a=1".to_vec()));
+    }
+
+    #[test]
+    fn body_fails_if_not_decodable() {
+        let mut req = request("POST", serde_json::json!({}), None);
+        req.post_data_entries = Some(vec![PostDataEntry::builder()
+            .bytes("%%%".to_string())
+            .build()]);
+        assert!(body(&req).is_err());
+    }
+
+    #[test]
+    fn body_fails_if_not_included() {
+        let mut req = request("POST", serde_json::json!({}), None);
+        req.has_post_data = Some(true);
+        assert!(req.as_http_parts().is_err());
+        assert!(req.as_curl().is_err());
+    }
+
+    #[test]
+    fn curl_get() {
+        let req = request("GET", serde_json::json!({}), None);
+        assert_eq!(req.as_curl().unwrap(), "curl 'https://example.com/'");
+    }
+
+    #[test]
+    fn curl_post_with_body() {
+        let req = request("POST", serde_json::json!({}), Some("a=1"));
+        assert_eq!(
+            req.as_curl().unwrap(),
+            "curl 'https://example.com/' \\\n  --data-raw 'a=1'"
+        );
+    }
+
+    #[test]
+    fn curl_get_with_body() {
+        let req = request("GET", serde_json::json!({}), Some("a=1"));
+        assert_eq!(
+            req.as_curl().unwrap(),
+            "curl 'https://example.com/' \\\n  -X 'GET' \\\n  --data-raw 'a=1'"
+        );
+    }
+
+    #[test]
+    fn curl_quotes_header_values() {
+        let req = request("GET", serde_json::json!({ "X-Name": "O'Brien" }), None);
+        assert_eq!(
+            req.as_curl().unwrap(),
+            "curl 'https://example.com/' \\\n  -H 'X-Name: O'\\''Brien'"
+        );
+    }
+
+    #[test]
+    fn curl_splits_merged_headers() {
+        let req = request("GET", serde_json::json!({ "Cookie": "a=1\nb=2" }), None);
+        assert_eq!(
+            req.as_curl().unwrap(),
+            "curl 'https://example.com/' \\\n  -H 'Cookie: a=1' \\\n  -H 'Cookie: b=2'"
+        );
+    }
+
+    #[test]
+    fn curl_skips_pseudo_headers() {
+        let req = request(
+            "GET",
+            serde_json::json!({ ":authority": "example.com", "Accept": "*/*" }),
+            None,
+        );
+        assert_eq!(
+            req.as_curl().unwrap(),
+            "curl 'https://example.com/' \\\n  -H 'Accept: */*'"
+        );
+    }
+}
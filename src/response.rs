@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use chromiumoxide_cdp::cdp::browser_protocol::network::{
+    self, EventResponseReceived, GetResponseBodyParams, Headers, LoaderId, RequestId, ResourceType,
+    SecurityDetails,
+};
+
+use crate::error::Result;
+use crate::handler::PageInner;
+
+/// A response that was received by a `Page`, see `Page::wait_for_response`.
+#[derive(Debug, Clone)]
+pub struct Response {
+    page: Arc<PageInner>,
+    event: Arc<EventResponseReceived>,
+}
+
+impl Response {
+    pub(crate) fn new(page: Arc<PageInner>, event: Arc<EventResponseReceived>) -> Self {
+        Self { page, event }
+    }
+
+    /// The identifier of the request this response belongs to
+    pub fn request_id(&self) -> &RequestId {
+        &self.event.request_id
+    }
+
+    /// The url of the response
+    pub fn url(&self) -> &str {
+        &self.event.response.url
+    }
+
+    /// The HTTP status code of the response
+    pub fn status(&self) -> i64 {
+        self.event.response.status
+    }
+
+    /// The HTTP response headers
+    pub fn headers(&self) -> &Headers {
+        &self.event.response.headers
+    }
+
+    /// How the requested resource is used, like `XHR` or `Fetch`
+    pub fn resource_type(&self) -> &ResourceType {
+        &self.event.r#type
+    }
+
+    /// The complete response data as received by the browser
+    pub fn response(&self) -> &network::Response {
+        &self.event.response
+    }
+
+    /// The details of the secure connection the response was received over,
+    /// like the protocol, the cipher and the validity of the certificate.
+    ///
+    /// `None` for responses that were not received via TLS or QUIC. The
+    /// complete certificate chain is returned by `Page::certificate`.
+    pub fn security_details(&self) -> Option<&SecurityDetails> {
+        self.event.response.security_details.as_ref()
+    }
+
+    /// Returns the decoded content of the response body.
+    ///
+    /// Fails if the browser already evicted the body from its cache.
+    pub async fn body(&self) -> Result<Vec<u8>> {
+        let resp = self
+            .page
+            .execute(GetResponseBodyParams::new(self.request_id().clone()))
+            .await?
+            .result;
+        if resp.base64_encoded {
+            Ok(base64::decode(&resp.body)?)
+        } else {
+            Ok(resp.body.into_bytes())
+        }
+    }
+
+    /// Returns the content of the response body as utf-8 string
+    pub async fn text(&self) -> Result<String> {
+        let body = self.body().await?;
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+}
+
+/// The outcome of a navigation, see `Page::goto_with_result`.
+///
+/// The responses belong to the document the navigation loaded, responses of
+/// other navigations that raced it, like a redirect via javascript, are not
+/// included.
+#[derive(Debug, Clone)]
+pub struct NavigationResult {
+    /// The loader of the new document, `None` if the navigation stayed within
+    /// the same document
+    pub loader_id: Option<LoaderId>,
+    /// The url of the page once the navigation finished
+    pub url: Option<String>,
+    /// The redirect responses that led to the document, in the order they
+    /// were received
+    pub redirect_chain: Vec<network::Response>,
+    /// The final response of the document, `None` for navigations within
+    /// the same document or documents that are not loaded over the network,
+    /// like `about:blank`
+    pub response: Option<Response>,
+}
+
+impl NavigationResult {
+    /// The HTTP status code of the final response
+    pub fn status(&self) -> Option<i64> {
+        self.response.as_ref().map(Response::status)
+    }
+
+    /// Whether the navigation followed any redirects
+    pub fn is_redirected(&self) -> bool {
+        !self.redirect_chain.is_empty()
+    }
+}
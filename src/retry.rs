@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use futures::Future;
+use futures_timer::Delay;
+
+use crate::error::{CdpError, Result};
+
+/// Error messages of chromium that are usually transient, like a command
+/// that was sent while the page's context was being replaced
+pub const TRANSIENT_ERRORS: [&str; 4] = [
+    "Target closed",
+    "Inspected target navigated or closed",
+    "Session with given id not found",
+    "Cannot find context with specified id",
+];
+
+/// When and how often to retry commands that failed with a transient error
+/// of chromium.
+///
+/// Retries are delayed with an exponential backoff, starting with the
+/// `initial_backoff` and doubled after every attempt up to the
+/// `max_backoff`.
+///
+/// # Example
+///
+/// ```no_run
+/// # use chromiumoxide::browser::{Browser, BrowserConfig};
+/// # use chromiumoxide::retry::RetryPolicy;
+/// # use std::time::Duration;
+/// # async fn demo() {
+///     let config = BrowserConfig::builder()
+///         .retry_policy(RetryPolicy {
+///             max_retries: 5,
+///             ..Default::default()
+///         })
+///         .build()
+///         .unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// How often a command is retried at most
+    pub max_retries: usize,
+    /// The delay before the first retry
+    pub initial_backoff: Duration,
+    /// The maximum delay between two retries
+    pub max_backoff: Duration,
+    /// Substrings of the error messages of chromium that are retried, see
+    /// [`TRANSIENT_ERRORS`]
+    pub transient_errors: Vec<String>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            transient_errors: TRANSIENT_ERRORS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether the `err` is an error of chromium that is worth retrying
+    pub fn is_transient(&self, err: &CdpError) -> bool {
+        match err {
+            CdpError::Chrome(err) => self
+                .transient_errors
+                .iter()
+                .any(|msg| err.message.contains(msg.as_str())),
+            _ => false,
+        }
+    }
+
+    /// The delay before the retry after `attempt` failed attempts
+    pub fn backoff(&self, attempt: usize) -> Duration {
+        let factor = 1u32.checked_shl(attempt as u32).unwrap_or(u32::MAX);
+        self.initial_backoff
+            .checked_mul(factor)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+}
+
+/// Runs the command created by `f` and retries it according to the `policy`
+/// as long as it fails with a transient error
+pub(crate) async fn with_retries<F, Fut, R>(policy: Option<&RetryPolicy>, mut f: F) -> Result<R>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<R>>,
+{
+    let mut attempt = 0;
+    loop {
+        match f().await {
+            Err(err) => match policy {
+                Some(policy) if attempt < policy.max_retries && policy.is_transient(&err) => {
+                    log::debug!("Retrying command after transient error: {}", err);
+                    Delay::new(policy.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                _ => return Err(err),
+            },
+            res => return res,
+        }
+    }
+}
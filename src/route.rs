@@ -0,0 +1,368 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use futures::StreamExt;
+
+use chromiumoxide_cdp::cdp::browser_protocol::fetch::{HeaderEntry, RequestPattern, RequestStage};
+use chromiumoxide_cdp::cdp::browser_protocol::network::{ErrorReason, ResourceType};
+
+#[cfg(feature = "http-cache")]
+use crate::http_cache::ResponseCache;
+use crate::interception::{InterceptResponse, InterceptedRequest, RequestInterception};
+use crate::utils;
+
+/// A canned response that requests matched by `Page::route` are fulfilled
+/// with, instead of sending them to the network.
+///
+/// # Example
+///
+/// ```no_run
+/// # use chromiumoxide::page::Page;
+/// # use chromiumoxide::error::Result;
+/// # use chromiumoxide::route::Fixture;
+/// # async fn demo(page: Page) -> Result<()> {
+///     page.route("*/api/users*", Fixture::file("fixtures/users.json"))
+///         .await?;
+///     page.route(
+///         "*/api/login",
+///         Fixture::bytes(r#"{"error":"locked"}"#)
+///             .status(403)
+///             .content_type("application/json"),
+///     )
+///     .await?;
+///     page.goto("https://example.com").await?;
+///     # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    status: i64,
+    headers: Vec<HeaderEntry>,
+    body: FixtureBody,
+}
+
+#[derive(Debug, Clone)]
+enum FixtureBody {
+    /// The body is read from the file every time it is served
+    File(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+impl Fixture {
+    /// A `200` response with the content of the file at `path` as body.
+    ///
+    /// The file is read whenever a request is fulfilled, so it may change
+    /// between requests. The `Content-Type` is derived from the extension of
+    /// the file, unless it is set explicitly. Missing files are answered with
+    /// a `404`.
+    pub fn file(path: impl AsRef<Path>) -> Self {
+        Self::new(FixtureBody::File(path.as_ref().to_path_buf()))
+    }
+
+    /// A `200` response with the `body`
+    pub fn bytes(body: impl Into<Vec<u8>>) -> Self {
+        Self::new(FixtureBody::Bytes(body.into()))
+    }
+
+    fn new(body: FixtureBody) -> Self {
+        Self {
+            status: 200,
+            headers: Vec::new(),
+            body,
+        }
+    }
+
+    /// Set the HTTP status code of the response
+    pub fn status(mut self, status: i64) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Add a response header
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push(HeaderEntry::new(name, value));
+        self
+    }
+
+    /// Set the `Content-Type` header of the response
+    pub fn content_type(self, content_type: impl Into<String>) -> Self {
+        self.header("Content-Type", content_type)
+    }
+
+    /// Reads the body and creates the response
+    async fn response(&self) -> InterceptResponse {
+        let mut headers = self.headers.clone();
+        let (status, body) = match &self.body {
+            FixtureBody::Bytes(body) => (self.status, body.clone()),
+            FixtureBody::File(path) => {
+                let read = path.clone();
+                match utils::spawn_blocking(move || std::fs::read(read)).await {
+                    Ok(body) => {
+                        if !has_content_type(&headers) {
+                            if let Some(content_type) = content_type_of(path) {
+                                headers.push(HeaderEntry::new("Content-Type", content_type));
+                            }
+                        }
+                        (self.status, body)
+                    }
+                    Err(err) => {
+                        log::warn!("Failed to read fixture {:?}: {}", path, err);
+                        (404, Vec::new())
+                    }
+                }
+            }
+        };
+        InterceptResponse {
+            status,
+            headers,
+            body: Some(body),
+            phrase: None,
+        }
+    }
+}
+
+/// Handles all requests whose url matches the glob `pattern` and that load a
+/// resource of the `resource_type`, if set
+#[derive(Debug, Clone)]
+pub(crate) struct Route {
+    pub pattern: String,
+    pub resource_type: Option<ResourceType>,
+    pub action: RouteAction,
+}
+
+impl Route {
+    /// Fulfills the requests that match the `pattern` with the `fixture`
+    pub fn fulfill(pattern: String, fixture: Fixture) -> Self {
+        Self {
+            pattern,
+            resource_type: None,
+            action: RouteAction::Fulfill(fixture),
+        }
+    }
+
+    /// Fulfills the requests that match the `pattern` from the `cache` and
+    /// stores the responses of all others
+    #[cfg(feature = "http-cache")]
+    pub fn cache(pattern: String, cache: ResponseCache) -> Self {
+        Self {
+            pattern,
+            resource_type: None,
+            action: RouteAction::Cache(cache),
+        }
+    }
+
+    /// Fails all requests for resources of the `resource_type`
+    pub fn block(resource_type: ResourceType) -> Self {
+        Self {
+            pattern: "*".to_string(),
+            resource_type: Some(resource_type),
+            action: RouteAction::Block,
+        }
+    }
+
+    /// The `Fetch` patterns that pause the requests of this route, cached
+    /// requests are also paused at the response stage
+    pub fn request_patterns(&self) -> Vec<RequestPattern> {
+        let pattern = |stage: RequestStage| {
+            let mut pattern = RequestPattern::builder()
+                .url_pattern(self.pattern.clone())
+                .request_stage(stage);
+            if let Some(resource_type) = self.resource_type.clone() {
+                pattern = pattern.resource_type(resource_type);
+            }
+            pattern.build()
+        };
+        if self.is_response_stage() {
+            vec![
+                pattern(RequestStage::Request),
+                pattern(RequestStage::Response),
+            ]
+        } else {
+            vec![pattern(RequestStage::Request)]
+        }
+    }
+
+    /// Whether the route also handles requests at the response stage
+    fn is_response_stage(&self) -> bool {
+        match self.action {
+            #[cfg(feature = "http-cache")]
+            RouteAction::Cache(_) => true,
+            _ => false,
+        }
+    }
+
+    fn matches(&self, request: &InterceptedRequest) -> bool {
+        self.resource_type
+            .as_ref()
+            .map(|ty| ty == request.resource_type())
+            .unwrap_or(true)
+            && glob_matches(&self.pattern, &request.request().url)
+    }
+}
+
+/// What happens to the requests of a route
+#[derive(Debug, Clone)]
+pub(crate) enum RouteAction {
+    /// Fulfill the request with the fixture
+    Fulfill(Fixture),
+    /// Fail the request as if it was blocked by the client
+    Block,
+    /// Fulfill the request from the cache or store its response
+    #[cfg(feature = "http-cache")]
+    Cache(ResponseCache),
+}
+
+/// The routes of a page, the most recently added route takes precedence
+pub(crate) type Routes = Arc<Mutex<Vec<Route>>>;
+
+/// Handles the paused requests with the action of the matching route and
+/// continues all other requests, until the page is closed
+pub(crate) async fn serve(mut interception: RequestInterception, routes: Routes) {
+    while let Some(request) = interception.next().await {
+        let action = find_action(&routes, &request);
+        let res = match action {
+            Some(RouteAction::Fulfill(fixture)) => request.fulfill(fixture.response().await).await,
+            Some(RouteAction::Block) => request.fail(ErrorReason::BlockedByClient).await,
+            #[cfg(feature = "http-cache")]
+            Some(RouteAction::Cache(cache)) => cache.handle(request).await,
+            None => request.continue_request().await,
+        };
+        if let Err(err) = res {
+            log::debug!("Failed to handle a routed request: {}", err);
+        }
+    }
+}
+
+fn find_action(routes: &Routes, request: &InterceptedRequest) -> Option<RouteAction> {
+    if request.auth_challenge().is_some() {
+        return None;
+    }
+    let response_stage = request.is_response_stage();
+    routes
+        .lock()
+        .unwrap()
+        .iter()
+        .rev()
+        .filter(|route| !response_stage || route.is_response_stage())
+        .find(|route| route.matches(request))
+        .map(|route| route.action.clone())
+}
+
+/// Whether the `url` matches the `pattern`, where `*` matches any number of
+/// characters and `?` exactly one, like the url patterns of the `Fetch`
+/// domain
+pub(crate) fn glob_matches(pattern: &str, url: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let url: Vec<char> = url.chars().collect();
+    let (mut p, mut u) = (0, 0);
+    // the position of the last `*` in the pattern and the url at that time
+    let mut backtrack = None;
+    while u < url.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, u));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == url[u] => {
+                p += 1;
+                u += 1;
+            }
+            _ => match backtrack {
+                // let the last `*` consume one more character
+                Some((star, matched)) => {
+                    p = star + 1;
+                    u = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn has_content_type(headers: &[HeaderEntry]) -> bool {
+    headers
+        .iter()
+        .any(|h| h.name.eq_ignore_ascii_case("content-type"))
+}
+
+/// The `Content-Type` of common file extensions
+fn content_type_of(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    let content_type = match ext.as_str() {
+        "json" => "application/json",
+        "html" | "htm" => "text/html; charset=utf-8",
+        "js" | "mjs" => "application/javascript",
+        "css" => "text/css",
+        "txt" => "text/plain; charset=utf-8",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "woff2" => "font/woff2",
+        _ => return None,
+    };
+    Some(content_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_wildcards() {
+        assert!(glob_matches("*", "https://example.com/"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches(
+            "https://example.com/?",
+            "https://example.com/a"
+        ));
+        assert!(!glob_matches(
+            "https://example.com/?",
+            "https://example.com/"
+        ));
+        assert!(!glob_matches(
+            "https://example.com/?",
+            "https://example.com/ab"
+        ));
+        assert!(glob_matches("*/api/*", "https://example.com/api/users"));
+        assert!(!glob_matches("*/api/*", "https://example.com/users"));
+    }
+
+    #[test]
+    fn glob_trailing_star() {
+        assert!(glob_matches(
+            "https://example.com/*",
+            "https://example.com/"
+        ));
+        assert!(glob_matches(
+            "https://example.com/**",
+            "https://example.com/a/b"
+        ));
+        assert!(!glob_matches(
+            "https://example.com/*",
+            "https://example.org/"
+        ));
+    }
+
+    #[test]
+    fn glob_empty_pattern() {
+        assert!(glob_matches("", ""));
+        assert!(!glob_matches("", "https://example.com/"));
+    }
+
+    #[test]
+    fn glob_backtracks_multiple_stars() {
+        assert!(glob_matches("*a*b*c", "xaxbxaxbxc"));
+        assert!(glob_matches(
+            "*.js?v=*",
+            "https://cdn.com/a.json/app.js?v=3"
+        ));
+        assert!(!glob_matches("*a*b*c", "xaxbxaxbx"));
+        assert!(glob_matches("*?*", "a"));
+        assert!(!glob_matches("*??*", "a"));
+    }
+}
@@ -0,0 +1,85 @@
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use chromiumoxide_cdp::cdp::js_protocol::runtime::EvaluateParams;
+
+use crate::error::{CdpError, Result};
+use crate::handler::PageInner;
+
+/// A link of a page, as returned by `Page::extract_links`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+pub struct Link {
+    /// The absolute url the link points to
+    pub href: String,
+    /// The visible text of the link, with leading and trailing whitespace
+    /// removed
+    pub text: String,
+}
+
+/// Returns all the `<a href>` and `<area href>` links of the document in
+/// document order
+pub(crate) async fn extract_links(page: &PageInner) -> Result<Vec<Link>> {
+    evaluate(
+        page,
+        "Array.from(document.querySelectorAll('a[href], area[href]'), link => ({
+            href: link.href,
+            text: (link.innerText || link.textContent || '').trim(),
+        }))"
+        .to_string(),
+    )
+    .await
+}
+
+/// Returns the text of the cells of the first table that matches the
+/// `selector`, row by row.
+///
+/// Header and body rows are included in document order, `colspan`s are not
+/// expanded.
+pub(crate) async fn extract_table(page: &PageInner, selector: &str) -> Result<Vec<Vec<String>>> {
+    evaluate::<Option<Vec<Vec<String>>>>(
+        page,
+        format!(
+            "(() => {{
+                const table = document.querySelector({});
+                if (!table) return null;
+                return Array.from(table.rows || table.querySelectorAll('tr'), row =>
+                    Array.from(row.cells || row.querySelectorAll('th, td'), cell =>
+                        cell.innerText.trim()));
+            }})()",
+            serde_json::Value::from(selector)
+        ),
+    )
+    .await?
+    .ok_or(CdpError::NotFound)
+}
+
+/// Returns the rendered text of the first element that matches the
+/// `selector`
+pub(crate) async fn inner_text(page: &PageInner, selector: &str) -> Result<String> {
+    evaluate::<Option<String>>(
+        page,
+        format!(
+            "(() => {{
+                const element = document.querySelector({});
+                return element ? element.innerText : null;
+            }})()",
+            serde_json::Value::from(selector)
+        ),
+    )
+    .await?
+    .ok_or(CdpError::NotFound)
+}
+
+/// Evaluates the `expression` in the page and deserializes its value
+async fn evaluate<T: DeserializeOwned>(page: &PageInner, expression: String) -> Result<T> {
+    let mut params = EvaluateParams::new(expression);
+    params.return_by_value = Some(true);
+    let resp = page.execute(params).await?.result;
+    if let Some(details) = resp.exception_details {
+        let description = details.exception.and_then(|ex| ex.description);
+        return Err(CdpError::msg(description.unwrap_or(details.text)));
+    }
+    Ok(serde_json::from_value(
+        resp.result.value.unwrap_or(serde_json::Value::Null),
+    )?)
+}
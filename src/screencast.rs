@@ -0,0 +1,151 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use futures::stream::FuturesUnordered;
+use futures::task::{Context, Poll};
+use futures::{FutureExt, Stream, StreamExt};
+
+use chromiumoxide_cdp::cdp::browser_protocol::page::{
+    EventScreencastFrame, ScreencastFrameAckParams, ScreencastFrameMetadata, StartScreencastFormat,
+    StopScreencastParams,
+};
+
+use crate::error::Result;
+use crate::handler::PageInner;
+use crate::listeners::EventStream;
+use crate::utils;
+
+/// A `Stream` over the frames of a running screencast of a `Page`.
+///
+/// Every frame is acknowledged once it is yielded by the stream, the browser
+/// sends the next frame only after the previous one was acknowledged. The
+/// screencast should be ended with [`Screencast::stop`].
+#[must_use = "streams do nothing unless polled"]
+pub struct Screencast {
+    page: Arc<PageInner>,
+    format: StartScreencastFormat,
+    frames: EventStream<EventScreencastFrame>,
+    /// The pending acknowledgements of the yielded frames
+    acks: FuturesUnordered<BoxFuture<'static, Result<()>>>,
+}
+
+impl Screencast {
+    pub(crate) fn new(
+        page: Arc<PageInner>,
+        format: StartScreencastFormat,
+        frames: EventStream<EventScreencastFrame>,
+    ) -> Self {
+        Self {
+            page,
+            format,
+            frames,
+            acks: FuturesUnordered::new(),
+        }
+    }
+
+    /// The image format of the frames
+    pub fn format(&self) -> &StartScreencastFormat {
+        &self.format
+    }
+
+    /// Writes the next `num_frames` frames as numbered image files to the `dir`
+    /// directory and returns their paths.
+    ///
+    /// The files are named `frame-00000.png`, `frame-00001.png`, ... so that
+    /// they can be turned into a video afterwards, for example with `ffmpeg -i
+    /// frame-%05d.png out.mp4`.
+    pub async fn save_frames(
+        &mut self,
+        dir: impl AsRef<Path>,
+        num_frames: usize,
+    ) -> Result<Vec<PathBuf>> {
+        let extension = match self.format {
+            StartScreencastFormat::Jpeg => "jpeg",
+            StartScreencastFormat::Png => "png",
+        };
+        let mut paths = Vec::with_capacity(num_frames);
+        for idx in 0..num_frames {
+            if let Some(frame) = self.next().await {
+                let path = dir.as_ref().join(format!("frame-{:05}.{}", idx, extension));
+                frame.save(&path).await?;
+                paths.push(path);
+            } else {
+                break;
+            }
+        }
+        Ok(paths)
+    }
+
+    /// Stops the screencast
+    pub async fn stop(mut self) -> Result<()> {
+        while let Some(ack) = self.acks.next().await {
+            ack?;
+        }
+        self.page.execute(StopScreencastParams::default()).await?;
+        Ok(())
+    }
+}
+
+impl Stream for Screencast {
+    type Item = ScreencastFrame;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        while let Poll::Ready(Some(_)) = pin.acks.poll_next_unpin(cx) {}
+        loop {
+            match Pin::new(&mut pin.frames).poll_next(cx) {
+                Poll::Ready(Some(ev)) => {
+                    let page = Arc::clone(&pin.page);
+                    let params = ScreencastFrameAckParams::new(ev.session_id);
+                    pin.acks.push(
+                        async move {
+                            page.execute(params).await?;
+                            Ok(())
+                        }
+                        .boxed(),
+                    );
+                    while let Poll::Ready(Some(_)) = pin.acks.poll_next_unpin(cx) {}
+                    // skip frames that are not valid base64
+                    if let Ok(data) = base64::decode(&ev.data) {
+                        return Poll::Ready(Some(ScreencastFrame {
+                            data,
+                            metadata: ev.metadata.clone(),
+                        }));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Screencast {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Screencast")
+            .field("page", &self.page)
+            .field("format", &self.format)
+            .field("frames", &self.frames)
+            .finish()
+    }
+}
+
+/// A single frame of a screencast
+#[derive(Debug, Clone)]
+pub struct ScreencastFrame {
+    /// The decoded image
+    pub data: Vec<u8>,
+    /// Information about the page at the time the frame was captured
+    pub metadata: ScreencastFrameMetadata,
+}
+
+impl ScreencastFrame {
+    /// Writes the image of this frame to the file at `path`
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        utils::write(path.as_ref(), &self.data).await?;
+        Ok(())
+    }
+}
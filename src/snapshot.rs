@@ -0,0 +1,340 @@
+use std::collections::HashMap;
+
+use chromiumoxide_cdp::cdp::browser_protocol::dom::BackendNodeId;
+use chromiumoxide_cdp::cdp::browser_protocol::dom_snapshot::{
+    ArrayOfStrings, CaptureSnapshotReturns, DocumentSnapshot, RareBooleanData, RareIntegerData,
+    RareStringData, Rectangle, StringIndex,
+};
+
+use crate::layout::BoundingBox;
+
+/// A snapshot of the documents of a page, their DOM trees, layouts and text
+/// boxes, as captured by `DOMSnapshot.captureSnapshot`.
+///
+/// All the strings of the snapshot are resolved.
+#[derive(Debug, Clone)]
+pub struct DomSnapshot {
+    /// The main document of the page followed by the documents of its
+    /// iframes
+    pub documents: Vec<SnapshotDocument>,
+}
+
+/// A document of a `DomSnapshot`
+#[derive(Debug, Clone)]
+pub struct SnapshotDocument {
+    /// The url of the document
+    pub url: String,
+    /// The title of the document
+    pub title: String,
+    /// The base url of the document
+    pub base_url: String,
+    /// The id of the frame that owns the document
+    pub frame_id: String,
+    /// All the nodes of the document in document order
+    pub nodes: Vec<SnapshotNode>,
+    /// The nodes that are laid out, with their computed styles
+    pub layouts: Vec<SnapshotLayout>,
+    /// The boxes of the text that was laid out
+    pub text_boxes: Vec<SnapshotTextBox>,
+    /// The horizontal scroll offset of the document
+    pub scroll_offset_x: Option<f64>,
+    /// The vertical scroll offset of the document
+    pub scroll_offset_y: Option<f64>,
+    /// The width of the document's content
+    pub content_width: Option<f64>,
+    /// The height of the document's content
+    pub content_height: Option<f64>,
+}
+
+/// A DOM node of a `SnapshotDocument`
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotNode {
+    /// The index of the parent node in `SnapshotDocument::nodes`
+    pub parent_index: Option<usize>,
+    /// The type of the node, like `1` for elements and `3` for text
+    pub node_type: i64,
+    /// The name of the node, like `DIV` or `#text`
+    pub node_name: String,
+    /// The value of the node, like the content of a text node
+    pub node_value: String,
+    /// The id of the node in the browser, that stays the same for the
+    /// lifetime of the node
+    pub backend_node_id: Option<BackendNodeId>,
+    /// The attributes of an element as name and value
+    pub attributes: Vec<(String, String)>,
+    /// The text of a `textarea`
+    pub text_value: Option<String>,
+    /// The value of an `input`
+    pub input_value: Option<String>,
+    /// Whether the checkbox or radio button is checked
+    pub input_checked: bool,
+    /// Whether the `option` is selected
+    pub option_selected: bool,
+    /// The index of the document of an iframe in `DomSnapshot::documents`
+    pub content_document_index: Option<usize>,
+    /// Whether the node has a click listener or is clickable by default,
+    /// like a link
+    pub is_clickable: bool,
+    /// The index of the node's layout in `SnapshotDocument::layouts`, if the
+    /// node was laid out
+    pub layout_index: Option<usize>,
+}
+
+/// The layout of a node in a `SnapshotDocument`
+#[derive(Debug, Clone)]
+pub struct SnapshotLayout {
+    /// The index of the node in `SnapshotDocument::nodes`
+    pub node_index: usize,
+    /// The computed styles that were requested, by their name
+    pub styles: HashMap<String, String>,
+    /// The absolute position and size of the node
+    pub bounds: BoundingBox,
+    /// The text of a text node
+    pub text: String,
+    /// The order the node was painted in, if requested
+    pub paint_order: Option<i64>,
+}
+
+/// A box of laid out text in a `SnapshotDocument`
+#[derive(Debug, Clone)]
+pub struct SnapshotTextBox {
+    /// The index of the text's layout in `SnapshotDocument::layouts`
+    pub layout_index: usize,
+    /// The absolute position and size of the box
+    pub bounds: BoundingBox,
+    /// The offset of the box's text in the text of the layout
+    pub start: usize,
+    /// The length of the box's text
+    pub length: usize,
+}
+
+impl DomSnapshot {
+    /// Resolves the snapshot, the `computed_styles` are the names of the
+    /// styles requested for the snapshot in the order they were requested.
+    pub(crate) fn new(snapshot: CaptureSnapshotReturns, computed_styles: &[String]) -> Self {
+        let strings = Strings(&snapshot.strings);
+        let documents = snapshot
+            .documents
+            .into_iter()
+            .map(|doc| SnapshotDocument::new(doc, &strings, computed_styles))
+            .collect();
+        Self { documents }
+    }
+}
+
+impl SnapshotDocument {
+    fn new(doc: DocumentSnapshot, strings: &Strings<'_>, computed_styles: &[String]) -> Self {
+        let nodes = doc.nodes;
+        let node_count = nodes
+            .parent_index
+            .as_ref()
+            .map(Vec::len)
+            .or_else(|| nodes.node_type.as_ref().map(Vec::len))
+            .unwrap_or_default();
+        let mut snapshot_nodes = vec![SnapshotNode::default(); node_count];
+
+        for (idx, node) in snapshot_nodes.iter_mut().enumerate() {
+            node.parent_index = nodes
+                .parent_index
+                .as_ref()
+                .and_then(|parents| to_index(parents.get(idx).copied()));
+            node.node_type = nodes
+                .node_type
+                .as_ref()
+                .and_then(|types| types.get(idx).copied())
+                .unwrap_or_default();
+            node.node_name = strings.get_at(nodes.node_name.as_deref(), idx);
+            node.node_value = strings.get_at(nodes.node_value.as_deref(), idx);
+            node.backend_node_id = nodes
+                .backend_node_id
+                .as_ref()
+                .and_then(|ids| ids.get(idx).copied());
+            node.attributes = nodes
+                .attributes
+                .as_ref()
+                .and_then(|attrs| attrs.get(idx))
+                .map(|attrs| strings.pairs(attrs))
+                .unwrap_or_default();
+        }
+        for (idx, value) in rare_strings(nodes.text_value.as_ref(), strings) {
+            if let Some(node) = snapshot_nodes.get_mut(idx) {
+                node.text_value = Some(value);
+            }
+        }
+        for (idx, value) in rare_strings(nodes.input_value.as_ref(), strings) {
+            if let Some(node) = snapshot_nodes.get_mut(idx) {
+                node.input_value = Some(value);
+            }
+        }
+        for idx in rare_booleans(nodes.input_checked.as_ref()) {
+            if let Some(node) = snapshot_nodes.get_mut(idx) {
+                node.input_checked = true;
+            }
+        }
+        for idx in rare_booleans(nodes.option_selected.as_ref()) {
+            if let Some(node) = snapshot_nodes.get_mut(idx) {
+                node.option_selected = true;
+            }
+        }
+        for idx in rare_booleans(nodes.is_clickable.as_ref()) {
+            if let Some(node) = snapshot_nodes.get_mut(idx) {
+                node.is_clickable = true;
+            }
+        }
+        for (idx, value) in rare_integers(nodes.content_document_index.as_ref()) {
+            if let Some(node) = snapshot_nodes.get_mut(idx) {
+                node.content_document_index = to_index(Some(value));
+            }
+        }
+
+        let layout = doc.layout;
+        let layouts = layout
+            .node_index
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, node_index)| {
+                let node_index = to_index(Some(*node_index))?;
+                if let Some(node) = snapshot_nodes.get_mut(node_index) {
+                    node.layout_index = Some(idx);
+                }
+                let styles = layout
+                    .styles
+                    .get(idx)
+                    .map(|styles| {
+                        computed_styles
+                            .iter()
+                            .cloned()
+                            .zip(styles.inner().iter().map(|s| strings.get(*s)))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Some(SnapshotLayout {
+                    node_index,
+                    styles,
+                    bounds: to_bounding_box(layout.bounds.get(idx)),
+                    text: layout
+                        .text
+                        .get(idx)
+                        .map(|s| strings.get(*s))
+                        .unwrap_or_default(),
+                    paint_order: layout
+                        .paint_orders
+                        .as_ref()
+                        .and_then(|orders| orders.get(idx).copied()),
+                })
+            })
+            .collect();
+
+        let text = doc.text_boxes;
+        let text_boxes = text
+            .layout_index
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, layout_index)| {
+                Some(SnapshotTextBox {
+                    layout_index: to_index(Some(*layout_index))?,
+                    bounds: to_bounding_box(text.bounds.get(idx)),
+                    start: to_index(text.start.get(idx).copied()).unwrap_or_default(),
+                    length: to_index(text.length.get(idx).copied()).unwrap_or_default(),
+                })
+            })
+            .collect();
+
+        Self {
+            url: strings.get(doc.document_url),
+            title: strings.get(doc.title),
+            base_url: strings.get(doc.base_url),
+            frame_id: strings.get(doc.frame_id),
+            nodes: snapshot_nodes,
+            layouts,
+            text_boxes,
+            scroll_offset_x: doc.scroll_offset_x,
+            scroll_offset_y: doc.scroll_offset_y,
+            content_width: doc.content_width,
+            content_height: doc.content_height,
+        }
+    }
+}
+
+/// The string table of a snapshot
+struct Strings<'a>(&'a [String]);
+
+impl<'a> Strings<'a> {
+    /// The string at the `index`, empty for `-1`
+    fn get(&self, index: StringIndex) -> String {
+        to_index(Some(*index.inner()))
+            .and_then(|idx| self.0.get(idx))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The string at the index of the node `idx`
+    fn get_at(&self, indices: Option<&[StringIndex]>, idx: usize) -> String {
+        indices
+            .and_then(|indices| indices.get(idx))
+            .map(|s| self.get(*s))
+            .unwrap_or_default()
+    }
+
+    /// Attributes are stored as a flat list of names and values
+    fn pairs(&self, attrs: &ArrayOfStrings) -> Vec<(String, String)> {
+        attrs
+            .inner()
+            .chunks(2)
+            .map(|pair| {
+                (
+                    self.get(pair[0]),
+                    pair.get(1).map(|s| self.get(*s)).unwrap_or_default(),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Negative indices mark missing values
+fn to_index(idx: Option<i64>) -> Option<usize> {
+    idx.filter(|idx| *idx >= 0).map(|idx| idx as usize)
+}
+
+fn to_bounding_box(rect: Option<&Rectangle>) -> BoundingBox {
+    let rect = rect.map(Rectangle::inner);
+    let value = |i: usize| rect.and_then(|r| r.get(i)).copied().unwrap_or_default();
+    BoundingBox {
+        x: value(0),
+        y: value(1),
+        width: value(2),
+        height: value(3),
+    }
+}
+
+fn rare_strings(data: Option<&RareStringData>, strings: &Strings<'_>) -> Vec<(usize, String)> {
+    data.map(|data| {
+        data.index
+            .iter()
+            .zip(data.value.iter())
+            .filter_map(|(idx, s)| Some((to_index(Some(*idx))?, strings.get(*s))))
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+fn rare_booleans(data: Option<&RareBooleanData>) -> Vec<usize> {
+    data.map(|data| {
+        data.index
+            .iter()
+            .filter_map(|idx| to_index(Some(*idx)))
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+fn rare_integers(data: Option<&RareIntegerData>) -> Vec<(usize, i64)> {
+    data.map(|data| {
+        data.index
+            .iter()
+            .zip(data.value.iter())
+            .filter_map(|(idx, value)| Some((to_index(Some(*idx))?, *value)))
+            .collect()
+    })
+    .unwrap_or_default()
+}
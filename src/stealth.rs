@@ -0,0 +1,119 @@
+//! Evasions that hide the most common signs of an automated, headless
+//! browser from the pages it visits.
+//!
+//! Enabled for all pages via
+//! [`BrowserConfigBuilder::stealth_mode`](crate::browser::BrowserConfigBuilder::stealth_mode).
+use chromiumoxide_cdp::cdp::browser_protocol::page::AddScriptToEvaluateOnNewDocumentParams;
+use chromiumoxide_types::Method;
+use std::borrow::Cow;
+
+/// Removes `navigator.webdriver`, which is `true` for automated browsers.
+pub const WEBDRIVER: &str = r#"(() => {
+    Object.defineProperty(Object.getPrototypeOf(navigator), 'webdriver', {
+        get: () => undefined,
+        configurable: true,
+    });
+})();"#;
+
+/// Headless chromium reports no plugins, this reports the PDF viewers of a
+/// regular chrome.
+pub const PLUGINS: &str = r#"(() => {
+    const names = ['PDF Viewer', 'Chrome PDF Viewer', 'Chromium PDF Viewer',
+        'Microsoft Edge PDF Viewer', 'WebKit built-in PDF'];
+    const mimeTypes = [];
+    const plugins = names.map((name) => {
+        const plugin = Object.create(Plugin.prototype);
+        const mimeType = Object.create(MimeType.prototype);
+        Object.defineProperties(mimeType, {
+            type: { get: () => 'application/pdf' },
+            suffixes: { get: () => 'pdf' },
+            description: { get: () => 'Portable Document Format' },
+            enabledPlugin: { get: () => plugin },
+        });
+        Object.defineProperties(plugin, {
+            name: { get: () => name },
+            filename: { get: () => 'internal-pdf-viewer' },
+            description: { get: () => 'Portable Document Format' },
+            length: { get: () => 1 },
+            0: { get: () => mimeType },
+        });
+        mimeTypes.push(mimeType);
+        return plugin;
+    });
+    const list = (items, proto) => {
+        const list = Object.create(proto);
+        items.forEach((item, i) => Object.defineProperty(list, i, { get: () => item }));
+        Object.defineProperties(list, {
+            length: { get: () => items.length },
+            item: { value: (i) => items[i] || null },
+            namedItem: { value: (name) => items.find((i) => i.name === name || i.type === name) || null },
+            [Symbol.iterator]: { value: () => items[Symbol.iterator]() },
+        });
+        return list;
+    };
+    const pluginArray = list(plugins, PluginArray.prototype);
+    const mimeTypeArray = list(mimeTypes.slice(0, 1), MimeTypeArray.prototype);
+    Object.defineProperty(Object.getPrototypeOf(navigator), 'plugins', {
+        get: () => pluginArray,
+        configurable: true,
+    });
+    Object.defineProperty(Object.getPrototypeOf(navigator), 'mimeTypes', {
+        get: () => mimeTypeArray,
+        configurable: true,
+    });
+})();"#;
+
+/// Headless chromium may report no languages at all.
+pub const LANGUAGES: &str = r#"(() => {
+    Object.defineProperty(Object.getPrototypeOf(navigator), 'languages', {
+        get: () => ['en-US', 'en'],
+        configurable: true,
+    });
+})();"#;
+
+/// Reports the WebGL vendor and renderer of a real graphics card instead of
+/// the software renderer of headless chromium.
+pub const WEBGL_VENDOR: &str = r#"(() => {
+    const UNMASKED_VENDOR_WEBGL = 0x9245;
+    const UNMASKED_RENDERER_WEBGL = 0x9246;
+    for (const context of [WebGLRenderingContext, window.WebGL2RenderingContext]) {
+        if (!context) continue;
+        const getParameter = context.prototype.getParameter;
+        context.prototype.getParameter = function (parameter) {
+            if (parameter === UNMASKED_VENDOR_WEBGL) return 'Intel Inc.';
+            if (parameter === UNMASKED_RENDERER_WEBGL) return 'Intel Iris OpenGL Engine';
+            return getParameter.call(this, parameter);
+        };
+    }
+})();"#;
+
+/// Adds the `window.chrome` object that is missing in headless chromium.
+pub const CHROME_RUNTIME: &str = r#"(() => {
+    if (!window.chrome) {
+        Object.defineProperty(window, 'chrome', {
+            value: { runtime: {} },
+            writable: true,
+            configurable: true,
+        });
+    }
+})();"#;
+
+/// All the scripts evaluated in every new document of a page in stealth mode
+pub const EVASIONS: [&str; 5] = [WEBDRIVER, PLUGINS, LANGUAGES, WEBGL_VENDOR, CHROME_RUNTIME];
+
+/// The commands that install the [`EVASIONS`] in a page
+pub(crate) fn init_commands() -> Vec<(Cow<'static, str>, serde_json::Value)> {
+    EVASIONS
+        .iter()
+        .map(|script| {
+            let params = AddScriptToEvaluateOnNewDocumentParams::new(*script);
+            (params.identifier(), serde_json::to_value(params).unwrap())
+        })
+        .collect()
+}
+
+/// Turns the user agent of a headless browser, like `HeadlessChrome/88.0`,
+/// into the one of a regular chrome, `Chrome/88.0`.
+pub fn user_agent(browser_user_agent: &str) -> String {
+    browser_user_agent.replace("HeadlessChrome", "Chrome")
+}
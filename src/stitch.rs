@@ -0,0 +1,145 @@
+use std::io::Cursor;
+
+use image::{imageops, DynamicImage, ImageOutputFormat, RgbaImage};
+
+use chromiumoxide_cdp::cdp::browser_protocol::dom::Rect;
+use chromiumoxide_cdp::cdp::browser_protocol::page::{
+    CaptureScreenshotFormat, CaptureScreenshotParams,
+};
+use chromiumoxide_cdp::cdp::js_protocol::runtime::EvaluateParams;
+
+use crate::error::{CdpError, Result};
+use crate::handler::PageInner;
+
+/// Captures the full page by scrolling through it and stitching the
+/// screenshots of the viewport together.
+///
+/// The tiles are placed at the scroll position the page actually ended up
+/// at, so tiles overlap at the right and bottom edge of the page instead of
+/// leaving gaps.
+pub(crate) async fn capture(page: &PageInner, params: CaptureScreenshotParams) -> Result<Vec<u8>> {
+    let format = params
+        .format
+        .clone()
+        .unwrap_or(CaptureScreenshotFormat::Png);
+
+    // chromium encodes jpeg screenshots with a quality of 80 by default
+    let quality = params.quality.unwrap_or(80).clamp(0, 100) as u8;
+
+    let metrics = page.layout_metrics().await?;
+    let width = metrics.content_size.width.ceil() as i64;
+    let height = metrics.content_size.height.ceil() as i64;
+    let viewport = metrics.layout_viewport;
+    let (tile_width, tile_height) = (viewport.client_width.max(1), viewport.client_height.max(1));
+
+    let mut tile_params = params;
+    tile_params.format = Some(CaptureScreenshotFormat::Png);
+    tile_params.quality = None;
+    tile_params.clip = None;
+
+    let res = stitch(
+        page,
+        &tile_params,
+        (width, height),
+        (tile_width, tile_height),
+    )
+    .await;
+    // restore the original scroll position, even if capturing a tile failed
+    scroll_to(page, viewport.page_x, viewport.page_y).await?;
+    encode(res?, format, quality)
+}
+
+/// Captures all the tiles and places them on a single canvas
+async fn stitch(
+    page: &PageInner,
+    params: &CaptureScreenshotParams,
+    (width, height): (i64, i64),
+    (tile_width, tile_height): (i64, i64),
+) -> Result<RgbaImage> {
+    let mut canvas: Option<(RgbaImage, f64)> = None;
+    let mut y = 0;
+    while y < height {
+        let mut x = 0;
+        while x < width {
+            let (scroll_x, scroll_y) = scroll_to(page, x, y).await?;
+            let data = base64::decode(&page.execute(params.clone()).await?.data)?;
+            let tile = image::load_from_memory(&data)
+                .map_err(|err| CdpError::msg(err.to_string()))?
+                .to_rgba8();
+            // the device pixels per css pixel, determined by the first tile
+            let (canvas, ratio) = canvas.get_or_insert_with(|| {
+                let ratio = tile.width() as f64 / tile_width as f64;
+                let canvas = RgbaImage::new(
+                    (width as f64 * ratio).ceil() as u32,
+                    (height as f64 * ratio).ceil() as u32,
+                );
+                (canvas, ratio)
+            });
+            imageops::replace(
+                canvas,
+                &tile,
+                (scroll_x * *ratio).round() as u32,
+                (scroll_y * *ratio).round() as u32,
+            );
+            x += tile_width;
+        }
+        y += tile_height;
+    }
+    canvas
+        .map(|(canvas, _)| canvas)
+        .ok_or_else(|| CdpError::msg("The page has no content to capture"))
+}
+
+/// Scrolls the page to the position and waits until it was rendered, returns
+/// the position the page was scrolled to
+async fn scroll_to(page: &PageInner, x: i64, y: i64) -> Result<(f64, f64)> {
+    let mut params = EvaluateParams::new(format!(
+        "new Promise(resolve => {{
+            window.scrollTo({}, {});
+            requestAnimationFrame(() => requestAnimationFrame(() =>
+                resolve([window.scrollX, window.scrollY])));
+        }})",
+        x, y
+    ));
+    params.await_promise = Some(true);
+    params.return_by_value = Some(true);
+    let res = page.execute(params).await?.result;
+    if let Some(exception) = res.exception_details {
+        return Err(CdpError::ScrollingFailed(exception.text));
+    }
+    let pos = res.result.value.unwrap_or_default();
+    let coord = |idx: usize| pos[idx].as_f64().unwrap_or_default();
+    Ok((coord(0), coord(1)))
+}
+
+/// Whether the dimensions of the screenshot `data` match the size of the
+/// `content`, used to detect browsers that ignore `captureBeyondViewport`
+pub(crate) fn covers(data: &[u8], content: &Rect) -> bool {
+    let dimensions = image::io::Reader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok());
+    match dimensions {
+        Some((width, height)) if width > 0 && height > 0 && content.height > 0. => {
+            let ratio = width as f64 / height as f64;
+            let expected = content.width / content.height;
+            (ratio - expected).abs() <= expected * 0.01
+        }
+        _ => false,
+    }
+}
+
+fn encode(image: RgbaImage, format: CaptureScreenshotFormat, quality: u8) -> Result<Vec<u8>> {
+    let (image, format) = match format {
+        CaptureScreenshotFormat::Jpeg => (
+            DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(image).to_rgb8()),
+            ImageOutputFormat::Jpeg(quality),
+        ),
+        _ => (DynamicImage::ImageRgba8(image), ImageOutputFormat::Png),
+    };
+    let mut buf = Vec::new();
+    image
+        .write_to(&mut buf, format)
+        .map_err(|err| CdpError::msg(err.to_string()))?;
+    Ok(buf)
+}
@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chromiumoxide_cdp::cdp::browser_protocol::dom_storage::{
+    ClearParams, GetDomStorageItemsParams, RemoveDomStorageItemParams, SetDomStorageItemParams,
+    StorageId,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::indexed_db::{
+    self, ClearObjectStoreParams, DatabaseWithObjectStores, DeleteDatabaseParams,
+    RequestDatabaseNamesParams, RequestDatabaseParams,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::storage::{ClearDataForOriginParams, StorageType};
+
+use crate::error::Result;
+use crate::handler::PageInner;
+
+/// Access to the storage of the origins within a page's browser context:
+/// `localStorage`, `sessionStorage` and IndexedDB databases.
+///
+/// Obtained via [`Page::storage`](crate::Page::storage) or
+/// [`BrowserContext::storage`](crate::BrowserContext::storage).
+///
+/// Origins are given as scheme, host and port, like `https://example.com`.
+///
+/// # Example
+///
+/// ```no_run
+/// # use chromiumoxide::page::Page;
+/// # use chromiumoxide::error::Result;
+/// # async fn demo(page: Page) -> Result<()> {
+///     let storage = page.storage();
+///     storage
+///         .local_storage_set("https://example.com", "theme", "dark")
+///         .await?;
+///     let items = storage.local_storage_get("https://example.com").await?;
+///     assert_eq!(items.get("theme").map(String::as_str), Some("dark"));
+///     # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Storage {
+    page: Arc<PageInner>,
+}
+
+impl Storage {
+    pub(crate) fn new(page: Arc<PageInner>) -> Self {
+        Self { page }
+    }
+
+    /// All the items in the `localStorage` of the `origin`
+    pub async fn local_storage_get(
+        &self,
+        origin: impl Into<String>,
+    ) -> Result<HashMap<String, String>> {
+        self.dom_storage_items(StorageId::new(origin, true)).await
+    }
+
+    /// Sets the item `key` in the `localStorage` of the `origin` to `value`
+    pub async fn local_storage_set(
+        &self,
+        origin: impl Into<String>,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Result<&Self> {
+        self.page
+            .execute(SetDomStorageItemParams::new(
+                StorageId::new(origin, true),
+                key,
+                value,
+            ))
+            .await?;
+        Ok(self)
+    }
+
+    /// Removes the item `key` from the `localStorage` of the `origin`
+    pub async fn local_storage_remove(
+        &self,
+        origin: impl Into<String>,
+        key: impl Into<String>,
+    ) -> Result<&Self> {
+        self.page
+            .execute(RemoveDomStorageItemParams::new(
+                StorageId::new(origin, true),
+                key,
+            ))
+            .await?;
+        Ok(self)
+    }
+
+    /// Removes all the items from the `localStorage` of the `origin`
+    pub async fn local_storage_clear(&self, origin: impl Into<String>) -> Result<&Self> {
+        self.page
+            .execute(ClearParams::new(StorageId::new(origin, true)))
+            .await?;
+        Ok(self)
+    }
+
+    /// All the items in the `sessionStorage` of the `origin` within this page
+    pub async fn session_storage_get(
+        &self,
+        origin: impl Into<String>,
+    ) -> Result<HashMap<String, String>> {
+        self.dom_storage_items(StorageId::new(origin, false)).await
+    }
+
+    /// Sets the item `key` in the `sessionStorage` of the `origin` within this
+    /// page to `value`
+    pub async fn session_storage_set(
+        &self,
+        origin: impl Into<String>,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Result<&Self> {
+        self.page
+            .execute(SetDomStorageItemParams::new(
+                StorageId::new(origin, false),
+                key,
+                value,
+            ))
+            .await?;
+        Ok(self)
+    }
+
+    /// Removes all the items from the `sessionStorage` of the `origin` within
+    /// this page
+    pub async fn session_storage_clear(&self, origin: impl Into<String>) -> Result<&Self> {
+        self.page
+            .execute(ClearParams::new(StorageId::new(origin, false)))
+            .await?;
+        Ok(self)
+    }
+
+    async fn dom_storage_items(&self, storage_id: StorageId) -> Result<HashMap<String, String>> {
+        let resp = self
+            .page
+            .execute(GetDomStorageItemsParams::new(storage_id))
+            .await?;
+        // every item is a `[key, value]` pair
+        Ok(resp
+            .result
+            .entries
+            .into_iter()
+            .filter_map(|item| match item.inner().as_slice() {
+                [key, value] => Some((key.clone(), value.clone())),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// The names of all the IndexedDB databases of the `origin`
+    pub async fn indexed_db_names(&self, origin: impl Into<String>) -> Result<Vec<String>> {
+        self.enable_indexed_db().await?;
+        let resp = self
+            .page
+            .execute(RequestDatabaseNamesParams::new(origin))
+            .await?;
+        Ok(resp.result.database_names)
+    }
+
+    /// The IndexedDB database `name` of the `origin` with its version and
+    /// object stores
+    pub async fn indexed_db(
+        &self,
+        origin: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Result<DatabaseWithObjectStores> {
+        self.enable_indexed_db().await?;
+        let resp = self
+            .page
+            .execute(RequestDatabaseParams::new(origin, name))
+            .await?;
+        Ok(resp.result.database_with_object_stores)
+    }
+
+    /// Removes all the entries of the object store `store` in the IndexedDB
+    /// database `name` of the `origin`
+    pub async fn indexed_db_clear_object_store(
+        &self,
+        origin: impl Into<String>,
+        name: impl Into<String>,
+        store: impl Into<String>,
+    ) -> Result<&Self> {
+        self.enable_indexed_db().await?;
+        self.page
+            .execute(ClearObjectStoreParams::new(origin, name, store))
+            .await?;
+        Ok(self)
+    }
+
+    /// Deletes the IndexedDB database `name` of the `origin`
+    pub async fn indexed_db_delete(
+        &self,
+        origin: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Result<&Self> {
+        self.enable_indexed_db().await?;
+        self.page
+            .execute(DeleteDatabaseParams::new(origin, name))
+            .await?;
+        Ok(self)
+    }
+
+    async fn enable_indexed_db(&self) -> Result<()> {
+        self.page
+            .execute(indexed_db::EnableParams::default())
+            .await?;
+        Ok(())
+    }
+
+    /// Clears the data of the `types` of storage, like cookies or
+    /// `localStorage`, for the `origin`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::cdp::browser_protocol::storage::StorageType;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.storage()
+    ///         .clear_data_for_origin(
+    ///             "https://example.com",
+    ///             vec![StorageType::LocalStorage, StorageType::Indexeddb],
+    ///         )
+    ///         .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn clear_data_for_origin(
+        &self,
+        origin: impl Into<String>,
+        types: impl IntoIterator<Item = StorageType>,
+    ) -> Result<&Self> {
+        let types = types
+            .into_iter()
+            .map(|ty| ty.as_ref().to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        self.page
+            .execute(ClearDataForOriginParams::new(origin, types))
+            .await?;
+        Ok(self)
+    }
+}
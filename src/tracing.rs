@@ -0,0 +1,98 @@
+use std::path::Path;
+
+use futures::StreamExt;
+
+use chromiumoxide_cdp::cdp::browser_protocol::tracing::{
+    EndParams, EventTracingComplete, StartParams, StartTransferMode, TraceConfig,
+};
+
+use crate::browser::Browser;
+use crate::error::{CdpError, Result};
+use crate::io_stream::IoStream;
+use crate::utils;
+
+/// Records a performance trace of the whole browser.
+///
+/// The trace is returned as JSON that can be loaded in `chrome://tracing` or
+/// [Perfetto](https://ui.perfetto.dev).
+///
+/// # Example
+///
+/// ```no_run
+/// # use chromiumoxide::browser::Browser;
+/// # use chromiumoxide::error::Result;
+/// # async fn demo(browser: Browser) -> Result<()> {
+///     browser.tracing().start(vec!["devtools.timeline"]).await?;
+///     let _page = browser.new_page("https://en.wikipedia.org").await?;
+///     browser.tracing().stop_and_save("trace.json").await?;
+///     # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Tracing<'a> {
+    browser: &'a Browser,
+}
+
+impl<'a> Tracing<'a> {
+    pub(crate) fn new(browser: &'a Browser) -> Self {
+        Self { browser }
+    }
+
+    /// Starts tracing the `categories`, like `devtools.timeline` or
+    /// `disabled-by-default-devtools.screenshot`.
+    ///
+    /// If `categories` is empty, chromium's default categories are recorded.
+    pub async fn start<I, S>(&self, categories: I) -> Result<&Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let categories = categories.into_iter().map(Into::into).collect::<Vec<_>>();
+        let mut config = TraceConfig::default();
+        if !categories.is_empty() {
+            config.included_categories = Some(categories);
+        }
+        self.start_with(StartParams::builder().trace_config(config).build())
+            .await
+    }
+
+    /// Starts tracing with custom parameters.
+    ///
+    /// The trace is always returned as stream, regardless of the
+    /// `transfer_mode` of the `params`.
+    pub async fn start_with(&self, mut params: StartParams) -> Result<&Self> {
+        params.transfer_mode = Some(StartTransferMode::ReturnAsStream);
+        self.browser.execute(params).await?;
+        Ok(self)
+    }
+
+    /// Stops tracing and returns the recorded trace
+    pub async fn stop(&self) -> Result<Vec<u8>> {
+        self.stop_stream().await?.into_bytes().await
+    }
+
+    /// Stops tracing and returns a stream over the recorded trace, that is
+    /// read in chunks via `IO.read`.
+    pub async fn stop_stream(&self) -> Result<IoStream> {
+        // subscribe before ending so that the event is not missed
+        let mut complete = self
+            .browser
+            .event_listener::<EventTracingComplete>()
+            .await?;
+        self.browser.execute(EndParams::default()).await?;
+        let event = complete.next().await.ok_or(CdpError::NoResponse)?;
+        let stream = event
+            .stream
+            .clone()
+            .ok_or_else(|| CdpError::msg("Trace was not returned as stream"))?;
+        Ok(self.browser.io_stream(stream))
+    }
+
+    /// Stops tracing, writes the recorded trace to the file at `output` and
+    /// returns it.
+    pub async fn stop_and_save(&self, output: impl AsRef<Path>) -> Result<Vec<u8>> {
+        let trace = self.stop().await?;
+        utils::write(output.as_ref(), &trace).await?;
+        Ok(trace)
+    }
+}
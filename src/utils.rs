@@ -0,0 +1,83 @@
+use std::future::Future;
+use std::path::Path;
+use std::time::Duration;
+
+use futures::future::{self, Either};
+use futures::pin_mut;
+use futures_timer::Delay;
+
+use crate::error::{CdpError, Result};
+
+/// Write the `contents` to the file at `path` with the configured runtime
+pub(crate) async fn write<P: AsRef<Path> + Unpin, C: AsRef<[u8]> + Unpin>(
+    path: P,
+    contents: C,
+) -> std::io::Result<()> {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "async-std-runtime")] {
+            async_std::fs::write(path.as_ref(), contents).await
+        } else if #[cfg(feature = "tokio-runtime")] {
+            tokio::fs::write(path.as_ref(), contents).await
+        }
+    }
+}
+
+/// Runs the `future` in the background on the configured runtime
+pub(crate) fn spawn<F>(future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "async-std-runtime")] {
+            async_std::task::spawn(future);
+        } else if #[cfg(feature = "tokio-runtime")] {
+            tokio::spawn(future);
+        }
+    }
+}
+
+/// Runs the blocking `f` on the thread pool of the configured runtime
+pub(crate) async fn spawn_blocking<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "async-std-runtime")] {
+            async_std::task::spawn_blocking(f).await
+        } else if #[cfg(feature = "tokio-runtime")] {
+            tokio::task::spawn_blocking(f).await.expect("Blocking task panicked")
+        }
+    }
+}
+
+/// Fails with `CdpError::Timeout` if the `future` doesn't resolve within the
+/// `timeout`, waits forever if there is none
+pub(crate) async fn timeout<F, T>(timeout: Option<Duration>, future: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return future.await,
+    };
+    pin_mut!(future);
+    match future::select(future, Delay::new(timeout)).await {
+        Either::Left((res, _)) => res,
+        Either::Right(_) => Err(CdpError::Timeout),
+    }
+}
+
+/// Resolves the `paths` relative to the current working directory, the
+/// browser expects absolute paths for files to upload
+pub(crate) fn absolute_paths<I, P>(paths: I) -> std::io::Result<Vec<String>>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    let cwd = std::env::current_dir()?;
+    Ok(paths
+        .into_iter()
+        .map(|path| cwd.join(path).to_string_lossy().into_owned())
+        .collect())
+}
@@ -0,0 +1,172 @@
+//! Compares screenshots pixel by pixel for visual regression tests.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     page.goto("https://example.com").await?;
+//!     // fails if the page looks different than the last time
+//!     page.expect_visual_match("tests/baselines/example.png").await?;
+//!     # Ok(())
+//! # }
+//! ```
+use image::{DynamicImage, ImageOutputFormat, Rgba, RgbaImage};
+
+use crate::error::{CdpError, Result};
+
+/// How much a color channel of two pixels may differ by default, before
+/// they count as mismatch
+pub const DEFAULT_THRESHOLD: f64 = 0.1;
+
+/// The color mismatched pixels are highlighted with in the diff image
+const MISMATCH_COLOR: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+/// The result of comparing two screenshots
+#[derive(Debug, Clone)]
+pub struct DiffResult {
+    /// A png that shows the first screenshot faded out with all mismatched
+    /// pixels highlighted in red
+    pub diff_image: Vec<u8>,
+    /// The number of pixels that differ
+    pub mismatched_pixels: u64,
+    /// The number of compared pixels, the area of the larger screenshot if
+    /// the sizes differ
+    pub total_pixels: u64,
+}
+
+impl DiffResult {
+    /// The share of mismatched pixels in percent
+    pub fn mismatch_percentage(&self) -> f64 {
+        if self.total_pixels == 0 {
+            return 0.;
+        }
+        self.mismatched_pixels as f64 * 100. / self.total_pixels as f64
+    }
+
+    /// Whether the screenshots are identical, within the threshold
+    pub fn is_match(&self) -> bool {
+        self.mismatched_pixels == 0
+    }
+}
+
+/// Compares the encoded screenshots `a` and `b` pixel by pixel.
+///
+/// Two pixels mismatch if any of their color channels differs by more than
+/// the `threshold`, between `0` (exact match) and `1`. If the screenshots
+/// have different sizes, all pixels that are only part of one of them
+/// mismatch.
+pub fn compare_screenshots(a: &[u8], b: &[u8], threshold: f64) -> Result<DiffResult> {
+    let a = decode(a)?;
+    let b = decode(b)?;
+    let width = a.width().max(b.width());
+    let height = a.height().max(b.height());
+    let tolerance = (threshold.clamp(0., 1.) * 255.).round() as i16;
+
+    let mut diff = RgbaImage::new(width, height);
+    let mut mismatched_pixels = 0;
+    for (x, y, out) in diff.enumerate_pixels_mut() {
+        *out = match (pixel(&a, x, y), pixel(&b, x, y)) {
+            (Some(pa), Some(pb)) if pixels_match(pa, pb, tolerance) => faded(*pa),
+            _ => {
+                mismatched_pixels += 1;
+                MISMATCH_COLOR
+            }
+        };
+    }
+
+    Ok(DiffResult {
+        diff_image: encode_png(diff)?,
+        mismatched_pixels,
+        total_pixels: width as u64 * height as u64,
+    })
+}
+
+fn decode(data: &[u8]) -> Result<RgbaImage> {
+    Ok(image::load_from_memory(data)
+        .map_err(|err| CdpError::msg(format!("Failed to decode screenshot: {}", err)))?
+        .to_rgba8())
+}
+
+fn pixel(image: &RgbaImage, x: u32, y: u32) -> Option<&Rgba<u8>> {
+    if x < image.width() && y < image.height() {
+        Some(image.get_pixel(x, y))
+    } else {
+        None
+    }
+}
+
+/// Whether no color channel of the pixels differs by more than `tolerance`
+fn pixels_match(a: &Rgba<u8>, b: &Rgba<u8>, tolerance: i16) -> bool {
+    a.0.iter()
+        .zip(b.0.iter())
+        .all(|(ca, cb)| (*ca as i16 - *cb as i16).abs() <= tolerance)
+}
+
+/// A light gray version of the matching pixel, so the mismatches stand out
+fn faded(pixel: Rgba<u8>) -> Rgba<u8> {
+    let [r, g, b, _] = pixel.0;
+    let luma = (0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64) as u8;
+    let faded = 255 - (255 - luma) / 5;
+    Rgba([faded, faded, faded, 255])
+}
+
+fn encode_png(image: RgbaImage) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    DynamicImage::ImageRgba8(image)
+        .write_to(&mut buf, ImageOutputFormat::Png)
+        .map_err(|err| CdpError::msg(err.to_string()))?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32, color: [u8; 4]) -> Vec<u8> {
+        encode_png(RgbaImage::from_pixel(width, height, Rgba(color))).unwrap()
+    }
+
+    #[test]
+    fn identical_screenshots_match() {
+        let a = png(4, 3, [10, 20, 30, 255]);
+        let diff = compare_screenshots(&a, &a, 0.).unwrap();
+        assert!(diff.is_match());
+        assert_eq!(diff.total_pixels, 12);
+        assert_eq!(diff.mismatch_percentage(), 0.);
+    }
+
+    #[test]
+    fn threshold_tolerates_small_differences() {
+        let a = png(2, 2, [100, 100, 100, 255]);
+        let b = png(2, 2, [110, 100, 100, 255]);
+        assert!(compare_screenshots(&a, &b, 0.1).unwrap().is_match());
+        let diff = compare_screenshots(&a, &b, 0.).unwrap();
+        assert_eq!(diff.mismatched_pixels, 4);
+        assert_eq!(diff.mismatch_percentage(), 100.);
+    }
+
+    #[test]
+    fn pixels_outside_the_smaller_screenshot_mismatch() {
+        let a = png(2, 2, [0, 0, 0, 255]);
+        let b = png(4, 2, [0, 0, 0, 255]);
+        let diff = compare_screenshots(&a, &b, 0.).unwrap();
+        assert_eq!(diff.total_pixels, 8);
+        assert_eq!(diff.mismatched_pixels, 4);
+        assert_eq!(diff.mismatch_percentage(), 50.);
+
+        let image = image::load_from_memory(&diff.diff_image)
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(image.dimensions(), (4, 2));
+        assert_eq!(*image.get_pixel(3, 0), MISMATCH_COLOR);
+        assert_ne!(*image.get_pixel(0, 0), MISMATCH_COLOR);
+    }
+
+    #[test]
+    fn invalid_screenshots_fail() {
+        let a = png(1, 1, [0, 0, 0, 255]);
+        assert!(compare_screenshots(&a, b"not an image", 0.).is_err());
+    }
+}
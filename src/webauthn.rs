@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use chromiumoxide_cdp::cdp::browser_protocol::web_authn::{
+    AddCredentialParams, AuthenticatorId, ClearCredentialsParams, Credential, GetCredentialParams,
+    GetCredentialsParams, RemoveCredentialParams, RemoveVirtualAuthenticatorParams,
+    SetAutomaticPresenceSimulationParams, SetUserVerifiedParams,
+};
+use chromiumoxide_types::Binary;
+
+use crate::error::Result;
+use crate::handler::PageInner;
+
+/// A virtual WebAuthn authenticator, like a security key or the platform
+/// authenticator of a device, that answers the `navigator.credentials`
+/// requests of a page without user interaction.
+///
+/// Obtained via
+/// [`Page::add_virtual_authenticator`](crate::Page::add_virtual_authenticator).
+/// Credential ids, private keys and user handles are base64 encoded.
+///
+/// # Example
+///
+/// ```no_run
+/// # use chromiumoxide::page::Page;
+/// # use chromiumoxide::error::Result;
+/// # use chromiumoxide::cdp::browser_protocol::web_authn::{
+/// #     AuthenticatorProtocol, AuthenticatorTransport, Ctap2Version, VirtualAuthenticatorOptions,
+/// # };
+/// # async fn demo(page: Page) -> Result<()> {
+///     let options = VirtualAuthenticatorOptions::builder()
+///         .protocol(AuthenticatorProtocol::Ctap2)
+///         .ctap2_version(Ctap2Version::Ctap21)
+///         .transport(AuthenticatorTransport::Internal)
+///         .has_resident_key(true)
+///         .has_user_verification(true)
+///         .is_user_verified(true)
+///         .build()
+///         .unwrap();
+///     let authenticator = page.add_virtual_authenticator(options).await?;
+///     page.goto("https://example.com/register").await?;
+///     // ... register a passkey
+///     let credentials = authenticator.credentials().await?;
+///     # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct VirtualAuthenticator {
+    page: Arc<PageInner>,
+    id: AuthenticatorId,
+}
+
+impl VirtualAuthenticator {
+    pub(crate) fn new(page: Arc<PageInner>, id: AuthenticatorId) -> Self {
+        Self { page, id }
+    }
+
+    /// The id of the authenticator
+    pub fn id(&self) -> &AuthenticatorId {
+        &self.id
+    }
+
+    /// Adds the `credential`, as if it was registered before
+    pub async fn add_credential(&self, credential: Credential) -> Result<&Self> {
+        self.page
+            .execute(AddCredentialParams::new(self.id.clone(), credential))
+            .await?;
+        Ok(self)
+    }
+
+    /// Returns the credential with the base64 encoded `credential_id`
+    pub async fn credential(&self, credential_id: impl Into<String>) -> Result<Credential> {
+        Ok(self
+            .page
+            .execute(GetCredentialParams::new(
+                self.id.clone(),
+                Binary::from(credential_id.into()),
+            ))
+            .await?
+            .result
+            .credential)
+    }
+
+    /// Returns all the credentials registered with the authenticator
+    pub async fn credentials(&self) -> Result<Vec<Credential>> {
+        Ok(self
+            .page
+            .execute(GetCredentialsParams::new(self.id.clone()))
+            .await?
+            .result
+            .credentials)
+    }
+
+    /// Removes the credential with the base64 encoded `credential_id`
+    pub async fn remove_credential(&self, credential_id: impl Into<String>) -> Result<&Self> {
+        self.page
+            .execute(RemoveCredentialParams::new(
+                self.id.clone(),
+                Binary::from(credential_id.into()),
+            ))
+            .await?;
+        Ok(self)
+    }
+
+    /// Removes all the credentials of the authenticator
+    pub async fn clear_credentials(&self) -> Result<&Self> {
+        self.page
+            .execute(ClearCredentialsParams::new(self.id.clone()))
+            .await?;
+        Ok(self)
+    }
+
+    /// Sets whether the user verification, like a fingerprint or PIN,
+    /// succeeds
+    pub async fn set_user_verified(&self, verified: bool) -> Result<&Self> {
+        self.page
+            .execute(SetUserVerifiedParams::new(self.id.clone(), verified))
+            .await?;
+        Ok(self)
+    }
+
+    /// Sets whether the presence of the user, like touching the security
+    /// key, is simulated automatically
+    pub async fn set_automatic_presence_simulation(&self, enabled: bool) -> Result<&Self> {
+        self.page
+            .execute(SetAutomaticPresenceSimulationParams::new(
+                self.id.clone(),
+                enabled,
+            ))
+            .await?;
+        Ok(self)
+    }
+
+    /// Removes the authenticator from the page, along with its credentials
+    pub async fn remove(self) -> Result<()> {
+        self.page
+            .execute(RemoveVirtualAuthenticatorParams::new(self.id))
+            .await?;
+        Ok(())
+    }
+}
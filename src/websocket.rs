@@ -0,0 +1,187 @@
+use std::pin::Pin;
+
+use futures::task::{Context, Poll};
+use futures::Stream;
+
+use chromiumoxide_cdp::cdp::browser_protocol::network::{
+    EventWebSocketClosed, EventWebSocketCreated, EventWebSocketFrameError,
+    EventWebSocketFrameReceived, EventWebSocketFrameSent, MonotonicTime, RequestId, WebSocketFrame,
+};
+
+use crate::listeners::EventStream;
+
+/// The decoded payload of a websocket frame
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebSocketPayload {
+    /// The payload of a text frame
+    Text(String),
+    /// The payload of a binary frame
+    Binary(Vec<u8>),
+    /// The payload of a control frame, like a ping or close frame, with its
+    /// opcode
+    Control { opcode: u8, data: String },
+}
+
+impl From<&WebSocketFrame> for WebSocketPayload {
+    fn from(frame: &WebSocketFrame) -> Self {
+        match frame.opcode as u8 {
+            1 => WebSocketPayload::Text(frame.payload_data.clone()),
+            // the payload of binary frames is base64 encoded
+            2 => WebSocketPayload::Binary(
+                base64::decode(&frame.payload_data)
+                    .unwrap_or_else(|_| frame.payload_data.clone().into_bytes()),
+            ),
+            opcode => WebSocketPayload::Control {
+                opcode,
+                data: frame.payload_data.clone(),
+            },
+        }
+    }
+}
+
+/// An event of a websocket that was opened by a page
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebSocketEvent {
+    /// The page opened a websocket to the `url`, identified by the
+    /// `request_id` in all its following events
+    Created { request_id: RequestId, url: String },
+    /// The page sent a frame over the websocket
+    FrameSent {
+        request_id: RequestId,
+        timestamp: MonotonicTime,
+        payload: WebSocketPayload,
+    },
+    /// The page received a frame over the websocket
+    FrameReceived {
+        request_id: RequestId,
+        timestamp: MonotonicTime,
+        payload: WebSocketPayload,
+    },
+    /// A frame could not be sent or received
+    FrameError {
+        request_id: RequestId,
+        timestamp: MonotonicTime,
+        error_message: String,
+    },
+    /// The websocket was closed
+    Closed {
+        request_id: RequestId,
+        timestamp: MonotonicTime,
+    },
+}
+
+impl WebSocketEvent {
+    /// The identifier of the websocket this event belongs to
+    pub fn request_id(&self) -> &RequestId {
+        match self {
+            WebSocketEvent::Created { request_id, .. }
+            | WebSocketEvent::FrameSent { request_id, .. }
+            | WebSocketEvent::FrameReceived { request_id, .. }
+            | WebSocketEvent::FrameError { request_id, .. }
+            | WebSocketEvent::Closed { request_id, .. } => request_id,
+        }
+    }
+}
+
+impl From<&EventWebSocketCreated> for WebSocketEvent {
+    fn from(ev: &EventWebSocketCreated) -> Self {
+        WebSocketEvent::Created {
+            request_id: ev.request_id.clone(),
+            url: ev.url.clone(),
+        }
+    }
+}
+
+impl From<&EventWebSocketFrameSent> for WebSocketEvent {
+    fn from(ev: &EventWebSocketFrameSent) -> Self {
+        WebSocketEvent::FrameSent {
+            request_id: ev.request_id.clone(),
+            timestamp: ev.timestamp.clone(),
+            payload: (&ev.response).into(),
+        }
+    }
+}
+
+impl From<&EventWebSocketFrameReceived> for WebSocketEvent {
+    fn from(ev: &EventWebSocketFrameReceived) -> Self {
+        WebSocketEvent::FrameReceived {
+            request_id: ev.request_id.clone(),
+            timestamp: ev.timestamp.clone(),
+            payload: (&ev.response).into(),
+        }
+    }
+}
+
+impl From<&EventWebSocketFrameError> for WebSocketEvent {
+    fn from(ev: &EventWebSocketFrameError) -> Self {
+        WebSocketEvent::FrameError {
+            request_id: ev.request_id.clone(),
+            timestamp: ev.timestamp.clone(),
+            error_message: ev.error_message.clone(),
+        }
+    }
+}
+
+impl From<&EventWebSocketClosed> for WebSocketEvent {
+    fn from(ev: &EventWebSocketClosed) -> Self {
+        WebSocketEvent::Closed {
+            request_id: ev.request_id.clone(),
+            timestamp: ev.timestamp.clone(),
+        }
+    }
+}
+
+/// A `Stream` over the `WebSocketEvent`s of all the websockets of a `Page`.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct WebSocketEvents {
+    created: EventStream<EventWebSocketCreated>,
+    frame_sent: EventStream<EventWebSocketFrameSent>,
+    frame_received: EventStream<EventWebSocketFrameReceived>,
+    frame_error: EventStream<EventWebSocketFrameError>,
+    closed: EventStream<EventWebSocketClosed>,
+}
+
+impl WebSocketEvents {
+    pub(crate) fn new(
+        created: EventStream<EventWebSocketCreated>,
+        frame_sent: EventStream<EventWebSocketFrameSent>,
+        frame_received: EventStream<EventWebSocketFrameReceived>,
+        frame_error: EventStream<EventWebSocketFrameError>,
+        closed: EventStream<EventWebSocketClosed>,
+    ) -> Self {
+        Self {
+            created,
+            frame_sent,
+            frame_received,
+            frame_error,
+            closed,
+        }
+    }
+}
+
+impl Stream for WebSocketEvents {
+    type Item = WebSocketEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        // a websocket is created before its frames are sent and received
+        if let Poll::Ready(Some(ev)) = Pin::new(&mut pin.created).poll_next(cx) {
+            return Poll::Ready(Some(ev.as_ref().into()));
+        }
+        if let Poll::Ready(Some(ev)) = Pin::new(&mut pin.frame_sent).poll_next(cx) {
+            return Poll::Ready(Some(ev.as_ref().into()));
+        }
+        if let Poll::Ready(Some(ev)) = Pin::new(&mut pin.frame_received).poll_next(cx) {
+            return Poll::Ready(Some(ev.as_ref().into()));
+        }
+        if let Poll::Ready(Some(ev)) = Pin::new(&mut pin.frame_error).poll_next(cx) {
+            return Poll::Ready(Some(ev.as_ref().into()));
+        }
+        match Pin::new(&mut pin.closed).poll_next(cx) {
+            Poll::Ready(Some(ev)) => Poll::Ready(Some(ev.as_ref().into())),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use chromiumoxide_types::{CallId, MethodCall};
+
+/// The `tracing` target the messages of the wire log are logged under
+pub const WIRE_LOG_TARGET: &str = "chromiumoxide::wire";
+
+/// Settings of the wire log, which logs every command sent to the browser
+/// and every response and event received from it.
+///
+/// Messages are recorded as `tracing` events with the `debug` level under
+/// the [`WIRE_LOG_TARGET`], with their call id, session, method, the duration
+/// of commands and their payload, truncated to `max_payload_len`. Without a
+/// `tracing` subscriber they are logged via `log` instead. If a `dump_file`
+/// is set, the complete messages are appended to it as JSON lines as well,
+/// by a dedicated thread.
+///
+/// # Example
+///
+/// ```no_run
+/// # use chromiumoxide::browser::BrowserConfig;
+/// # use chromiumoxide::wire_log::WireLogConfig;
+/// let config = BrowserConfig::builder()
+///     .wire_log(WireLogConfig {
+///         dump_file: Some("cdp.jsonl".into()),
+///         ..Default::default()
+///     })
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WireLogConfig {
+    /// How many bytes of the payload of a message are logged at most
+    pub max_payload_len: usize,
+    /// The file the messages are appended to as JSON lines, if any
+    pub dump_file: Option<PathBuf>,
+}
+
+impl Default for WireLogConfig {
+    fn default() -> Self {
+        Self {
+            max_payload_len: 512,
+            dump_file: None,
+        }
+    }
+}
+
+/// Logs the messages exchanged with the browser according to the
+/// `WireLogConfig`
+#[derive(Debug)]
+pub(crate) struct WireLog {
+    max_payload_len: usize,
+    /// Sends the lines to the thread that writes them to the dump file
+    dump: Option<mpsc::Sender<String>>,
+    /// The method and send time of the commands awaiting their response
+    in_flight: HashMap<CallId, (String, Instant)>,
+}
+
+impl WireLog {
+    pub fn new(config: &WireLogConfig) -> Self {
+        let dump = config.dump_file.as_ref().and_then(|path| {
+            match OpenOptions::new().create(true).append(true).open(path) {
+                Ok(file) => Some(spawn_writer(path, file)),
+                Err(err) => {
+                    tracing::warn!(target: WIRE_LOG_TARGET, "Failed to open {:?}: {}", path, err);
+                    None
+                }
+            }
+        });
+        Self {
+            max_payload_len: config.max_payload_len,
+            dump,
+            in_flight: Default::default(),
+        }
+    }
+
+    /// Logs the command that was sent
+    pub fn on_send(&mut self, call: &MethodCall) {
+        self.in_flight
+            .insert(call.id, (call.method.to_string(), Instant::now()));
+        tracing::debug!(
+            target: WIRE_LOG_TARGET,
+            "-> {} session={:?} {} {}",
+            call.id,
+            call.session_id,
+            call.method,
+            truncate(&call.params.to_string(), self.max_payload_len)
+        );
+        if self.dump.is_some() {
+            let msg = serde_json::to_value(call).unwrap_or_default();
+            self.dump("sent", &msg, None);
+        }
+    }
+
+    /// Forgets the command that will not receive a response anymore, like
+    /// after it timed out
+    pub fn on_expired(&mut self, id: CallId) {
+        if let Some((method, sent)) = self.in_flight.remove(&id) {
+            tracing::debug!(
+                target: WIRE_LOG_TARGET,
+                "<- {} {} expired after {:?}",
+                id,
+                method,
+                sent.elapsed()
+            );
+        }
+    }
+
+    /// Logs the response or event that was received as `msg`
+    pub fn on_receive(&mut self, msg: &[u8]) {
+        let value: serde_json::Value = match serde_json::from_slice(msg) {
+            Ok(value) => value,
+            Err(_) => return,
+        };
+        let session_id = value["sessionId"].as_str();
+        if let Some(id) = value["id"].as_u64() {
+            let id = CallId::new(id as usize);
+            let (method, duration) = match self.in_flight.remove(&id) {
+                Some((method, sent)) => (method, Some(sent.elapsed())),
+                None => (String::new(), None),
+            };
+            let payload = if value.get("error").is_some() {
+                &value["error"]
+            } else {
+                &value["result"]
+            };
+            tracing::debug!(
+                target: WIRE_LOG_TARGET,
+                "<- {} session={:?} {} ({:?}) {}",
+                id,
+                session_id,
+                method,
+                duration.unwrap_or_default(),
+                truncate(&payload.to_string(), self.max_payload_len)
+            );
+            self.dump(
+                "received",
+                &value,
+                duration.map(|d| d.as_secs_f64() * 1000.),
+            );
+        } else {
+            tracing::debug!(
+                target: WIRE_LOG_TARGET,
+                "<- event session={:?} {} {}",
+                session_id,
+                value["method"].as_str().unwrap_or_default(),
+                truncate(&value["params"].to_string(), self.max_payload_len)
+            );
+            self.dump("received", &value, None);
+        }
+    }
+
+    /// Appends the message to the dump file
+    fn dump(&mut self, direction: &str, msg: &serde_json::Value, duration_ms: Option<f64>) {
+        let dump = match self.dump.as_mut() {
+            Some(dump) => dump,
+            None => return,
+        };
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|t| t.as_millis() as u64)
+            .unwrap_or_default();
+        let line = serde_json::json!({
+            "direction": direction,
+            "timestamp_ms": timestamp_ms,
+            "duration_ms": duration_ms,
+            "message": msg,
+        });
+        if dump.send(line.to_string()).is_err() {
+            // the writer failed and already logged why
+            self.dump = None;
+        }
+    }
+}
+
+/// Spawns the thread that appends the lines it receives to the `file`, so
+/// that the `Handler` never waits for the disk
+fn spawn_writer(path: &Path, file: File) -> mpsc::Sender<String> {
+    let (tx, rx) = mpsc::channel::<String>();
+    let path = path.to_path_buf();
+    std::thread::spawn(move || {
+        let mut writer = BufWriter::new(file);
+        while let Ok(line) = rx.recv() {
+            let mut res = writeln!(writer, "{}", line);
+            // write everything that is queued before flushing
+            for line in rx.try_iter() {
+                if res.is_err() {
+                    break;
+                }
+                res = writeln!(writer, "{}", line);
+            }
+            if let Err(err) = res.and_then(|_| writer.flush()) {
+                tracing::warn!(target: WIRE_LOG_TARGET, "Failed to dump to {:?}: {}", path, err);
+                return;
+            }
+        }
+    });
+    tx
+}
+
+/// Cuts the `s` after `max` bytes, at a char boundary
+fn truncate(s: &str, max: usize) -> String {
+    if s.len() <= max {
+        return s.to_string();
+    }
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}... ({} bytes)", &s[..end], s.len())
+}
@@ -0,0 +1,114 @@
+use futures::channel::mpsc::{unbounded, Sender};
+use futures::channel::oneshot::channel as oneshot_channel;
+use futures::SinkExt;
+
+use chromiumoxide_cdp::cdp::browser_protocol::log::EventEntryAdded;
+use chromiumoxide_cdp::cdp::browser_protocol::target::{SessionId, TargetId, TargetInfo};
+use chromiumoxide_cdp::cdp::js_protocol::runtime::{
+    self, EvaluateParams, EventConsoleApiCalled, EventExceptionThrown, RemoteObject,
+};
+use chromiumoxide_types::{Command, CommandResponse, EventKind};
+
+use crate::cmd::{to_command_response, CommandMessage};
+use crate::console::ConsoleMessages;
+use crate::error::Result;
+use crate::handler::HandlerMessage;
+use crate::listeners::{EventListenerRequest, EventStream};
+
+/// A handle to a worker target, like a service worker or a dedicated worker
+/// spawned by a page.
+///
+/// Workers are obtained via [`Browser::workers`](crate::Browser::workers) and
+/// [`Browser::service_workers`](crate::Browser::service_workers). The
+/// sessions of extension background pages are handled the same way, see
+/// [`ExtensionTarget`](crate::extension::ExtensionTarget).
+#[derive(Debug, Clone)]
+pub struct Worker {
+    /// Info about the worker target
+    info: TargetInfo,
+    /// The session the worker is attached to
+    session_id: SessionId,
+    /// The `Sender` to send messages to the connection handler
+    sender: Sender<HandlerMessage>,
+}
+
+impl Worker {
+    pub(crate) fn new(
+        info: TargetInfo,
+        session_id: SessionId,
+        sender: Sender<HandlerMessage>,
+    ) -> Self {
+        Self {
+            info,
+            session_id,
+            sender,
+        }
+    }
+
+    /// The identifier of the worker's target
+    pub fn target_id(&self) -> &TargetId {
+        &self.info.target_id
+    }
+
+    /// The identifier of the session the worker is attached to
+    pub fn session_id(&self) -> &SessionId {
+        &self.session_id
+    }
+
+    /// Info about the worker's target, like its type and url
+    pub fn info(&self) -> &TargetInfo {
+        &self.info
+    }
+
+    /// The url of the worker's script
+    pub fn url(&self) -> &str {
+        &self.info.url
+    }
+
+    /// Execute a command within the session of the worker
+    pub async fn execute<T: Command>(&self, cmd: T) -> Result<CommandResponse<T::Response>> {
+        let (tx, rx) = oneshot_channel();
+        let method = cmd.identifier();
+        let msg = CommandMessage::with_session(cmd, tx, Some(self.session_id.clone()))?;
+        self.sender
+            .clone()
+            .send(HandlerMessage::Command(msg))
+            .await?;
+        let resp = rx.await??;
+        to_command_response::<T>(resp, method)
+    }
+
+    /// Evaluates the expression in the global scope of the worker and returns
+    /// the result
+    pub async fn evaluate(&self, evaluate: impl Into<EvaluateParams>) -> Result<RemoteObject> {
+        Ok(self.execute(evaluate.into()).await?.result.result)
+    }
+
+    /// Returns a stream over all the events of type `T` that are emitted
+    /// within the session of the worker.
+    ///
+    /// The stream ends once the worker is detached.
+    pub async fn event_listener<T: EventKind>(&self) -> Result<EventStream<T>> {
+        let (tx, rx) = unbounded();
+        self.sender
+            .clone()
+            .send(HandlerMessage::AddSessionEventListener(
+                self.session_id.clone(),
+                EventListenerRequest::new::<T>(tx),
+            ))
+            .await?;
+        Ok(EventStream::new(rx))
+    }
+
+    /// Returns a stream over all the messages logged to the console of the
+    /// worker and its uncaught exceptions.
+    ///
+    /// This enables the `Runtime` domain of the worker.
+    pub async fn console_messages(&self) -> Result<ConsoleMessages> {
+        let console_api = self.event_listener::<EventConsoleApiCalled>().await?;
+        let log = self.event_listener::<EventEntryAdded>().await?;
+        let exceptions = self.event_listener::<EventExceptionThrown>().await?;
+        self.execute(runtime::EnableParams::default()).await?;
+        Ok(ConsoleMessages::new(console_api, log, exceptions))
+    }
+}